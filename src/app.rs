@@ -1,72 +1,588 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, Instant};
 
-use iced::widget::{button, column, container, image, row, rule, scrollable, text, Space};
-use iced::{Color, Element, Length, Subscription, Task, Theme};
-
-use crate::catalog::{self, Catalog};
-use crate::duplicates::{self, DuplicateGroup, ImageHashes, MatchKind};
+use iced::widget::{
+    button, column, container, image, radio, row, rule, scrollable, slider, text, text_input, Space,
+};
+use iced::{window, Color, Element, Length, Subscription, Task, Theme};
+
+use crate::catalog::{self, Catalog, Flag, FolderSession, Rating, TrashedPhoto};
+use crate::contact_sheet::{self, SheetPhoto};
+use crate::decoder::{self, DecodeKind, Priority};
+use crate::duplicates::{self, DuplicateGroup, FolderOverlap, GroupOrder, ImageHashes, MatchKind};
+use crate::edit::{self, CropAspect, PhotoEdit};
+use crate::grid_layout::{Geometry, GridLayout};
+use crate::export;
+use crate::file_ops::{self, OnConflict, Outcome, Transfer};
+use crate::file_queue::{FileQueue, JobId};
+use crate::filter::{self, FilterChoices, GridFilter, Orientation};
+use crate::focus::FocusArea;
+use crate::i18n::{self, tr, trf, trn, Language};
+use crate::import::{self, ImportItem};
 use crate::key_listener::KeyListener;
-use crate::metadata::{self, PhotoMetadata};
+use crate::metadata::{self, PhotoInfo, PhotoMetadata};
 use crate::server;
+use crate::settings::Settings;
+use crate::share;
+use crate::slideshow;
+use crate::status::{self, JobProgress};
 use crate::thumbnail;
-use crate::viewer::ViewerState;
+use crate::trash;
+use crate::viewer::{self as viewer_state, Background, FitMode, ViewerState, ZoomPreset};
+use crate::watcher::FolderWatcher;
 
 const THUMBNAIL_BATCH_SIZE: usize = 32;
+/// Long edge of the grid thumbnails generated and cached on disk.
+pub const GRID_THUMBNAIL_PIXELS: u32 = 400;
 const PREVIEW_BATCH_SIZE: usize = 16;
 const MAX_UPGRADE_BATCHES_IN_FLIGHT: usize = 3;
+/// Times a thumbnail that failed for what looks like a passing reason is
+/// tried again, waiting this much longer each time.
+const THUMBNAIL_RETRIES: u32 = 3;
+const THUMBNAIL_RETRY_DELAY: Duration = Duration::from_secs(10);
 const DUP_HASH_BATCH_SIZE: usize = 32;
-const VISUAL_DUP_THRESHOLD: u32 = 10;
+/// Photos hashed per batch while idle, one after another on a single
+/// thread, so a key press never waits long for the disk.
+const IDLE_HASH_BATCH_SIZE: usize = 4;
+/// Photos checked against the catalog per idle tick for missing hashes.
+const IDLE_HASH_LOOKUPS: usize = 512;
+/// Photos listed per set of folders in the library-wide duplicates report.
+const LIBRARY_DUPS_SHOWN: usize = 50;
+/// Photos copied, moved or trashed per batch on the file queue.
+const FILE_BATCH_SIZE: usize = 16;
+const INFO_BATCH_SIZE: usize = 64;
+const LIBRARY_HASH_BATCH_SIZE: usize = 16;
+const EXPORT_BATCH_SIZE: usize = 8;
+const CONTACT_SHEET_BATCH_SIZE: usize = 8;
+/// Files copied per import batch; each can be tens of megabytes.
+const IMPORT_BATCH_SIZE: usize = 4;
+pub const VISUAL_DUP_THRESHOLD: u32 = 10;
 const THUMB_FADE_MS: f32 = 300.0;
+/// Width of the `/display/` image sent to a Chromecast.
+const CAST_DISPLAY_WIDTH: u32 = 1920;
+/// How long a new file must go without further events before it's picked up,
+/// so half-copied images aren't thumbnailed.
+const WATCHER_SETTLE: Duration = Duration::from_secs(2);
+/// Choices for stopping the share automatically, in minutes.
+const SHARE_TIME_LIMITS: [(Option<u64>, &str); 4] = [
+    (None, "Never"),
+    (Some(30), "After 30 minutes"),
+    (Some(120), "After 2 hours"),
+    (Some(480), "After 8 hours"),
+];
+/// Choices for stopping the share once nobody has fetched anything for a
+/// while, in minutes.
+const SHARE_IDLE_LIMITS: [(Option<u64>, &str); 4] = [
+    (None, "Never"),
+    (Some(10), "After 10 idle minutes"),
+    (Some(30), "After 30 idle minutes"),
+    (Some(60), "After 1 idle hour"),
+];
+
+// Viewer preloading and decoded image memory
+/// Neighbours decoded on each side before there are decode times to go by.
+const DEFAULT_PRELOAD_RADIUS: usize = 3;
+/// Weight of each new decode time in the running average.
+const DECODE_AVERAGE_WEIGHT: f32 = 0.2;
+/// Choices for how far the viewer decodes ahead; `None` goes by decode times.
+const PRELOAD_RADII: [(Option<usize>, &str); 5] = [
+    (None, "Automatic"),
+    (Some(1), "1 image each way"),
+    (Some(3), "3 images each way"),
+    (Some(6), "6 images each way"),
+    (Some(12), "12 images each way"),
+];
+const DEFAULT_VIEWER_CACHE_MB: u64 = 1024;
+/// Choices for the viewer's memory; `None` is `DEFAULT_VIEWER_CACHE_MB`.
+const VIEWER_CACHE_SIZES: [(Option<u64>, &str); 5] = [
+    (Some(256), "256 MB"),
+    (Some(512), "512 MB"),
+    (None, "1 GB"),
+    (Some(4096), "4 GB"),
+    (Some(16384), "16 GB"),
+];
+
+// Grid thumbnail memory
+const DEFAULT_GRID_CACHE_MB: u64 = 512;
+/// Choices for the grid's memory; `None` is `DEFAULT_GRID_CACHE_MB`.
+const GRID_CACHE_SIZES: [(Option<u64>, &str); 5] = [
+    (Some(128), "128 MB"),
+    (Some(256), "256 MB"),
+    (None, "512 MB"),
+    (Some(1024), "1 GB"),
+    (Some(4096), "4 GB"),
+];
+
+const DEFAULT_SHARPEN_PERCENT: u32 = 50;
+const SHARPEN_AMOUNTS: [(Option<u32>, &str); 3] = [
+    (Some(25), "Light"),
+    (None, "Medium"),
+    (Some(100), "Strong"),
+];
+/// Orientations a photo can be shown with in place of its EXIF tag, as EXIF
+/// values; `None` follows the tag.
+const ORIENTATION_OVERRIDES: [(Option<u32>, &str); 5] = [
+    (None, "As tagged"),
+    (Some(1), "Ignore tag"),
+    (Some(6), "90° clockwise"),
+    (Some(3), "180°"),
+    (Some(8), "90° counterclockwise"),
+];
+/// Long edge of the blurry stand-in kept for thumbnails evicted from memory.
+const THUMB_STAND_IN_SIZE: u32 = 16;
+/// Choices for the viewer's zoom ceiling.
+const MAX_ZOOM_LEVELS: [(Option<u32>, &str); 3] = [
+    (Some(4), "4×"),
+    (None, "8×"),
+    (Some(16), "16×"),
+];
+/// How strongly the screensaver favours recent photos: the newest comes up
+/// about e^n times as often as the oldest.
+const SCREENSAVER_RECENCY: [(Option<u32>, &str); 3] = [
+    (None, "Every photo equally"),
+    (Some(2), "Somewhat favor recent photos"),
+    (Some(4), "Strongly favor recent photos"),
+];
+const SCAN_DEPTHS: [(Option<u32>, &str); 4] = [
+    (None, "All subfolders"),
+    (Some(0), "This folder only"),
+    (Some(1), "One level of subfolders"),
+    (Some(2), "Two levels of subfolders"),
+];
+/// Choices for how many requests the share serves at once; `None` is the
+/// server's default.
+const SHARE_HTTP_WORKERS: [(Option<usize>, &str); 4] = [
+    (None, "4 at a time"),
+    (Some(8), "8 at a time"),
+    (Some(16), "16 at a time"),
+    (Some(32), "32 at a time"),
+];
+/// Choices for the share's thumbnail memory; `None` is the server's default.
+const SHARE_THUMB_CACHE_SIZES: [(Option<u64>, &str); 4] = [
+    (Some(16), "16 MB"),
+    (None, "64 MB"),
+    (Some(256), "256 MB"),
+    (Some(1024), "1 GB"),
+];
+/// Columns and rows of photos on each contact sheet page.
+const DEFAULT_CONTACT_SHEET_GRID: (usize, usize) = (4, 5);
+const CONTACT_SHEET_GRIDS: [(Option<(usize, usize)>, &str); 4] = [
+    (Some((3, 4)), "3 × 4 (12 per page)"),
+    (None, "4 × 5 (20 per page)"),
+    (Some((5, 7)), "5 × 7 (35 per page)"),
+    (Some((6, 8)), "6 × 8 (48 per page)"),
+];
+
+/// Every open window, each with its own folder, grid and viewer.
+struct App {
+    windows: BTreeMap<window::Id, Looky>,
+    /// The settings every window works from. A change made in one window is
+    /// copied here and into the others, so a save from any of them keeps it.
+    settings: Settings,
+}
+
+#[derive(Debug, Clone)]
+enum AppMessage {
+    Window(window::Id, Message),
+    NewWindow,
+    WindowResized(window::Id, f32, f32),
+    WindowRescaled(window::Id, f32),
+    WindowClosed(window::Id),
+}
 
-fn boot() -> (Looky, Task<Message>) {
-    let mut state = Looky::default();
+fn boot() -> (App, Task<AppMessage>) {
+    let mut app = App {
+        windows: BTreeMap::new(),
+        settings: Settings::load(),
+    };
+    let task = open_window(&mut app, load_last_folder());
+    (app, task)
+}
 
-    // Open the catalog database
-    if let Some(dir) = config_dir() {
-        let db_path = dir.join("catalog.db");
-        match Catalog::open(&db_path) {
-            Ok(cat) => state.catalog = Some(cat),
-            Err(e) => log::warn!("Failed to open catalog DB: {}", e),
+/// Open a new window, scanning `folder` in it if there is one.
+fn open_window(app: &mut App, folder: Option<PathBuf>) -> Task<AppMessage> {
+    let (id, opened) = window::open(window::Settings {
+        position: window::Position::Centered,
+        ..window::Settings::default()
+    });
+    let (state, task) = new_window(id, folder, &app.settings);
+    app.windows.insert(id, state);
+    Task::batch([
+        opened.discard(),
+        task.map(move |message| AppMessage::Window(id, message)),
+    ])
+}
+
+/// Open the catalog database and apply the orientation overrides and edits
+/// kept in it.
+pub fn open_catalog() -> Option<Catalog> {
+    let db_path = config_dir()?.join("catalog.db");
+    match Catalog::open(&db_path) {
+        Ok(cat) => {
+            for (path, orientation) in cat.orientation_overrides() {
+                thumbnail::set_orientation_override(&path, Some(orientation));
+            }
+            for (path, photo_edit) in cat.edits() {
+                edit::set_edit(&path, Some(photo_edit));
+            }
+            Some(cat)
+        }
+        Err(e) => {
+            log::warn!("Failed to open catalog DB: {}", e);
+            None
         }
     }
+}
+
+fn new_window(id: window::Id, folder: Option<PathBuf>, settings: &Settings) -> (Looky, Task<Message>) {
+    let mut state = Looky::new(id);
+    state.settings = settings.clone();
+    state.share_name_input = state.settings.share_name.clone().unwrap_or_default();
+    state.share_port_input = state
+        .settings
+        .share_port
+        .map(|p| p.to_string())
+        .unwrap_or_default();
+    state.viewer.fit_mode = state.settings.viewer_fit_mode.unwrap_or_default();
+    state.viewer.max_zoom = max_zoom(&state.settings);
+    state.zoom_presets_input = ZoomPreset::format_list(zoom_presets(&state.settings));
+    i18n::set_language(state.settings.language.unwrap_or_else(Language::system));
+    state.time_zone_input = state.settings.time_zone.clone().unwrap_or_default();
+    metadata::set_time_zone(state.settings.time_zone.as_deref());
+    set_high_contrast(state.settings.high_contrast);
+    apply_sharpening(&state.settings);
+
+    if let Some(cat) = open_catalog() {
+        state.ratings = cat.ratings().into_iter().collect();
+        state.catalog = Some(cat);
+    }
 
-    if let Some(folder) = load_last_folder() {
+    let scale_factor = window::scale_factor(id).map(Message::ScaleFactorChanged);
+    if let Some(folder) = folder {
         state.folder = Some(folder.clone());
         state.loading = true;
-        let task = Task::perform(scan_folder(folder), Message::ImagesFound);
-        return (state, task);
+        state.scan_job = Some(JobProgress::start(0));
+        let depth = scan_depth(&state);
+        let task = Task::perform(scan_folder(folder, depth), Message::ImagesFound);
+        return (state, Task::batch([task, scale_factor]));
     }
-    (state, Task::none())
+    (state, scale_factor)
 }
 
 pub fn run() -> iced::Result {
-    iced::application(boot, update, view)
-        .title("Looky")
+    iced::daemon(boot, update_app, view_app)
+        .title(title)
         .theme(theme)
-        .subscription(subscription)
-        .centered()
+        .subscription(subscription_app)
         .run()
 }
 
+fn update_app(app: &mut App, message: AppMessage) -> Task<AppMessage> {
+    let task = update_window(app, message);
+    share_settings(app);
+    task
+}
+
+/// Copy a window's changed settings into the app and every other window.
+fn share_settings(app: &mut App) {
+    let Some(changed) = app.windows.values().find(|state| state.settings != app.settings) else {
+        return;
+    };
+    app.settings = changed.settings.clone();
+    for state in app.windows.values_mut() {
+        state.settings.clone_from(&app.settings);
+    }
+}
+
+fn update_window(app: &mut App, message: AppMessage) -> Task<AppMessage> {
+    match message {
+        AppMessage::Window(id, message) => {
+            let new_window = matches!(message, Message::NewWindow);
+            let Some(state) = app.windows.get_mut(&id) else {
+                return Task::none();
+            };
+            let task = update(state, message).map(move |message| AppMessage::Window(id, message));
+            if new_window {
+                return Task::batch([task, open_window(app, None)]);
+            }
+            task
+        }
+        AppMessage::NewWindow => open_window(app, None),
+        AppMessage::WindowResized(id, width, height) => app
+            .windows
+            .get_mut(&id)
+            .map(|state| update(state, Message::WindowResized(width, height)))
+            .unwrap_or_else(Task::none)
+            .map(move |message| AppMessage::Window(id, message)),
+        AppMessage::WindowRescaled(id, scale_factor) => app
+            .windows
+            .get_mut(&id)
+            .map(|state| update(state, Message::ScaleFactorChanged(scale_factor)))
+            .unwrap_or_else(Task::none)
+            .map(move |message| AppMessage::Window(id, message)),
+        AppMessage::WindowClosed(id) => {
+            if let Some(mut state) = app.windows.remove(&id) {
+                save_session(&mut state);
+            }
+            if app.windows.is_empty() {
+                return iced::exit();
+            }
+            Task::none()
+        }
+    }
+}
+
+fn view_app(app: &App, id: window::Id) -> Element<'_, AppMessage> {
+    match app.windows.get(&id) {
+        Some(state) => view(state).map(move |message| AppMessage::Window(id, message)),
+        None => Space::new().into(),
+    }
+}
+
+fn title(app: &App, id: window::Id) -> String {
+    let folder = app
+        .windows
+        .get(&id)
+        .and_then(|state| state.folder.as_deref())
+        .and_then(|folder| folder.file_name());
+    match folder {
+        Some(name) => format!("{} — Looky", name.to_string_lossy()),
+        None => "Looky".to_string(),
+    }
+}
+
+fn theme(_app: &App, _id: window::Id) -> Option<Theme> {
+    if dark_room() {
+        return Some(Theme::custom(
+            "Dark Room",
+            iced::theme::Palette {
+                background: Color::BLACK,
+                text: DARK_ROOM_TEXT,
+                primary: Color::from_rgb(0.35, 0.3, 0.2),
+                success: Color::from_rgb(0.15, 0.3, 0.15),
+                warning: Color::from_rgb(0.35, 0.25, 0.1),
+                danger: Color::from_rgb(0.4, 0.15, 0.15),
+            },
+        ));
+    }
+    if !high_contrast() {
+        return Some(Theme::Dark);
+    }
+    Some(Theme::custom(
+        "High Contrast",
+        iced::theme::Palette {
+            background: Color::BLACK,
+            text: Color::WHITE,
+            primary: Color::from_rgb(1.0, 0.85, 0.0),
+            success: Color::from_rgb(0.3, 1.0, 0.3),
+            warning: Color::from_rgb(1.0, 0.6, 0.0),
+            danger: Color::from_rgb(1.0, 0.35, 0.35),
+        },
+    ))
+}
+
+/// Shared by every window, like the language.
+static HIGH_CONTRAST: AtomicBool = AtomicBool::new(false);
+
+fn set_high_contrast(on: bool) {
+    HIGH_CONTRAST.store(on, Ordering::Relaxed);
+}
+
+fn high_contrast() -> bool {
+    HIGH_CONTRAST.load(Ordering::Relaxed)
+}
+
+/// Chrome dimmed to near-black so it doesn't light up a dark room and skew
+/// how photos look. Shared by every window; photos keep full brightness.
+static DARK_ROOM: AtomicBool = AtomicBool::new(false);
+const DARK_ROOM_TEXT: Color = Color::from_rgb(0.25, 0.25, 0.25);
+
+fn dark_room() -> bool {
+    DARK_ROOM.load(Ordering::Relaxed)
+}
+
+/// Text on the app's own dark panels, dimmed in the dark room.
+fn chrome_text() -> Color {
+    if dark_room() {
+        DARK_ROOM_TEXT
+    } else {
+        Color::WHITE
+    }
+}
+
+/// A `gray` panel backdrop, taken most of the way to black in the dark room.
+fn chrome_background(gray: f32, alpha: f32) -> iced::Background {
+    let gray = if dark_room() { gray * 0.25 } else { gray };
+    iced::Background::Color(Color::from_rgba(gray, gray, gray, alpha))
+}
+
+/// Outline marking what the keyboard will act on.
+fn focus_border(theme: &Theme) -> iced::Border {
+    iced::Border {
+        color: theme.palette().primary,
+        width: 2.0,
+        radius: 4.0.into(),
+    }
+}
+
+fn subscription_app(app: &App) -> Subscription<AppMessage> {
+    // Window resize still goes through subscription (not latency-sensitive).
+    // Keyboard events are handled by KeyListener widget for instant response,
+    // except the one shortcut that belongs to no particular window.
+    let events = iced::event::listen_with(|event, _status, id| match event {
+        iced::Event::Window(window::Event::Resized(size)) => {
+            Some(AppMessage::WindowResized(id, size.width, size.height))
+        }
+        iced::Event::Window(window::Event::Rescaled(scale_factor)) => {
+            Some(AppMessage::WindowRescaled(id, scale_factor))
+        }
+        iced::Event::Window(window::Event::Closed) => Some(AppMessage::WindowClosed(id)),
+        iced::Event::Keyboard(iced::keyboard::Event::KeyPressed {
+            key: iced::keyboard::Key::Character(c),
+            modifiers,
+            repeat: false,
+            ..
+        }) if modifiers.command() && c.as_str() == "n" => Some(AppMessage::NewWindow),
+        iced::Event::Keyboard(iced::keyboard::Event::ModifiersChanged(modifiers)) => {
+            Some(AppMessage::Window(id, Message::ModifiersChanged(modifiers)))
+        }
+        iced::Event::Keyboard(iced::keyboard::Event::KeyPressed { .. })
+        | iced::Event::Mouse(iced::mouse::Event::ButtonPressed(_) | iced::mouse::Event::WheelScrolled { .. }) => {
+            Some(AppMessage::Window(id, Message::UserActive))
+        }
+        _ => None,
+    });
+
+    let mut subs = vec![events];
+    subs.extend(app.windows.iter().map(|(&id, state)| {
+        subscription(state)
+            .with(id)
+            .map(|(id, message)| AppMessage::Window(id, message))
+    }));
+    Subscription::batch(subs)
+}
+
+/// A connected Chromecast and its worker-reported state, polled once a second.
+struct CastConnection {
+    session: server::cast::CastSession,
+    status: server::cast::CastStatus,
+}
+
+/// What a job on the file queue does with each photo.
+#[derive(Debug, Clone, PartialEq)]
+enum FileJobKind {
+    /// Copy or move into this folder.
+    Transfer(Transfer, PathBuf),
+    Trash,
+}
+
+/// A batch action on the selected photos, run on the file queue.
+struct FileJob {
+    id: JobId,
+    kind: FileJobKind,
+    /// Every photo in the job, so a second job started meanwhile leaves
+    /// them alone.
+    indices: HashSet<usize>,
+    pending: Vec<(usize, PathBuf)>,
+    total: usize,
+    /// Photos whose name is taken in the destination, waiting for a choice.
+    conflicts: Vec<(usize, PathBuf)>,
+    /// Give every remaining conflict the next choice made.
+    same_for_all: bool,
+    /// Batches being worked on.
+    in_flight: usize,
+    /// Stop handing out batches until resumed.
+    paused: bool,
+    /// Where each photo went.
+    done: Vec<(usize, PathBuf)>,
+    skipped: usize,
+    failed: Vec<(usize, String)>,
+    /// Whether the photos came from the selection, so failures go back to it.
+    from_selection: bool,
+    progress: JobProgress,
+}
+
+impl FileJob {
+    fn finished_count(&self) -> usize {
+        self.done.len() + self.skipped + self.failed.len()
+    }
+
+    fn awaiting_choice(&self) -> bool {
+        self.pending.is_empty() && !self.conflicts.is_empty()
+    }
+}
+
+/// A crop and straighten being worked on in the viewer.
+struct EditSession {
+    index: usize,
+    /// The photo's edit before this session, put back on cancel.
+    saved: Option<PhotoEdit>,
+    edit: PhotoEdit,
+    aspect: CropAspect,
+    /// How saving an edited copy went.
+    status: Option<String>,
+}
+
 struct Looky {
+    window: window::Id,
     folder: Option<PathBuf>,
     image_paths: Vec<PathBuf>,
     thumbnails: Vec<(PathBuf, image::Handle, Instant)>,
+    // Width/height of each thumbnail as loaded, kept while it's a stand-in
+    thumb_aspects: Vec<f32>,
     pending_thumbnails: Vec<PathBuf>,
+    // Decoded bytes held by `thumbnails`, and the ones shrunk to a stand-in
+    // to stay within the memory budget
+    thumbnail_bytes: u64,
+    evicted_thumbnails: HashSet<usize>,
     // Two-pass loading: path → index in thumbnails vec for O(1) upgrade
     thumbnail_index: HashMap<PathBuf, usize>,
     pending_upgrades: Vec<PathBuf>,
     upgrade_batches_in_flight: usize,
+    // Files that failed to decode, with the decoder's error
+    unreadable: HashMap<PathBuf, String>,
+    /// Unreadable files to try again: the retries so far, and when the next
+    /// is due, or `None` while it's queued.
+    thumb_retries: HashMap<PathBuf, (u32, Option<Instant>)>,
+    unreadable_view_active: bool,
+    // Background jobs shown in the status bar
+    scan_job: Option<JobProgress>,
+    thumb_job: Option<JobProgress>,
+    thumbs_done: usize,
+    thumbs_cancelled: bool,
+    dup_job: Option<JobProgress>,
+    // Camera and lens info for filtering, from the catalog or read in the
+    // background for photos it doesn't have yet
+    photo_info: HashMap<PathBuf, PhotoInfo>,
+    info_pending: Vec<PathBuf>,
+    info_total: usize,
+    info_job: Option<JobProgress>,
     viewer: ViewerState,
     loading: bool,
     cached_metadata: Option<(usize, PhotoMetadata)>,
+    /// Luminance histogram of the image shown, by its handle.
+    histogram: Option<(iced::advanced::image::Id, [u32; HISTOGRAM_BINS])>,
     catalog: Option<Catalog>,
     // Duplicate detection state
     dup_hashes: Vec<(usize, ImageHashes)>,
     dup_pending: Vec<(usize, PathBuf)>,
+    /// Files sharing a size and head/tail sample with another, which get a
+    /// full content hash.
+    dup_candidates: HashSet<usize>,
+    /// A paused scan keeps `dup_pending` but issues no more batches.
+    dup_paused: bool,
+    /// A hash batch (or the size and sample pass) is running.
+    dup_in_flight: bool,
     dup_scanning: bool,
+    // Hashing while idle, so a later duplicate scan finds it all cached
+    /// Position in `image_paths` up to which photos have been hashed or
+    /// found in the catalog.
+    idle_hash_cursor: usize,
+    idle_hash_in_flight: bool,
+    /// Last key press, click or scroll in this window.
+    last_input: Instant,
     dup_total: usize,
     dup_groups: Vec<DuplicateGroup>,
     dup_badge_set: HashSet<usize>,
@@ -74,49 +590,232 @@ struct Looky {
     dup_compare: Option<usize>,
     dup_summaries: HashMap<usize, metadata::FileSummary>,
     grid_scroll_y: f32,
+    // Session restore: a grid offset waiting for enough thumbnails to reach
+    // it, and what was last written to the catalog
+    pending_grid_scroll: Option<f32>,
+    saved_session: Option<FolderSession>,
+    /// The photo last shown in the viewer in this folder.
+    last_viewed: Option<PathBuf>,
+    /// Image to offer going back to, from the last time the folder was open.
+    resume_offer: Option<usize>,
     dup_scroll_y: f32,
     grid_columns: usize,
     viewport_width: f32,
     viewport_height: f32,
     selected_thumb: Option<usize>,
+    // What the grid shows: the filter, the indices into `image_paths` that
+    // pass it (ascending, so grid order is folder order), and the
+    // subfolders, cameras and lenses there are to pick from
+    grid_filter: GridFilter,
+    grid_shown: Vec<usize>,
+    grid_subfolders: Vec<PathBuf>,
+    filter_choices: FilterChoices,
+    /// Text of the aspect ratio limits, kept as typed.
+    min_aspect_input: String,
+    max_aspect_input: String,
+    search_input: String,
     viewer_cache: HashMap<usize, image::Handle>,
     viewer_dimensions: HashMap<usize, (u32, u32)>,
-    viewer_preload_handles: Vec<(usize, iced::task::Handle)>,
+    /// Cached viewer images that are still the screen-sized preview.
+    viewer_previews: HashSet<usize>,
+    /// Cached viewer images decoded fitted to the screen for the screensaver,
+    /// which only it takes as finished.
+    viewer_fitted: HashSet<usize>,
+    /// Physical pixels per logical pixel of the window.
+    scale_factor: f32,
+    decoder: decoder::DecodeScheduler,
+    /// Previous viewer image and which way the user is moving, so the
+    /// decoder works ahead in the direction of travel.
+    viewer_last_index: Option<usize>,
+    viewer_forward: bool,
+    /// Running average of full-size decode times this session, which sets
+    /// the preload radius when it's automatic.
+    decode_ms_average: Option<f32>,
+    /// Rates for the debug overlay, while it's shown.
+    debug: Option<DebugStats>,
     fullscreen: bool,
     // Screensaver mode
     screensaver_active: bool,
     screensaver_order: Vec<usize>,
     screensaver_position: usize,
+    /// How recent each photo in the screensaver is, once worked out, for
+    /// reshuffling with the same bias.
+    screensaver_ranks: Vec<(usize, f32)>,
+    /// Holding on the current photo (Space).
+    screensaver_paused: bool,
     was_fullscreen: bool,
+    // Culling: stepping through the grid's photos full-screen, flagging each
+    culling: bool,
+    /// Past the last photo, offering to trash the rejects.
+    cull_finished: bool,
+    cull_was_fullscreen: bool,
     // Sharing server
     server_handle: Option<server::ServerHandle>,
     server_url: Option<String>,
     qr_handle: Option<image::Handle>,
+    /// The share's QR code shown large over everything, for scanning from
+    /// across the room.
+    qr_overlay_open: bool,
+    /// The viewed photo is being cropped and straightened.
+    editing: Option<EditSession>,
+    /// Stars and favourites, by file.
+    ratings: HashMap<PathBuf, Rating>,
+    /// Wheel scrolled towards the next step between photos.
+    wheel_travel: f32,
+    /// The share URL was copied since the menu last opened.
+    share_url_copied: bool,
+    allow_uploads: bool,
+    share_error: Option<String>,
+    share_started: Option<Instant>,
     // Chromecast
-    cast_session: Option<server::cast::CastSession>,
-    cast_target_name: Option<String>,
+    cast_sessions: Vec<CastConnection>,
     cast_scanning: bool,
     cast_devices: Vec<server::cast::CastTarget>,
     cast_error: Option<String>,
+    /// Devices sessions are being opened to, while `CastSession::connect` runs.
+    cast_connecting: Vec<server::cast::CastTarget>,
+    cast_picker_open: bool,
+    /// Shuffled slideshow running on the TV alone, leaving the window free.
+    cast_slideshow_active: bool,
+    cast_slideshow_order: Vec<usize>,
+    cast_slideshow_position: usize,
     menu_open: bool,
+    /// Entry in the open menu or cast picker picked with the keyboard.
+    menu_focus: Option<usize>,
+    /// Group card in the duplicates list picked with the keyboard.
+    dup_selected: Option<usize>,
+    // Folder watching
+    watcher: Option<FolderWatcher>,
+    watcher_pending: HashMap<PathBuf, Instant>,
+    // Settings
+    settings: Settings,
+    settings_view_active: bool,
+    // Trash
+    trash_view_active: bool,
+    /// What's in Looky's trash, read from the catalog when the view opens.
+    trash_items: Vec<TrashedPhoto>,
+    /// Photos of this folder moved to the trash, hidden from the grid.
+    trashed: HashSet<PathBuf>,
+    /// Photos moved out of this folder by a batch action, hidden likewise.
+    moved_away: HashSet<PathBuf>,
+    /// Photos picked with Ctrl- or Shift-click for batch actions.
+    selection: BTreeSet<usize>,
+    /// How the last batch action went.
+    batch_status: Option<String>,
+    /// Copies, moves and trashing of selections, in the order started.
+    file_jobs: Vec<FileJob>,
+    files: FileQueue,
+    /// Modifier keys held, to tell a plain click on a thumbnail from one
+    /// that picks it.
+    modifiers: iced::keyboard::Modifiers,
+    trash_error: Option<String>,
+    /// Why the share sheet couldn't open, shown in the menus.
+    share_sheet_error: Option<String>,
+    // Checking the folder against the library
+    /// Images whose content is already in the library, i.e. catalogued
+    /// outside this folder.
+    imported: HashSet<usize>,
+    /// This folder has been, or is being, checked against the library.
+    library_checked: bool,
+    library_pending: Vec<(usize, PathBuf)>,
+    library_total: usize,
+    library_job: Option<JobProgress>,
+    // Gallery export
+    export_dest: Option<PathBuf>,
+    /// Photos still to write, numbered in grid order.
+    export_pending: Vec<(usize, PathBuf)>,
+    export_total: usize,
+    /// Photos written so far, by number, with their original names.
+    export_written: Vec<(usize, String)>,
+    export_job: Option<JobProgress>,
+    /// The export's id on the file queue.
+    export_id: JobId,
+    export_paused: bool,
+    /// A batch of the export is being written.
+    export_in_flight: bool,
+    /// Where the last gallery went, or why it didn't.
+    export_status: Option<String>,
+    // Contact sheet
+    contact_sheet_dest: Option<PathBuf>,
+    /// Photos still to shrink, numbered in grid order.
+    contact_sheet_pending: Vec<(usize, PathBuf)>,
+    contact_sheet_total: usize,
+    contact_sheet_photos: Vec<(usize, SheetPhoto)>,
+    contact_sheet_job: Option<JobProgress>,
+    /// Where the last contact sheet went, or why it didn't.
+    contact_sheet_status: Option<String>,
+    // Import
+    import_view_active: bool,
+    // Identical files across every folder in the catalog
+    library_dups_view_active: bool,
+    library_dups: Vec<FolderOverlap>,
+    /// Cards found when the import view opened.
+    import_volumes: Vec<PathBuf>,
+    import_source: Option<PathBuf>,
+    /// What's on the card, once scanned and hashed.
+    import_items: Vec<ImportItem>,
+    import_scanning: bool,
+    /// Files still to copy in the running import.
+    import_pending: Vec<ImportItem>,
+    import_total: usize,
+    import_done: usize,
+    import_failed: usize,
+    import_job: Option<JobProgress>,
+    /// The running import was cancelled; the batch under way still finishes
+    /// and is recorded.
+    import_cancelled: bool,
+    /// How the last import went, shown in the import view.
+    import_status: Option<String>,
+    share_interfaces: Vec<server::NetInterface>,
+    share_name_input: String,
+    /// Time zone field in the settings, as typed.
+    time_zone_input: String,
+    share_port_input: String,
+    /// Text of the zoom presets field, kept as typed.
+    zoom_presets_input: String,
+    peer_scanning: bool,
+    peers: Vec<server::mdns::Peer>,
 }
 
-impl Default for Looky {
-    fn default() -> Self {
+impl Looky {
+    fn new(window: window::Id) -> Self {
         Self {
+            window,
             folder: None,
             image_paths: Vec::new(),
             thumbnails: Vec::new(),
+            thumb_aspects: Vec::new(),
             pending_thumbnails: Vec::new(),
+            thumbnail_bytes: 0,
+            evicted_thumbnails: HashSet::new(),
             thumbnail_index: HashMap::new(),
             pending_upgrades: Vec::new(),
             upgrade_batches_in_flight: 0,
+            unreadable: HashMap::new(),
+            thumb_retries: HashMap::new(),
+            unreadable_view_active: false,
+            scan_job: None,
+            thumb_job: None,
+            thumbs_done: 0,
+            thumbs_cancelled: false,
+            dup_job: None,
+            photo_info: HashMap::new(),
+            info_pending: Vec::new(),
+            info_total: 0,
+            info_job: None,
             viewer: ViewerState::default(),
             loading: false,
             cached_metadata: None,
+            histogram: None,
             catalog: None,
             dup_hashes: Vec::new(),
             dup_pending: Vec::new(),
+            dup_candidates: HashSet::new(),
+            dup_paused: false,
+            dup_in_flight: false,
+            idle_hash_cursor: 0,
+            idle_hash_in_flight: false,
+            last_input: Instant::now(),
             dup_scanning: false,
             dup_total: 0,
             dup_groups: Vec::new(),
@@ -125,28 +824,121 @@ impl Default for Looky {
             dup_compare: None,
             dup_summaries: HashMap::new(),
             grid_scroll_y: 0.0,
+            pending_grid_scroll: None,
+            saved_session: None,
+            last_viewed: None,
+            resume_offer: None,
             dup_scroll_y: 0.0,
             grid_columns: 4,
             viewport_width: 800.0,
             viewport_height: 600.0,
             selected_thumb: None,
+            grid_filter: GridFilter::default(),
+            grid_shown: Vec::new(),
+            grid_subfolders: Vec::new(),
+            filter_choices: FilterChoices::default(),
+            min_aspect_input: String::new(),
+            max_aspect_input: String::new(),
+            search_input: String::new(),
             viewer_cache: HashMap::new(),
             viewer_dimensions: HashMap::new(),
-            viewer_preload_handles: Vec::new(),
+            viewer_previews: HashSet::new(),
+            viewer_fitted: HashSet::new(),
+            scale_factor: 1.0,
+            decoder: decoder::DecodeScheduler::new(),
+            viewer_last_index: None,
+            viewer_forward: true,
+            decode_ms_average: None,
+            debug: None,
             fullscreen: false,
             screensaver_active: false,
             screensaver_order: Vec::new(),
             screensaver_position: 0,
+            screensaver_ranks: Vec::new(),
+            screensaver_paused: false,
             was_fullscreen: false,
+            culling: false,
+            cull_finished: false,
+            cull_was_fullscreen: false,
             server_handle: None,
             server_url: None,
             qr_handle: None,
-            cast_session: None,
-            cast_target_name: None,
+            qr_overlay_open: false,
+            editing: None,
+            ratings: HashMap::new(),
+            wheel_travel: 0.0,
+            share_url_copied: false,
+            allow_uploads: false,
+            share_error: None,
+            share_started: None,
+            cast_sessions: Vec::new(),
             cast_scanning: false,
             cast_devices: Vec::new(),
             cast_error: None,
+            cast_connecting: Vec::new(),
+            cast_picker_open: false,
+            cast_slideshow_active: false,
+            cast_slideshow_order: Vec::new(),
+            cast_slideshow_position: 0,
             menu_open: false,
+            menu_focus: None,
+            dup_selected: None,
+            watcher: None,
+            watcher_pending: HashMap::new(),
+            settings: Settings::default(),
+            settings_view_active: false,
+            trash_view_active: false,
+            trash_items: Vec::new(),
+            trashed: HashSet::new(),
+            moved_away: HashSet::new(),
+            selection: BTreeSet::new(),
+            batch_status: None,
+            file_jobs: Vec::new(),
+            files: FileQueue::new(),
+            modifiers: iced::keyboard::Modifiers::default(),
+            trash_error: None,
+            share_sheet_error: None,
+            imported: HashSet::new(),
+            library_checked: false,
+            library_pending: Vec::new(),
+            library_total: 0,
+            library_job: None,
+            export_dest: None,
+            export_pending: Vec::new(),
+            export_total: 0,
+            export_written: Vec::new(),
+            export_job: None,
+            export_id: 0,
+            export_paused: false,
+            export_in_flight: false,
+            export_status: None,
+            contact_sheet_dest: None,
+            contact_sheet_pending: Vec::new(),
+            contact_sheet_total: 0,
+            contact_sheet_photos: Vec::new(),
+            contact_sheet_job: None,
+            contact_sheet_status: None,
+            import_view_active: false,
+            library_dups_view_active: false,
+            library_dups: Vec::new(),
+            import_volumes: Vec::new(),
+            import_source: None,
+            import_items: Vec::new(),
+            import_scanning: false,
+            import_pending: Vec::new(),
+            import_total: 0,
+            import_done: 0,
+            import_failed: 0,
+            import_job: None,
+            import_cancelled: false,
+            import_status: None,
+            share_interfaces: Vec::new(),
+            share_name_input: String::new(),
+            time_zone_input: String::new(),
+            share_port_input: String::new(),
+            zoom_presets_input: String::new(),
+            peer_scanning: false,
+            peers: Vec::new(),
         }
     }
 }
@@ -154,30 +946,89 @@ impl Default for Looky {
 #[derive(Debug, Clone)]
 pub enum Message {
     OpenFolder,
+    NewWindow,
     FolderSelected(Option<PathBuf>),
     ImagesFound(Vec<PathBuf>),
-    ThumbnailBatchReady(Vec<(PathBuf, Vec<u8>, u32, u32)>),
+    RefreshFolder,
+    FolderRescanned(Vec<PathBuf>),
+    /// Scan the open folder one step deeper, or back to only its top level.
+    CycleFolderScanDepth,
+    SelectScanDepth(Option<u32>),
+    ExcludeSubfolder,
+    ToggleSubfolderInDupScans,
+    ThumbnailBatchReady(Vec<(PathBuf, thumbnail::ThumbnailResult)>),
     PreviewBatchReady(Vec<(PathBuf, Option<(Vec<u8>, u32, u32)>)>),
-    ThumbnailUpgradeReady(Vec<(PathBuf, Vec<u8>, u32, u32)>),
+    ThumbnailUpgradeReady(Vec<(PathBuf, thumbnail::ThumbnailResult)>),
+    PhotoInfoBatchReady(Vec<(PathBuf, PhotoInfo)>),
+    CancelIndexing,
     ViewImage(usize),
     NextImage,
     PrevImage,
     BackToGrid,
     ToggleInfo,
-    ViewerImageLoaded(usize, Vec<u8>, u32, u32),
+    ToggleFocusPoints,
+    ToggleCullingOverlay,
+    /// A full-size image decoded, and how long that took.
+    ViewerImageLoaded(usize, Vec<u8>, u32, u32, Duration),
+    ToggleDebugOverlay,
+    DebugTick,
+    ViewerImageFailed(usize, String),
+    ViewerPreviewLoaded(usize, Vec<u8>, u32, u32),
+    ViewerFittedLoaded(usize, Vec<u8>, u32, u32),
+    ScaleFactorChanged(f32),
     Tick,
     // Duplicate detection messages
     FindDuplicates,
     CancelDupScan,
+    CancelFolderScan,
+    CancelThumbnails,
+    ToggleSkipUnreadable,
+    SetDupOrder(GroupOrder),
+    SetDupReportCapturePairs(bool),
+    /// The files to hash, and which of them could be exact copies.
+    DupCandidatesReady(Vec<(usize, PathBuf)>, HashSet<usize>),
     DupHashBatchReady(Vec<(usize, Option<ImageHashes>)>),
+    /// A key press, click or scroll anywhere in the window.
+    UserActive,
+    IdleHashTick,
+    IdleHashBatchReady(Vec<(PathBuf, Option<ImageHashes>)>),
+    /// Stop issuing hash batches, keeping what's pending, or carry on.
+    ToggleDupScanPause,
     DupAnalysisReady(Vec<DuplicateGroup>, HashMap<usize, metadata::FileSummary>),
     CachedDupAnalysisReady(Vec<DuplicateGroup>, HashMap<usize, metadata::FileSummary>),
     ShowDuplicatesView,
     BackFromDuplicates,
     CompareDuplicates(usize),
+    /// Compare the duplicate group holding this image (its DUP badge).
+    CompareDuplicatesOf(usize),
+    /// Move every file of a duplicate group but the largest to the trash.
+    KeepLargestOfGroup(usize),
     BackFromCompare,
     // Zoom
     ToggleZoom,
+    /// Jump to the zoom preset at this index (number keys).
+    JumpToZoomPreset(usize),
+    CycleZoomPreset,
+    ToggleFlipMark,
+    FlipCompare,
+    /// Open the photo last viewed in this folder, offered when it's reopened.
+    ResumeViewing(usize),
+    DismissResume,
+    /// Jump to a photo picked at random from those the grid shows (R).
+    RandomPhoto,
+    /// Scroll wheel in the viewer while it steps between photos.
+    WheelNavigate(f32),
+    /// Give the viewed or selected photo one more star, wrapping to none (T).
+    CycleRating,
+    /// Mark or unmark the viewed or selected photo as a favourite (H).
+    ToggleFavorite,
+    /// Flag the viewed or selected photo, or clear a flag it already has
+    /// (P, X, U). While culling, moves on to the next photo.
+    FlagPhoto(Flag),
+    StartCulling,
+    StopCulling,
+    /// Move every photo the grid shows that's flagged as a reject to the trash.
+    TrashRejected,
     CenterZoomScroll,
     ZoomAdjust(f32, f32, f32),
     ZoomScrolled(f32, f32),
@@ -189,18 +1040,42 @@ pub enum Message {
     PinchZoom(f32, f32, f32),
     // Screensaver
     ToggleScreensaver,
+    ScreensaverRanksReady(Vec<(usize, f32)>),
+    ToggleScreensaverPause,
+    /// Step the screensaver forward or back by hand.
+    ScreensaverStep(isize),
+    SelectScreensaverRecency(Option<u32>),
     ScreensaverAdvance,
     // Sharing
     ToggleSharing,
+    ToggleUploads,
+    ShareTick,
     // Chromecast
     StartCastScan,
     CastDevicesFound(Vec<server::cast::CastTarget>),
     CastSelect(usize),
     CastConnected(server::cast::CastSession),
+    CastFailed(server::cast::CastTarget, String),
+    ToggleCastPicker,
+    ToggleCastSlideshow,
+    CastTick,
+    CastSlideshowAdvance,
     CastImage,
     StopCast,
+    StopCastTo(usize),
     // Navigation
     GridScrolled(f32),
+    /// Show only this folder's part of the scan, from the breadcrumb bar.
+    ShowSubfolder(PathBuf),
+    SelectCamera(Option<String>),
+    SelectLens(Option<String>),
+    SelectOrientation(Option<Orientation>),
+    SelectFlag(Option<Flag>),
+    MinAspectChanged(String),
+    MaxAspectChanged(String),
+    SearchChanged(String),
+    /// Ctrl+F: put the cursor in the search box.
+    FocusSearch,
     WindowResized(f32, f32),
     KeyEscape,
     KeyLeft,
@@ -209,32 +1084,215 @@ pub enum Message {
     KeyDown,
     KeyEnter,
     ToggleFullscreen,
+    /// Dim the chrome around photos to near-black (L).
+    ToggleDarkRoom,
+    /// Show the viewed photo with the next orientation override.
+    CycleOrientationOverride,
+    /// Crop and straighten the viewed photo (E).
+    StartEdit,
+    SelectCropAspect(CropAspect),
+    /// Crop size for a fixed aspect, as a fraction of the largest that fits.
+    EditCropSize(f32),
+    EditCropWidth(f32),
+    EditCropHeight(f32),
+    EditStraighten(f32),
+    ResetEdit,
+    SaveEdit,
+    CancelEdit,
+    SaveEditedCopy,
+    EditedCopyDestinationSelected(Option<PathBuf>),
+    EditedCopySaved(Result<PathBuf, String>),
     ToggleMenu,
+    /// Tab and Shift+Tab: step through whatever the current view lets you pick.
+    FocusNext,
+    FocusPrev,
+    // Folder watching
+    WatcherTick,
+    SessionTick,
+    // Settings
+    ShowSettings,
+    BackFromSettings,
+    /// Move the photo open in the viewer, or selected in the grid, to the trash.
+    TrashPhoto,
+    /// Hand the photo open in the viewer, or selected in the grid, to the
+    /// system share sheet.
+    SharePhoto,
+    ShareDone(Result<(), String>),
+    ModifiersChanged(iced::keyboard::Modifiers),
+    ClearSelection,
+    /// Copy or move the selected photos to a folder picked next.
+    TransferSelection(Transfer),
+    TransferDestinationSelected(Transfer, Option<PathBuf>),
+    FileBatchDone(JobId, Vec<(usize, Outcome)>),
+    /// Settle the first conflict, or all of them, this way.
+    ResolveConflict(OnConflict),
+    SetConflictSameForAll(bool),
+    ToggleFileJobPause(JobId),
+    CancelFileJob(JobId),
+    TrashSelection,
+    ShareSelection,
+    ShowTrash,
+    BackFromTrash,
+    ShowLibraryDuplicates,
+    BackFromLibraryDuplicates,
+    ShowUnreadable,
+    BackFromUnreadable,
+    /// Make thumbnails again for every unreadable file.
+    RetryUnreadable,
+    ThumbnailRetryTick,
+    /// Put a trashed photo back, by its path in the trash.
+    RestoreTrashed(PathBuf),
+    DeleteTrashedForever(PathBuf),
+    EmptyTrash,
+    /// Hash the folder's photos to find those already in the library.
+    CheckAgainstLibrary,
+    LibraryHashBatchReady(Vec<(usize, Option<[u8; 32]>)>),
+    CancelLibraryCheck,
+    SetNewOnly(bool),
+    /// Save the photos the grid shows as a static web gallery.
+    ExportGallery,
+    ExportDestinationSelected(Option<PathBuf>),
+    ExportBatchDone(Vec<(usize, String)>),
+    ToggleExportPause,
+    CancelExport,
+    /// Save the photos the grid shows as a printable PDF contact sheet.
+    ExportContactSheet,
+    ContactSheetDestinationSelected(Option<PathBuf>),
+    ContactSheetBatchDone(Vec<(usize, SheetPhoto)>),
+    ContactSheetSaved(Result<PathBuf, String>),
+    CancelContactSheet,
+    SelectContactSheetGrid(Option<(usize, usize)>),
+    ShowImport,
+    BackFromImport,
+    /// Look for camera cards again.
+    RefreshImportVolumes,
+    PickImportSource,
+    ImportSourceSelected(Option<PathBuf>),
+    /// A card and what's on it, scanned and hashed.
+    ImportScanReady(PathBuf, Vec<ImportItem>),
+    PickImportDestination,
+    ImportDestinationSelected(Option<PathBuf>),
+    SetEjectAfterImport(bool),
+    StartImport,
+    /// Copied files, or why they couldn't be.
+    ImportBatchDone(Vec<(ImportItem, Result<PathBuf, String>)>),
+    CancelImport,
+    EjectDone(Result<(), String>),
+    SelectShareInterface(usize),
+    ShareNameChanged(String),
+    TimeZoneChanged(String),
+    SharePortChanged(String),
+    SelectShareTimeLimit(Option<u64>),
+    SelectShareIdleLimit(Option<u64>),
+    SetShareAvifThumbs(bool),
+    /// Show or hide the large QR code for the share (Q).
+    ToggleQrOverlay,
+    CopyShareUrl,
+    /// Open the share in this computer's browser.
+    OpenShareUrl,
+    SelectShareThumbCacheSize(Option<u64>),
+    SelectShareHttpWorkers(Option<usize>),
+    SelectPreloadRadius(Option<usize>),
+    SelectViewerCacheSize(Option<u64>),
+    SelectGridCacheSize(Option<u64>),
+    SelectGridLayout(GridLayout),
+    SetGridSharpen(bool),
+    SetScreensaverDwellByAspect(bool),
+    SetGridRatingBadges(bool),
+    SetViewerWheelNavigates(bool),
+    SelectGridSharpenAmount(Option<u32>),
+    SelectFitMode(FitMode),
+    SelectMaxZoom(Option<u32>),
+    ZoomPresetsChanged(String),
+    SelectViewerBackground(Background),
+    SelectLanguage(Option<Language>),
+    SetHighContrast(bool),
+    FindPeers,
+    PeersFound(Vec<server::mdns::Peer>),
 }
 
-fn subscription(state: &Looky) -> Subscription<Message> {
-    // Window resize still goes through subscription (not latency-sensitive).
-    // Keyboard events are handled by KeyListener widget for instant response.
-    let events = iced::event::listen_with(|event, _status, _window| match event {
-        iced::Event::Window(iced::window::Event::Resized(size)) => {
-            Some(Message::WindowResized(size.width, size.height))
-        }
-        _ => None,
-    });
+/// How often the folder session (scroll, selection, open photo) is checked
+/// for changes and saved to the catalog.
+const SESSION_SAVE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long without a key press, click or scroll before photos are hashed
+/// for duplicates in the background, and how often that's checked.
+const IDLE_HASH_DELAY: Duration = Duration::from_secs(20);
+const IDLE_HASH_CHECK: Duration = Duration::from_secs(5);
+
+/// How often the debug overlay's numbers are brought up to date.
+const DEBUG_REFRESH: Duration = Duration::from_secs(1);
+
+/// How long each photo stays up in the screensaver and the TV slideshow.
+const SLIDESHOW_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How long the screensaver keeps the current photo up. With dwell by shape
+/// on, panoramas stay longer the wider they are, up to three times as long,
+/// and portraits a little shorter.
+fn screensaver_interval(state: &Looky) -> Duration {
+    if !state.settings.screensaver_dwell_by_aspect {
+        return SLIDESHOW_INTERVAL;
+    }
+    let Some(&index) = state.screensaver_order.get(state.screensaver_position) else {
+        return SLIDESHOW_INTERVAL;
+    };
+    let aspect = state
+        .image_paths
+        .get(index)
+        .and_then(|path| state.photo_info.get(path))
+        .and_then(PhotoInfo::aspect_ratio)
+        .or_else(|| state.thumb_aspects.get(index).copied())
+        .unwrap_or(1.5);
+    let scale = if aspect >= 2.0 {
+        (aspect / 2.0).clamp(1.5, 3.0)
+    } else if aspect < 1.0 {
+        0.7
+    } else {
+        1.0
+    };
+    SLIDESHOW_INTERVAL.mul_f32(scale)
+}
 
+fn subscription(state: &Looky) -> Subscription<Message> {
     let needs_tick = state.viewer.is_transitioning()
         || state.viewer.is_zoom_animating()
         || thumbnails_fading(state);
 
-    let mut subs = vec![events];
+    let mut subs = Vec::new();
     if needs_tick {
         subs.push(iced::time::every(Duration::from_millis(16)).map(|_| Message::Tick));
     }
-    if state.screensaver_active {
+    if state.screensaver_active && !state.screensaver_paused {
         subs.push(
-            iced::time::every(Duration::from_secs(10)).map(|_| Message::ScreensaverAdvance),
+            iced::time::every(screensaver_interval(state)).map(|_| Message::ScreensaverAdvance),
         );
     }
+    if !state.cast_sessions.is_empty() {
+        subs.push(iced::time::every(Duration::from_secs(1)).map(|_| Message::CastTick));
+    }
+    if state.cast_slideshow_active && !state.cast_sessions.is_empty() {
+        subs.push(iced::time::every(SLIDESHOW_INTERVAL).map(|_| Message::CastSlideshowAdvance));
+    }
+    if state.debug.is_some() {
+        subs.push(iced::time::every(DEBUG_REFRESH).map(|_| Message::DebugTick));
+    }
+    if state.watcher.is_some() {
+        subs.push(iced::time::every(Duration::from_secs(1)).map(|_| Message::WatcherTick));
+    }
+    if state.thumb_retries.values().any(|(_, at)| at.is_some()) {
+        subs.push(iced::time::every(Duration::from_secs(1)).map(|_| Message::ThumbnailRetryTick));
+    }
+    if state.folder.is_some() && state.catalog.is_some() {
+        subs.push(iced::time::every(SESSION_SAVE_INTERVAL).map(|_| Message::SessionTick));
+    }
+    if state.catalog.is_some() && state.idle_hash_cursor < state.image_paths.len() {
+        subs.push(iced::time::every(IDLE_HASH_CHECK).map(|_| Message::IdleHashTick));
+    }
+    if state.server_handle.is_some()
+        && (state.settings.share_time_limit.is_some() || state.settings.share_idle_limit.is_some())
+    {
+        subs.push(iced::time::every(Duration::from_secs(1)).map(|_| Message::ShareTick));
+    }
     Subscription::batch(subs)
 }
 
@@ -251,68 +1309,234 @@ fn update(state: &mut Looky, message: Message) -> Task<Message> {
         let close_menu = matches!(
             message,
             Message::OpenFolder
+                | Message::NewWindow
+                | Message::RefreshFolder
+                | Message::ExcludeSubfolder
+                | Message::ToggleSubfolderInDupScans
                 | Message::ShowDuplicatesView
                 | Message::ToggleScreensaver
                 | Message::BackToGrid
                 | Message::ToggleInfo
+                | Message::ToggleFocusPoints
+                | Message::ToggleCullingOverlay
                 | Message::ToggleFullscreen
+                | Message::ToggleDarkRoom
+                | Message::StartEdit
+                | Message::RandomPhoto
+                | Message::StartCulling
+                | Message::TrashRejected
                 | Message::BackFromDuplicates
                 | Message::BackFromCompare
+                | Message::ShowSettings
+                | Message::BackFromSettings
+                | Message::TrashPhoto
+                | Message::ShowTrash
+                | Message::BackFromTrash
+                | Message::ShowLibraryDuplicates
+                | Message::BackFromLibraryDuplicates
+                | Message::ShowUnreadable
+                | Message::BackFromUnreadable
+                | Message::RetryUnreadable
+                | Message::EmptyTrash
+                | Message::ShowImport
+                | Message::BackFromImport
+                | Message::CheckAgainstLibrary
+                | Message::ExportGallery
+                | Message::ExportContactSheet
+                | Message::SharePhoto
+                | Message::ToggleQrOverlay
+                | Message::OpenShareUrl
         );
         if close_menu {
             state.menu_open = false;
+            state.menu_focus = None;
         }
     }
     match message {
         Message::OpenFolder => {
             return Task::perform(pick_folder(), Message::FolderSelected);
         }
+        // Opened by `update_app`; here it only closes the menu
+        Message::NewWindow => {}
         Message::FolderSelected(Some(path)) => {
+            save_session(state);
+            state.library_dups_view_active = false;
+            state.unreadable_view_active = false;
+            state.saved_session = None;
+            state.last_viewed = None;
+            state.resume_offer = None;
+            state.pending_grid_scroll = None;
+            state.grid_scroll_y = 0.0;
+            state.selected_thumb = None;
+            state.grid_filter = GridFilter::default();
+            state.grid_shown.clear();
+            state.grid_subfolders.clear();
+            state.filter_choices = FilterChoices::default();
+            state.min_aspect_input.clear();
+            state.max_aspect_input.clear();
+            state.search_input.clear();
+            state.photo_info.clear();
+            state.info_pending.clear();
+            state.info_total = 0;
+            state.info_job = None;
             save_last_folder(&path);
             // Stop casting and sharing on folder change
-            if let Some(session) = state.cast_session.take() {
-                session.stop();
-            }
-            state.cast_target_name = None;
-            state.cast_devices.clear();
-            state.cast_error = None;
-            if let Some(handle) = state.server_handle.take() {
-                std::thread::spawn(move || handle.stop());
-            }
-            state.server_url = None;
-            state.qr_handle = None;
+            stop_sharing(state);
+            state.watcher = None;
+            state.watcher_pending.clear();
             state.folder = Some(path.clone());
             state.thumbnails.clear();
+            state.thumb_aspects.clear();
+            state.thumbnail_bytes = 0;
+            state.evicted_thumbnails.clear();
             state.image_paths.clear();
             state.pending_thumbnails.clear();
             state.thumbnail_index.clear();
             state.pending_upgrades.clear();
             state.upgrade_batches_in_flight = 0;
+            state.unreadable.clear();
+            state.thumb_retries.clear();
+            state.scan_job = Some(JobProgress::start(0));
+            state.thumb_job = None;
+            state.thumbs_done = 0;
+            state.thumbs_cancelled = false;
+            state.dup_job = None;
+            let (fit_mode, max_zoom) = (state.viewer.fit_mode, state.viewer.max_zoom);
             state.viewer = ViewerState::default();
+            state.viewer.fit_mode = fit_mode;
+            state.viewer.max_zoom = max_zoom;
             state.loading = true;
+            state.trashed.clear();
+            state.moved_away.clear();
+            state.selection.clear();
+            state.batch_status = None;
+            state.imported.clear();
+            state.library_checked = false;
+            state.library_pending.clear();
+            state.library_job = None;
             // Reset dup state on folder change
             state.dup_hashes.clear();
             state.dup_pending.clear();
+            state.dup_candidates.clear();
+            state.dup_paused = false;
+            state.dup_in_flight = false;
             state.dup_scanning = false;
+            state.idle_hash_cursor = 0;
             state.dup_groups.clear();
             state.dup_badge_set.clear();
             state.dup_view_active = false;
             state.dup_compare = None;
             state.dup_summaries.clear();
-            return Task::perform(scan_folder(path), Message::ImagesFound);
+            let depth = scan_depth(state);
+            return Task::perform(scan_folder(path, depth), Message::ImagesFound);
         }
         Message::FolderSelected(None) => {}
+        Message::RefreshFolder => {
+            // The first scan is still walking the folder
+            if state.scan_job.is_some() {
+                return Task::none();
+            }
+            if let Some(folder) = state.folder.clone() {
+                let depth = scan_depth(state);
+                return Task::perform(scan_folder(folder, depth), Message::FolderRescanned);
+            }
+        }
+        Message::CycleFolderScanDepth => {
+            let (Some(cat), Some(folder)) = (state.catalog.as_ref(), state.folder.as_deref()) else {
+                return Task::none();
+            };
+            let current = scan_depth(state);
+            let at = SCAN_DEPTHS.iter().position(|&(depth, _)| depth == current).unwrap_or(0);
+            cat.set_scan_depth(folder, SCAN_DEPTHS[(at + 1) % SCAN_DEPTHS.len()].0);
+            watch_folder(state);
+            return update(state, Message::RefreshFolder);
+        }
+        Message::SelectScanDepth(depth) => {
+            state.settings.scan_depth = depth;
+            state.settings.save();
+            if state.watcher.is_some() {
+                watch_folder(state);
+            }
+        }
+        Message::FolderRescanned(paths) => {
+            return apply_rescan(state, paths);
+        }
+        Message::ExcludeSubfolder => {
+            let Some(dir) = state.grid_filter.subfolder.clone() else {
+                return Task::none();
+            };
+            if let Err(e) = std::fs::write(dir.join(EXCLUDE_MARKERS[1]), "") {
+                state.batch_status = Some(trf("Couldn't exclude the folder: {}", &[&e]));
+                return Task::none();
+            }
+            let parent = dir.parent().map(std::path::Path::to_path_buf).or(state.folder.clone());
+            let back_up = match parent {
+                Some(parent) => update(state, Message::ShowSubfolder(parent)),
+                None => Task::none(),
+            };
+            return Task::batch([back_up, update(state, Message::RefreshFolder)]);
+        }
+        Message::ToggleSubfolderInDupScans => {
+            let (Some(cat), Some(dir)) = (state.catalog.as_ref(), state.grid_filter.subfolder.clone())
+            else {
+                return Task::none();
+            };
+            let excluded = !cat.dup_excluded_folders().contains(&dir);
+            cat.set_dup_excluded(&dir, excluded);
+            if state.dup_scanning || state.dup_hashes.is_empty() {
+                return Task::none();
+            }
+            if !excluded {
+                state.batch_status = Some(tr("Find duplicates again to include this folder").to_string());
+                return Task::none();
+            }
+            // Its hashes are already known, so regroup without them
+            let paths = &state.image_paths;
+            state.dup_hashes.retain(|(i, _)| !paths.get(*i).is_some_and(|p| p.starts_with(&dir)));
+            return analyze_duplicates(state);
+        }
         Message::ImagesFound(paths) => {
+            // The scan was cancelled from the status bar
+            if state.scan_job.take().is_none() {
+                return Task::none();
+            }
+            state.thumb_job = Some(JobProgress::start(0));
             if let Some(cat) = state.catalog.as_ref() {
                 cat.prune_missing();
             }
             state.image_paths = paths.clone();
-            state.pending_thumbnails = paths;
+            state.pending_thumbnails = paths.clone();
+            let index = start_indexing(state, paths);
+            watch_folder(state);
+            let mut restore = restore_session(state);
+            // A camera card: show what's on it that's already imported
+            let on_card = state
+                .folder
+                .as_deref()
+                .is_some_and(|folder| import::find_volumes().iter().any(|v| folder.starts_with(v)));
+            if on_card {
+                restore = Task::batch([restore, start_library_check(state)]);
+            }
+
+            // Pick up a duplicate scan left unfinished in this folder; hashes
+            // it already computed come back from the catalog
+            let unfinished_scan = match (state.catalog.as_ref(), state.folder.as_deref()) {
+                (Some(cat), Some(folder)) => cat.get_dup_scan(folder),
+                _ => None,
+            };
+            if let Some(paused) = unfinished_scan {
+                let scan = start_dup_scan(state, paused);
+                return Task::batch([load_next_preview_batch(state), scan, restore, index]);
+            }
 
             // Auto-load cached duplicate groups from catalog
             if let Some(cat) = state.catalog.as_ref() {
+                let excluded = cat.dup_excluded_folders();
                 let mut cached_hashes = Vec::new();
                 for (i, path) in state.image_paths.iter().enumerate() {
+                    if in_any_folder(&excluded, path) {
+                        continue;
+                    }
                     if let Some((ch, ph)) = cat.get_hashes(path) {
                         cached_hashes.push((
                             i,
@@ -333,6 +1557,7 @@ fn update(state: &mut Looky, message: Message) -> Task<Message> {
                         }
                     }
                     state.dup_hashes = cached_hashes.clone();
+                    let report_pairs = state.settings.dup_report_capture_pairs;
                     let task = Task::perform(
                         async move {
                             let groups = duplicates::find_duplicates(
@@ -350,21 +1575,32 @@ fn update(state: &mut Looky, message: Message) -> Task<Message> {
                                     Some((idx, metadata::read_file_summary(path)))
                                 })
                                 .collect();
+                            let groups = if report_pairs {
+                                groups
+                            } else {
+                                duplicates::without_capture_sets(
+                                    groups,
+                                    |i| image_paths.get(i).cloned(),
+                                    |i| summaries.get(&i)?.date_taken.clone(),
+                                )
+                            };
                             (groups, summaries)
                         },
                         |(g, s)| Message::CachedDupAnalysisReady(g, s),
                     );
-                    return Task::batch([load_next_preview_batch(state), task]);
+                    return Task::batch([load_next_preview_batch(state), task, restore, index]);
                 }
             }
-            return load_next_preview_batch(state);
+            return Task::batch([load_next_preview_batch(state), restore, index]);
         }
         Message::ThumbnailBatchReady(results) => {
             let now = Instant::now();
-            for (path, rgba, width, height) in results {
-                let handle = image::Handle::from_rgba(width, height, rgba);
-                state.thumbnails.push((path, handle, now));
+            state.thumbs_done += results.len();
+            for (path, result) in results {
+                let handle = thumbnail_handle(state, &path, result);
+                push_thumbnail(state, path, handle, now);
             }
+            enforce_thumbnail_budget(state);
             return load_next_batch(state);
         }
         Message::PreviewBatchReady(results) => {
@@ -378,89 +1614,190 @@ fn update(state: &mut Looky, message: Message) -> Task<Message> {
                     // Placeholder — will be replaced by upgrade batch
                     image::Handle::from_rgba(1, 1, vec![60, 60, 60, 255])
                 };
-                state.thumbnails.push((path.clone(), handle, now));
-                state.pending_upgrades.push(path);
+                push_thumbnail(state, path.clone(), handle, now);
+                if !state.thumbs_cancelled {
+                    state.pending_upgrades.push(path);
+                }
             }
+            finish_thumbnails_if_idle(state);
+            let scroll_task = apply_pending_grid_scroll(state);
             // Continue loading previews AND fire upgrade batches
             let preview_task = load_next_preview_batch(state);
             let upgrade_task = load_upgrade_batches(state);
-            return Task::batch([preview_task, upgrade_task]);
+            return Task::batch([scroll_task, preview_task, upgrade_task]);
         }
         Message::ThumbnailUpgradeReady(results) => {
             state.upgrade_batches_in_flight =
                 state.upgrade_batches_in_flight.saturating_sub(1);
             let now = Instant::now();
-            for (path, rgba, width, height) in results {
-                let handle = image::Handle::from_rgba(width, height, rgba);
+            state.thumbs_done += results.len();
+            for (path, result) in results {
+                let handle = thumbnail_handle(state, &path, result);
                 if let Some(&idx) = state.thumbnail_index.get(&path) {
-                    if idx < state.thumbnails.len() {
-                        state.thumbnails[idx] = (path, handle, now);
-                    }
+                    replace_thumbnail(state, idx, handle, now);
                 }
             }
-            if state.pending_upgrades.is_empty()
-                && state.upgrade_batches_in_flight == 0
-                && state.pending_thumbnails.is_empty()
-            {
-                state.loading = false;
-            }
+            enforce_thumbnail_budget(state);
+            finish_thumbnails_if_idle(state);
             return load_upgrade_batches(state);
         }
+        Message::PhotoInfoBatchReady(results) => {
+            // Indexing was cancelled or the folder changed
+            if state.info_job.is_none() {
+                return Task::none();
+            }
+            for (path, info) in results {
+                let stat = catalog::file_size_and_mtime_for(&path);
+                if let (Some(cat), Some((file_size, mtime_ns))) = (state.catalog.as_ref(), stat) {
+                    cat.insert_photo_info(&path, file_size, mtime_ns, &info);
+                }
+                if state.grid_filter.in_subfolder(&path) {
+                    state.filter_choices.add(&info);
+                }
+                state.photo_info.insert(path, info);
+            }
+            if state.grid_filter.uses_info() {
+                refresh_grid_filter(state);
+            }
+            return load_next_info_batch(state);
+        }
+        Message::CancelIndexing => {
+            state.info_job = None;
+            state.info_pending.clear();
+        }
+        Message::CancelFolderScan => {
+            state.scan_job = None;
+            state.folder = None;
+            state.loading = false;
+        }
+        Message::CancelThumbnails => {
+            // Keep the previews already shown; just stop the full-quality pass
+            state.thumbs_cancelled = true;
+            state.pending_upgrades.clear();
+            finish_thumbnails_if_idle(state);
+        }
+        Message::ViewImage(index) if state.modifiers.command() || state.modifiers.shift() => {
+            extend_selection(state, index);
+        }
         Message::ViewImage(index) => {
+            state.selection.clear();
             state.selected_thumb = Some(index);
             state.viewer.open_index(index);
-            refresh_metadata(state);
-            return preload_viewer_images(state);
+            return viewer_changed(state);
         }
         Message::NextImage => {
-            state.viewer.next(state.image_paths.len());
-            state.selected_thumb = state.viewer.current_index;
-            refresh_metadata(state);
-            return preload_viewer_images(state);
+            return step_viewer(state, 1);
         }
         Message::PrevImage => {
-            state.viewer.prev();
-            state.selected_thumb = state.viewer.current_index;
-            refresh_metadata(state);
-            return preload_viewer_images(state);
+            return step_viewer(state, -1);
         }
         Message::BackToGrid => {
+            state.culling = false;
             state.viewer.close();
             state.cached_metadata = None;
             state.viewer_cache.clear();
             state.viewer_dimensions.clear();
-            return restore_grid_scroll(state);
+            state.viewer_previews.clear();
+            state.viewer_fitted.clear();
+            state.viewer_last_index = None;
+            state.decoder.schedule(Vec::new());
+            let upgrades = load_upgrade_batches(state);
+            return Task::batch([restore_grid_scroll(state), upgrades]);
         }
         Message::ToggleInfo => {
             state.viewer.toggle_info();
         }
-        Message::ViewerImageLoaded(index, rgba, width, height) => {
-            log::debug!("viewer: [{}] loaded ({}x{})", index, width, height);
+        Message::ToggleFocusPoints => {
+            state.viewer.toggle_focus();
+        }
+        Message::ToggleCullingOverlay => {
+            state.settings.viewer_culling_overlay = !state.settings.viewer_culling_overlay;
+            state.settings.save();
+            refresh_histogram(state);
+        }
+        Message::ToggleDebugOverlay => {
+            state.debug = match state.debug {
+                Some(_) => None,
+                None => Some(DebugStats::start(state.thumbs_done)),
+            };
+        }
+        Message::DebugTick => {
+            if let Some(debug) = state.debug.as_mut() {
+                debug.sample(state.thumbs_done);
+            }
+        }
+        Message::ViewerImageLoaded(index, rgba, width, height, elapsed) => {
+            log::debug!("viewer: [{}] loaded ({}x{}) in {:?}", index, width, height, elapsed);
+            let ms = elapsed.as_secs_f32() * 1000.0;
+            state.decode_ms_average = Some(match state.decode_ms_average {
+                Some(average) => average + (ms - average) * DECODE_AVERAGE_WEIGHT,
+                None => ms,
+            });
             let handle = image::Handle::from_rgba(width, height, rgba);
             state.viewer_cache.insert(index, handle);
             state.viewer_dimensions.insert(index, (width, height));
-            // Evict distant entries to limit memory (keep ±3 of current)
+            state.viewer_previews.remove(&index);
+            state.viewer_fitted.remove(&index);
+            refresh_histogram(state);
             if let Some(current) = state.viewer.current_index {
-                let keep_min = current.saturating_sub(3);
-                let keep_max = current + 3;
-                // During screensaver, also keep the next image (random order, not a neighbor)
-                let ss_next = if state.screensaver_active {
-                    state.screensaver_order.get(state.screensaver_position + 1).copied()
-                } else {
-                    None
-                };
-                state
-                    .viewer_cache
-                    .retain(|&k, _| (k >= keep_min && k <= keep_max) || ss_next == Some(k));
-                state
-                    .viewer_dimensions
-                    .retain(|&k, _| (k >= keep_min && k <= keep_max) || ss_next == Some(k));
-                // Current image just arrived — now preload neighbors
+                evict_viewer_cache(state, current);
+                // Current image just arrived — now preload neighbors and
+                // let held-back thumbnail work resume
+                if index == current {
+                    let upgrades = load_upgrade_batches(state);
+                    return Task::batch([preload_viewer_images(state), upgrades]);
+                }
+            }
+        }
+        Message::ViewerFittedLoaded(index, rgba, width, height) => {
+            // A full-size decode may have won the race; keep it
+            if state.viewer_cache.contains_key(&index)
+                && !state.viewer_previews.contains(&index)
+                && !state.viewer_fitted.contains(&index)
+            {
+                return Task::none();
+            }
+            log::debug!("viewer: [{}] fitted ({}x{})", index, width, height);
+            let handle = image::Handle::from_rgba(width, height, rgba);
+            state.viewer_cache.insert(index, handle);
+            state.viewer_dimensions.insert(index, (width, height));
+            state.viewer_previews.remove(&index);
+            state.viewer_fitted.insert(index);
+            refresh_histogram(state);
+            if let Some(current) = state.viewer.current_index {
+                evict_viewer_cache(state, current);
                 if index == current {
-                    return preload_viewer_neighbors(state);
+                    let upgrades = load_upgrade_batches(state);
+                    return Task::batch([preload_viewer_images(state), upgrades]);
                 }
             }
         }
+        Message::ScaleFactorChanged(scale_factor) => {
+            state.scale_factor = scale_factor;
+        }
+        Message::ViewerImageFailed(index, error) => {
+            log::debug!("viewer: [{}] failed: {}", index, error);
+            if let Some(path) = state.image_paths.get(index) {
+                state.unreadable.insert(path.clone(), error);
+            }
+            // Nothing more is coming for the current image; move on as if it loaded
+            if state.viewer.current_index == Some(index) {
+                let upgrades = load_upgrade_batches(state);
+                return Task::batch([preload_viewer_images(state), upgrades]);
+            }
+        }
+        Message::ViewerPreviewLoaded(index, rgba, width, height) => {
+            // The full image may have won the race; never replace it.
+            if state.viewer_cache.contains_key(&index) {
+                return Task::none();
+            }
+            log::debug!("viewer: [{}] preview ({}x{})", index, width, height);
+            let handle = image::Handle::from_rgba(width, height, rgba);
+            state.viewer_cache.insert(index, handle);
+            state.viewer_dimensions.insert(index, (width, height));
+            state.viewer_previews.insert(index);
+            refresh_histogram(state);
+        }
         Message::Tick => {
             state.viewer.tick();
             let old_zoom = state.viewer.zoom_level;
@@ -474,21 +1811,23 @@ fn update(state: &mut Looky, message: Message) -> Task<Message> {
         }
         // Duplicate detection
         Message::FindDuplicates => {
-            state.dup_hashes.clear();
-            state.dup_groups.clear();
-            state.dup_badge_set.clear();
-            state.dup_summaries.clear();
-            state.dup_scanning = true;
-            state.dup_compare = None;
-            state.dup_view_active = false;
-            state.dup_total = state.image_paths.len();
-
-            // Check catalog for cached hashes; only queue uncached/stale files
+            return start_dup_scan(state, false);
+        }
+        Message::DupCandidatesReady(items, candidates) => {
+            state.dup_in_flight = false;
+            if !state.dup_scanning {
+                return Task::none();
+            }
+            // Check catalog for cached hashes; only queue uncached/stale
+            // files, and ones that now need the content hash skipped before
             let mut pending = Vec::new();
-            for (i, path) in state.image_paths.iter().enumerate() {
-                if let Some((content_hash, perceptual_hash)) =
-                    state.catalog.as_ref().and_then(|c| c.get_hashes(path))
-                {
+            for (i, path) in items {
+                let cached = state
+                    .catalog
+                    .as_ref()
+                    .and_then(|c| c.get_hashes(&path))
+                    .filter(|(content_hash, _)| content_hash.is_some() || !candidates.contains(&i));
+                if let Some((content_hash, perceptual_hash)) = cached {
                     state.dup_hashes.push((
                         i,
                         ImageHashes {
@@ -497,86 +1836,101 @@ fn update(state: &mut Looky, message: Message) -> Task<Message> {
                         },
                     ));
                 } else {
-                    pending.push((i, path.clone()));
+                    pending.push((i, path));
                 }
             }
+            state.dup_job = Some(JobProgress::start(state.dup_hashes.len()));
+            state.dup_candidates = candidates;
             state.dup_pending = pending;
+            if state.dup_pending.is_empty() {
+                return analyze_duplicates(state);
+            }
             return load_next_dup_batch(state);
         }
+        Message::ToggleSkipUnreadable => {
+            state.settings.dup_skip_unreadable = !state.settings.dup_skip_unreadable;
+            state.settings.save();
+        }
+        Message::SetDupReportCapturePairs(on) => {
+            state.settings.dup_report_capture_pairs = on;
+            state.settings.save();
+            // Regroup what's already hashed rather than waiting for a rescan
+            if !state.dup_scanning && !state.dup_hashes.is_empty() {
+                return analyze_duplicates(state);
+            }
+        }
+        Message::SetDupOrder(order) => {
+            state.settings.dup_order = Some(order).filter(|&o| o != GroupOrder::Folder);
+            state.settings.save();
+            sort_dup_groups(state);
+        }
+        Message::ToggleDupScanPause => {
+            state.dup_paused = !state.dup_paused;
+            if let (Some(cat), Some(folder)) = (state.catalog.as_ref(), state.folder.as_deref()) {
+                cat.save_dup_scan(folder, state.dup_paused);
+            }
+            // Otherwise the batch in flight carries on when it lands
+            if !state.dup_paused && !state.dup_in_flight {
+                state.dup_job = Some(JobProgress::start(state.dup_total - state.dup_pending.len()));
+                if state.dup_pending.is_empty() {
+                    return analyze_duplicates(state);
+                }
+                return load_next_dup_batch(state);
+            }
+        }
         Message::CancelDupScan => {
+            if let (Some(cat), Some(folder)) = (state.catalog.as_ref(), state.folder.as_deref()) {
+                cat.clear_dup_scan(folder);
+            }
+            state.dup_job = None;
+            state.dup_paused = false;
             state.dup_pending.clear();
+            state.dup_candidates.clear();
             state.dup_scanning = false;
             state.dup_hashes.clear();
             state.dup_total = 0;
         }
         Message::DupHashBatchReady(results) => {
+            state.dup_in_flight = false;
             if !state.dup_scanning {
                 // Scan was cancelled — discard late-arriving batch
                 return Task::none();
             }
             for (idx, maybe_hash) in results {
                 if let Some(h) = maybe_hash {
-                    // Persist to catalog
-                    if let (Some(cat), Some(path)) =
-                        (state.catalog.as_ref(), state.image_paths.get(idx))
-                    {
-                        if let Some((file_size, mtime_ns)) =
-                            catalog::file_size_and_mtime_for(path)
-                        {
-                            cat.insert_hashes(
-                                path,
-                                file_size,
-                                mtime_ns,
-                                &h.content_hash,
-                                &h.perceptual_hash,
-                            );
-                        }
+                    if let Some(path) = state.image_paths.get(idx) {
+                        store_hashes(state, path, &h);
                     }
                     state.dup_hashes.push((idx, h));
                 }
             }
             if state.dup_pending.is_empty() {
-                // All hashes computed — run analysis off the main thread
-                let hashes = state.dup_hashes.clone();
-                let image_paths = state.image_paths.clone();
-
-                // Pre-collect cached summaries from the catalog (on main thread)
-                let mut cached_summaries: HashMap<usize, metadata::FileSummary> = HashMap::new();
-                if let Some(cat) = state.catalog.as_ref() {
-                    // We don't know dup_indices yet, but we can pre-cache all image paths
-                    // to avoid disk reads in the async block. This is fast (just DB lookups).
-                    for (i, path) in image_paths.iter().enumerate() {
-                        if let Some(summary) = cat.get_file_summary(path) {
-                            cached_summaries.insert(i, summary);
-                        }
-                    }
-                }
-
-                return Task::perform(
-                    async move {
-                        let groups =
-                            duplicates::find_duplicates(&hashes, VISUAL_DUP_THRESHOLD);
-                        let dup_indices = duplicates::duplicate_indices(&groups);
-                        let summaries: HashMap<usize, metadata::FileSummary> = dup_indices
-                            .iter()
-                            .filter_map(|&idx| {
-                                if let Some(cached) = cached_summaries.get(&idx) {
-                                    return Some((idx, cached.clone()));
-                                }
-                                let path = image_paths.get(idx)?;
-                                Some((idx, metadata::read_file_summary(path)))
-                            })
-                            .collect();
-                        (groups, summaries)
-                    },
-                    |(groups, summaries)| Message::DupAnalysisReady(groups, summaries),
-                );
+                return analyze_duplicates(state);
             } else {
                 return load_next_dup_batch(state);
             }
         }
+        Message::UserActive => {
+            state.last_input = Instant::now();
+        }
+        Message::IdleHashTick => {
+            return load_next_idle_hash_batch(state);
+        }
+        Message::IdleHashBatchReady(results) => {
+            state.idle_hash_in_flight = false;
+            // By path, as the folder may have changed meanwhile
+            for (path, h) in results.into_iter().filter_map(|(p, h)| Some((p, h?))) {
+                store_hashes(state, &path, &h);
+            }
+            return load_next_idle_hash_batch(state);
+        }
         Message::DupAnalysisReady(groups, summaries) => {
             state.dup_scanning = false;
+            state.dup_job = None;
+            state.dup_paused = false;
+            if let (Some(cat), Some(folder)) = (state.catalog.as_ref(), state.folder.as_deref()) {
+                cat.clear_dup_scan(folder);
+            }
             state.dup_badge_set = duplicates::duplicate_indices(&groups);
             state.dup_groups = groups;
 
@@ -593,6 +1947,7 @@ fn update(state: &mut Looky, message: Message) -> Task<Message> {
                 }
             }
             state.dup_summaries = summaries;
+            sort_dup_groups(state);
         }
         Message::CachedDupAnalysisReady(groups, summaries) => {
             // Only apply if we're not currently in a full scan
@@ -609,6 +1964,7 @@ fn update(state: &mut Looky, message: Message) -> Task<Message> {
                     }
                 }
                 state.dup_summaries = summaries;
+                sort_dup_groups(state);
             }
         }
         Message::ShowDuplicatesView => {
@@ -620,15 +1976,45 @@ fn update(state: &mut Looky, message: Message) -> Task<Message> {
             state.dup_view_active = false;
         }
         Message::CompareDuplicates(group_idx) => {
+            state.dup_selected = Some(group_idx);
             state.dup_compare = Some(group_idx);
         }
-        Message::BackFromCompare => {
-            state.dup_compare = None;
-        }
-        // Zoom
+        Message::KeepLargestOfGroup(group_idx) => {
+            let Some(group) = state.dup_groups.get(group_idx) else {
+                return Task::none();
+            };
+            let size_of = |idx: usize| state.dup_summaries.get(&idx).map_or(0, |s| s.file_size);
+            let Some(&keep) = group.indices.iter().rev().max_by_key(|&&idx| size_of(idx)) else {
+                return Task::none();
+            };
+            let others: Vec<usize> = group.indices.iter().copied().filter(|&idx| idx != keep).collect();
+            let removed: Vec<usize> =
+                others.into_iter().filter(|&idx| move_index_to_trash(state, idx)).collect();
+            forget_removed(state, &removed);
+            state.batch_status = Some(trn(
+                removed.len(),
+                "Moved {} photo to the trash",
+                "Moved {} photos to the trash",
+            ));
+            // Carry on with whichever group moved up into its place
+            if !state.dup_groups.is_empty() {
+                state.dup_selected = Some(group_idx.min(state.dup_groups.len() - 1));
+            }
+            return scroll_to_dup_selection(state);
+        }
+        Message::CompareDuplicatesOf(index) => {
+            if let Some(group_idx) = state.dup_groups.iter().position(|g| g.indices.contains(&index)) {
+                state.selected_thumb = Some(index);
+                state.dup_compare = Some(group_idx);
+            }
+        }
+        Message::BackFromCompare => {
+            state.dup_compare = None;
+        }
+        // Zoom
         Message::ToggleZoom => {
             if let Some(idx) = state.viewer.current_index {
-                if !state.viewer_cache.contains_key(&idx) {
+                if !has_full_image(state, idx) {
                     return Task::none();
                 }
                 state.viewer.toggle_zoom();
@@ -636,14 +2022,88 @@ fn update(state: &mut Looky, message: Message) -> Task<Message> {
                 // In grid: open selected image (current Space behavior)
                 if !state.dup_view_active
                     && state.dup_compare.is_none()
+                    && !panel_showing(state)
                     && idx < state.thumbnails.len()
                 {
                     state.viewer.open_index(idx);
-                    refresh_metadata(state);
-                    return preload_viewer_images(state);
+                    return viewer_changed(state);
+                }
+            }
+        }
+        Message::CycleRating => {
+            update_rating(state, |rating| rating.stars = (rating.stars + 1) % (Rating::MAX_STARS + 1));
+        }
+        Message::ToggleFavorite => {
+            update_rating(state, |rating| rating.favorite = !rating.favorite);
+        }
+        Message::FlagPhoto(flag) => {
+            update_rating(state, |rating| {
+                rating.flag = if rating.flag == flag { Flag::None } else { flag };
+            });
+            let Some(index) = state.viewer.current_index else {
+                // The photo may no longer pass a flag filter
+                if state.grid_filter.flag.is_some() {
+                    refresh_grid_filter(state);
+                }
+                return Task::none();
+            };
+            if !state.culling || state.cull_finished {
+                return Task::none();
+            }
+            match grid_neighbor(state, index, 1) {
+                Some(next) => {
+                    state.viewer.navigate_to(next);
+                    return viewer_changed(state);
                 }
+                None => state.cull_finished = true,
+            }
+        }
+        Message::StartCulling => {
+            let start = state
+                .selected_thumb
+                .filter(|&i| grid_position(state, i).is_some())
+                .or(state.grid_shown.first().copied());
+            let Some(index) = start else {
+                return Task::none();
+            };
+            state.culling = true;
+            state.cull_finished = false;
+            state.cull_was_fullscreen = state.fullscreen;
+            state.selected_thumb = Some(index);
+            state.viewer.open_index(index);
+            let opened = viewer_changed(state);
+            if state.fullscreen {
+                return opened;
+            }
+            state.fullscreen = true;
+            return Task::batch([opened, window::set_mode(state.window, window::Mode::Fullscreen)]);
+        }
+        Message::StopCulling => return stop_culling(state),
+        Message::TrashRejected => {
+            let rejected = rejected_indices(state);
+            let stopped = if state.culling { stop_culling(state) } else { Task::none() };
+            return Task::batch([stopped, start_file_job_for(state, FileJobKind::Trash, rejected, false)]);
+        }
+        Message::ToggleFlipMark => {
+            state.viewer.toggle_flip_mark();
+            return preload_viewer_images(state);
+        }
+        Message::FlipCompare => {
+            if state.viewer.flip() {
+                state.selected_thumb = state.viewer.current_index;
+                refresh_metadata(state);
+                cast_current_image(state);
+                return preload_viewer_images(state);
             }
         }
+        Message::JumpToZoomPreset(preset) => {
+            return jump_to_zoom_preset(state, preset);
+        }
+        Message::CycleZoomPreset => {
+            let count = zoom_presets(&state.settings).len();
+            let next = state.viewer.zoom_preset.map_or(0, |i| (i + 1) % count);
+            return jump_to_zoom_preset(state, next);
+        }
         Message::CenterZoomScroll => {
             return center_zoom_scroll(state);
         }
@@ -651,7 +2111,7 @@ fn update(state: &mut Looky, message: Message) -> Task<Message> {
             if let Some(idx) = state.viewer.current_index {
                 // Don't zoom until the full-res image is loaded — zooming the
                 // thumbnail gives wrong dimensions and stretches badly.
-                if !state.viewer_cache.contains_key(&idx) {
+                if !has_full_image(state, idx) {
                     return Task::none();
                 }
                 state.viewer.zoom_anchor = Some((cursor_x, cursor_y));
@@ -666,15 +2126,47 @@ fn update(state: &mut Looky, message: Message) -> Task<Message> {
                 }
             }
         }
+        Message::ResumeViewing(index) => {
+            state.resume_offer = None;
+            if index < state.image_paths.len() {
+                state.selected_thumb = Some(index);
+                state.viewer.open_index(index);
+                return viewer_changed(state);
+            }
+        }
+        Message::DismissResume => {
+            state.resume_offer = None;
+        }
+        Message::RandomPhoto => {
+            return jump_to_random(state);
+        }
+        Message::WheelNavigate(delta) => {
+            // Trackpads scroll in small steps; move once a notch's worth adds up
+            state.wheel_travel += delta;
+            if state.wheel_travel.abs() >= 1.0 {
+                let step = if state.wheel_travel > 0.0 { -1 } else { 1 };
+                state.wheel_travel = 0.0;
+                return step_viewer(state, step);
+            }
+        }
         Message::ZoomScrolled(x, y) => {
             state.viewer.zoom_offset = (x, y);
         }
         Message::ViewerDrag(dx, dy) => {
-            if state.viewer.is_zoomed() {
+            if let Some((_, _, width, height)) = state.editing.as_ref().and_then(|s| edit_frame(state, s)) {
+                if let Some(session) = state.editing.as_mut() {
+                    session.edit = session.edit.moved(dx / width, dy / height);
+                }
+                return Task::none();
+            }
+            if state.viewer.is_pannable() {
                 return pan_zoom(state, -dx, -dy);
             }
         }
         Message::DragScroll(_dx, dy) => {
+            if panel_showing(state) {
+                return Task::none();
+            }
             let (scroll_id, scroll_y) = if state.dup_view_active {
                 (dup_list_scroll_id(), &mut state.dup_scroll_y)
             } else {
@@ -693,7 +2185,7 @@ fn update(state: &mut Looky, message: Message) -> Task<Message> {
         }
         Message::ViewerClickZoom(cx, cy) => {
             if let Some(idx) = state.viewer.current_index {
-                if state.viewer_cache.contains_key(&idx) {
+                if has_full_image(state, idx) {
                     state.viewer.zoom_anchor = Some((cx, cy));
                     let old_zoom = state.viewer.zoom_level;
                     state.viewer.adjust_zoom(4.0);
@@ -707,7 +2199,7 @@ fn update(state: &mut Looky, message: Message) -> Task<Message> {
         }
         Message::ViewerClickUnzoom(cx, cy) => {
             if let Some(idx) = state.viewer.current_index {
-                if state.viewer_cache.contains_key(&idx) {
+                if has_full_image(state, idx) {
                     state.viewer.zoom_anchor = Some((cx, cy));
                     let old_zoom = state.viewer.zoom_level;
                     state.viewer.adjust_zoom(-4.0);
@@ -722,19 +2214,19 @@ fn update(state: &mut Looky, message: Message) -> Task<Message> {
         }
         Message::PinchZoom(scale, cx, cy) => {
             if let Some(idx) = state.viewer.current_index {
-                if !state.viewer_cache.contains_key(&idx) {
+                if !has_full_image(state, idx) {
                     return Task::none();
                 }
                 state.viewer.zoom_anchor = Some((cx, cy));
                 let old_zoom = state.viewer.zoom_level;
-                let new_zoom = (old_zoom * scale).clamp(1.0, 8.0);
+                let new_zoom = (old_zoom * scale).clamp(1.0, state.viewer.max_zoom);
                 let new_zoom = if new_zoom < 1.02 { 1.0 } else { new_zoom };
                 state.viewer.zoom_level = new_zoom;
                 state.viewer.zoom_target = new_zoom;
                 if new_zoom > 1.0 && (new_zoom - old_zoom).abs() > 0.001 {
                     return anchor_zoom_scroll(state, old_zoom, new_zoom);
                 }
-                if new_zoom <= 1.0 && old_zoom > 1.0 {
+                if new_zoom <= 1.0 && old_zoom > 1.0 && !state.viewer.is_pannable() {
                     state.viewer.zoom_offset = (0.0, 0.0);
                 }
             }
@@ -748,59 +2240,159 @@ fn update(state: &mut Looky, message: Message) -> Task<Message> {
                 state.cached_metadata = None;
                 if !state.was_fullscreen {
                     state.fullscreen = false;
-                    return iced::window::latest()
-                        .and_then(|id| iced::window::set_mode(id, iced::window::Mode::Windowed));
+                    return window::set_mode(state.window, window::Mode::Windowed);
                 }
                 return Task::none();
-            } else if !state.image_paths.is_empty() {
+            } else if !state.grid_shown.is_empty() {
                 // Start screensaver
                 state.was_fullscreen = state.fullscreen;
                 state.screensaver_active = true;
-                // Build shuffled order
-                let mut order: Vec<usize> = (0..state.image_paths.len()).collect();
+                // Build shuffled order from what the grid shows
+                let mut order = state.grid_shown.clone();
                 use rand::seq::SliceRandom;
                 order.shuffle(&mut rand::rng());
                 state.screensaver_order = order;
                 state.screensaver_position = 0;
+                state.screensaver_ranks.clear();
+                state.screensaver_paused = false;
                 // Open first image
                 let idx = state.screensaver_order[0];
                 state.viewer.open_index(idx);
                 refresh_metadata(state);
-                let preload = preload_viewer_images(state);
-                let preload_next = preload_next_screensaver_image(state);
+                cast_current_image(state);
+                let mut preload = preload_viewer_images(state);
+                // Start on the plain shuffle and switch once dates are read
+                if state.settings.screensaver_recency.is_some() {
+                    let photos: Vec<(usize, PathBuf)> = state
+                        .grid_shown
+                        .iter()
+                        .map(|&i| (i, state.image_paths[i].clone()))
+                        .collect();
+                    let catalog_path = config_dir().map(|d| d.join("catalog.db"));
+                    let ranks = Task::perform(
+                        slideshow::recency_ranks(photos, catalog_path),
+                        Message::ScreensaverRanksReady,
+                    );
+                    preload = Task::batch([preload, ranks]);
+                }
                 // Go fullscreen
                 if !state.fullscreen {
                     state.fullscreen = true;
-                    let fs = iced::window::latest()
-                        .and_then(|id| iced::window::set_mode(id, iced::window::Mode::Fullscreen));
-                    return Task::batch([preload, preload_next, fs]);
+                    let fs = window::set_mode(state.window, window::Mode::Fullscreen);
+                    return Task::batch([preload, fs]);
                 }
-                return Task::batch([preload, preload_next]);
+                return preload;
             }
         }
-        Message::ScreensaverAdvance => {
+        Message::ScreensaverAdvance => return step_screensaver(state, 1),
+        Message::ScreensaverStep(step) => return step_screensaver(state, step),
+        Message::ToggleScreensaverPause => {
+            state.screensaver_paused = state.screensaver_active && !state.screensaver_paused;
+        }
+        Message::ScreensaverRanksReady(ranks) => {
+            let Some(strength) = state.settings.screensaver_recency else {
+                return Task::none();
+            };
             if !state.screensaver_active {
                 return Task::none();
             }
-            state.screensaver_position += 1;
-            if state.screensaver_position >= state.screensaver_order.len() {
-                // Reshuffle and restart
-                use rand::seq::SliceRandom;
-                state.screensaver_order.shuffle(&mut rand::rng());
-                state.screensaver_position = 0;
+            // Keep the photo on screen, and reorder everything after it
+            let current = state.screensaver_order.get(state.screensaver_position).copied();
+            let mut order = slideshow::weighted_shuffle(&ranks, strength as f32);
+            if let Some(current) = current {
+                order.retain(|&i| i != current);
+                order.insert(0, current);
             }
-            let idx = state.screensaver_order[state.screensaver_position];
-            state.viewer.open_index(idx);
-            state.viewer.reset_zoom();
-            refresh_metadata(state);
-            let preload = preload_viewer_images(state);
-            let preload_next = preload_next_screensaver_image(state);
-            return Task::batch([preload, preload_next]);
+            state.screensaver_order = order;
+            state.screensaver_position = 0;
+            state.screensaver_ranks = ranks;
+            return preload_viewer_images(state);
+        }
+        Message::SelectScreensaverRecency(strength) => {
+            state.settings.screensaver_recency = strength;
+            state.settings.save();
+        }
+        Message::SetScreensaverDwellByAspect(on) => {
+            state.settings.screensaver_dwell_by_aspect = on;
+            state.settings.save();
         }
         // Navigation
         Message::GridScrolled(y) => {
             state.grid_scroll_y = y;
+            reload_evicted_thumbnails(state);
             prioritize_upgrades(state);
+            return load_upgrade_batches(state);
+        }
+        Message::ShowSubfolder(dir) => {
+            let root = state.folder.as_deref();
+            state.grid_filter.subfolder = Some(dir).filter(|dir| Some(dir.as_path()) != root);
+            refresh_grid_filter(state);
+            // Drop a camera or lens that isn't in this folder
+            let choices = &state.filter_choices;
+            let camera_gone = state
+                .grid_filter
+                .camera
+                .as_ref()
+                .is_some_and(|c| !choices.cameras.contains(c));
+            let lens_gone = state
+                .grid_filter
+                .lens
+                .as_ref()
+                .is_some_and(|l| !choices.lenses.contains(l));
+            if camera_gone || lens_gone {
+                if camera_gone {
+                    state.grid_filter.camera = None;
+                }
+                if lens_gone {
+                    state.grid_filter.lens = None;
+                }
+                refresh_grid_filter(state);
+            }
+            return show_filtered_grid(state);
+        }
+        Message::SelectCamera(camera) => {
+            state.grid_filter.camera = camera;
+            refresh_grid_filter(state);
+            return show_filtered_grid(state);
+        }
+        Message::SelectLens(lens) => {
+            state.grid_filter.lens = lens;
+            refresh_grid_filter(state);
+            return show_filtered_grid(state);
+        }
+        Message::SelectOrientation(orientation) => {
+            state.grid_filter.orientation = orientation;
+            refresh_grid_filter(state);
+            return show_filtered_grid(state);
+        }
+        Message::SelectFlag(flag) => {
+            state.grid_filter.flag = flag;
+            refresh_grid_filter(state);
+            return show_filtered_grid(state);
+        }
+        Message::MinAspectChanged(input) => {
+            state.grid_filter.min_aspect = filter::parse_aspect(&input);
+            state.min_aspect_input = input;
+            refresh_grid_filter(state);
+            return show_filtered_grid(state);
+        }
+        Message::MaxAspectChanged(input) => {
+            state.grid_filter.max_aspect = filter::parse_aspect(&input);
+            state.max_aspect_input = input;
+            refresh_grid_filter(state);
+            return show_filtered_grid(state);
+        }
+        Message::SearchChanged(input) => {
+            let query = input.trim().to_lowercase();
+            state.grid_filter.search = (!query.is_empty()).then_some(query);
+            state.search_input = input;
+            refresh_grid_filter(state);
+            return show_filtered_grid(state);
+        }
+        Message::FocusSearch => {
+            if grid_showing(state) {
+                return iced::widget::operation::focus(search_id());
+            }
         }
         Message::WindowResized(width, height) => {
             let available = width - GRID_PADDING * 2.0;
@@ -816,37 +2408,49 @@ fn update(state: &mut Looky, message: Message) -> Task<Message> {
                 state.cached_metadata = None;
                 if !state.was_fullscreen {
                     state.fullscreen = false;
-                    return iced::window::latest()
-                        .and_then(|id| iced::window::set_mode(id, iced::window::Mode::Windowed));
+                    return window::set_mode(state.window, window::Mode::Windowed);
                 }
                 return Task::none();
+            } else if state.culling {
+                return stop_culling(state);
             } else if state.fullscreen {
                 state.fullscreen = false;
-                return iced::window::latest()
-                    .and_then(|id| iced::window::set_mode(id, iced::window::Mode::Windowed));
+                return window::set_mode(state.window, window::Mode::Windowed);
             } else if state.viewer.current_index.is_some() && state.viewer.is_zoomed() {
                 state.viewer.reset_zoom();
             } else if state.viewer.current_index.is_some() {
                 state.viewer.close();
                 state.cached_metadata = None;
                 return restore_grid_scroll(state);
+            } else if state.settings_view_active {
+                state.settings_view_active = false;
+            } else if state.trash_view_active {
+                state.trash_view_active = false;
+            } else if state.import_view_active {
+                state.import_view_active = false;
+            } else if state.library_dups_view_active {
+                state.library_dups_view_active = false;
+            } else if state.unreadable_view_active {
+                state.unreadable_view_active = false;
             } else if state.dup_compare.is_some() {
                 state.dup_compare = None;
             } else if state.dup_view_active {
                 state.dup_view_active = false;
             } else {
                 state.selected_thumb = None;
+                state.selection.clear();
+                state.batch_status = None;
             }
         }
         Message::KeyLeft => {
             if state.viewer.current_index.is_some() && state.viewer.is_zoomed() {
                 return pan_zoom(state, -30.0, 0.0);
             } else if state.viewer.current_index.is_some() {
-                state.viewer.prev();
-                state.selected_thumb = state.viewer.current_index;
-                refresh_metadata(state);
-                return preload_viewer_images(state);
-            } else if !state.dup_view_active && state.dup_compare.is_none() {
+                return step_viewer(state, -1);
+            } else if !state.dup_view_active
+                && state.dup_compare.is_none()
+                && !panel_showing(state)
+            {
                 return move_grid_selection(state, -1);
             }
         }
@@ -854,845 +2458,5120 @@ fn update(state: &mut Looky, message: Message) -> Task<Message> {
             if state.viewer.current_index.is_some() && state.viewer.is_zoomed() {
                 return pan_zoom(state, 30.0, 0.0);
             } else if state.viewer.current_index.is_some() {
-                state.viewer.next(state.image_paths.len());
-                state.selected_thumb = state.viewer.current_index;
-                refresh_metadata(state);
-                return preload_viewer_images(state);
-            } else if !state.dup_view_active && state.dup_compare.is_none() {
+                return step_viewer(state, 1);
+            } else if !state.dup_view_active
+                && state.dup_compare.is_none()
+                && !panel_showing(state)
+            {
                 return move_grid_selection(state, 1);
             }
         }
         Message::KeyUp => {
-            if state.viewer.current_index.is_some() && state.viewer.is_zoomed() {
+            if state.menu_open || state.cast_picker_open {
+                move_menu_focus(state, -1);
+            } else if state.dup_view_active && state.dup_compare.is_none() {
+                return move_dup_selection(state, -1);
+            } else if state.viewer.current_index.is_some() && state.viewer.is_pannable() {
                 return pan_zoom(state, 0.0, -30.0);
             } else if !state.dup_view_active
                 && state.dup_compare.is_none()
+                && !panel_showing(state)
                 && state.viewer.current_index.is_none()
             {
-                let cols = state.grid_columns.max(1) as i32;
-                return move_grid_selection(state, -cols);
+                return move_grid_rows(state, -1);
             }
         }
         Message::KeyDown => {
-            if state.viewer.current_index.is_some() && state.viewer.is_zoomed() {
+            if state.menu_open || state.cast_picker_open {
+                move_menu_focus(state, 1);
+            } else if state.dup_view_active && state.dup_compare.is_none() {
+                return move_dup_selection(state, 1);
+            } else if state.viewer.current_index.is_some() && state.viewer.is_pannable() {
                 return pan_zoom(state, 0.0, 30.0);
             } else if !state.dup_view_active
                 && state.dup_compare.is_none()
+                && !panel_showing(state)
                 && state.viewer.current_index.is_none()
             {
-                let cols = state.grid_columns.max(1) as i32;
-                return move_grid_selection(state, cols);
+                return move_grid_rows(state, 1);
             }
         }
         Message::KeyEnter => {
+            if state.menu_open || state.cast_picker_open {
+                let action = state
+                    .menu_focus
+                    .and_then(|i| menu_actions(state).into_iter().nth(i));
+                return action.map_or_else(Task::none, |msg| update(state, msg));
+            }
+            if state.dup_view_active && state.dup_compare.is_none() {
+                if let Some(group) = state.dup_selected.filter(|&g| g < state.dup_groups.len()) {
+                    return update(state, Message::CompareDuplicates(group));
+                }
+                return Task::none();
+            }
             if let Some(idx) = state.selected_thumb {
                 if state.viewer.current_index.is_none()
                     && !state.dup_view_active
                     && state.dup_compare.is_none()
+                    && !panel_showing(state)
                     && idx < state.thumbnails.len()
                 {
                     state.selected_thumb = Some(idx);
                     state.viewer.open_index(idx);
-                    refresh_metadata(state);
-                    return preload_viewer_images(state);
+                    return viewer_changed(state);
+                }
+            }
+        }
+        Message::CycleOrientationOverride => {
+            let Some(index) = state.viewer.current_index else {
+                return Task::none();
+            };
+            let path = state.image_paths[index].clone();
+            let current = thumbnail::orientation_override(&path);
+            let position = ORIENTATION_OVERRIDES.iter().position(|&(o, _)| o == current).unwrap_or(0);
+            let next = ORIENTATION_OVERRIDES[(position + 1) % ORIENTATION_OVERRIDES.len()].0;
+            thumbnail::set_orientation_override(&path, next);
+            if let Some(cat) = state.catalog.as_ref() {
+                cat.set_orientation_override(&path, next);
+            }
+            return redisplay_photo(state, index);
+        }
+        Message::StartEdit => {
+            let Some(index) = state.viewer.current_index else {
+                return Task::none();
+            };
+            if state.editing.is_some() || is_unreadable(state, index) {
+                return Task::none();
+            }
+            let path = &state.image_paths[index];
+            let saved = edit::edit_for(path);
+            state.editing = Some(EditSession {
+                index,
+                saved,
+                edit: saved.unwrap_or_default(),
+                aspect: CropAspect::Free,
+                status: None,
+            });
+            state.viewer.zoom_level = 1.0;
+            // Edit from the original; the saved edit comes back on cancel
+            if saved.is_some() {
+                edit::set_edit(path, None);
+                return redecode_viewer_image(state, index);
+            }
+        }
+        Message::SelectCropAspect(aspect) => {
+            let dims = editing_dimensions(state);
+            if let Some(session) = state.editing.as_mut() {
+                session.aspect = aspect;
+                if let Some((w, h)) = dims.and_then(|(w, h)| aspect.crop_size(1.0, w, h)) {
+                    session.edit = session.edit.with_crop_size(w, h);
                 }
             }
         }
+        Message::EditCropSize(size) => {
+            let dims = editing_dimensions(state);
+            if let Some(session) = state.editing.as_mut()
+                && let Some((w, h)) = dims.and_then(|(w, h)| session.aspect.crop_size(size, w, h))
+            {
+                session.edit = session.edit.with_crop_size(w, h);
+            }
+        }
+        Message::EditCropWidth(width) => {
+            if let Some(session) = state.editing.as_mut() {
+                session.edit = session.edit.with_crop_size(width, session.edit.crop[3]);
+            }
+        }
+        Message::EditCropHeight(height) => {
+            if let Some(session) = state.editing.as_mut() {
+                session.edit = session.edit.with_crop_size(session.edit.crop[2], height);
+            }
+        }
+        Message::EditStraighten(angle) => {
+            if let Some(session) = state.editing.as_mut() {
+                session.edit.angle = angle;
+            }
+        }
+        Message::ResetEdit => {
+            if let Some(session) = state.editing.as_mut() {
+                session.edit = PhotoEdit::default();
+                session.aspect = CropAspect::Free;
+            }
+        }
+        Message::SaveEdit => {
+            let Some(session) = state.editing.take() else {
+                return Task::none();
+            };
+            let path = &state.image_paths[session.index];
+            let saved = Some(session.edit).filter(|e| !e.is_identity());
+            edit::set_edit(path, saved);
+            if let Some(cat) = state.catalog.as_ref() {
+                cat.set_edit(path, saved.as_ref());
+            }
+            return redisplay_photo(state, session.index);
+        }
+        Message::CancelEdit => {
+            let Some(session) = state.editing.take() else {
+                return Task::none();
+            };
+            if session.saved.is_some() {
+                edit::set_edit(&state.image_paths[session.index], session.saved);
+                return redecode_viewer_image(state, session.index);
+            }
+        }
+        Message::SaveEditedCopy => {
+            let Some(session) = state.editing.as_ref() else {
+                return Task::none();
+            };
+            let stem = state.image_paths[session.index]
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let name = format!("{stem}-edited.jpg");
+            return Task::perform(
+                async move {
+                    rfd::AsyncFileDialog::new()
+                        .set_title(tr("Save edited copy"))
+                        .set_file_name(name)
+                        .add_filter("JPEG", &["jpg", "jpeg"])
+                        .save_file()
+                        .await
+                        .map(|handle| handle.path().to_path_buf())
+                },
+                Message::EditedCopyDestinationSelected,
+            );
+        }
+        Message::EditedCopyDestinationSelected(Some(dest)) => {
+            let Some(session) = state.editing.as_ref() else {
+                return Task::none();
+            };
+            let source = state.image_paths[session.index].clone();
+            let photo_edit = session.edit;
+            return Task::perform(
+                async move { edit::save_copy(&source, photo_edit, &dest).map(|()| dest) },
+                Message::EditedCopySaved,
+            );
+        }
+        Message::EditedCopyDestinationSelected(None) => {}
+        Message::EditedCopySaved(result) => {
+            if let Some(session) = state.editing.as_mut() {
+                session.status = Some(match result {
+                    Ok(dest) => trf("Saved to {}", &[&dest.display()]),
+                    Err(e) => {
+                        log::warn!("Failed to save edited copy: {}", e);
+                        trf("Couldn't save the copy: {}", &[&e])
+                    }
+                });
+            }
+        }
+        Message::ToggleDarkRoom => {
+            DARK_ROOM.store(!dark_room(), Ordering::Relaxed);
+        }
         Message::ToggleFullscreen => {
             state.fullscreen = !state.fullscreen;
             let mode = if state.fullscreen {
-                iced::window::Mode::Fullscreen
+                window::Mode::Fullscreen
             } else {
-                iced::window::Mode::Windowed
+                window::Mode::Windowed
             };
-            return iced::window::latest()
-                .and_then(move |id| iced::window::set_mode(id, mode));
+            return window::set_mode(state.window, mode);
         }
         Message::ToggleSharing => {
             if state.server_handle.is_some() {
-                // Stop sharing — also stop casting
-                if let Some(session) = state.cast_session.take() {
-                    session.stop();
-                }
-                state.cast_target_name = None;
-                state.cast_devices.clear();
-                state.cast_error = None;
-                if let Some(handle) = state.server_handle.take() {
-                    std::thread::spawn(move || handle.stop());
-                }
-                state.server_url = None;
-                state.qr_handle = None;
+                stop_sharing(state);
             } else if !state.image_paths.is_empty() {
-                // Start
-                let folder_name = state
-                    .folder
-                    .as_ref()
-                    .and_then(|p| p.file_name())
-                    .map(|n| n.to_string_lossy().to_string())
-                    .unwrap_or_else(|| "Photos".to_string());
-                if let Some((handle, url)) = server::start_server(
-                    state.image_paths.clone(),
-                    folder_name,
-                ) {
-                    state.qr_handle = Some(render_qr(&url));
-                    state.server_url = Some(url);
-                    state.server_handle = Some(handle);
-                }
+                start_sharing(state);
+            }
+        }
+        Message::ShareTick => {
+            if share_time_remaining(state).is_some_and(|left| left.is_zero()) {
+                log::info!("Share limit reached, stopping sharing");
+                stop_sharing(state);
+            }
+        }
+        Message::ToggleUploads => {
+            state.allow_uploads = !state.allow_uploads;
+            if let Some(handle) = state.server_handle.as_ref() {
+                handle.set_uploads_enabled(state.allow_uploads);
             }
         }
         Message::StartCastScan => {
-            state.cast_scanning = true;
-            state.cast_devices.clear();
-            state.cast_error = None;
-            return Task::perform(
-                async { server::cast::discover_devices() },
-                Message::CastDevicesFound,
-            );
+            return start_cast_scan(state);
         }
         Message::CastDevicesFound(devices) => {
             state.cast_scanning = false;
             state.cast_devices = devices;
         }
         Message::CastSelect(i) => {
-            if let Some(target) = state.cast_devices.get(i).cloned() {
-                state.cast_devices.clear();
-                state.cast_error = None;
-                let image_url = cast_image_url(state);
-                return Task::perform(
-                    async move {
-                        let session = server::cast::CastSession::connect(target)?;
-                        if let Some(url) = image_url {
-                            let _ = session.load_image(&url);
-                        }
-                        Ok::<_, String>(session)
-                    },
-                    |result| match result {
-                        Ok(session) => Message::CastConnected(session),
-                        Err(e) => {
-                            log::warn!("Cast connect failed: {e}");
-                            Message::StopCast
-                        }
-                    },
-                );
+            let Some(target) = state.cast_devices.get(i).cloned() else {
+                return Task::none();
+            };
+            let busy = state.cast_sessions.iter().any(|c| c.session.target.host == target.host)
+                || state.cast_connecting.iter().any(|t| t.host == target.host);
+            if busy {
+                return Task::none();
+            }
+            state.cast_error = None;
+            // The TV fetches images from the share server, so start it first.
+            if state.server_handle.is_none() {
+                start_sharing(state);
+                if let Some(e) = &state.share_error {
+                    state.cast_error = Some(trf("Couldn't start sharing: {}", &[&e]));
+                    return Task::none();
+                }
             }
+            state.cast_connecting.push(target.clone());
+            let image_url = cast_image_url(state);
+            return Task::perform(
+                async move {
+                    let session = server::cast::CastSession::connect(target.clone())
+                        .map_err(|e| (target, e))?;
+                    if let Some(url) = image_url {
+                        let _ = session.load_image(&url);
+                    }
+                    Ok(session)
+                },
+                |result| match result {
+                    Ok(session) => Message::CastConnected(session),
+                    Err((target, e)) => {
+                        log::warn!("Cast connect failed: {e}");
+                        Message::CastFailed(target, e)
+                    }
+                },
+            );
         }
         Message::CastConnected(session) => {
-            state.cast_target_name = Some(session.target.name.clone());
-            state.cast_session = Some(session);
+            let before = state.cast_connecting.len();
+            state.cast_connecting.retain(|t| t.host != session.target.host);
+            if state.cast_connecting.len() == before {
+                // Cancelled while connecting.
+                session.stop();
+                return Task::none();
+            }
+            let status = session.status();
+            state.cast_sessions.push(CastConnection { session, status });
+        }
+        Message::CastFailed(target, e) => {
+            // Ignore failures for a connection that was cancelled meanwhile.
+            let before = state.cast_connecting.len();
+            state.cast_connecting.retain(|t| t.host != target.host);
+            if state.cast_connecting.len() != before {
+                state.cast_error = Some(trf("Couldn't connect to {}: {}", &[&target.name, &e]));
+            }
+        }
+        Message::ToggleCastPicker => {
+            state.cast_picker_open = !state.cast_picker_open;
+            state.menu_open = false;
+            state.menu_focus = None;
+            let idle = state.cast_sessions.is_empty()
+                && state.cast_connecting.is_empty()
+                && !state.cast_scanning
+                && state.cast_devices.is_empty();
+            if state.cast_picker_open && idle {
+                return start_cast_scan(state);
+            }
+        }
+        Message::ToggleCastSlideshow => {
+            if state.cast_slideshow_active {
+                state.cast_slideshow_active = false;
+                // Hand the TV back to the viewer.
+                cast_current_image(state);
+            } else if !state.grid_shown.is_empty() {
+                let mut order = state.grid_shown.clone();
+                use rand::seq::SliceRandom;
+                order.shuffle(&mut rand::rng());
+                state.cast_slideshow_order = order;
+                state.cast_slideshow_position = 0;
+                state.cast_slideshow_active = true;
+                cast_slideshow_image(state);
+            }
+        }
+        Message::CastTick => {
+            for cast in &mut state.cast_sessions {
+                cast.status = cast.session.status();
+            }
+        }
+        Message::CastSlideshowAdvance => {
+            if !state.cast_slideshow_active {
+                return Task::none();
+            }
+            state.cast_slideshow_position += 1;
+            if state.cast_slideshow_position >= state.cast_slideshow_order.len() {
+                // Reshuffle and restart
+                use rand::seq::SliceRandom;
+                state.cast_slideshow_order.shuffle(&mut rand::rng());
+                state.cast_slideshow_position = 0;
+            }
+            cast_slideshow_image(state);
         }
         Message::CastImage => {
             cast_current_image(state);
         }
         Message::StopCast => {
-            if let Some(session) = state.cast_session.take() {
-                session.stop();
+            stop_casting(state);
+        }
+        Message::StopCastTo(i) => {
+            if i < state.cast_sessions.len() {
+                state.cast_sessions.remove(i).session.stop();
+            }
+            if state.cast_sessions.is_empty() {
+                state.cast_slideshow_active = false;
             }
-            state.cast_target_name = None;
-            state.cast_devices.clear();
-            state.cast_error = None;
         }
         Message::ToggleMenu => {
             state.menu_open = !state.menu_open;
+            state.share_url_copied = false;
+            state.cast_picker_open = false;
+            state.menu_focus = None;
         }
-    }
-    Task::none()
-}
-
-/// Build the HTTP URL for the current image, if casting is possible.
-fn cast_image_url(state: &Looky) -> Option<String> {
-    let idx = state.viewer.current_index.or(state.selected_thumb)?;
-    let url = state.server_url.as_ref()?;
-    let path = &state.image_paths[idx];
-    let filename = path
-        .file_name()
-        .map(|n| n.to_string_lossy().to_string())
-        .unwrap_or_default();
-    Some(format!("{url}/cast/{idx}/{filename}"))
-}
-
-fn cast_current_image(state: &Looky) {
-    let Some(session) = &state.cast_session else {
-        return;
-    };
-    let Some(image_url) = cast_image_url(state) else {
-        return;
-    };
-    if let Err(e) = session.load_image(&image_url) {
-        log::warn!("Cast send failed: {e}");
-    }
-}
-
-fn grid_scroll_id() -> iced::widget::Id {
-    iced::widget::Id::new("grid")
-}
-
-fn dup_list_scroll_id() -> iced::widget::Id {
-    iced::widget::Id::new("dup-list")
-}
-
-fn move_grid_selection(state: &mut Looky, delta: i32) -> Task<Message> {
-    let count = state.thumbnails.len();
-    if count == 0 {
-        return Task::none();
-    }
-    let current = state.selected_thumb.unwrap_or(0) as i32;
-    let next = (current + delta).clamp(0, count as i32 - 1) as usize;
-    state.selected_thumb = Some(next);
-    scroll_to_thumb(state, next)
-}
-
-fn scroll_to_thumb(state: &Looky, index: usize) -> Task<Message> {
-    let cols = state.grid_columns.max(1);
-    let row = index / cols;
-    let row_top = GRID_PADDING + row as f32 * THUMB_CELL;
-    let row_bottom = row_top + THUMB_CELL;
-
-    // Toolbar height is roughly 50px; visible area starts after that.
-    // We just ensure the row is within the scroll viewport.
-    // If the row is above the current scroll, scroll up to it.
-    // If it's below, scroll down so it's visible.
-    // We don't know the viewport height exactly, so use a conservative estimate.
-    let viewport = state.viewport_height;
-    let target = if row_top < state.grid_scroll_y {
-        row_top
-    } else if row_bottom > state.grid_scroll_y + viewport {
-        row_bottom - viewport
-    } else {
-        return Task::none();
-    };
-
-    use iced::widget::operation::AbsoluteOffset;
-    iced::widget::operation::scroll_to(
-        grid_scroll_id(),
-        AbsoluteOffset {
-            x: None,
+        Message::FocusNext | Message::FocusPrev => {
+            let step = if matches!(message, Message::FocusNext) { 1 } else { -1 };
+            if state.menu_open || state.cast_picker_open {
+                move_menu_focus(state, step);
+            } else if state.settings_view_active {
+                return if step > 0 {
+                    iced::widget::operation::focus_next()
+                } else {
+                    iced::widget::operation::focus_previous()
+                };
+            } else if state.dup_view_active && state.dup_compare.is_none() {
+                return move_dup_selection(state, step);
+            } else if !state.dup_view_active
+                && !panel_showing(state)
+                && state.viewer.current_index.is_none()
+            {
+                return move_grid_selection(state, step as i32);
+            }
+        }
+        Message::ShowSettings => {
+            state.settings_view_active = true;
+            state.share_interfaces = server::list_interfaces();
+        }
+        Message::BackFromSettings => {
+            state.settings_view_active = false;
+        }
+        Message::TrashPhoto => {
+            if state.dup_view_active && state.dup_compare.is_none() {
+                return match state.dup_selected {
+                    Some(group_idx) => update(state, Message::KeepLargestOfGroup(group_idx)),
+                    None => Task::none(),
+                };
+            }
+            return trash_photo(state);
+        }
+        Message::SharePhoto => {
+            let idx = state
+                .viewer
+                .current_index
+                .or(state.selected_thumb.filter(|_| grid_showing(state)));
+            let Some(path) = idx.and_then(|i| state.image_paths.get(i)).cloned() else {
+                return Task::none();
+            };
+            state.share_sheet_error = None;
+            return Task::perform(share::share_files(vec![path]), Message::ShareDone);
+        }
+        Message::ModifiersChanged(modifiers) => {
+            state.modifiers = modifiers;
+        }
+        Message::ClearSelection => {
+            state.selection.clear();
+            state.batch_status = None;
+        }
+        Message::TransferSelection(op) => {
+            let title = match op {
+                Transfer::Copy => tr("Select where to copy the photos"),
+                Transfer::Move => tr("Select where to move the photos"),
+            };
+            return Task::perform(pick_folder_titled(title), move |dest| {
+                Message::TransferDestinationSelected(op, dest)
+            });
+        }
+        Message::TransferDestinationSelected(op, Some(dest)) => {
+            return start_file_job(state, FileJobKind::Transfer(op, dest));
+        }
+        Message::TransferDestinationSelected(_, None) => {}
+        Message::FileBatchDone(id, outcomes) => {
+            // Cancelled from the status bar
+            let Some(job) = state.file_jobs.iter_mut().find(|j| j.id == id) else {
+                return Task::none();
+            };
+            job.in_flight -= 1;
+            for (index, outcome) in outcomes {
+                match outcome {
+                    Outcome::Done(to) => job.done.push((index, to)),
+                    Outcome::Trashed(photo) => {
+                        // Recorded right away, so the trash can restore it
+                        // even if Looky is closed before the job ends
+                        if let Some(cat) = state.catalog.as_ref() {
+                            cat.add_trashed(&photo);
+                        }
+                        state.trashed.insert(photo.original_path);
+                        job.done.push((index, photo.trashed_path));
+                    }
+                    Outcome::Skipped => job.skipped += 1,
+                    Outcome::Conflict => job.conflicts.push((index, state.image_paths[index].clone())),
+                    Outcome::Failed(e) => job.failed.push((index, e)),
+                }
+            }
+            if job.paused {
+                return Task::none();
+            }
+            if !job.pending.is_empty() {
+                return load_next_file_batch(state, id);
+            }
+            // Conflicts wait for the dialog
+            if job.conflicts.is_empty() && job.in_flight == 0 {
+                finish_file_job(state, id);
+            }
+        }
+        Message::ResolveConflict(choice) => {
+            let Some(job) = state.file_jobs.iter_mut().find(|j| j.awaiting_choice()) else {
+                return Task::none();
+            };
+            let FileJobKind::Transfer(op, dest) = job.kind.clone() else {
+                return Task::none();
+            };
+            let count = if job.same_for_all { job.conflicts.len() } else { 1 };
+            let items: Vec<(usize, PathBuf)> = job.conflicts.drain(..count.min(job.conflicts.len())).collect();
+            job.in_flight += 1;
+            let id = job.id;
+            let done = state.files.submit(id, move |cancelled| {
+                file_ops::transfer(&items, &dest, op, Some(choice), cancelled)
+            });
+            return Task::perform(async move { done.await.unwrap_or_default() }, move |outcomes| {
+                Message::FileBatchDone(id, outcomes)
+            });
+        }
+        Message::SetConflictSameForAll(on) => {
+            if let Some(job) = state.file_jobs.iter_mut().find(|j| j.awaiting_choice()) {
+                job.same_for_all = on;
+            }
+        }
+        Message::ToggleFileJobPause(id) => {
+            let Some(job) = state.file_jobs.iter_mut().find(|j| j.id == id) else {
+                return Task::none();
+            };
+            job.paused = !job.paused;
+            if !job.paused && job.in_flight == 0 {
+                if !job.pending.is_empty() {
+                    return load_next_file_batch(state, id);
+                }
+                if job.conflicts.is_empty() {
+                    finish_file_job(state, id);
+                }
+            }
+        }
+        Message::CancelFileJob(id) => {
+            let Some(job) = state.file_jobs.iter_mut().find(|j| j.id == id) else {
+                return Task::none();
+            };
+            job.skipped += job.pending.len() + job.conflicts.len();
+            job.pending.clear();
+            job.conflicts.clear();
+            state.files.cancel(id);
+            // Otherwise the batch under way finishes it
+            if job.in_flight == 0 {
+                finish_file_job(state, id);
+            }
+        }
+        Message::TrashSelection => {
+            return start_file_job(state, FileJobKind::Trash);
+        }
+        Message::ShareSelection => {
+            let paths: Vec<PathBuf> = selected_indices(state)
+                .into_iter()
+                .map(|i| state.image_paths[i].clone())
+                .collect();
+            state.share_sheet_error = None;
+            return Task::perform(share::share_files(paths), Message::ShareDone);
+        }
+        Message::ShareDone(result) => {
+            state.share_sheet_error = result.err().map(|e| {
+                log::warn!("Failed to open the share sheet: {}", e);
+                trf("Couldn't share: {}", &[&e])
+            });
+        }
+        Message::ShowTrash => {
+            state.trash_view_active = true;
+            state.trash_error = None;
+            state.trash_items = state.catalog.as_ref().map(Catalog::trashed).unwrap_or_default();
+        }
+        Message::BackFromTrash => {
+            state.trash_view_active = false;
+        }
+        Message::ShowLibraryDuplicates => {
+            state.library_dups_view_active = true;
+            let sets = state.catalog.as_ref().map(Catalog::same_content_files).unwrap_or_default();
+            state.library_dups = duplicates::group_by_folders(sets);
+        }
+        Message::BackFromLibraryDuplicates => {
+            state.library_dups_view_active = false;
+        }
+        Message::ShowUnreadable => {
+            state.unreadable_view_active = true;
+        }
+        Message::BackFromUnreadable => {
+            state.unreadable_view_active = false;
+        }
+        Message::RetryUnreadable => {
+            let paths: Vec<PathBuf> = state.unreadable.keys().cloned().collect();
+            state.thumb_retries.clear();
+            return retry_thumbnails(state, paths);
+        }
+        Message::ThumbnailRetryTick => {
+            let now = Instant::now();
+            let due: Vec<PathBuf> = state
+                .thumb_retries
+                .iter_mut()
+                .filter(|(_, (_, at))| at.is_some_and(|at| at <= now))
+                .map(|(path, (_, at))| {
+                    *at = None;
+                    path.clone()
+                })
+                .collect();
+            return retry_thumbnails(state, due);
+        }
+        Message::RestoreTrashed(trashed_path) => {
+            let Some(pos) = state.trash_items.iter().position(|p| p.trashed_path == trashed_path)
+            else {
+                return Task::none();
+            };
+            match trash::restore(&state.trash_items[pos]) {
+                Ok(()) => {
+                    let photo = state.trash_items.remove(pos);
+                    if let Some(cat) = state.catalog.as_ref() {
+                        cat.remove_trashed(&photo.trashed_path);
+                    }
+                    // Photos trashed before this folder was opened come back
+                    // through the watcher instead
+                    if state.trashed.remove(&photo.original_path) {
+                        refresh_grid_filter(state);
+                    }
+                    state.trash_error = None;
+                }
+                Err(e) => {
+                    log::warn!("Failed to restore {}: {}", trashed_path.display(), e);
+                    state.trash_error = Some(trf("Couldn't restore: {}", &[&e]));
+                }
+            }
+        }
+        Message::DeleteTrashedForever(trashed_path) => {
+            let Some(pos) = state.trash_items.iter().position(|p| p.trashed_path == trashed_path)
+            else {
+                return Task::none();
+            };
+            purge_trashed(state, pos);
+        }
+        Message::EmptyTrash => {
+            for pos in (0..state.trash_items.len()).rev() {
+                purge_trashed(state, pos);
+            }
+        }
+        Message::CheckAgainstLibrary => return start_library_check(state),
+        Message::LibraryHashBatchReady(results) => {
+            // Cancelled, or the folder changed
+            if state.library_job.is_none() {
+                return Task::none();
+            }
+            let folder = state.folder.clone().unwrap_or_default();
+            let mut found = false;
+            for (idx, hash) in results {
+                let (Some(hash), Some(path)) = (hash, state.image_paths.get(idx)) else {
+                    continue;
+                };
+                if let Some(cat) = state.catalog.as_ref() {
+                    if let Some((size, mtime)) = catalog::file_size_and_mtime_for(path) {
+                        cat.insert_content_hash(path, size, mtime, &hash);
+                    }
+                    if cat.has_content_hash_outside(&hash, &folder) {
+                        found |= state.imported.insert(idx);
+                    }
+                }
+            }
+            if found && state.grid_filter.new_only {
+                refresh_grid_filter(state);
+            }
+            if state.library_pending.is_empty() {
+                state.library_job = None;
+                return Task::none();
+            }
+            return load_next_library_batch(state);
+        }
+        Message::CancelLibraryCheck => {
+            state.library_job = None;
+            state.library_pending.clear();
+        }
+        Message::SetNewOnly(new_only) => {
+            state.grid_filter.new_only = new_only;
+            refresh_grid_filter(state);
+            let shown = show_filtered_grid(state);
+            if new_only && !state.library_checked {
+                return Task::batch([shown, start_library_check(state)]);
+            }
+            return shown;
+        }
+        Message::ExportGallery => {
+            return Task::perform(
+                pick_folder_titled(tr("Select where to save the gallery")),
+                Message::ExportDestinationSelected,
+            );
+        }
+        Message::ExportDestinationSelected(Some(dest)) => {
+            if state.export_job.is_some() {
+                return Task::none();
+            }
+            // Just the picked photos when there are some
+            let selected = selected_indices(state);
+            let photos = if selected.is_empty() { &state.grid_shown[..] } else { &selected[..] };
+            state.export_pending = photos
+                .iter()
+                .enumerate()
+                .map(|(n, &i)| (n + 1, state.image_paths[i].clone()))
+                .collect();
+            state.export_total = state.export_pending.len();
+            state.export_written.clear();
+            state.export_dest = Some(dest);
+            state.export_status = None;
+            state.export_job = Some(JobProgress::start(0));
+            state.export_id = state.files.new_job();
+            state.export_paused = false;
+            return load_next_export_batch(state);
+        }
+        Message::ExportDestinationSelected(None) => {}
+        Message::ExportBatchDone(written) => {
+            // Cancelled from the status bar
+            if state.export_job.is_none() {
+                return Task::none();
+            }
+            state.export_in_flight = false;
+            state.export_written.extend(written);
+            if state.export_paused {
+                return Task::none();
+            }
+            if !state.export_pending.is_empty() {
+                return load_next_export_batch(state);
+            }
+            finish_export(state);
+        }
+        Message::ToggleExportPause => {
+            state.export_paused = !state.export_paused;
+            if state.export_paused || state.export_in_flight || state.export_job.is_none() {
+                return Task::none();
+            }
+            if !state.export_pending.is_empty() {
+                return load_next_export_batch(state);
+            }
+            finish_export(state);
+        }
+        Message::CancelExport => {
+            state.files.cancel(state.export_id);
+            state.export_job = None;
+            state.export_in_flight = false;
+            state.export_pending.clear();
+            state.export_written.clear();
+            state.export_dest = None;
+        }
+        Message::ExportContactSheet => {
+            let name = format!("{}.pdf", folder_title(state));
+            return Task::perform(
+                async move {
+                    rfd::AsyncFileDialog::new()
+                        .set_title(tr("Save contact sheet"))
+                        .set_file_name(name)
+                        .add_filter("PDF", &["pdf"])
+                        .save_file()
+                        .await
+                        .map(|handle| handle.path().to_path_buf())
+                },
+                Message::ContactSheetDestinationSelected,
+            );
+        }
+        Message::ContactSheetDestinationSelected(Some(dest)) => {
+            if state.contact_sheet_job.is_some() {
+                return Task::none();
+            }
+            state.contact_sheet_pending = state
+                .grid_shown
+                .iter()
+                .enumerate()
+                .map(|(n, &i)| (n, state.image_paths[i].clone()))
+                .collect();
+            state.contact_sheet_total = state.contact_sheet_pending.len();
+            state.contact_sheet_photos.clear();
+            state.contact_sheet_dest = Some(dest);
+            state.contact_sheet_status = None;
+            state.contact_sheet_job = Some(JobProgress::start(0));
+            return load_next_contact_sheet_batch(state);
+        }
+        Message::ContactSheetDestinationSelected(None) => {}
+        Message::ContactSheetBatchDone(photos) => {
+            // Cancelled from the status bar
+            if state.contact_sheet_job.is_none() {
+                return Task::none();
+            }
+            state.contact_sheet_photos.extend(photos);
+            if !state.contact_sheet_pending.is_empty() {
+                return load_next_contact_sheet_batch(state);
+            }
+            let Some(dest) = state.contact_sheet_dest.take() else {
+                return Task::none();
+            };
+            let mut photos = std::mem::take(&mut state.contact_sheet_photos);
+            photos.sort_by_key(|(n, _)| *n);
+            let photos: Vec<SheetPhoto> = photos.into_iter().map(|(_, photo)| photo).collect();
+            let (columns, rows) = state
+                .settings
+                .contact_sheet_grid
+                .unwrap_or(DEFAULT_CONTACT_SHEET_GRID);
+            let title = folder_title(state);
+            // The job stays in the status bar until the file is written
+            return Task::perform(
+                async move {
+                    contact_sheet::write_pdf(&dest, &title, columns, rows, &photos)
+                        .map(|()| dest)
+                        .map_err(|e| e.to_string())
+                },
+                Message::ContactSheetSaved,
+            );
+        }
+        Message::ContactSheetSaved(result) => {
+            if state.contact_sheet_job.take().is_none() {
+                return Task::none();
+            }
+            state.contact_sheet_status = Some(match result {
+                Ok(dest) => trf("Contact sheet saved to {}", &[&dest.display()]),
+                Err(e) => {
+                    log::warn!("Failed to write contact sheet: {}", e);
+                    trf("Couldn't save the contact sheet: {}", &[&e])
+                }
+            });
+        }
+        Message::CancelContactSheet => {
+            state.contact_sheet_job = None;
+            state.contact_sheet_pending.clear();
+            state.contact_sheet_photos.clear();
+            state.contact_sheet_dest = None;
+        }
+        Message::SelectContactSheetGrid(grid) => {
+            state.settings.contact_sheet_grid = grid;
+            state.settings.save();
+        }
+        Message::ShowImport => {
+            state.import_view_active = true;
+            state.import_volumes = import::find_volumes();
+            // Go straight to the only card there is
+            if let ([volume], None) = (state.import_volumes.as_slice(), &state.import_source) {
+                return update(state, Message::ImportSourceSelected(Some(volume.clone())));
+            }
+        }
+        Message::BackFromImport => {
+            state.import_view_active = false;
+        }
+        Message::RefreshImportVolumes => {
+            state.import_volumes = import::find_volumes();
+        }
+        Message::PickImportSource => {
+            return Task::perform(
+                pick_folder_titled(tr("Select a card or folder to import from")),
+                Message::ImportSourceSelected,
+            );
+        }
+        Message::ImportSourceSelected(Some(source)) => {
+            if state.import_job.is_some() {
+                return Task::none();
+            }
+            state.import_source = Some(source.clone());
+            state.import_items.clear();
+            state.import_scanning = true;
+            state.import_status = None;
+            return Task::perform(import::scan_source(source.clone()), move |items| {
+                Message::ImportScanReady(source.clone(), items)
+            });
+        }
+        Message::ImportSourceSelected(None) => {}
+        Message::ImportScanReady(source, mut items) => {
+            // Another source was picked meanwhile
+            if state.import_source.as_ref() != Some(&source) {
+                return Task::none();
+            }
+            if let Some(cat) = state.catalog.as_ref() {
+                for item in &mut items {
+                    item.already_imported =
+                        cat.has_content_hash_outside(&item.content_hash, &source);
+                }
+            }
+            state.import_items = items;
+            state.import_scanning = false;
+        }
+        Message::PickImportDestination => {
+            return Task::perform(
+                pick_folder_titled(tr("Select where to import to")),
+                Message::ImportDestinationSelected,
+            );
+        }
+        Message::ImportDestinationSelected(Some(dest)) => {
+            state.settings.import_destination = Some(dest);
+            state.settings.save();
+        }
+        Message::ImportDestinationSelected(None) => {}
+        Message::SetEjectAfterImport(eject) => {
+            state.settings.eject_after_import = eject;
+            state.settings.save();
+        }
+        Message::StartImport => {
+            if state.import_job.is_some() || import_destination(state).is_none() {
+                return Task::none();
+            }
+            state.import_pending = state
+                .import_items
+                .iter()
+                .filter(|item| !item.already_imported)
+                .cloned()
+                .collect();
+            state.import_total = state.import_pending.len();
+            state.import_done = 0;
+            state.import_failed = 0;
+            state.import_status = None;
+            state.import_cancelled = false;
+            state.import_job = Some(JobProgress::start(0));
+            return load_next_import_batch(state);
+        }
+        Message::ImportBatchDone(results) => {
+            for (item, result) in results {
+                match result {
+                    Ok(path) => {
+                        state.import_done += 1;
+                        if let (Some(cat), Some((size, mtime))) =
+                            (state.catalog.as_ref(), catalog::file_size_and_mtime_for(&path))
+                        {
+                            cat.insert_content_hash(&path, size, mtime, &item.content_hash);
+                        }
+                        if let Some(imported) =
+                            state.import_items.iter_mut().find(|i| i.path == item.path)
+                        {
+                            imported.already_imported = true;
+                        }
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to import {}: {}", item.path.display(), e);
+                        state.import_failed += 1;
+                    }
+                }
+            }
+            if !state.import_pending.is_empty() {
+                return load_next_import_batch(state);
+            }
+            state.import_job = None;
+            if std::mem::take(&mut state.import_cancelled) {
+                state.import_status = Some(trf(
+                    "Import cancelled after {} of {} photos",
+                    &[&state.import_done, &state.import_total],
+                ));
+                return Task::none();
+            }
+            state.import_status = Some(if state.import_failed > 0 {
+                trf(
+                    "Imported {} photos, {} failed",
+                    &[&state.import_done, &state.import_failed],
+                )
+            } else {
+                trn(state.import_done, "Imported {} photo", "Imported {} photos")
+            });
+            let card = state
+                .import_source
+                .clone()
+                .filter(|source| state.import_volumes.contains(source));
+            if let Some(card) = card.filter(|_| state.settings.eject_after_import) {
+                return Task::perform(import::eject(card), Message::EjectDone);
+            }
+        }
+        Message::CancelImport => {
+            // No more batches; the one under way is recorded when it's done
+            state.import_pending.clear();
+            state.import_cancelled = state.import_job.is_some();
+        }
+        Message::EjectDone(Ok(())) => {
+            state.import_source = None;
+            state.import_items.clear();
+            state.import_volumes = import::find_volumes();
+            state.import_status = Some(tr("Card ejected").to_string());
+        }
+        Message::EjectDone(Err(e)) => {
+            log::warn!("Failed to eject: {}", e);
+            state.import_status = Some(trf("Couldn't eject the card: {}", &[&e]));
+        }
+        Message::SelectShareInterface(choice) => {
+            // 0 is "Automatic"; the rest index into the listed interface names.
+            state.settings.share_interface = choice
+                .checked_sub(1)
+                .and_then(|i| share_interface_names(state).get(i).cloned());
+            state.settings.save();
+        }
+        Message::ShareNameChanged(input) => {
+            let name = input.trim();
+            state.settings.share_name = (!name.is_empty()).then(|| name.to_string());
+            state.share_name_input = input;
+            state.settings.save();
+        }
+        Message::TimeZoneChanged(input) => {
+            let name = input.trim();
+            let zone = (!name.is_empty()).then(|| name.to_string());
+            // Kept as typed, but only saved once it names a zone
+            if metadata::set_time_zone(zone.as_deref()) {
+                state.settings.time_zone = zone;
+                state.settings.save();
+            }
+            state.time_zone_input = input;
+        }
+        Message::SharePortChanged(input) => {
+            let digits: String = input.chars().filter(|c| c.is_ascii_digit()).take(5).collect();
+            state.settings.share_port = digits.parse().ok().filter(|&p: &u16| p > 0);
+            state.share_port_input = digits;
+            state.settings.save();
+        }
+        Message::SelectShareTimeLimit(minutes) => {
+            state.settings.share_time_limit = minutes;
+            state.settings.save();
+        }
+        Message::ToggleQrOverlay => {
+            state.qr_overlay_open = !state.qr_overlay_open && state.qr_handle.is_some();
+        }
+        Message::CopyShareUrl => {
+            if let Some(url) = &state.server_url {
+                state.share_url_copied = true;
+                return iced::clipboard::write(url.clone());
+            }
+        }
+        Message::OpenShareUrl => {
+            if let Some(url) = &state.server_url {
+                open_url(url);
+            }
+        }
+        Message::SetShareAvifThumbs(enabled) => {
+            state.settings.share_avif_thumbs = enabled;
+            state.settings.save();
+        }
+        Message::SelectShareThumbCacheSize(mb) => {
+            state.settings.share_thumb_cache_mb = mb;
+            state.settings.save();
+        }
+        Message::SelectShareHttpWorkers(workers) => {
+            state.settings.share_http_workers = workers;
+            state.settings.save();
+        }
+        Message::SelectShareIdleLimit(minutes) => {
+            state.settings.share_idle_limit = minutes;
+            state.settings.save();
+        }
+        Message::SelectPreloadRadius(radius) => {
+            state.settings.viewer_preload_radius = radius;
+            state.settings.save();
+        }
+        Message::SelectViewerCacheSize(mb) => {
+            state.settings.viewer_cache_mb = mb;
+            state.settings.save();
+        }
+        Message::SelectGridCacheSize(mb) => {
+            state.settings.grid_cache_mb = mb;
+            state.settings.save();
+            enforce_thumbnail_budget(state);
+        }
+        Message::SelectGridLayout(layout) => {
+            state.settings.grid_layout = Some(layout).filter(|&l| l != GridLayout::Square);
+            state.settings.save();
+        }
+        Message::SetViewerWheelNavigates(on) => {
+            state.settings.viewer_wheel_navigates = on;
+            state.settings.save();
+        }
+        Message::SetGridRatingBadges(on) => {
+            state.settings.grid_hide_ratings = !on;
+            state.settings.save();
+        }
+        Message::SetGridSharpen(on) => {
+            state.settings.grid_sharpen = on;
+            state.settings.save();
+            apply_sharpening(&state.settings);
+            return regenerate_thumbnails(state);
+        }
+        Message::SelectGridSharpenAmount(percent) => {
+            state.settings.grid_sharpen_percent = percent;
+            state.settings.save();
+            apply_sharpening(&state.settings);
+            if state.settings.grid_sharpen {
+                return regenerate_thumbnails(state);
+            }
+        }
+        Message::SetHighContrast(on) => {
+            state.settings.high_contrast = on;
+            state.settings.save();
+            set_high_contrast(on);
+        }
+        Message::SelectLanguage(language) => {
+            state.settings.language = language;
+            state.settings.save();
+            i18n::set_language(language.unwrap_or_else(Language::system));
+        }
+        Message::SelectFitMode(mode) => {
+            state.viewer.fit_mode = mode;
+            state.settings.viewer_fit_mode = Some(mode).filter(|&m| m != FitMode::Fit);
+            state.settings.save();
+            if state.viewer.current_index.is_some() {
+                state.viewer.reset_zoom();
+                return Task::done(Message::CenterZoomScroll);
+            }
+        }
+        Message::SelectViewerBackground(background) => {
+            state.settings.viewer_background = Some(background).filter(|&b| b != Background::Theme);
+            state.settings.save();
+        }
+        Message::ZoomPresetsChanged(input) => {
+            state.settings.viewer_zoom_presets = ZoomPreset::parse_list(&input)
+                .filter(|presets| presets.as_slice() != viewer_state::DEFAULT_ZOOM_PRESETS);
+            state.zoom_presets_input = input;
+            state.settings.save();
+        }
+        Message::SelectMaxZoom(zoom) => {
+            state.settings.viewer_max_zoom = zoom;
+            state.settings.save();
+            state.viewer.max_zoom = max_zoom(&state.settings);
+            if state.viewer.zoom_target > state.viewer.max_zoom {
+                state.viewer.zoom_target = state.viewer.max_zoom;
+            }
+        }
+        Message::FindPeers => {
+            state.peer_scanning = true;
+            state.peers.clear();
+            let own_uuid = state
+                .server_handle
+                .as_ref()
+                .map(|h| h.device_uuid().to_string());
+            return Task::perform(
+                async move { server::mdns::discover_peers(own_uuid.as_deref()) },
+                Message::PeersFound,
+            );
+        }
+        Message::PeersFound(peers) => {
+            state.peer_scanning = false;
+            state.peers = peers;
+        }
+        Message::SessionTick => {
+            save_session(state);
+        }
+        Message::WatcherTick => {
+            let (new_paths, changed_paths) = collect_watched_images(state);
+            if let Some(handle) = state.server_handle.as_ref() {
+                let (present, gone): (Vec<PathBuf>, Vec<PathBuf>) =
+                    changed_paths.into_iter().partition(|p| p.is_file());
+                handle.remove_images(&gone);
+                handle.restore_images(&present);
+            }
+            if !new_paths.is_empty() {
+                return add_new_images(state, new_paths);
+            }
+        }
+    }
+    Task::none()
+}
+
+/// Watch the open folder for changes as deep as it's scanned.
+fn watch_folder(state: &mut Looky) {
+    let depth = scan_depth(state);
+    state.watcher = state.folder.as_deref().and_then(|folder| {
+        FolderWatcher::new(folder, depth)
+            .map_err(|e| log::warn!("Failed to watch {}: {}", folder.display(), e))
+            .ok()
+    });
+}
+
+/// Drain watcher events and return image files that have settled since they
+/// appeared, plus already-known images whose files changed (deleted or back).
+fn collect_watched_images(state: &mut Looky) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let Some(watcher) = state.watcher.as_ref() else {
+        return (Vec::new(), Vec::new());
+    };
+    let now = Instant::now();
+    while let Ok(event) = watcher.events.try_recv() {
+        let Ok(event) = event else {
+            continue;
+        };
+        use notify::event::{EventKind, ModifyKind};
+        if !matches!(
+            event.kind,
+            EventKind::Create(_)
+                | EventKind::Remove(_)
+                | EventKind::Modify(ModifyKind::Name(_) | ModifyKind::Data(_))
+        ) {
+            continue;
+        }
+        let depth = scan_depth(state);
+        for path in event.paths {
+            if is_image_file(&path)
+                && !in_excluded_folder(state.folder.as_deref(), &path)
+                && within_depth(state.folder.as_deref(), depth, &path)
+            {
+                state.watcher_pending.insert(path, now);
+            }
+        }
+    }
+
+    if state.watcher_pending.is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+    let known: HashSet<&PathBuf> = state.image_paths.iter().collect();
+    let mut ready = Vec::new();
+    let mut changed = Vec::new();
+    state.watcher_pending.retain(|path, last_event| {
+        if last_event.elapsed() < WATCHER_SETTLE {
+            return true;
+        }
+        if known.contains(path) {
+            changed.push(path.clone());
+        } else if path.is_file() {
+            ready.push(path.clone());
+        }
+        false
+    });
+    ready.sort();
+    (ready, changed)
+}
+
+/// Append newly discovered images to the grid and the active share.
+fn add_new_images(state: &mut Looky, paths: Vec<PathBuf>) -> Task<Message> {
+    log::info!("Found {} new image(s)", paths.len());
+    if let Some(handle) = state.server_handle.as_ref() {
+        handle.add_images(&paths);
+    }
+    // Thumbnails are appended in order, so only start a new preview chain if
+    // the previous one has finished; otherwise it picks these up itself.
+    let chain_idle = state.pending_thumbnails.is_empty()
+        && state.thumbnails.len() == state.image_paths.len();
+    state.image_paths.extend(paths.iter().cloned());
+    state.pending_thumbnails.extend(paths.iter().cloned());
+    let index = start_indexing(state, paths);
+    if chain_idle {
+        state.loading = true;
+        state.thumbs_cancelled = false;
+        state.thumb_job = Some(JobProgress::start(state.thumbs_done));
+        return Task::batch([load_next_preview_batch(state), index]);
+    }
+    index
+}
+
+/// Bring the grid in line with a fresh walk of the folder. Indices stay put:
+/// files that are gone are hidden like moved ones, files back again are
+/// shown, and new ones are appended, so thumbnails, the selection and the
+/// duplicate state of everything else are kept.
+fn apply_rescan(state: &mut Looky, paths: Vec<PathBuf>) -> Task<Message> {
+    let found: HashSet<&PathBuf> = paths.iter().collect();
+    let mut removed = Vec::new();
+    let mut returned = Vec::new();
+    for (i, path) in state.image_paths.iter().enumerate() {
+        if state.trashed.contains(path) {
+            continue;
+        }
+        if !found.contains(path) {
+            if state.moved_away.insert(path.clone()) {
+                removed.push(i);
+            }
+        } else if state.moved_away.remove(path) {
+            returned.push(path.clone());
+        }
+    }
+    let known: HashSet<&PathBuf> = state.image_paths.iter().collect();
+    let new_paths: Vec<PathBuf> = paths.iter().filter(|p| !known.contains(p)).cloned().collect();
+
+    if let Some(handle) = state.server_handle.as_ref() {
+        let gone: Vec<PathBuf> = removed.iter().map(|&i| state.image_paths[i].clone()).collect();
+        handle.remove_images(&gone);
+        handle.restore_images(&returned);
+    }
+    state.selection.retain(|i| !removed.contains(i));
+    if removed.is_empty() {
+        if !returned.is_empty() {
+            refresh_grid_filter(state);
+        }
+    } else {
+        forget_removed(state, &removed);
+    }
+    let added = new_paths.len() + returned.len();
+    state.batch_status = Some(if added == 0 && removed.is_empty() {
+        tr("The folder is up to date").to_string()
+    } else {
+        trf("{} added, {} removed", &[&added, &removed.len()])
+    });
+    if new_paths.is_empty() {
+        return Task::none();
+    }
+    for path in &new_paths {
+        state.watcher_pending.remove(path);
+    }
+    add_new_images(state, new_paths)
+}
+
+fn start_cast_scan(state: &mut Looky) -> Task<Message> {
+    state.cast_scanning = true;
+    state.cast_devices.clear();
+    state.cast_error = None;
+    Task::perform(
+        async { server::cast::discover_devices() },
+        Message::CastDevicesFound,
+    )
+}
+
+/// Build the HTTP URL for the current image, if casting is possible.
+fn cast_image_url(state: &Looky) -> Option<String> {
+    cast_url_for(state, state.viewer.current_index.or(state.selected_thumb)?)
+}
+
+fn cast_url_for(state: &Looky, idx: usize) -> Option<String> {
+    let url = state.server_url.as_ref()?;
+    let path = state.image_paths.get(idx)?;
+    // The display endpoint always serves JPEG, so name it accordingly for the
+    // receiver's content-type guess.
+    let stem = path
+        .file_stem()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let id = server::photo_id(path);
+    let v = thumbnail::version_token(path);
+    Some(format!("{url}/display/{id}/{stem}.jpg?w={CAST_DISPLAY_WIDTH}&v={v}"))
+}
+
+/// Push the current image to the TV, unless the TV is running its own slideshow.
+fn cast_current_image(state: &Looky) {
+    if state.cast_slideshow_active {
+        return;
+    }
+    if let Some(image_url) = cast_image_url(state) {
+        send_to_cast(state, &image_url);
+    }
+    // The screensaver knows what comes next, so have it ready to serve
+    if state.screensaver_active
+        && let Some(&next) = state.screensaver_order.get(state.screensaver_position + 1)
+    {
+        render_cast_image(state, next);
+    }
+}
+
+fn cast_slideshow_image(state: &Looky) {
+    let url = state
+        .cast_slideshow_order
+        .get(state.cast_slideshow_position)
+        .and_then(|&idx| cast_url_for(state, idx));
+    if let Some(image_url) = url {
+        send_to_cast(state, &image_url);
+    }
+    if let Some(&next) = state.cast_slideshow_order.get(state.cast_slideshow_position + 1) {
+        render_cast_image(state, next);
+    }
+}
+
+/// Render the image a TV would fetch for `idx` into the display cache on a
+/// background thread, so that fetch is answered without a decode when the
+/// show moves on to it.
+fn render_cast_image(state: &Looky, idx: usize) {
+    if state.cast_sessions.is_empty() || state.server_url.is_none() {
+        return;
+    }
+    let Some(path) = state.image_paths.get(idx).cloned() else {
+        return;
+    };
+    let spawned = std::thread::Builder::new()
+        .name("looky-cast-render".into())
+        .spawn(move || {
+            thumbnail::display_jpeg_bytes(&path, CAST_DISPLAY_WIDTH, server::http::DISPLAY_QUALITY);
+        });
+    if let Err(e) = spawned {
+        log::warn!("Failed to spawn cast render: {}", e);
+    }
+}
+
+/// Send the image to every connected Chromecast.
+fn send_to_cast(state: &Looky, image_url: &str) {
+    for cast in &state.cast_sessions {
+        if let Err(e) = cast.session.load_image(image_url) {
+            log::warn!("Cast send to '{}' failed: {e}", cast.session.target.name);
+        }
+    }
+}
+
+fn grid_scroll_id() -> iced::widget::Id {
+    iced::widget::Id::new("grid")
+}
+
+fn search_id() -> iced::widget::Id {
+    iced::widget::Id::new("search")
+}
+
+/// Whether one of the full-window panels (settings, trash, import, library
+/// duplicates, unreadable files) is covering the grid. A new panel only needs
+/// adding here for keys and clicks meant for the grid to leave it alone.
+fn panel_showing(state: &Looky) -> bool {
+    state.settings_view_active
+        || state.trash_view_active
+        || state.import_view_active
+        || state.library_dups_view_active
+        || state.unreadable_view_active
+}
+
+/// Whether the grid is on screen, rather than the viewer, the duplicates
+/// view or one of the panels [`panel_showing`] covers.
+fn grid_showing(state: &Looky) -> bool {
+    state.viewer.current_index.is_none()
+        && !panel_showing(state)
+        && !state.dup_view_active
+        && state.dup_compare.is_none()
+        && !state.image_paths.is_empty()
+}
+
+fn dup_list_scroll_id() -> iced::widget::Id {
+    iced::widget::Id::new("dup-list")
+}
+
+/// Recompute which images pass the grid filter, and the subfolders,
+/// cameras and lenses the filter bar offers.
+fn refresh_grid_filter(state: &mut Looky) {
+    state.grid_shown = state.grid_filter.apply(&state.image_paths, &state.photo_info);
+    state.grid_shown.retain(|&i| {
+        let path = &state.image_paths[i];
+        !state.trashed.contains(path) && !state.moved_away.contains(path)
+    });
+    if state.grid_filter.new_only {
+        state.grid_shown.retain(|i| !state.imported.contains(i));
+    }
+    if let Some(flag) = state.grid_filter.flag {
+        let flag_of = |i: usize| state.ratings.get(&state.image_paths[i]).map_or(Flag::None, |r| r.flag);
+        state.grid_shown.retain(|&i| flag_of(i) == flag);
+    }
+    state.grid_subfolders = match state.grid_filter.subfolder.as_deref().or(state.folder.as_deref()) {
+        Some(dir) => filter::child_folders(&state.image_paths, dir),
+        None => Vec::new(),
+    };
+    let mut choices = FilterChoices::default();
+    for path in &state.image_paths {
+        if let Some(info) = state.photo_info.get(path).filter(|_| state.grid_filter.in_subfolder(path)) {
+            choices.add(info);
+        }
+    }
+    state.filter_choices = choices;
+}
+
+/// Back to the top of the grid after the filter changed, keeping the
+/// selection if it's still shown.
+fn show_filtered_grid(state: &mut Looky) -> Task<Message> {
+    if state.selected_thumb.is_some_and(|i| grid_position(state, i).is_none()) {
+        state.selected_thumb = None;
+    }
+    state.grid_scroll_y = 0.0;
+    reload_evicted_thumbnails(state);
+    prioritize_upgrades(state);
+    let upgrades = load_upgrade_batches(state);
+    Task::batch([restore_grid_scroll(state), upgrades])
+}
+
+/// Number of cells in the grid: images that pass the filter and whose
+/// thumbnail has arrived.
+fn grid_len(state: &Looky) -> usize {
+    state.grid_shown.partition_point(|&i| i < state.thumbnails.len())
+}
+
+/// Where image `index` sits in the grid, if the filter shows it.
+fn grid_position(state: &Looky, index: usize) -> Option<usize> {
+    state.grid_shown.binary_search(&index).ok()
+}
+
+/// The image `step` places from `index` in grid order, or in folder order
+/// for an image the grid doesn't show (e.g. opened from the duplicates).
+fn grid_neighbor(state: &Looky, index: usize, step: isize) -> Option<usize> {
+    match grid_position(state, index) {
+        Some(pos) => state.grid_shown.get(pos.checked_add_signed(step)?).copied(),
+        None => index
+            .checked_add_signed(step)
+            .filter(|&i| i < state.image_paths.len()),
+    }
+}
+
+/// Move the viewer to the next or previous photo in the grid.
+fn step_viewer(state: &mut Looky, step: isize) -> Task<Message> {
+    let next = state
+        .viewer
+        .current_index
+        .and_then(|i| grid_neighbor(state, i, step));
+    if let Some(next) = next {
+        state.viewer.navigate_to(next);
+    }
+    state.selected_thumb = state.viewer.current_index;
+    viewer_changed(state)
+}
+
+/// Move the photo open in the viewer, or else the one selected in the grid,
+/// to Looky's trash and hide it. The viewer goes on to the next photo.
+/// Move the screensaver `step` photos along its order, reshuffling when it
+/// runs off the end. Stepping back stops at the first photo.
+fn step_screensaver(state: &mut Looky, step: isize) -> Task<Message> {
+    if !state.screensaver_active {
+        return Task::none();
+    }
+    if step < 0 {
+        state.screensaver_position = state.screensaver_position.saturating_sub(step.unsigned_abs());
+    } else {
+        state.screensaver_position += step as usize;
+    }
+    if state.screensaver_position >= state.screensaver_order.len() {
+        // Reshuffle and restart
+        match state.settings.screensaver_recency {
+            Some(strength) if !state.screensaver_ranks.is_empty() => {
+                state.screensaver_order =
+                    slideshow::weighted_shuffle(&state.screensaver_ranks, strength as f32);
+            }
+            _ => {
+                use rand::seq::SliceRandom;
+                state.screensaver_order.shuffle(&mut rand::rng());
+            }
+        }
+        state.screensaver_position = 0;
+    }
+    let idx = state.screensaver_order[state.screensaver_position];
+    state.viewer.open_index(idx);
+    state.viewer.reset_zoom();
+    refresh_metadata(state);
+    cast_current_image(state);
+    preload_viewer_images(state)
+}
+
+fn trash_photo(state: &mut Looky) -> Task<Message> {
+    let viewing = state.viewer.current_index;
+    let Some(idx) = viewing.or(state.selected_thumb.filter(|_| grid_showing(state))) else {
+        return Task::none();
+    };
+    let next = grid_neighbor(state, idx, 1).or_else(|| grid_neighbor(state, idx, -1));
+    if !move_index_to_trash(state, idx) {
+        return Task::none();
+    }
+    forget_removed(state, &[idx]);
+
+    state.selected_thumb = next;
+    if viewing.is_none() {
+        return Task::none();
+    }
+    match next {
+        Some(next) => {
+            state.viewer.navigate_to(next);
+            viewer_changed(state)
+        }
+        None => {
+            state.viewer.close();
+            state.cached_metadata = None;
+            restore_grid_scroll(state)
+        }
+    }
+}
+
+/// Move image `idx` to Looky's trash and note it as gone. False if it
+/// couldn't be moved.
+fn move_index_to_trash(state: &mut Looky, idx: usize) -> bool {
+    let (Some(dir), Some(path)) = (config_dir(), state.image_paths.get(idx).cloned()) else {
+        return false;
+    };
+    match trash::move_to_trash(&dir.join("trash"), &path) {
+        Ok(photo) => {
+            if let Some(cat) = state.catalog.as_ref() {
+                cat.add_trashed(&photo);
+            }
+            state.trashed.insert(path);
+            true
+        }
+        Err(e) => {
+            log::warn!("Failed to move {} to the trash: {}", path.display(), e);
+            false
+        }
+    }
+}
+
+/// Hide images that left the folder from the grid and the duplicate groups.
+fn forget_removed(state: &mut Looky, removed: &[usize]) {
+    if removed.is_empty() {
+        return;
+    }
+    refresh_grid_filter(state);
+    if state.selected_thumb.is_some_and(|i| removed.contains(&i)) {
+        state.selected_thumb = None;
+    }
+
+    let group_count = state.dup_groups.len();
+    for group in &mut state.dup_groups {
+        group.indices.retain(|i| !removed.contains(i));
+    }
+    state.dup_groups.retain(|group| group.indices.len() > 1);
+    if state.dup_groups.len() != group_count {
+        state.dup_selected = None;
+        state.dup_compare = None;
+    }
+    state.dup_badge_set = duplicates::duplicate_indices(&state.dup_groups);
+}
+
+/// Queue `kind` for the selected photos. They leave the selection, and
+/// any already in another job are left out.
+fn start_file_job(state: &mut Looky, kind: FileJobKind) -> Task<Message> {
+    let indices = selected_indices(state);
+    state.selection.clear();
+    start_file_job_for(state, kind, indices, true)
+}
+
+/// Queue `kind` for `indices`, leaving the selection as it is.
+fn start_file_job_for(
+    state: &mut Looky,
+    kind: FileJobKind,
+    indices: Vec<usize>,
+    from_selection: bool,
+) -> Task<Message> {
+    let pending: Vec<(usize, PathBuf)> = indices
+        .into_iter()
+        .filter(|i| !state.file_jobs.iter().any(|j| j.indices.contains(i)))
+        .map(|i| (i, state.image_paths[i].clone()))
+        .collect();
+    if pending.is_empty() {
+        return Task::none();
+    }
+    let id = state.files.new_job();
+    state.batch_status = None;
+    state.file_jobs.push(FileJob {
+        id,
+        kind,
+        indices: pending.iter().map(|&(i, _)| i).collect(),
+        total: pending.len(),
+        pending,
+        conflicts: Vec::new(),
+        same_for_all: false,
+        in_flight: 0,
+        paused: false,
+        done: Vec::new(),
+        skipped: 0,
+        failed: Vec::new(),
+        from_selection,
+        progress: JobProgress::start(0),
+    });
+    load_next_file_batch(state, id)
+}
+
+fn load_next_file_batch(state: &mut Looky, id: JobId) -> Task<Message> {
+    let Some(job) = state.file_jobs.iter_mut().find(|j| j.id == id) else {
+        return Task::none();
+    };
+    let count = FILE_BATCH_SIZE.min(job.pending.len());
+    let batch: Vec<(usize, PathBuf)> = job.pending.drain(..count).collect();
+    let done = match job.kind.clone() {
+        FileJobKind::Transfer(op, dest) => state.files.submit(id, move |cancelled| {
+            file_ops::transfer(&batch, &dest, op, None, cancelled)
+        }),
+        FileJobKind::Trash => {
+            let Some(dir) = config_dir() else {
+                job.failed.extend(batch.into_iter().map(|(i, _)| (i, "no trash folder".to_string())));
+                return Task::none();
+            };
+            state.files.submit(id, move |cancelled| {
+                file_ops::trash(&batch, &dir.join("trash"), cancelled)
+            })
+        }
+    };
+    job.in_flight += 1;
+    Task::perform(async move { done.await.unwrap_or_default() }, move |outcomes| {
+        Message::FileBatchDone(id, outcomes)
+    })
+}
+
+/// Wrap up a job: moved and trashed photos leave the grid and the catalog
+/// follows the moved ones, and what couldn't be done is selected again to
+/// try once more if the job was started on the selection.
+fn finish_file_job(state: &mut Looky, id: JobId) {
+    let Some(at) = state.file_jobs.iter().position(|j| j.id == id) else {
+        return;
+    };
+    let job = state.file_jobs.remove(at);
+    if !matches!(job.kind, FileJobKind::Transfer(Transfer::Copy, _)) {
+        if let FileJobKind::Transfer(Transfer::Move, _) = job.kind {
+            for (index, to) in &job.done {
+                let from = state.image_paths[*index].clone();
+                follow_moved_file(state, &from, to);
+                state.moved_away.insert(from);
+            }
+        }
+        let removed: Vec<usize> = job.done.iter().map(|&(i, _)| i).collect();
+        forget_removed(state, &removed);
+    }
+    if job.from_selection {
+        state.selection.extend(job.failed.iter().map(|&(i, _)| i));
+    }
+    let done = job.done.len();
+    let mut status = match job.kind {
+        FileJobKind::Transfer(Transfer::Copy, _) => trn(done, "Copied {} photo", "Copied {} photos"),
+        FileJobKind::Transfer(Transfer::Move, _) => trn(done, "Moved {} photo", "Moved {} photos"),
+        FileJobKind::Trash => trn(done, "Moved {} photo to the trash", "Moved {} photos to the trash"),
+    };
+    // Batches dropped by a cancel never report back, so count from the total
+    let skipped = job.total - done - job.failed.len();
+    if skipped > 0 {
+        status.push_str(", ");
+        status.push_str(&trf("{} skipped", &[&skipped]));
+    }
+    if let Some((_, e)) = job.failed.first() {
+        status.push_str(", ");
+        status.push_str(&trf("{} failed: {}", &[&job.failed.len(), e]));
+    }
+    state.batch_status = Some(status);
+}
+
+/// Carry a moved photo's rating, edit and orientation override over to its
+/// new path.
+fn follow_moved_file(state: &mut Looky, from: &std::path::Path, to: &std::path::Path) {
+    if let Some(cat) = state.catalog.as_ref() {
+        cat.move_path(from, to);
+    }
+    if let Some(rating) = state.ratings.remove(from) {
+        state.ratings.insert(to.to_path_buf(), rating);
+    }
+    if let Some(photo_edit) = edit::edit_for(from) {
+        edit::set_edit(from, None);
+        edit::set_edit(to, Some(photo_edit));
+    }
+    if let Some(orientation) = thumbnail::orientation_override(from) {
+        thumbnail::set_orientation_override(from, None);
+        thumbnail::set_orientation_override(to, Some(orientation));
+    }
+}
+
+/// Pick `index` along with the others: Shift adds every photo between it and
+/// the selected one, Ctrl adds or removes just it.
+fn extend_selection(state: &mut Looky, index: usize) {
+    let anchor = state.selected_thumb.and_then(|i| grid_position(state, i));
+    match (state.modifiers.shift(), anchor, grid_position(state, index)) {
+        (true, Some(from), Some(to)) => {
+            let range = from.min(to)..=from.max(to);
+            state.selection.extend(state.grid_shown[range].iter().copied());
+        }
+        _ => {
+            if !state.selection.remove(&index) {
+                state.selection.insert(index);
+            }
+        }
+    }
+    state.selected_thumb = Some(index);
+    state.batch_status = None;
+}
+
+/// Picked photos the grid still shows, in grid order.
+fn selected_indices(state: &Looky) -> Vec<usize> {
+    state
+        .selection
+        .iter()
+        .copied()
+        .filter(|&i| grid_position(state, i).is_some())
+        .collect()
+}
+
+/// Look for the folder's photos in the library: take SHA-256s the catalog
+/// already has and queue the rest to be hashed.
+fn start_library_check(state: &mut Looky) -> Task<Message> {
+    let (Some(cat), Some(folder)) = (state.catalog.as_ref(), state.folder.as_deref()) else {
+        return Task::none();
+    };
+    state.library_checked = true;
+    let mut pending = Vec::new();
+    for (i, path) in state.image_paths.iter().enumerate() {
+        match cat.get_content_hash(path) {
+            Some(hash) => {
+                if cat.has_content_hash_outside(&hash, folder) {
+                    state.imported.insert(i);
+                }
+            }
+            None => pending.push((i, path.clone())),
+        }
+    }
+    refresh_grid_filter(state);
+    state.library_total = state.image_paths.len();
+    state.library_pending = pending;
+    if state.library_pending.is_empty() {
+        return Task::none();
+    }
+    state.library_job = Some(JobProgress::start(state.library_total - state.library_pending.len()));
+    load_next_library_batch(state)
+}
+
+fn load_next_library_batch(state: &mut Looky) -> Task<Message> {
+    let count = LIBRARY_HASH_BATCH_SIZE.min(state.library_pending.len());
+    let batch: Vec<(usize, PathBuf)> = state.library_pending.drain(..count).collect();
+    Task::perform(
+        async move { duplicates::content_hash_batch(&batch) },
+        Message::LibraryHashBatchReady,
+    )
+}
+
+/// The open folder's name, for titling galleries and contact sheets.
+fn folder_title(state: &Looky) -> String {
+    state
+        .folder
+        .as_ref()
+        .and_then(|f| f.file_name())
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| tr("Photos").to_string())
+}
+
+fn load_next_contact_sheet_batch(state: &mut Looky) -> Task<Message> {
+    let count = CONTACT_SHEET_BATCH_SIZE.min(state.contact_sheet_pending.len());
+    let batch: Vec<(usize, PathBuf)> = state.contact_sheet_pending.drain(..count).collect();
+    Task::perform(
+        async move { contact_sheet::prepare_batch(&batch) },
+        Message::ContactSheetBatchDone,
+    )
+}
+
+fn load_next_export_batch(state: &mut Looky) -> Task<Message> {
+    let Some(dest) = state.export_dest.clone() else {
+        return Task::none();
+    };
+    let count = EXPORT_BATCH_SIZE.min(state.export_pending.len());
+    let batch: Vec<(usize, PathBuf)> = state.export_pending.drain(..count).collect();
+    state.export_in_flight = true;
+    let written = state.files.submit(state.export_id, move |_| export::export_batch(&dest, &batch));
+    Task::perform(async move { written.await.unwrap_or_default() }, Message::ExportBatchDone)
+}
+
+/// Write the gallery's index page once every photo is in.
+fn finish_export(state: &mut Looky) {
+    state.export_job = None;
+    state.export_written.sort();
+    let Some(dest) = state.export_dest.take() else {
+        return;
+    };
+    let title = folder_title(state);
+    state.export_status = Some(match export::write_index(&dest, &title, &state.export_written) {
+        Ok(()) => trf("Gallery saved to {}", &[&dest.display()]),
+        Err(e) => {
+            log::warn!("Failed to write gallery index: {}", e);
+            trf("Couldn't save the gallery: {}", &[&e])
+        }
+    });
+    state.export_written.clear();
+}
+
+/// Where imports copy to: the chosen library folder, or the open one.
+fn import_destination(state: &Looky) -> Option<PathBuf> {
+    state.settings.import_destination.clone().or_else(|| state.folder.clone())
+}
+
+fn load_next_import_batch(state: &mut Looky) -> Task<Message> {
+    let Some(dest) = import_destination(state) else {
+        return Task::none();
+    };
+    let count = IMPORT_BATCH_SIZE.min(state.import_pending.len());
+    let batch: Vec<ImportItem> = state.import_pending.drain(..count).collect();
+    Task::perform(
+        async move {
+            batch
+                .into_iter()
+                .map(|item| {
+                    let result = import::import_item(&item, &dest).map_err(|e| e.to_string());
+                    (item, result)
+                })
+                .collect()
+        },
+        Message::ImportBatchDone,
+    )
+}
+
+/// Delete trash entry `pos` for good, keeping it listed if that fails.
+fn purge_trashed(state: &mut Looky, pos: usize) {
+    match trash::purge(&state.trash_items[pos]) {
+        Ok(()) => {
+            let photo = state.trash_items.remove(pos);
+            if let Some(cat) = state.catalog.as_ref() {
+                cat.remove_trashed(&photo.trashed_path);
+            }
+        }
+        Err(e) => {
+            log::warn!("Failed to delete {}: {}", state.trash_items[pos].trashed_path.display(), e);
+            state.trash_error = Some(trf("Couldn't delete: {}", &[&e]));
+        }
+    }
+}
+
+fn move_grid_selection(state: &mut Looky, delta: i32) -> Task<Message> {
+    let count = grid_len(state);
+    if count == 0 {
+        return Task::none();
+    }
+    let current = state
+        .selected_thumb
+        .and_then(|i| grid_position(state, i))
+        .unwrap_or(0) as i32;
+    let next = (current + delta).clamp(0, count as i32 - 1) as usize;
+    state.selected_thumb = Some(state.grid_shown[next]);
+    scroll_to_thumb(state, next)
+}
+
+/// Open, or in the grid select, a photo picked at random from those the
+/// filter shows, never the current one unless it's the only one.
+fn jump_to_random(state: &mut Looky) -> Task<Message> {
+    use rand::Rng;
+    let count = grid_len(state);
+    if count == 0
+        || state.dup_view_active
+        || state.dup_compare.is_some()
+        || panel_showing(state)
+    {
+        return Task::none();
+    }
+    let current = state
+        .viewer
+        .current_index
+        .or(state.selected_thumb)
+        .and_then(|i| grid_position(state, i));
+    let mut position = rand::rng().random_range(0..count);
+    if count > 1 && Some(position) == current {
+        // Pick among the others by skipping over the current one
+        position = (position + rand::rng().random_range(1..count)) % count;
+    }
+    let index = state.grid_shown[position];
+    state.selected_thumb = Some(index);
+    if state.viewer.current_index.is_some() {
+        state.viewer.navigate_to(index);
+        return viewer_changed(state);
+    }
+    scroll_to_thumb(state, position)
+}
+
+/// Move the selection `delta` rows up or down, to the cell nearest it
+/// horizontally.
+fn move_grid_rows(state: &mut Looky, delta: isize) -> Task<Message> {
+    let count = grid_len(state);
+    if count == 0 {
+        return Task::none();
+    }
+    let current = state
+        .selected_thumb
+        .and_then(|i| grid_position(state, i))
+        .unwrap_or(0);
+    let next = grid_geometry(state, state.viewport_width).vertical_step(current, delta);
+    state.selected_thumb = Some(state.grid_shown[next]);
+    scroll_to_thumb(state, next)
+}
+
+fn scroll_to_thumb(state: &Looky, position: usize) -> Task<Message> {
+    let geometry = grid_geometry(state, state.viewport_width);
+    let Some(row) = geometry.row_of(position).map(|r| geometry.rows[r]) else {
+        return Task::none();
+    };
+    let row_top = GRID_PADDING + row.top;
+    let row_bottom = row_top + row.height;
+
+    // Toolbar height is roughly 50px; visible area starts after that.
+    // We just ensure the row is within the scroll viewport.
+    // If the row is above the current scroll, scroll up to it.
+    // If it's below, scroll down so it's visible.
+    // We don't know the viewport height exactly, so use a conservative estimate.
+    let viewport = state.viewport_height;
+    let target = if row_top < state.grid_scroll_y {
+        row_top
+    } else if row_bottom > state.grid_scroll_y + viewport {
+        row_bottom - viewport
+    } else {
+        return Task::none();
+    };
+
+    use iced::widget::operation::AbsoluteOffset;
+    iced::widget::operation::scroll_to(
+        grid_scroll_id(),
+        AbsoluteOffset {
+            x: None,
             y: Some(target.max(0.0)),
         },
     )
 }
 
-fn restore_grid_scroll(state: &Looky) -> Task<Message> {
-    use iced::widget::operation::AbsoluteOffset;
-    let offset = AbsoluteOffset {
-        x: None,
-        y: Some(state.grid_scroll_y),
-    };
-    iced::widget::operation::scroll_to(grid_scroll_id(), offset)
+/// Put the selection, grid offset and open photo back the way they were the
+/// last time this folder was open.
+fn restore_session(state: &mut Looky) -> Task<Message> {
+    let session = match (state.catalog.as_ref(), state.folder.as_deref()) {
+        (Some(cat), Some(folder)) => cat.get_session(folder),
+        _ => None,
+    };
+    let Some(session) = session else {
+        return Task::none();
+    };
+    let index_of = |path: &Option<PathBuf>| {
+        let path = path.as_ref()?;
+        state.image_paths.iter().position(|p| p == path)
+    };
+    let selected = index_of(&session.selected);
+    let viewing = index_of(&session.viewing);
+    let last_viewed = index_of(&session.last_viewed);
+
+    state.selected_thumb = selected.or(last_viewed);
+    state.last_viewed = session.last_viewed.clone();
+    state.resume_offer = last_viewed.filter(|_| viewing.is_none());
+    state.pending_grid_scroll = Some(session.grid_scroll_y).filter(|&y| y > 0.0);
+    state.saved_session = Some(session);
+    match viewing {
+        Some(index) => {
+            state.viewer.open_index(index);
+            viewer_changed(state)
+        }
+        None => Task::none(),
+    }
+}
+
+/// Scroll to the restored grid offset once enough thumbnails are in for
+/// the grid to reach it.
+fn apply_pending_grid_scroll(state: &mut Looky) -> Task<Message> {
+    let Some(target) = state.pending_grid_scroll else {
+        return Task::none();
+    };
+    let height = grid_geometry(state, state.viewport_width).height();
+    let reachable = (height - state.viewport_height).max(0.0);
+    if reachable < target && !state.pending_thumbnails.is_empty() {
+        return Task::none();
+    }
+    state.pending_grid_scroll = None;
+    state.grid_scroll_y = target.min(reachable);
+    prioritize_upgrades(state);
+    restore_grid_scroll(state)
+}
+
+/// Write the folder's session to the catalog if it changed since last time.
+fn save_session(state: &mut Looky) {
+    let (Some(cat), Some(folder)) = (state.catalog.as_ref(), state.folder.as_deref()) else {
+        return;
+    };
+    // Nothing worth keeping yet, or the restored offset hasn't been applied
+    if state.image_paths.is_empty() || state.scan_job.is_some() || state.pending_grid_scroll.is_some() {
+        return;
+    }
+    let path_of = |index: Option<usize>| index.and_then(|i| state.image_paths.get(i)).cloned();
+    let viewing = state.viewer.current_index.filter(|_| !state.screensaver_active);
+    let session = FolderSession {
+        grid_scroll_y: state.grid_scroll_y,
+        selected: path_of(state.selected_thumb),
+        viewing: path_of(viewing),
+        last_viewed: state.last_viewed.clone(),
+    };
+    if state.saved_session.as_ref() == Some(&session) {
+        return;
+    }
+    cat.save_session(folder, &session);
+    state.saved_session = Some(session);
+}
+
+fn restore_grid_scroll(state: &Looky) -> Task<Message> {
+    use iced::widget::operation::AbsoluteOffset;
+    let offset = AbsoluteOffset {
+        x: None,
+        y: Some(state.grid_scroll_y),
+    };
+    iced::widget::operation::scroll_to(grid_scroll_id(), offset)
+}
+
+/// Grid positions of the cells on screen.
+fn visible_grid_range(state: &Looky) -> std::ops::Range<usize> {
+    let top = state.grid_scroll_y - GRID_PADDING;
+    grid_geometry(state, state.viewport_width).positions_between(top, top + state.viewport_height)
+}
+
+/// Where the grid's cells go across `width`, in the chosen layout.
+fn grid_geometry(state: &Looky, width: f32) -> Geometry {
+    let width = width - GRID_PADDING * 2.0;
+    let shown = &state.grid_shown[..grid_len(state)];
+    match state.settings.grid_layout.unwrap_or_default() {
+        GridLayout::Square => Geometry::square(shown.len(), width, THUMB_CELL),
+        GridLayout::Justified => {
+            let aspects: Vec<f32> = shown.iter().map(|&i| state.thumb_aspects[i]).collect();
+            Geometry::justified(&aspects, width, THUMB_SIZE)
+        }
+    }
+}
+
+/// Width/height of a thumbnail handle, or square if unknown.
+fn handle_aspect(handle: &image::Handle) -> f32 {
+    match handle {
+        image::Handle::Rgba { width, height, .. } if *height > 0 => *width as f32 / *height as f32,
+        _ => 1.0,
+    }
+}
+
+fn prioritize_upgrades(state: &mut Looky) {
+    if state.pending_upgrades.is_empty() {
+        return;
+    }
+    let visible = visible_grid_range(state);
+    let visible_paths: HashSet<&PathBuf> = state.grid_shown[visible]
+        .iter()
+        .map(|&i| &state.thumbnails[i].0)
+        .collect();
+    // Partition: visible first, then rest
+    state
+        .pending_upgrades
+        .sort_by_key(|p| if visible_paths.contains(p) { 0 } else { 1 });
+}
+
+/// Decoded RGBA size of a thumbnail handle.
+fn handle_bytes(handle: &image::Handle) -> u64 {
+    match handle {
+        image::Handle::Rgba { pixels, .. } => pixels.len() as u64,
+        _ => 0,
+    }
+}
+
+fn push_thumbnail(state: &mut Looky, path: PathBuf, handle: image::Handle, added: Instant) {
+    state.thumbnail_bytes += handle_bytes(&handle);
+    state.thumb_aspects.push(handle_aspect(&handle));
+    state.thumbnails.push((path, handle, added));
+}
+
+fn replace_thumbnail(state: &mut Looky, idx: usize, handle: image::Handle, added: Instant) {
+    let Some(entry) = state.thumbnails.get_mut(idx) else {
+        return;
+    };
+    state.thumbnail_bytes = state.thumbnail_bytes - handle_bytes(&entry.1) + handle_bytes(&handle);
+    // A stand-in's few pixels round off the shape, so keep the loaded one
+    if !matches!(&handle, image::Handle::Rgba { width, height, .. } if (*width).max(*height) <= THUMB_STAND_IN_SIZE) {
+        state.thumb_aspects[idx] = handle_aspect(&handle);
+    }
+    entry.1 = handle;
+    entry.2 = added;
+    state.evicted_thumbnails.remove(&idx);
+}
+
+pub fn apply_sharpening(settings: &Settings) {
+    let percent = settings.grid_sharpen_percent.unwrap_or(DEFAULT_SHARPEN_PERCENT);
+    thumbnail::set_sharpening(if settings.grid_sharpen { percent } else { 0 });
+}
+
+/// Remake the loaded thumbnails, e.g. once the sharpening changed. Each
+/// stays up until its replacement arrives; shrunk ones are remade when
+/// scrolled back to.
+fn regenerate_thumbnails(state: &mut Looky) -> Task<Message> {
+    let queued: HashSet<&PathBuf> = state.pending_upgrades.iter().collect();
+    let paths: Vec<PathBuf> = state
+        .thumbnails
+        .iter()
+        .enumerate()
+        .filter(|(i, (path, ..))| !state.evicted_thumbnails.contains(i) && !queued.contains(path))
+        .map(|(_, (path, ..))| path.clone())
+        .collect();
+    state.pending_upgrades.extend(paths);
+    prioritize_upgrades(state);
+    load_upgrade_batches(state)
+}
+
+fn grid_cache_budget(state: &Looky) -> u64 {
+    state.settings.grid_cache_mb.unwrap_or(DEFAULT_GRID_CACHE_MB) * 1024 * 1024
+}
+
+/// Grid positions within a screen of the visible rows, whose thumbnails are
+/// never evicted.
+fn thumbnail_keep_range(state: &Looky) -> std::ops::Range<usize> {
+    let visible = visible_grid_range(state);
+    let margin = visible.len().max(state.grid_columns);
+    visible.start.saturating_sub(margin)..(visible.end + margin).min(grid_len(state))
+}
+
+/// Over budget, shrink the thumbnails farthest from the viewport to a tiny
+/// stand-in until back under. They're reloaded from the disk cache when
+/// scrolled back into view.
+fn enforce_thumbnail_budget(state: &mut Looky) {
+    let budget = grid_cache_budget(state);
+    if state.thumbnail_bytes <= budget {
+        return;
+    }
+    let keep = thumbnail_keep_range(state);
+    let center = state
+        .grid_shown
+        .get((keep.start + keep.end) / 2)
+        .copied()
+        .unwrap_or(0);
+    let keep: HashSet<usize> = state.grid_shown[keep].iter().copied().collect();
+    let mut candidates: Vec<usize> = (0..state.thumbnails.len())
+        .filter(|i| !keep.contains(i) && !state.evicted_thumbnails.contains(i))
+        .collect();
+    candidates.sort_by_key(|&i| std::cmp::Reverse(i.abs_diff(center)));
+
+    // Free a little extra so every new batch doesn't trigger another pass
+    let target = budget - budget / 10;
+    for idx in candidates {
+        if state.thumbnail_bytes <= target {
+            break;
+        }
+        let (_, handle, added) = &state.thumbnails[idx];
+        let added = *added;
+        let image::Handle::Rgba { width, height, pixels, .. } = handle else {
+            continue;
+        };
+        let Some((rgba, w, h)) =
+            thumbnail::shrink_rgba(pixels, *width, *height, THUMB_STAND_IN_SIZE)
+        else {
+            continue;
+        };
+        replace_thumbnail(state, idx, image::Handle::from_rgba(w, h, rgba), added);
+        state.evicted_thumbnails.insert(idx);
+    }
+    log::debug!(
+        "grid: {} thumbnails shrunk, {} MB resident",
+        state.evicted_thumbnails.len(),
+        state.thumbnail_bytes / (1024 * 1024)
+    );
+}
+
+/// Queue evicted thumbnails near the viewport for reloading, ahead of
+/// other upgrades.
+fn reload_evicted_thumbnails(state: &mut Looky) {
+    if state.evicted_thumbnails.is_empty() {
+        return;
+    }
+    let reload: Vec<usize> = state.grid_shown[thumbnail_keep_range(state)]
+        .iter()
+        .copied()
+        .filter(|i| state.evicted_thumbnails.contains(i))
+        .collect();
+    let paths: Vec<PathBuf> = reload.iter().map(|&i| state.thumbnails[i].0.clone()).collect();
+    for idx in reload {
+        state.evicted_thumbnails.remove(&idx);
+    }
+    state.pending_upgrades.splice(0..0, paths);
+}
+
+/// Turn a generated thumbnail into a handle, remembering files that failed
+/// to decode so the grid and viewer can flag them.
+fn thumbnail_handle(
+    state: &mut Looky,
+    path: &std::path::Path,
+    result: thumbnail::ThumbnailResult,
+) -> image::Handle {
+    match result {
+        Ok((rgba, width, height)) => {
+            state.unreadable.remove(path);
+            state.thumb_retries.remove(path);
+            image::Handle::from_rgba(width, height, rgba)
+        }
+        Err(error) => {
+            let tries = state.thumb_retries.get(path).map_or(0, |&(tries, _)| tries);
+            if tries < THUMBNAIL_RETRIES && thumbnail::failure_may_be_transient(path) {
+                let due = Instant::now() + THUMBNAIL_RETRY_DELAY * (tries + 1);
+                state.thumb_retries.insert(path.to_path_buf(), (tries + 1, Some(due)));
+            } else {
+                state.thumb_retries.remove(path);
+            }
+            state.unreadable.insert(path.to_path_buf(), error);
+            image::Handle::from_rgba(1, 1, vec![60, 60, 60, 255])
+        }
+    }
+}
+
+/// Thumbnail loading is over once previews, upgrades and batches in flight
+/// have all drained.
+fn finish_thumbnails_if_idle(state: &mut Looky) {
+    if state.pending_upgrades.is_empty()
+        && state.upgrade_batches_in_flight == 0
+        && state.pending_thumbnails.is_empty()
+    {
+        state.loading = false;
+        state.thumb_job = None;
+    }
+}
+
+fn load_next_batch(state: &mut Looky) -> Task<Message> {
+    if state.pending_thumbnails.is_empty() {
+        state.loading = false;
+        state.thumb_job = None;
+        return Task::none();
+    }
+
+    let count = THUMBNAIL_BATCH_SIZE.min(state.pending_thumbnails.len());
+    let batch: Vec<PathBuf> = state.pending_thumbnails.drain(..count).collect();
+
+    Task::perform(
+        async move { thumbnail::generate_thumbnails_parallel(&batch, GRID_THUMBNAIL_PIXELS) },
+        Message::ThumbnailBatchReady,
+    )
+}
+
+fn load_next_preview_batch(state: &mut Looky) -> Task<Message> {
+    if state.pending_thumbnails.is_empty() {
+        return Task::none();
+    }
+
+    let count = PREVIEW_BATCH_SIZE.min(state.pending_thumbnails.len());
+    let batch: Vec<PathBuf> = state.pending_thumbnails.drain(..count).collect();
+
+    Task::perform(
+        async move { thumbnail::extract_previews_parallel(&batch, 400) },
+        Message::PreviewBatchReady,
+    )
+}
+
+/// Make the thumbnails of `paths` again, ahead of other thumbnail work.
+fn retry_thumbnails(state: &mut Looky, paths: Vec<PathBuf>) -> Task<Message> {
+    if paths.is_empty() {
+        return Task::none();
+    }
+    // Don't count them as done a second time
+    state.thumbs_done = state.thumbs_done.saturating_sub(paths.len());
+    state.pending_upgrades.retain(|p| !paths.contains(p));
+    state.pending_upgrades.splice(0..0, paths);
+    load_upgrade_batches(state)
+}
+
+fn load_upgrade_batches(state: &mut Looky) -> Task<Message> {
+    // Thumbnail batches saturate the CPU; hold them back until the image
+    // being viewed has decoded.
+    let waiting = state
+        .viewer
+        .current_index
+        .is_some_and(|i| !has_full_image(state, i) && !is_unreadable(state, i));
+    if waiting {
+        return Task::none();
+    }
+    let mut tasks = Vec::new();
+    while state.upgrade_batches_in_flight < MAX_UPGRADE_BATCHES_IN_FLIGHT
+        && !state.pending_upgrades.is_empty()
+    {
+        let count = THUMBNAIL_BATCH_SIZE.min(state.pending_upgrades.len());
+        let batch: Vec<PathBuf> = state.pending_upgrades.drain(..count).collect();
+        state.upgrade_batches_in_flight += 1;
+        tasks.push(Task::perform(
+            async move { thumbnail::generate_thumbnails_parallel(&batch, GRID_THUMBNAIL_PIXELS) },
+            Message::ThumbnailUpgradeReady,
+        ));
+    }
+    Task::batch(tasks)
+}
+
+/// Hash the folder for duplicates, picking up what the catalog already has.
+/// A scan resumed from an earlier session may start out `paused`.
+fn start_dup_scan(state: &mut Looky, paused: bool) -> Task<Message> {
+    state.dup_hashes.clear();
+    state.dup_groups.clear();
+    state.dup_badge_set.clear();
+    state.dup_summaries.clear();
+    state.dup_scanning = true;
+    state.dup_compare = None;
+    state.dup_view_active = false;
+    state.dup_job = None;
+    state.dup_paused = paused;
+    if let (Some(cat), Some(folder)) = (state.catalog.as_ref(), state.folder.as_deref()) {
+        cat.save_dup_scan(folder, paused);
+    }
+
+    let excluded = state.catalog.as_ref().map(Catalog::dup_excluded_folders).unwrap_or_default();
+    let items: Vec<(usize, PathBuf)> = state
+        .image_paths
+        .iter()
+        .enumerate()
+        .filter(|(_, path)| {
+            !(state.settings.dup_skip_unreadable && state.unreadable.contains_key(*path))
+        })
+        .filter(|(_, path)| !in_any_folder(&excluded, path))
+        .map(|(i, path)| (i, path.clone()))
+        .collect();
+    // Sizes and head/tail samples first, to know which files need a
+    // full content hash
+    state.dup_total = items.len();
+    state.dup_job = Some(JobProgress::start(0));
+    // Counts as not started in the status bar until then
+    state.dup_pending = items.clone();
+    state.dup_in_flight = true;
+    Task::perform(
+        async move {
+            let candidates = duplicates::exact_candidates(&items);
+            (items, candidates)
+        },
+        |(items, candidates)| Message::DupCandidatesReady(items, candidates),
+    )
+}
+
+/// Bytes freed by deleting all but the largest file of `group`.
+fn group_reclaimable(state: &Looky, group: &DuplicateGroup) -> u64 {
+    group.reclaimable(|idx| state.dup_summaries.get(&idx).map(|s| s.file_size))
+}
+
+/// Put the duplicate groups in the chosen order, keeping the selected and
+/// compared groups selected.
+fn sort_dup_groups(state: &mut Looky) {
+    let mut order: Vec<usize> = (0..state.dup_groups.len()).collect();
+    match state.settings.dup_order.unwrap_or_default() {
+        GroupOrder::Folder => {
+            order.sort_by_key(|&g| state.dup_groups[g].indices.iter().min().copied());
+        }
+        GroupOrder::SpaceFreed => {
+            order.sort_by_key(|&g| std::cmp::Reverse(group_reclaimable(state, &state.dup_groups[g])));
+        }
+        GroupOrder::Folders => {
+            order.sort_by_cached_key(|&g| {
+                let indices = &state.dup_groups[g].indices;
+                let mut folders: Vec<&std::path::Path> = indices
+                    .iter()
+                    .filter_map(|&i| state.image_paths.get(i)?.parent())
+                    .collect();
+                folders.sort();
+                folders.dedup();
+                (folders, indices.iter().min().copied())
+            });
+        }
+    }
+    let mut groups: Vec<Option<DuplicateGroup>> =
+        std::mem::take(&mut state.dup_groups).into_iter().map(Some).collect();
+    state.dup_groups = order.iter().filter_map(|&g| groups[g].take()).collect();
+    let moved = |old: usize| order.iter().position(|&g| g == old);
+    state.dup_selected = state.dup_selected.and_then(moved);
+    state.dup_compare = state.dup_compare.and_then(moved);
+}
+
+/// All hashes computed — group them off the main thread.
+fn analyze_duplicates(state: &mut Looky) -> Task<Message> {
+    let hashes = state.dup_hashes.clone();
+    let image_paths = state.image_paths.clone();
+    let report_pairs = state.settings.dup_report_capture_pairs;
+
+    // Pre-collect cached summaries from the catalog (on main thread)
+    let mut cached_summaries: HashMap<usize, metadata::FileSummary> = HashMap::new();
+    if let Some(cat) = state.catalog.as_ref() {
+        // We don't know dup_indices yet, but we can pre-cache all image paths
+        // to avoid disk reads in the async block. This is fast (just DB lookups).
+        for (i, path) in image_paths.iter().enumerate() {
+            if let Some(summary) = cat.get_file_summary(path) {
+                cached_summaries.insert(i, summary);
+            }
+        }
+    }
+
+    Task::perform(
+        async move {
+            let groups = duplicates::find_duplicates(&hashes, VISUAL_DUP_THRESHOLD);
+            let dup_indices = duplicates::duplicate_indices(&groups);
+            let summaries: HashMap<usize, metadata::FileSummary> = dup_indices
+                .iter()
+                .filter_map(|&idx| {
+                    if let Some(cached) = cached_summaries.get(&idx) {
+                        return Some((idx, cached.clone()));
+                    }
+                    let path = image_paths.get(idx)?;
+                    Some((idx, metadata::read_file_summary(path)))
+                })
+                .collect();
+            let groups = if report_pairs {
+                groups
+            } else {
+                duplicates::without_capture_sets(
+                    groups,
+                    |i| image_paths.get(i).cloned(),
+                    |i| summaries.get(&i)?.date_taken.clone(),
+                )
+            };
+            (groups, summaries)
+        },
+        |(groups, summaries)| Message::DupAnalysisReady(groups, summaries),
+    )
+}
+
+/// Take camera info for `paths` from the catalog and queue the rest to be
+/// read in the background. Joins a run that's already going.
+fn start_indexing(state: &mut Looky, paths: Vec<PathBuf>) -> Task<Message> {
+    let mut pending = Vec::new();
+    for path in paths {
+        match state.catalog.as_ref().and_then(|c| c.get_photo_info(&path)) {
+            Some(info) => {
+                state.photo_info.insert(path, info);
+            }
+            None => pending.push(path),
+        }
+    }
+    refresh_grid_filter(state);
+    if pending.is_empty() {
+        return Task::none();
+    }
+    let running = state.info_job.is_some();
+    if !running {
+        state.info_total = 0;
+        state.info_job = Some(JobProgress::start(0));
+    }
+    state.info_total += pending.len();
+    state.info_pending.extend(pending);
+    if running {
+        Task::none()
+    } else {
+        load_next_info_batch(state)
+    }
+}
+
+fn load_next_info_batch(state: &mut Looky) -> Task<Message> {
+    if state.info_pending.is_empty() {
+        state.info_job = None;
+        return Task::none();
+    }
+
+    let count = INFO_BATCH_SIZE.min(state.info_pending.len());
+    let batch: Vec<PathBuf> = state.info_pending.drain(..count).collect();
+
+    Task::perform(
+        async move { metadata::read_photo_info_batch(&batch) },
+        Message::PhotoInfoBatchReady,
+    )
+}
+
+/// Cache `h` in the catalog for the photo at `path`.
+fn store_hashes(state: &Looky, path: &std::path::Path, h: &ImageHashes) {
+    let Some(cat) = state.catalog.as_ref() else {
+        return;
+    };
+    if let Some((file_size, mtime_ns)) = catalog::file_size_and_mtime_for(path) {
+        cat.insert_hashes(path, file_size, mtime_ns, h.content_hash.as_ref(), &h.perceptual_hash);
+    }
+}
+
+/// Nothing the user started is running and they haven't touched anything
+/// for a while.
+fn idle_for_hashing(state: &Looky) -> bool {
+    state.last_input.elapsed() >= IDLE_HASH_DELAY
+        && !state.loading
+        && state.pending_thumbnails.is_empty()
+        && !state.dup_scanning
+        && state.library_job.is_none()
+        && state.export_job.is_none()
+        && state.contact_sheet_job.is_none()
+        && state.import_job.is_none()
+        && state.file_jobs.is_empty()
+}
+
+/// While idle, hash the next few photos that have no hashes in the catalog,
+/// content and perceptual both so any later scan can use them. One batch at
+/// a time, checking for idleness again before each.
+fn load_next_idle_hash_batch(state: &mut Looky) -> Task<Message> {
+    if state.idle_hash_in_flight || !idle_for_hashing(state) {
+        return Task::none();
+    }
+    let Some(cat) = state.catalog.as_ref() else {
+        return Task::none();
+    };
+    let mut batch = Vec::new();
+    let end = (state.idle_hash_cursor + IDLE_HASH_LOOKUPS).min(state.image_paths.len());
+    while state.idle_hash_cursor < end && batch.len() < IDLE_HASH_BATCH_SIZE {
+        let path = &state.image_paths[state.idle_hash_cursor];
+        state.idle_hash_cursor += 1;
+        let cached = cat.get_hashes(path).is_some_and(|(content_hash, _)| content_hash.is_some());
+        if !cached && !state.unreadable.contains_key(path) {
+            batch.push(path.clone());
+        }
+    }
+    if batch.is_empty() {
+        return Task::none();
+    }
+    state.idle_hash_in_flight = true;
+    Task::perform(
+        async move {
+            batch
+                .into_iter()
+                .map(|path| {
+                    let hashes = duplicates::compute_hashes(&path, true);
+                    (path, hashes)
+                })
+                .collect()
+        },
+        Message::IdleHashBatchReady,
+    )
+}
+
+fn load_next_dup_batch(state: &mut Looky) -> Task<Message> {
+    if state.dup_pending.is_empty() || state.dup_paused {
+        return Task::none();
+    }
+    state.dup_in_flight = true;
+
+    let count = DUP_HASH_BATCH_SIZE.min(state.dup_pending.len());
+    let batch: Vec<(usize, PathBuf, bool)> = state
+        .dup_pending
+        .drain(..count)
+        .map(|(i, path)| (i, path, state.dup_candidates.contains(&i)))
+        .collect();
+
+    Task::perform(
+        async move { duplicates::compute_hashes_batch(&batch) },
+        Message::DupHashBatchReady,
+    )
+}
+
+/// Hand the decoder everything the viewer wants, most urgent first: the
+/// current image (a screen-sized preview, then full size), and once that is
+/// in, its neighbours in the direction of travel, then behind. Work for
+/// images no longer wanted is cancelled.
+fn preload_viewer_images(state: &mut Looky) -> Task<Message> {
+    let Some(idx) = state.viewer.current_index else {
+        state.decoder.schedule(Vec::new());
+        return Task::none();
+    };
+    if let Some(last) = state.viewer_last_index.filter(|&last| last != idx) {
+        state.viewer_forward = idx > last;
+    }
+    state.viewer_last_index = Some(idx);
+
+    let mut wanted = Vec::new();
+    let request = |index: usize, kind, priority| decoder::Request {
+        index,
+        path: state.image_paths[index].clone(),
+        kind,
+        priority,
+    };
+    // The screensaver never zooms, so it only needs images as big as the
+    // screen, which come in about as fast as a preview
+    let screen = state.viewport_width.max(state.viewport_height) * state.scale_factor;
+    let full = if state.screensaver_active {
+        DecodeKind::Fitted(screen.round() as u32)
+    } else {
+        DecodeKind::Full
+    };
+    // An unreadable image has already failed; don't retry it
+    let unreadable = is_unreadable(state, idx);
+    if !state.viewer_cache.contains_key(&idx) && !unreadable && full == DecodeKind::Full {
+        // Large JPEGs take seconds to decode in full; show a screen-sized
+        // decode in the meantime so the viewer is sharp right away.
+        let max_size = state.viewport_width.max(state.viewport_height).max(1024.0) as u32;
+        wanted.push(request(idx, DecodeKind::Preview(max_size), Priority::Current));
+    }
+    if !has_full_image(state, idx) && !unreadable {
+        wanted.push(request(idx, full, Priority::Current));
+    } else {
+        wanted.extend(
+            viewer_neighbors(state, idx)
+                .into_iter()
+                .filter(|&(i, _)| !has_full_image(state, i) && !is_unreadable(state, i))
+                .map(|(i, priority)| request(i, full, priority)),
+        );
+    }
+    // During screensaver, also decode the next image (random order, not a neighbor)
+    let ss_next = state
+        .screensaver_order
+        .get(state.screensaver_position + 1)
+        .copied()
+        .filter(|&i| {
+            state.screensaver_active && !has_full_image(state, i) && !is_unreadable(state, i)
+        });
+    if let Some(next) = ss_next {
+        wanted.push(request(next, full, Priority::Ahead(1)));
+    }
+    // Keep the A/B partner ready so flipping is instant
+    let partner = state
+        .viewer
+        .flip_partner
+        .filter(|&i| !has_full_image(state, i) && !is_unreadable(state, i));
+    if let Some(partner) = partner {
+        wanted.push(request(partner, DecodeKind::Full, Priority::Ahead(0)));
+    }
+
+    let tasks: Vec<Task<Message>> = state
+        .decoder
+        .schedule(wanted)
+        .into_iter()
+        .map(|rx| {
+            Task::perform(rx, |result| match result {
+                Ok(Ok(d)) if d.kind == DecodeKind::Full => {
+                    Message::ViewerImageLoaded(d.index, d.rgba, d.width, d.height, d.elapsed)
+                }
+                Ok(Ok(d)) if matches!(d.kind, DecodeKind::Fitted(_)) => {
+                    Message::ViewerFittedLoaded(d.index, d.rgba, d.width, d.height)
+                }
+                Ok(Ok(d)) => Message::ViewerPreviewLoaded(d.index, d.rgba, d.width, d.height),
+                Ok(Err(failed)) => Message::ViewerImageFailed(failed.index, failed.error),
+                // Cancelled, or no preview for this format
+                Err(_) => Message::Tick,
+            })
+        })
+        .collect();
+    Task::batch(tasks)
+}
+
+/// Neighbours to preload within the preload radius, nearest first and the
+/// direction of travel ahead of the other, limited to as many as the memory
+/// budget can hold assuming they're about the size of the current image.
+fn viewer_neighbors(state: &Looky, idx: usize) -> Vec<(usize, Priority)> {
+    let per_image = viewer_image_bytes(state, idx).max(1);
+    let affordable = (viewer_cache_budget(state) / per_image).saturating_sub(1) as usize;
+    let forward: isize = if state.viewer_forward { 1 } else { -1 };
+    let mut neighbors: Vec<(usize, Priority)> = (1..=preload_radius(state))
+        .flat_map(|d| {
+            let step = d as isize * forward;
+            [
+                grid_neighbor(state, idx, step).map(|i| (i, Priority::Ahead(d))),
+                grid_neighbor(state, idx, -step).map(|i| (i, Priority::Behind(d))),
+            ]
+        })
+        .flatten()
+        .collect();
+    neighbors.truncate(affordable);
+    neighbors
+}
+
+/// Whether the full-resolution image (not just its preview) is cached.
+fn has_full_image(state: &Looky, idx: usize) -> bool {
+    state.viewer_cache.contains_key(&idx)
+        && !state.viewer_previews.contains(&idx)
+        && (state.screensaver_active || !state.viewer_fitted.contains(&idx))
+}
+
+fn is_unreadable(state: &Looky, idx: usize) -> bool {
+    state
+        .image_paths
+        .get(idx)
+        .is_some_and(|p| state.unreadable.contains_key(p))
+}
+
+fn max_zoom(settings: &Settings) -> f32 {
+    settings
+        .viewer_max_zoom
+        .map_or(viewer_state::DEFAULT_MAX_ZOOM, |zoom| zoom.clamp(2, 16) as f32)
+}
+
+fn zoom_presets(settings: &Settings) -> &[ZoomPreset] {
+    settings
+        .viewer_zoom_presets
+        .as_deref()
+        .unwrap_or(&viewer_state::DEFAULT_ZOOM_PRESETS)
+}
+
+/// Neighbours decoded ahead on each side: the setting, or else picked from
+/// how long decodes have been taking. Memory is limited separately, in
+/// `viewer_neighbors`.
+fn preload_radius(state: &Looky) -> usize {
+    if let Some(radius) = state.settings.viewer_preload_radius {
+        return radius;
+    }
+    match state.decode_ms_average {
+        None => DEFAULT_PRELOAD_RADIUS,
+        // Small JPEGs: plenty of time to work further ahead
+        Some(ms) if ms < 150.0 => 6,
+        Some(ms) if ms < 600.0 => DEFAULT_PRELOAD_RADIUS,
+        Some(ms) if ms < 1500.0 => 2,
+        // Huge files: one each way, so the next one isn't stuck behind others
+        Some(_) => 1,
+    }
+}
+
+fn viewer_cache_budget(state: &Looky) -> u64 {
+    state.settings.viewer_cache_mb.unwrap_or(DEFAULT_VIEWER_CACHE_MB) * 1024 * 1024
+}
+
+/// Decoded RGBA size of a cached viewer image.
+fn viewer_image_bytes(state: &Looky, idx: usize) -> u64 {
+    state
+        .viewer_dimensions
+        .get(&idx)
+        .map_or(0, |&(w, h)| u64::from(w) * u64::from(h) * 4)
+}
+
+/// Drop cached full-size images outside the preload radius, then the ones
+/// farthest from `current` until the cache fits the memory budget. The
+/// current image (and the screensaver's next one) always stay.
+fn evict_viewer_cache(state: &mut Looky, current: usize) {
+    let radius = preload_radius(state);
+    // During screensaver, also keep the next image (random order, not a neighbor)
+    let ss_next = if state.screensaver_active {
+        state.screensaver_order.get(state.screensaver_position + 1).copied()
+    } else {
+        None
+    };
+    let partner = state.viewer.flip_partner;
+    let pinned = |k: usize| k == current || ss_next == Some(k) || partner == Some(k);
+    let mut evict: Vec<usize> = state
+        .viewer_cache
+        .keys()
+        .copied()
+        .filter(|&k| !pinned(k) && k.abs_diff(current) > radius)
+        .collect();
+
+    let mut remaining: Vec<usize> = state
+        .viewer_cache
+        .keys()
+        .copied()
+        .filter(|&k| !pinned(k) && k.abs_diff(current) <= radius)
+        .collect();
+    remaining.sort_by_key(|&k| std::cmp::Reverse(k.abs_diff(current)));
+    let mut total: u64 = state
+        .viewer_cache
+        .keys()
+        .filter(|k| !evict.contains(k))
+        .map(|&k| viewer_image_bytes(state, k))
+        .sum();
+    let budget = viewer_cache_budget(state);
+    for k in remaining {
+        if total <= budget {
+            break;
+        }
+        total -= viewer_image_bytes(state, k);
+        evict.push(k);
+    }
+
+    for k in evict {
+        state.viewer_cache.remove(&k);
+        state.viewer_dimensions.remove(&k);
+        state.viewer_previews.remove(&k);
+        state.viewer_fitted.remove(&k);
+    }
+}
+
+/// After the viewer moves to another image: refresh its info, mirror it to the
+/// TV if casting, and preload the neighbours.
+fn viewer_changed(state: &mut Looky) -> Task<Message> {
+    if !state.screensaver_active
+        && let Some(path) = state.viewer.current_index.and_then(|i| state.image_paths.get(i))
+    {
+        state.last_viewed = Some(path.clone());
+        state.resume_offer = None;
+    }
+    refresh_metadata(state);
+    cast_current_image(state);
+    let preload = preload_viewer_images(state);
+    if state.viewer.is_pannable() {
+        // The pannable view is reused across images; start each one centred.
+        return Task::batch([preload, center_zoom_scroll(state)]);
+    }
+    preload
+}
+
+/// Drop the viewer's decodes of `index` and its info, so they're redone the
+/// way the photo now shows.
+fn redecode_viewer_image(state: &mut Looky, index: usize) -> Task<Message> {
+    state.viewer_cache.remove(&index);
+    state.viewer_dimensions.remove(&index);
+    state.viewer_previews.remove(&index);
+    state.viewer_fitted.remove(&index);
+    state.cached_metadata = None;
+    viewer_changed(state)
+}
+
+/// Redo the viewer's decode of `index` and its thumbnail, once its
+/// orientation or edit changed.
+fn redisplay_photo(state: &mut Looky, index: usize) -> Task<Message> {
+    let path = state.image_paths[index].clone();
+    state.pending_upgrades.retain(|p| *p != path);
+    state.pending_upgrades.insert(0, path);
+    let upgrade_task = load_upgrade_batches(state);
+    Task::batch([redecode_viewer_image(state, index), upgrade_task])
+}
+
+/// Change the rating of the viewed photo, or of the selected one in the grid,
+/// and keep it in the catalog.
+fn update_rating(state: &mut Looky, change: impl FnOnce(&mut Rating)) {
+    let index = state.viewer.current_index.or(state.selected_thumb.filter(|_| {
+        !state.dup_view_active
+            && state.dup_compare.is_none()
+            && !panel_showing(state)
+    }));
+    let Some(path) = index.and_then(|i| state.image_paths.get(i)) else {
+        return;
+    };
+    let mut rating = state.ratings.get(path).copied().unwrap_or_default();
+    change(&mut rating);
+    if let Some(cat) = state.catalog.as_ref() {
+        cat.set_rating(path, rating);
+    }
+    if rating.is_unrated() {
+        state.ratings.remove(path);
+    } else {
+        state.ratings.insert(path.clone(), rating);
+    }
+}
+
+/// Stars, a heart, then a tick or cross for a pick or reject, e.g.
+/// "★★★ ♥ ✓", or `None` for an unrated photo.
+fn rating_label(rating: &Rating) -> Option<String> {
+    let mut label = "\u{2605}".repeat(rating.stars as usize);
+    let mut mark = |symbol: char| {
+        if !label.is_empty() {
+            label.push(' ');
+        }
+        label.push(symbol);
+    };
+    if rating.favorite {
+        mark('\u{2665}');
+    }
+    match rating.flag {
+        Flag::None => {}
+        Flag::Pick => mark('\u{2713}'),
+        Flag::Reject => mark('\u{2715}'),
+    }
+    (!label.is_empty()).then_some(label)
+}
+
+/// Leave culling and the viewer, and full screen unless it was on before.
+fn stop_culling(state: &mut Looky) -> Task<Message> {
+    state.culling = false;
+    state.cull_finished = false;
+    state.viewer.close();
+    state.cached_metadata = None;
+    // Flags given while culling may change what a flag filter shows
+    refresh_grid_filter(state);
+    let back = restore_grid_scroll(state);
+    if state.cull_was_fullscreen || !state.fullscreen {
+        return back;
+    }
+    state.fullscreen = false;
+    Task::batch([back, window::set_mode(state.window, window::Mode::Windowed)])
+}
+
+/// Photos the grid shows that are flagged as rejects.
+fn rejected_indices(state: &Looky) -> Vec<usize> {
+    state
+        .grid_shown
+        .iter()
+        .copied()
+        .filter(|&i| state.ratings.get(&state.image_paths[i]).is_some_and(|r| r.flag == Flag::Reject))
+        .collect()
+}
+
+/// While culling, the viewed photo's flag and the keys to change it, or
+/// once past the last photo, how it went and what to do with the rejects.
+fn cull_overlay(state: &Looky, index: usize) -> Element<'_, Message> {
+    let panel_style = |_theme: &Theme| container::Style {
+        background: Some(iced::Background::Color(Color::from_rgba(0.0, 0.0, 0.0, 0.7))),
+        text_color: Some(Color::WHITE),
+        border: iced::Border {
+            radius: 6.0.into(),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    if state.cull_finished {
+        let count = |flag: Flag| {
+            state
+                .grid_shown
+                .iter()
+                .filter(|&&i| state.ratings.get(&state.image_paths[i]).is_some_and(|r| r.flag == flag))
+                .count()
+        };
+        let rejected = count(Flag::Reject);
+        let mut buttons = row![].spacing(8);
+        if rejected > 0 {
+            buttons = buttons.push(
+                button(text(trn(rejected, "Move {} Reject to Trash", "Move {} Rejects to Trash")))
+                    .on_press(Message::TrashRejected)
+                    .style(button::danger),
+            );
+        }
+        buttons = buttons.push(button(text(tr("Done"))).on_press(Message::StopCulling).style(button::secondary));
+        let panel = column![
+            text(tr("That was the last photo")).size(18),
+            text(trf("{} picked, {} rejected", &[&count(Flag::Pick), &rejected])).size(14),
+            buttons,
+        ]
+        .spacing(12)
+        .align_x(iced::Alignment::Center);
+        return container(container(panel).padding(20).style(panel_style))
+            .center(Length::Fill)
+            .into();
+    }
+    let flag = state
+        .image_paths
+        .get(index)
+        .and_then(|path| state.ratings.get(path))
+        .map_or(Flag::None, |r| r.flag);
+    let (label, color) = match flag {
+        Flag::None => (tr("Not flagged"), Color::WHITE),
+        Flag::Pick => (tr("Picked"), Color::from_rgb(0.3, 0.85, 0.4)),
+        Flag::Reject => (tr("Rejected"), Color::from_rgb(0.95, 0.3, 0.3)),
+    };
+    let position = grid_position(state, index).map_or(0, |p| p + 1);
+    let panel = row![
+        text(label).size(14).color(color),
+        text(trf("{} of {}", &[&position, &state.grid_shown.len()])).size(12),
+        text(tr("P pick \u{00B7} X reject \u{00B7} U clear \u{00B7} Esc stop")).size(12),
+    ]
+    .spacing(16)
+    .align_y(iced::Alignment::Center);
+    container(container(panel).padding([6, 12]).style(panel_style))
+        .align_x(iced::Alignment::Center)
+        .align_bottom(Length::Fill)
+        .width(Length::Fill)
+        .padding(16)
+        .into()
+}
+
+/// Size of the photo being edited, once it has decoded.
+fn editing_dimensions(state: &Looky) -> Option<(u32, u32)> {
+    let session = state.editing.as_ref()?;
+    state.viewer_dimensions.get(&session.index).copied()
+}
+
+/// On-screen width and height of the photo being edited, then of the
+/// rectangle it still fills once straightened, which the crop is relative to.
+fn edit_frame(state: &Looky, session: &EditSession) -> Option<(f32, f32, f32, f32)> {
+    let (w, h) = state.viewer_dimensions.get(&session.index).copied()?;
+    let (w, h) = (w.max(1) as f32, h.max(1) as f32);
+    let avail_w = (state.viewport_width - EDIT_PANEL_WIDTH - 2.0 * EDIT_MARGIN).max(1.0);
+    let avail_h = (state.viewport_height - 2.0 * EDIT_MARGIN).max(1.0);
+    let scale = (avail_w / w).min(avail_h / h);
+    let (shown_w, shown_h) = (w * scale, h * scale);
+    let k = edit::inner_scale(session.edit.angle, shown_w, shown_h);
+    Some((shown_w, shown_h, shown_w * k, shown_h * k))
+}
+
+fn refresh_metadata(state: &mut Looky) {
+    refresh_histogram(state);
+    if let Some(index) = state.viewer.current_index {
+        if state.cached_metadata.as_ref().is_some_and(|(i, _)| *i == index) {
+            return;
+        }
+        if let Some(path) = state.image_paths.get(index) {
+            let meta = metadata::read_metadata(path);
+            state.cached_metadata = Some((index, meta));
+        }
+    }
+}
+
+/// Bars in the culling overlay's histogram.
+const HISTOGRAM_BINS: usize = 64;
+/// Pixels sampled for the histogram at most; plenty for its shape.
+const HISTOGRAM_SAMPLES: usize = 250_000;
+
+/// Work out the current photo's luminance histogram for the culling
+/// overlay, once per image shown (the preview, then the full decode).
+fn refresh_histogram(state: &mut Looky) {
+    if !state.settings.viewer_culling_overlay {
+        return;
+    }
+    let Some(handle) = state.viewer.current_index.and_then(|i| state.viewer_cache.get(&i)) else {
+        return;
+    };
+    if state.histogram.as_ref().is_some_and(|(id, _)| *id == handle.id()) {
+        return;
+    }
+    let image::Handle::Rgba { pixels, .. } = handle else {
+        return;
+    };
+    let count = pixels.len() / 4;
+    let step = count.div_ceil(HISTOGRAM_SAMPLES).max(1);
+    let mut bins = [0u32; HISTOGRAM_BINS];
+    for pixel in pixels.chunks_exact(4).step_by(step) {
+        // Rec. 709 weights in 8-bit fixed point
+        let luma = (pixel[0] as usize * 54 + pixel[1] as usize * 183 + pixel[2] as usize * 19) >> 8;
+        bins[luma * HISTOGRAM_BINS / 256] += 1;
+    }
+    state.histogram = Some((handle.id(), bins));
+}
+
+fn view(state: &Looky) -> Element<'_, Message> {
+    let content = view_inner(state);
+    let in_viewer = state.viewer.current_index.is_some();
+    let screensaver = state.screensaver_active;
+    let menu_open = state.menu_open;
+    let cast_picker_open = state.cast_picker_open;
+    let qr_overlay_open = state.qr_overlay_open;
+    let editing = state.editing.is_some();
+    let choosing = conflict_dialog_open(state);
+    let wheel_navigates = state.settings.viewer_wheel_navigates && !state.viewer.is_zoomed();
+    let culling = state.culling && in_viewer;
+    KeyListener::new(content, move |key, modifiers, repeat| {
+        use iced::keyboard::key::Named;
+        use iced::keyboard::Key;
+        // Deciding a file conflict: Escape skips it
+        if choosing {
+            return match &key {
+                Key::Named(Named::Escape) if !repeat => Some(Message::ResolveConflict(OnConflict::Skip)),
+                _ => None,
+            };
+        }
+        // While editing: Escape cancels, Enter saves
+        if editing {
+            return match &key {
+                _ if repeat => None,
+                Key::Named(Named::Escape) => Some(Message::CancelEdit),
+                Key::Named(Named::Enter) => Some(Message::SaveEdit),
+                _ => None,
+            };
+        }
+        // During screensaver: Escape exits, Space pauses, arrows step
+        if screensaver {
+            return match &key {
+                _ if repeat => None,
+                Key::Named(Named::Escape) => Some(Message::KeyEscape),
+                Key::Named(Named::Space) => Some(Message::ToggleScreensaverPause),
+                Key::Named(Named::ArrowLeft) => Some(Message::ScreensaverStep(-1)),
+                Key::Named(Named::ArrowRight) => Some(Message::ScreensaverStep(1)),
+                _ => None,
+            };
+        }
+        match &key {
+            // Arrow/WASD keys allow repeats for smooth panning
+            Key::Named(Named::ArrowLeft) => Some(Message::KeyLeft),
+            Key::Named(Named::ArrowRight) => Some(Message::KeyRight),
+            Key::Named(Named::ArrowUp) => Some(Message::KeyUp),
+            Key::Named(Named::ArrowDown) => Some(Message::KeyDown),
+            Key::Character(c) if matches!(c.as_str(), "a" | "w" | "d" | "s") => {
+                match c.as_str() {
+                    "a" => Some(Message::KeyLeft),
+                    "d" => Some(Message::KeyRight),
+                    "w" => Some(Message::KeyUp),
+                    "s" => Some(Message::KeyDown),
+                    _ => None,
+                }
+            }
+            Key::Named(Named::Tab) if modifiers.shift() => Some(Message::FocusPrev),
+            Key::Named(Named::Tab) => Some(Message::FocusNext),
+            _ if repeat => None,
+            Key::Named(Named::F10 | Named::ContextMenu) => Some(Message::ToggleMenu),
+            Key::Named(Named::F5) if !in_viewer => Some(Message::RefreshFolder),
+            Key::Named(Named::F12) => Some(Message::ToggleDebugOverlay),
+            Key::Named(Named::Space) => Some(Message::ToggleZoom),
+            Key::Named(Named::Enter) => Some(Message::KeyEnter),
+            Key::Named(Named::Delete) => Some(Message::TrashPhoto),
+            Key::Named(Named::Escape) => {
+                if qr_overlay_open {
+                    Some(Message::ToggleQrOverlay)
+                } else if menu_open {
+                    Some(Message::ToggleMenu)
+                } else if cast_picker_open {
+                    Some(Message::ToggleCastPicker)
+                } else {
+                    Some(Message::KeyEscape)
+                }
+            }
+            Key::Character(c) if c.as_str() == "i" => {
+                if repeat { return None; }
+                Some(Message::ToggleInfo)
+            }
+            Key::Character(c) if c.as_str() == "f" && modifiers.command() => {
+                Some(Message::FocusSearch)
+            }
+            Key::Character(c) if c.as_str() == "f" => {
+                if repeat { return None; }
+                Some(Message::ToggleFullscreen)
+            }
+            Key::Character(c) if c.as_str() == "c" => {
+                if repeat { return None; }
+                Some(Message::CastImage)
+            }
+            Key::Character(c) if c.as_str() == "q" => Some(Message::ToggleQrOverlay),
+            Key::Character(c) if c.as_str() == "l" => Some(Message::ToggleDarkRoom),
+            Key::Character(c) if c.as_str() == "r" => Some(Message::RandomPhoto),
+            Key::Character(c) if c.as_str() == "t" => Some(Message::CycleRating),
+            Key::Character(c) if c.as_str() == "h" => Some(Message::ToggleFavorite),
+            Key::Character(c) if in_viewer && c.as_str() == "z" => Some(Message::CycleZoomPreset),
+            Key::Character(c) if in_viewer && c.as_str() == "m" => Some(Message::ToggleFlipMark),
+            // Flags, in the grid and while culling; outside culling the viewer leaves P and X alone
+            Key::Character(c) if (culling || !in_viewer) && c.as_str() == "p" => Some(Message::FlagPhoto(Flag::Pick)),
+            Key::Character(c) if (culling || !in_viewer) && c.as_str() == "x" => Some(Message::FlagPhoto(Flag::Reject)),
+            Key::Character(c) if (culling || !in_viewer) && c.as_str() == "u" => Some(Message::FlagPhoto(Flag::None)),
+            Key::Character(c) if in_viewer && c.as_str() == "b" => Some(Message::FlipCompare),
+            Key::Character(c) if in_viewer && c.as_str() == "k" => Some(Message::ToggleFocusPoints),
+            Key::Character(c) if in_viewer && c.as_str() == "o" => {
+                if repeat { return None; }
+                Some(Message::ToggleCullingOverlay)
+            }
+            Key::Character(c) if in_viewer && c.as_str() == "e" => Some(Message::StartEdit),
+            Key::Character(c) if in_viewer => {
+                let digit = c.as_str().parse::<usize>().ok().filter(|&d| d >= 1)?;
+                Some(Message::JumpToZoomPreset(digit - 1))
+            }
+            _ => None,
+        }
+    })
+    .on_scroll(move |delta, cx, cy, modifiers| {
+        if screensaver || editing { return None; }
+        if in_viewer && wheel_navigates && !modifiers.command() {
+            Some(Message::WheelNavigate(delta))
+        } else if in_viewer {
+            Some(Message::ZoomAdjust(delta, cx, cy))
+        } else {
+            None
+        }
+    })
+    .on_drag(move |dx, dy| {
+        if screensaver { return None; }
+        if in_viewer {
+            Some(Message::ViewerDrag(dx, dy))
+        } else {
+            Some(Message::DragScroll(dx, dy))
+        }
+    })
+    .on_click(move |cx, cy| {
+        if screensaver || editing { return None; }
+        if in_viewer {
+            Some(Message::ViewerClickZoom(cx, cy))
+        } else {
+            None
+        }
+    })
+    .on_right_click(move |cx, cy| {
+        if screensaver || editing { return None; }
+        if in_viewer {
+            Some(Message::ViewerClickUnzoom(cx, cy))
+        } else {
+            None
+        }
+    })
+    .on_pinch(move |scale, cx, cy| {
+        if screensaver || editing { return None; }
+        if in_viewer {
+            Some(Message::PinchZoom(scale, cx, cy))
+        } else {
+            None
+        }
+    })
+    .into()
+}
+
+fn view_inner(state: &Looky) -> Element<'_, Message> {
+    // Screensaver: no menu overlay
+    if state.screensaver_active {
+        if let Some(index) = state.viewer.current_index {
+            if state.image_paths.get(index).is_some() {
+                let full_handle = state.viewer_cache.get(&index);
+                let thumb_handle = state.thumbnails.get(index).map(|(_, h, _)| h);
+                let photo = viewer_view(
+                    thumb_handle,
+                    full_handle,
+                    grid_neighbor(state, index, -1).is_some(),
+                    grid_neighbor(state, index, 1).is_some(),
+                    state.cached_metadata.as_ref().map(|(_, m)| m),
+                    state.viewer.show_info,
+                    false,
+                    None,
+                    state.viewer.zoom_level,
+                    state.viewer.fit_mode,
+                    Background::Black,
+                    state.viewer_dimensions.get(&index).copied(),
+                    state.viewport_width,
+                    state.viewport_height,
+                    true,
+                );
+                if !state.screensaver_paused {
+                    return photo;
+                }
+                // A small, dim marker in the corner while paused
+                let paused = container(
+                    text(format!("‖  {}", tr("Paused")))
+                        .size(13)
+                        .color(Color::from_rgba(1.0, 1.0, 1.0, 0.7)),
+                )
+                .padding([4, 10])
+                .style(|_theme: &Theme| container::Style {
+                    background: Some(iced::Background::Color(Color::from_rgba(0.0, 0.0, 0.0, 0.4))),
+                    border: iced::Border {
+                        radius: 12.0.into(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                });
+                let corner = container(paused)
+                    .padding(16)
+                    .width(Length::Fill)
+                    .align_right(Length::Fill);
+                return iced::widget::Stack::with_children(vec![photo, corner.into()])
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .into();
+            }
+        }
+    }
+
+    // Editing: just the photo and its controls, no menu
+    if let Some(session) = &state.editing {
+        return edit_view(state, session);
+    }
+
+    // Build view content (without toolbars)
+    let content: Element<'_, Message> = if let Some(index) = state.viewer.current_index {
+        if state.image_paths.get(index).is_some() {
+            let full_handle = state.viewer_cache.get(&index);
+            let thumb_handle = state.thumbnails.get(index).map(|(_, h, _)| h);
+            let photo = viewer_view(
+                thumb_handle,
+                full_handle,
+                grid_neighbor(state, index, -1).is_some(),
+                grid_neighbor(state, index, 1).is_some(),
+                state.cached_metadata.as_ref().map(|(_, m)| m),
+                state.viewer.show_info,
+                state.viewer.show_focus,
+                state
+                    .image_paths
+                    .get(index)
+                    .and_then(|p| state.unreadable.get(p))
+                    .map(String::as_str),
+                state.viewer.zoom_level,
+                state.viewer.fit_mode,
+                state.settings.viewer_background.unwrap_or_default(),
+                state.viewer_dimensions.get(&index).copied(),
+                state.viewport_width,
+                state.viewport_height,
+                false,
+            );
+            let photo = match histogram_overlay(state, index) {
+                Some(overlay) => iced::widget::stack![photo, overlay]
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .into(),
+                None => photo,
+            };
+            if state.culling {
+                iced::widget::stack![photo, cull_overlay(state, index)]
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .into()
+            } else {
+                photo
+            }
+        } else {
+            container(Space::new()).into()
+        }
+    } else if state.settings_view_active {
+        settings_view(state)
+    } else if state.trash_view_active {
+        trash_view(state)
+    } else if state.import_view_active {
+        import_view(state)
+    } else if state.library_dups_view_active {
+        library_duplicates_view(state)
+    } else if state.unreadable_view_active {
+        unreadable_view(state)
+    } else if let Some(group_idx) = state.dup_compare {
+        if let Some(group) = state.dup_groups.get(group_idx) {
+            duplicates_compare_view(state, group)
+        } else {
+            container(Space::new()).into()
+        }
+    } else if state.dup_view_active {
+        duplicates_list_view(state)
+    } else if state.loading && state.thumbnails.is_empty() {
+        container(text(tr("Loading..."))).center(Length::Fill).into()
+    } else if !state.loading && state.thumbnails.is_empty() {
+        container(text(tr("Open a folder to browse photos")))
+            .center(Length::Fill)
+            .into()
+    } else {
+        let grid = scrollable(thumbnail_grid(state))
+            .id(grid_scroll_id())
+            .on_scroll(|vp| Message::GridScrolled(vp.absolute_offset().y))
+            .height(Length::Fill);
+        let bars = [selection_bar(state), resume_bar(state), filter_bar(state)];
+        column(bars.into_iter().flatten().chain([grid.into()])).into()
+    };
+
+    // Background jobs and the photo count along the bottom, outside the viewer
+    let content = match status_bar(state) {
+        Some(bar) if state.viewer.current_index.is_none() => column![content, bar].into(),
+        _ => content,
+    };
+
+    // Wrap with menu overlay, and the big QR code over that
+    let mut layers: Vec<Element<'_, Message>> = vec![content, menu_overlay(state)];
+    if let Some(overlay) = debug_overlay(state) {
+        layers.push(overlay);
+    }
+    if let Some(overlay) = qr_overlay(state) {
+        layers.push(overlay);
+    }
+    if let Some(dialog) = conflict_dialog(state) {
+        layers.push(dialog);
+    }
+    iced::widget::Stack::with_children(layers)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+}
+
+/// Thumbnail and catalog throughput over the last refresh, for the debug
+/// overlay.
+struct DebugStats {
+    sampled_at: Instant,
+    thumbs_done: usize,
+    queries: (u64, Duration),
+    thumbs_per_second: f32,
+    queries_per_second: f32,
+    query_ms: f32,
+}
+
+impl DebugStats {
+    fn start(thumbs_done: usize) -> Self {
+        Self {
+            sampled_at: Instant::now(),
+            thumbs_done,
+            queries: catalog::query_stats(),
+            thumbs_per_second: 0.0,
+            queries_per_second: 0.0,
+            query_ms: 0.0,
+        }
+    }
+
+    fn sample(&mut self, thumbs_done: usize) {
+        let seconds = self.sampled_at.elapsed().as_secs_f32().max(0.001);
+        let (queries, query_time) = catalog::query_stats();
+        let ran = queries - self.queries.0;
+        self.thumbs_per_second = thumbs_done.saturating_sub(self.thumbs_done) as f32 / seconds;
+        self.queries_per_second = ran as f32 / seconds;
+        self.query_ms = match ran {
+            0 => 0.0,
+            ran => (query_time - self.queries.1).as_secs_f32() * 1000.0 / ran as f32,
+        };
+        self.sampled_at = Instant::now();
+        self.thumbs_done = thumbs_done;
+        self.queries = (queries, query_time);
+    }
+}
+
+/// Throughput, queues and cache sizes in the top right corner, shown with
+/// F12 for reporting slowness. Not translated, as it's for bug reports.
+fn debug_overlay(state: &Looky) -> Option<Element<'_, Message>> {
+    let debug = state.debug.as_ref()?;
+    let (decodes_waiting, decodes_running) = state.decoder.queue_lengths();
+    let (file_batches_waiting, file_batches_running) = state.files.queue_lengths();
+    let viewer_bytes: u64 = state.viewer_cache.keys().map(|&i| viewer_image_bytes(state, i)).sum();
+    let (queries, query_time) = catalog::query_stats();
+    let lines = [
+        format!("thumbnails: {:.1}/s, {} done", debug.thumbs_per_second, state.thumbs_done),
+        format!(
+            "thumbnail queue: {} previews, {} upgrades, {} upgrade batches running",
+            state.pending_thumbnails.len(),
+            state.pending_upgrades.len(),
+            state.upgrade_batches_in_flight
+        ),
+        format!("decode queue: {decodes_waiting} waiting, {decodes_running} running"),
+        format!(
+            "viewer decode: {}",
+            state.decode_ms_average.map_or("-".to_string(), |ms| format!("{ms:.0} ms average"))
+        ),
+        format!(
+            "indexing queue: {}, hashing queue: {}",
+            state.info_pending.len(),
+            state.dup_pending.len()
+        ),
+        format!("file queue: {file_batches_waiting} waiting, {file_batches_running} running"),
+        format!(
+            "thumbnail cache: {} ({} images)",
+            metadata::format_file_size(state.thumbnail_bytes),
+            state.thumbnails.len()
+        ),
+        format!(
+            "viewer cache: {} ({} images)",
+            metadata::format_file_size(viewer_bytes),
+            state.viewer_cache.len()
+        ),
+        format!(
+            "catalog: {:.1} queries/s, {:.2} ms each; {} in all, {:.0} ms",
+            debug.queries_per_second,
+            debug.query_ms,
+            queries,
+            query_time.as_secs_f32() * 1000.0
+        ),
+    ];
+    let content = column(lines.into_iter().map(|line| {
+        text(line)
+            .size(11)
+            .font(iced::Font::MONOSPACE)
+            .color(Color::from_rgba(1.0, 1.0, 1.0, 0.9))
+            .into()
+    }))
+    .spacing(2);
+    let panel = container(content).padding([6, 10]).style(|_theme: &Theme| container::Style {
+        background: Some(iced::Background::Color(Color::from_rgba(0.0, 0.0, 0.0, 0.7))),
+        border: iced::Border {
+            radius: 6.0.into(),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    Some(container(panel).padding(16).width(Length::Fill).align_right(Length::Fill).into())
+}
+
+/// One line per running background job, with the photo count on the right.
+/// `None` when there's no folder open.
+fn status_bar(state: &Looky) -> Option<Element<'_, Message>> {
+    let mut jobs: Vec<Element<'_, Message>> = Vec::new();
+    if state.scan_job.is_some() {
+        jobs.push(job_row(
+            tr("Scanning folder"),
+            None,
+            None,
+            None,
+            Message::CancelFolderScan,
+        ));
+    }
+    if let Some(job) = state.thumb_job {
+        let total = state.image_paths.len();
+        let done = state.thumbs_done.min(total);
+        jobs.push(job_row(
+            tr("Thumbnails"),
+            Some((done, total)),
+            job.eta(done, total),
+            None,
+            Message::CancelThumbnails,
+        ));
+    }
+    if let Some(job) = state.info_job {
+        let total = state.info_total;
+        let done = total - state.info_pending.len();
+        jobs.push(job_row(
+            tr("Reading photo info"),
+            Some((done, total)),
+            job.eta(done, total),
+            None,
+            Message::CancelIndexing,
+        ));
+    }
+    if let Some(job) = state.dup_job {
+        let total = state.dup_total;
+        let done = total - state.dup_pending.len();
+        let (label, eta) = if state.dup_paused {
+            (tr("Duplicate scan paused"), None)
+        } else {
+            (tr("Hashing for duplicates"), job.eta(done, total))
+        };
+        jobs.push(job_row(
+            label,
+            Some((done, total)),
+            eta,
+            Some((state.dup_paused, Message::ToggleDupScanPause)),
+            Message::CancelDupScan,
+        ));
+    }
+
+    if let Some(job) = state.library_job {
+        let total = state.library_total;
+        let done = total - state.library_pending.len();
+        jobs.push(job_row(
+            tr("Checking against library"),
+            Some((done, total)),
+            job.eta(done, total),
+            None,
+            Message::CancelLibraryCheck,
+        ));
+    }
+    if let Some(job) = state.export_job {
+        let total = state.export_total;
+        let done = total - state.export_pending.len();
+        jobs.push(job_row(
+            tr("Exporting gallery"),
+            Some((done, total)),
+            job.eta(done, total),
+            Some((state.export_paused, Message::ToggleExportPause)),
+            Message::CancelExport,
+        ));
+    }
+    for job in &state.file_jobs {
+        let label = match &job.kind {
+            _ if job.awaiting_choice() => tr("Waiting for a choice"),
+            FileJobKind::Transfer(Transfer::Copy, _) => tr("Copying"),
+            FileJobKind::Transfer(Transfer::Move, _) => tr("Moving"),
+            FileJobKind::Trash => tr("Moving to the trash"),
+        };
+        let done = job.finished_count();
+        jobs.push(job_row(
+            label,
+            Some((done, job.total)),
+            job.progress.eta(done, job.total),
+            Some((job.paused, Message::ToggleFileJobPause(job.id))),
+            Message::CancelFileJob(job.id),
+        ));
+    }
+    if let Some(job) = state.contact_sheet_job {
+        let total = state.contact_sheet_total;
+        let done = total - state.contact_sheet_pending.len();
+        jobs.push(job_row(
+            tr("Making contact sheet"),
+            Some((done, total)),
+            job.eta(done, total),
+            None,
+            Message::CancelContactSheet,
+        ));
+    }
+    if let Some(job) = state.import_job {
+        let total = state.import_total;
+        let done = state.import_done + state.import_failed;
+        let label = if state.import_cancelled { "Cancelling import" } else { "Importing" };
+        jobs.push(job_row(
+            tr(label),
+            Some((done, total)),
+            job.eta(done, total),
+            None,
+            Message::CancelImport,
+        ));
+    }
+
+    let count = state.image_paths.len() - state.trashed.len();
+    if jobs.is_empty() && count == 0 {
+        return None;
+    }
+    let count_label = if state.grid_filter.is_active() {
+        trf("{} of {} photos", &[&state.grid_shown.len(), &count])
+    } else {
+        trn(count, "{} photo", "{} photos")
+    };
+    let count_text = text(count_label).size(13).color(label_color());
+    let unreadable: Element<'_, Message> = match state.unreadable.len() {
+        0 => Space::new().into(),
+        n => button(text(trn(n, "{} unreadable file", "{} unreadable files")).size(13))
+            .on_press(Message::ShowUnreadable)
+            .padding([0, 4])
+            .style(button::text)
+            .into(),
+    };
+    let bar = row![
+        column(jobs).spacing(2).width(Length::Fill),
+        unreadable,
+        count_text,
+    ]
+    .spacing(12)
+    .align_y(iced::Alignment::Center);
+    Some(
+        container(bar)
+            .padding([4, 12])
+            .width(Length::Fill)
+            .style(status_bar_style)
+            .into(),
+    )
+}
+
+/// A job's name, progress bar, count and ETA, and a cancel button. Jobs
+/// without a known total (the folder scan) show just the name. Jobs that
+/// can be paused pass whether they are and the message that toggles it.
+fn job_row<'a>(
+    label: &'a str,
+    progress: Option<(usize, usize)>,
+    eta: Option<Duration>,
+    pause: Option<(bool, Message)>,
+    cancel: Message,
+) -> Element<'a, Message> {
+    let mut items: Vec<Element<'a, Message>> = vec![text(label).size(13).width(170).into()];
+    if let Some((done, total)) = progress {
+        items.push(
+            iced::widget::progress_bar(0.0..=total.max(1) as f32, done as f32)
+                .length(200)
+                .girth(6)
+                .into(),
+        );
+        items.push(text(format!("{} / {}", done, total)).size(13).color(label_color()).into());
+    } else {
+        items.push(text("…").size(13).color(label_color()).into());
+    }
+    if let Some(eta) = eta {
+        let left = trf("about {} left", &[&status::format_eta(eta)]);
+        items.push(text(left).size(13).color(label_color()).into());
+    }
+    if let Some((paused, toggle)) = pause {
+        let (icon, name) = if paused {
+            ("\u{25B6}", tr("Resume"))
+        } else {
+            ("\u{2016}", tr("Pause"))
+        };
+        let toggle = button(text(icon).size(12))
+            .on_press(toggle)
+            .padding([0, 6])
+            .style(button::text);
+        items.push(named(toggle, name));
+    }
+    let cancel = button(text("\u{2715}").size(12))
+        .on_press(cancel)
+        .padding([0, 6])
+        .style(button::text);
+    items.push(named(cancel, tr("Cancel")));
+    row(items).spacing(10).align_y(iced::Alignment::Center).into()
 }
 
-fn visible_index_range(state: &Looky) -> std::ops::Range<usize> {
-    let cols = state.grid_columns.max(1);
-    let first_row = (state.grid_scroll_y / THUMB_CELL).floor().max(0.0) as usize;
-    let visible_rows = (state.viewport_height / THUMB_CELL).ceil() as usize + 1;
-    let first_idx = first_row * cols;
-    let last_idx = ((first_row + visible_rows) * cols).min(state.thumbnails.len());
-    first_idx..last_idx
+/// An entry in a filter dropdown: one value to match, or everything.
+#[derive(Debug, Clone, PartialEq)]
+struct FilterChoice<T> {
+    value: Option<T>,
+    label: String,
 }
 
-fn prioritize_upgrades(state: &mut Looky) {
-    if state.pending_upgrades.is_empty() {
-        return;
+impl<T> std::fmt::Display for FilterChoice<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.label)
     }
-    let visible = visible_index_range(state);
-    let visible_paths: HashSet<&PathBuf> = state.thumbnails[visible]
-        .iter()
-        .map(|(p, _, _)| p)
+}
+
+/// A dropdown of `values` plus an "all" entry, showing `selected`.
+fn filter_pick_list<'a, T>(
+    all: &'static str,
+    values: impl IntoIterator<Item = T>,
+    selected: Option<T>,
+    on_select: fn(Option<T>) -> Message,
+) -> Element<'a, Message>
+where
+    T: std::fmt::Display + Clone + PartialEq + 'static,
+{
+    let choice = |value: Option<T>| FilterChoice {
+        label: value.as_ref().map_or_else(|| tr(all).to_string(), T::to_string),
+        value,
+    };
+    let options: Vec<FilterChoice<T>> = std::iter::once(choice(None))
+        .chain(values.into_iter().map(|v| choice(Some(v))))
         .collect();
-    // Partition: visible first, then rest
-    state
-        .pending_upgrades
-        .sort_by_key(|p| if visible_paths.contains(p) { 0 } else { 1 });
+    iced::widget::pick_list(options, Some(choice(selected)), move |c: FilterChoice<T>| {
+        on_select(c.value)
+    })
+    .text_size(13)
+    .padding([2, 8])
+    .into()
 }
 
-fn load_next_batch(state: &mut Looky) -> Task<Message> {
-    if state.pending_thumbnails.is_empty() {
-        state.loading = false;
-        return Task::none();
+/// Batch actions for the picked photos, or how the last one went.
+fn selection_bar(state: &Looky) -> Option<Element<'_, Message>> {
+    let count = selected_indices(state).len();
+    if count == 0 && state.batch_status.is_none() {
+        return None;
+    }
+    let mut items: Vec<Element<'_, Message>> = Vec::new();
+    if count > 0 {
+        items.push(text(trn(count, "{} selected", "{} selected")).size(13).into());
+        let mut actions = vec![
+            ("Copy to...", Message::TransferSelection(Transfer::Copy)),
+            ("Move to...", Message::TransferSelection(Transfer::Move)),
+            ("Move to Trash", Message::TrashSelection),
+            ("Export Gallery", Message::ExportGallery),
+        ];
+        if share::AVAILABLE {
+            actions.push(("Share...", Message::ShareSelection));
+        }
+        for (label, message) in actions {
+            items.push(
+                button(text(tr(label)).size(13))
+                    .on_press(message)
+                    .style(button::secondary)
+                    .into(),
+            );
+        }
     }
+    if let Some(status) = &state.batch_status {
+        items.push(text(status).size(12).color(label_color()).into());
+    }
+    items.push(Space::new().width(Length::Fill).into());
+    items.push(
+        button(text("\u{2715}").size(13))
+            .on_press(Message::ClearSelection)
+            .style(button::text)
+            .into(),
+    );
+    let bar = row(items).spacing(8).align_y(iced::Alignment::Center);
+    Some(container(bar).padding([4, 8]).width(Length::Fill).into())
+}
 
-    let count = THUMBNAIL_BATCH_SIZE.min(state.pending_thumbnails.len());
-    let batch: Vec<PathBuf> = state.pending_thumbnails.drain(..count).collect();
+/// Offer to reopen the photo last viewed when the folder was open before.
+fn resume_bar(state: &Looky) -> Option<Element<'_, Message>> {
+    let index = state.resume_offer?;
+    let name = state.image_paths.get(index)?.file_name()?.to_string_lossy().into_owned();
+    let bar = row![
+        button(text(trf("Resume at {}", &[&name])).size(13))
+            .on_press(Message::ResumeViewing(index))
+            .style(button::secondary),
+        Space::new().width(Length::Fill),
+        button(text("\u{2715}").size(13))
+            .on_press(Message::DismissResume)
+            .style(button::text),
+    ]
+    .spacing(8)
+    .align_y(iced::Alignment::Center);
+    Some(container(bar).padding([4, 8]).width(Length::Fill).into())
+}
 
-    Task::perform(
-        async move { thumbnail::generate_thumbnails_parallel(&batch, 400) },
-        Message::ThumbnailBatchReady,
+/// The path from the open folder down to the subfolder being shown, each
+/// step clickable to go back up, the camera and lens dropdowns, and the
+/// subfolders one level further down. `None` when there's nothing to pick.
+fn filter_bar(state: &Looky) -> Option<Element<'_, Message>> {
+    let root = state.folder.as_deref()?;
+    if state.grid_subfolders.is_empty()
+        && state.filter_choices.is_empty()
+        && !state.grid_filter.is_active()
+        && !state.library_checked
+    {
+        return None;
+    }
+    let current = state.grid_filter.subfolder.as_deref().unwrap_or(root);
+    let name = |dir: &std::path::Path| {
+        dir.file_name()
+            .map_or_else(|| dir.display().to_string(), |n| n.to_string_lossy().into_owned())
+    };
+
+    let mut crumbs: Vec<Element<'_, Message>> = Vec::new();
+    for dir in filter::breadcrumbs(root, current) {
+        if !crumbs.is_empty() {
+            crumbs.push(text("\u{203A}").size(13).color(label_color()).into());
+        }
+        let label = text(name(&dir)).size(13);
+        if dir == current {
+            crumbs.push(label.into());
+        } else {
+            crumbs.push(
+                button(label)
+                    .on_press(Message::ShowSubfolder(dir))
+                    .padding([2, 6])
+                    .style(button::text)
+                    .into(),
+            );
+        }
+    }
+    let children = state.grid_subfolders.iter().map(|dir| {
+        button(text(name(dir)).size(13))
+            .on_press(Message::ShowSubfolder(dir.clone()))
+            .padding([2, 8])
+            .style(button::secondary)
+            .into()
+    });
+
+    crumbs.push(Space::new().width(Length::Fill).into());
+    let filter = &state.grid_filter;
+    let choices = &state.filter_choices;
+    if !choices.cameras.is_empty() || filter.camera.is_some() {
+        crumbs.push(filter_pick_list(
+            "All cameras",
+            choices.cameras.iter().cloned(),
+            filter.camera.clone(),
+            Message::SelectCamera,
+        ));
+    }
+    if !choices.lenses.is_empty() || filter.lens.is_some() {
+        crumbs.push(filter_pick_list(
+            "All lenses",
+            choices.lenses.iter().cloned(),
+            filter.lens.clone(),
+            Message::SelectLens,
+        ));
+    }
+    if choices.has_dimensions || filter.orientation.is_some() {
+        crumbs.push(filter_pick_list(
+            "All shapes",
+            Orientation::ALL,
+            filter.orientation,
+            Message::SelectOrientation,
+        ));
+    }
+    if state.ratings.values().any(|r| r.flag != Flag::None) || filter.flag.is_some() {
+        crumbs.push(filter_pick_list("All flags", Flag::ALL, filter.flag, Message::SelectFlag));
+    }
+    if state.library_checked {
+        crumbs.push(
+            iced::widget::checkbox(filter.new_only)
+                .label(tr("New only"))
+                .on_toggle(Message::SetNewOnly)
+                .size(14)
+                .text_size(13)
+                .into(),
+        );
+    }
+    let aspect_typed = !state.min_aspect_input.is_empty() || !state.max_aspect_input.is_empty();
+    if choices.has_dimensions || aspect_typed {
+        let aspect_input = |placeholder: &str, value: &str, on_input: fn(String) -> Message| {
+            text_input(placeholder, value)
+                .on_input(on_input)
+                .size(13)
+                .padding([2, 6])
+                .width(70)
+        };
+        crumbs.push(text(tr("Aspect")).size(13).color(label_color()).into());
+        crumbs.push(named(
+            aspect_input(tr("min"), &state.min_aspect_input, Message::MinAspectChanged),
+            tr("Narrowest width:height, e.g. 2:1 for panoramas"),
+        ));
+        crumbs.push(text("\u{2013}").size(13).color(label_color()).into());
+        crumbs.push(named(
+            aspect_input(tr("max"), &state.max_aspect_input, Message::MaxAspectChanged),
+            tr("Widest width:height, e.g. 4:5"),
+        ));
+    }
+
+    let mut bar = column![row(crumbs).spacing(4).align_y(iced::Alignment::Center)].spacing(6);
+    if !state.grid_subfolders.is_empty() {
+        bar = bar.push(row(children).spacing(6).wrap());
+    }
+    // Leave room above for the toolbar, which floats over the top of the view
+    let padding = iced::Padding { top: TOOLBAR_HEIGHT, right: 12.0, bottom: 8.0, left: 12.0 };
+    Some(
+        container(bar)
+            .padding(padding)
+            .width(Length::Fill)
+            .style(status_bar_style)
+            .into(),
     )
 }
 
-fn load_next_preview_batch(state: &mut Looky) -> Task<Message> {
-    if state.pending_thumbnails.is_empty() {
-        return Task::none();
+fn status_bar_style(_theme: &Theme) -> container::Style {
+    container::Style {
+        background: Some(chrome_background(0.12, 1.0)),
+        text_color: Some(chrome_text()),
+        ..Default::default()
     }
+}
 
-    let count = PREVIEW_BATCH_SIZE.min(state.pending_thumbnails.len());
-    let batch: Vec<PathBuf> = state.pending_thumbnails.drain(..count).collect();
+const THUMB_SIZE: f32 = 200.0;
+const THUMB_CELL: f32 = THUMB_SIZE;
+const GRID_PADDING: f32 = 0.0;
 
-    Task::perform(
-        async move { thumbnail::extract_previews_parallel(&batch, 400) },
-        Message::PreviewBatchReady,
-    )
+fn thumbnail_grid(state: &Looky) -> Element<'_, Message> {
+    let thumbnails = &state.thumbnails;
+    let shown = &state.grid_shown[..grid_len(state)];
+    let badge_set = &state.dup_badge_set;
+    let imported = &state.imported;
+    let photo_info = &state.photo_info;
+    let ratings = (!state.settings.grid_hide_ratings).then_some(&state.ratings);
+    let selection = &state.selection;
+    let unreadable = &state.unreadable;
+    let selected = state.selected_thumb;
+    let scroll_y = state.grid_scroll_y;
+    let viewport_h = state.viewport_height;
+
+    iced::widget::responsive(move |size| {
+        let geometry = grid_geometry(state, size.width);
+        let total_rows = geometry.rows.len();
+
+        // Determine visible row range (with 1-row buffer above and below)
+        let visible = geometry.rows_between(scroll_y, scroll_y + viewport_h);
+        let first_row = visible.start.saturating_sub(1);
+        let last_row = (visible.end + 1).min(total_rows);
+
+        let mut items: Vec<Element<Message>> = Vec::new();
+
+        // Top spacer for rows above visible range
+        if first_row > 0 {
+            let spacer_height = geometry.rows[first_row].top;
+            items.push(
+                Space::new()
+                    .width(Length::Fill)
+                    .height(spacer_height)
+                    .into(),
+            );
+        }
+
+        // Render only visible rows
+        for grid_row in &geometry.rows[first_row..last_row] {
+            let cell_height = grid_row.height;
+            let row_items: Vec<Element<Message>> = (grid_row.start..grid_row.end)
+                .map(|position| {
+                    let index = shown[position];
+                    let cell_width = geometry.widths[position];
+                    let (path, handle, added) = &thumbnails[index];
+                    let age_ms = added.elapsed().as_secs_f32() * 1000.0;
+                    let opacity = (age_ms / THUMB_FADE_MS).min(1.0);
+                    let img = image(handle.clone())
+                        .width(cell_width)
+                        .height(cell_height)
+                        .content_fit(iced::ContentFit::Cover)
+                        .opacity(opacity);
+
+                    let thumb_content: Element<'_, Message> =
+                        if badge_set.contains(&index) {
+                            iced::widget::stack![
+                                img,
+                                container(
+                                    button(
+                                        text("DUP").size(11).color(Color::WHITE),
+                                    )
+                                    .on_press(Message::CompareDuplicatesOf(index))
+                                    .padding([2, 6])
+                                    .style(dup_badge_style),
+                                )
+                                .align_right(cell_width)
+                                .padding(4),
+                            ]
+                            .into()
+                        } else if unreadable.contains_key(path) {
+                            iced::widget::stack![
+                                img,
+                                container(
+                                    container(
+                                        text(tr("\u{26A0} UNREADABLE")).size(11).color(Color::BLACK),
+                                    )
+                                    .padding([2, 6])
+                                    .style(unreadable_badge_style),
+                                )
+                                .align_right(cell_width)
+                                .padding(4),
+                            ]
+                            .into()
+                        } else {
+                            img.into()
+                        };
+
+                    let thumb_content: Element<'_, Message> = if imported.contains(&index) {
+                        iced::widget::stack![
+                            thumb_content,
+                            container(
+                                container(text(tr("IMPORTED")).size(11).color(Color::WHITE))
+                                    .padding([2, 6])
+                                    .style(imported_badge_style),
+                            )
+                            .padding(4),
+                        ]
+                        .into()
+                    } else {
+                        thumb_content
+                    };
+
+                    let kinds = file_badges(path, photo_info.get(path));
+                    let thumb_content: Element<'_, Message> = if kinds.is_empty() {
+                        thumb_content
+                    } else {
+                        let badges: Vec<Element<'_, Message>> = kinds
+                            .into_iter()
+                            .map(|label| {
+                                container(text(label).size(11).color(Color::WHITE))
+                                    .padding([2, 6])
+                                    .style(file_badge_style)
+                                    .into()
+                            })
+                            .collect();
+                        iced::widget::stack![
+                            thumb_content,
+                            container(row(badges).spacing(4))
+                                .align_bottom(cell_height)
+                                .padding(4),
+                        ]
+                        .into()
+                    };
+
+                    let rating = ratings
+                        .and_then(|r| r.get(path))
+                        .and_then(rating_label);
+                    let thumb_content: Element<'_, Message> = match rating {
+                        Some(label) => iced::widget::stack![
+                            thumb_content,
+                            container(
+                                container(text(label).size(11).color(Color::WHITE))
+                                    .padding([2, 6])
+                                    .style(file_badge_style),
+                            )
+                            .align_right(cell_width)
+                            .align_bottom(cell_height)
+                            .padding(4),
+                        ]
+                        .into(),
+                        None => thumb_content,
+                    };
+
+                    let is_selected = selected == Some(index) || selection.contains(&index);
+                    let thumb_content: Element<'_, Message> = if is_selected {
+                        iced::widget::stack![
+                            thumb_content,
+                            container(Space::new())
+                                .width(cell_width)
+                                .height(cell_height)
+                                .style(selection_overlay_style),
+                        ]
+                        .into()
+                    } else {
+                        thumb_content
+                    };
+                    button(thumb_content)
+                        .on_press(Message::ViewImage(index))
+                        .padding(0)
+                        .style(thumb_button_normal)
+                        .into()
+                })
+                .collect();
+            items.push(row(row_items).spacing(0).into());
+        }
+
+        // Bottom spacer for rows below visible range
+        if last_row < total_rows {
+            let spacer_height = geometry.height() - geometry.rows[last_row].top;
+            items.push(
+                Space::new()
+                    .width(Length::Fill)
+                    .height(spacer_height)
+                    .into(),
+            );
+        }
+
+        column(items).spacing(0).padding(GRID_PADDING).into()
+    })
+    .into()
 }
 
-fn load_upgrade_batches(state: &mut Looky) -> Task<Message> {
-    let mut tasks = Vec::new();
-    while state.upgrade_batches_in_flight < MAX_UPGRADE_BATCHES_IN_FLIGHT
-        && !state.pending_upgrades.is_empty()
-    {
-        let count = THUMBNAIL_BATCH_SIZE.min(state.pending_upgrades.len());
-        let batch: Vec<PathBuf> = state.pending_upgrades.drain(..count).collect();
-        state.upgrade_batches_in_flight += 1;
-        tasks.push(Task::perform(
-            async move { thumbnail::generate_thumbnails_parallel(&batch, 400) },
-            Message::ThumbnailUpgradeReady,
-        ));
+/// Photos with more pixels than this get a size badge in the grid.
+const LARGE_PHOTO_PIXELS: u64 = 50_000_000;
+
+/// Badges telling file kinds apart in the grid: RAW, GIF, video, and the
+/// megapixels of very large photos.
+fn file_badges(path: &std::path::Path, info: Option<&PhotoInfo>) -> Vec<String> {
+    let mut badges = Vec::new();
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_lowercase)
+        .unwrap_or_default();
+    match ext.as_str() {
+        "dng" | "cr2" | "cr3" | "nef" | "nrw" | "arw" | "orf" | "rw2" | "raf" | "pef" | "srw" => {
+            badges.push("RAW".to_string())
+        }
+        "gif" => badges.push("GIF".to_string()),
+        "mp4" | "mov" | "m4v" | "avi" | "mkv" | "webm" => badges.push("VIDEO".to_string()),
+        _ => {}
     }
-    Task::batch(tasks)
+    if let Some((w, h)) = info.and_then(|i| i.dimensions) {
+        let pixels = w as u64 * h as u64;
+        if pixels > LARGE_PHOTO_PIXELS {
+            badges.push(format!("{} MP", pixels / 1_000_000));
+        }
+    }
+    badges
 }
 
-fn load_next_dup_batch(state: &mut Looky) -> Task<Message> {
-    if state.dup_pending.is_empty() {
-        return Task::none();
+fn file_badge_style(_theme: &Theme) -> container::Style {
+    container::Style {
+        background: Some(iced::Background::Color(Color::from_rgba(0.0, 0.0, 0.0, 0.6))),
+        border: iced::Border {
+            radius: 4.0.into(),
+            ..Default::default()
+        },
+        ..Default::default()
     }
-
-    let count = DUP_HASH_BATCH_SIZE.min(state.dup_pending.len());
-    let batch: Vec<(usize, PathBuf)> = state.dup_pending.drain(..count).collect();
-
-    Task::perform(
-        async move { duplicates::compute_hashes_batch(&batch) },
-        Message::DupHashBatchReady,
-    )
 }
 
-fn preload_viewer_images(state: &mut Looky) -> Task<Message> {
-    // Abort all in-flight preloads — the user navigated, old work is stale
-    for (idx, handle) in state.viewer_preload_handles.drain(..) {
-        log::debug!("viewer: [{}] aborted", idx);
-        handle.abort();
+fn thumb_button_normal(_theme: &Theme, _status: button::Status) -> button::Style {
+    button::Style {
+        background: None,
+        border: iced::Border::default(),
+        ..button::Style::default()
     }
+}
 
-    let Some(idx) = state.viewer.current_index else {
-        return Task::none();
-    };
-
-    // Prioritize the current image — load it first, neighbors come after
-    if state.viewer_cache.contains_key(&idx) {
-        log::debug!("viewer: [{}] already cached, loading neighbors", idx);
-        return preload_viewer_neighbors(state);
-    }
-    log::debug!("viewer: [{}] loading (current)", idx);
-    let path = state.image_paths[idx].clone();
-    let (task, handle) = Task::perform(
-        async move {
-            match open_image_oriented(&path) {
-                Some(rgba) => {
-                    let (w, h) = rgba.dimensions();
-                    Message::ViewerImageLoaded(idx, rgba.into_raw(), w, h)
-                }
-                None => Message::Tick,
-            }
+fn selection_overlay_style(_theme: &Theme) -> container::Style {
+    container::Style {
+        border: iced::Border {
+            color: Color::WHITE,
+            width: 3.0,
+            ..Default::default()
         },
-        |msg| msg,
-    )
-    .abortable();
-    state.viewer_preload_handles.push((idx, handle));
-    task
+        ..Default::default()
+    }
 }
 
-fn preload_viewer_neighbors(state: &mut Looky) -> Task<Message> {
-    let Some(idx) = state.viewer.current_index else {
-        return Task::none();
+fn dup_badge_style(theme: &Theme, status: button::Status) -> button::Style {
+    let palette = theme.palette();
+    let background = match status {
+        button::Status::Hovered | button::Status::Pressed => palette.danger.scale_alpha(0.8),
+        _ => palette.danger,
     };
-    let total = state.image_paths.len();
-    let mut tasks = Vec::new();
-    let start = idx.saturating_sub(3);
-    let end = (idx + 3).min(total.saturating_sub(1));
-    for i in start..=end {
-        if i != idx && !state.viewer_cache.contains_key(&i) {
-            let path = state.image_paths[i].clone();
-            let index = i;
-            log::debug!("viewer: [{}] loading (neighbor)", i);
-            let (task, handle) = Task::perform(
-                async move {
-                    match open_image_oriented(&path) {
-                        Some(rgba) => {
-                            let (w, h) = rgba.dimensions();
-                            Message::ViewerImageLoaded(index, rgba.into_raw(), w, h)
-                        }
-                        None => Message::Tick,
-                    }
-                },
-                |msg| msg,
-            )
-            .abortable();
-            state.viewer_preload_handles.push((i, handle));
-            tasks.push(task);
-        }
+    button::Style {
+        background: Some(iced::Background::Color(background)),
+        text_color: Color::WHITE,
+        border: iced::Border {
+            radius: 4.0.into(),
+            ..Default::default()
+        },
+        ..Default::default()
     }
-    Task::batch(tasks)
 }
 
-fn preload_next_screensaver_image(state: &mut Looky) -> Task<Message> {
-    if !state.screensaver_active {
-        return Task::none();
-    }
-    let next_pos = state.screensaver_position + 1;
-    // If we're at the end, we'll reshuffle on advance — can't predict the order
-    if next_pos >= state.screensaver_order.len() {
-        return Task::none();
-    }
-    let next_idx = state.screensaver_order[next_pos];
-    if state.viewer_cache.contains_key(&next_idx) {
-        return Task::none();
+fn imported_badge_style(theme: &Theme) -> container::Style {
+    let palette = theme.palette();
+    container::Style {
+        background: Some(iced::Background::Color(palette.success)),
+        border: iced::Border {
+            radius: 4.0.into(),
+            ..Default::default()
+        },
+        ..Default::default()
     }
-    let path = state.image_paths[next_idx].clone();
-    let (task, handle) = Task::perform(
-        async move {
-            match open_image_oriented(&path) {
-                Some(rgba) => {
-                    let (w, h) = rgba.dimensions();
-                    Message::ViewerImageLoaded(next_idx, rgba.into_raw(), w, h)
-                }
-                None => Message::Tick,
-            }
+}
+
+fn unreadable_badge_style(theme: &Theme) -> container::Style {
+    let palette = theme.palette();
+    container::Style {
+        background: Some(iced::Background::Color(palette.warning)),
+        border: iced::Border {
+            radius: 4.0.into(),
+            ..Default::default()
         },
-        |msg| msg,
-    )
-    .abortable();
-    state.viewer_preload_handles.push((next_idx, handle));
-    task
+        ..Default::default()
+    }
 }
 
-fn open_image_oriented(path: &std::path::Path) -> Option<::image::RgbaImage> {
-    let img = ::image::open(path).ok()?;
-    let orientation = thumbnail::read_orientation(path);
-    let oriented = match orientation {
-        2 => img.fliph(),
-        3 => img.rotate180(),
-        4 => img.flipv(),
-        5 => img.rotate90().fliph(),
-        6 => img.rotate90(),
-        7 => img.rotate270().fliph(),
-        8 => img.rotate270(),
-        _ => img,
-    };
-    Some(oriented.to_rgba8())
+fn dup_card_focused_style(theme: &Theme) -> container::Style {
+    container::Style {
+        border: focus_border(theme),
+        ..container::bordered_box(theme)
+    }
 }
 
-fn refresh_metadata(state: &mut Looky) {
-    if let Some(index) = state.viewer.current_index {
-        if state.cached_metadata.as_ref().is_some_and(|(i, _)| *i == index) {
-            return;
-        }
-        if let Some(path) = state.image_paths.get(index) {
-            let meta = metadata::read_metadata(path);
-            state.cached_metadata = Some((index, meta));
-        }
+/// Height of each group's card in the duplicates list, fixed so the list can
+/// scroll to a group from the keyboard.
+const DUP_CARD_HEIGHT: f32 = 262.0;
+const DUP_CARD_SPACING: f32 = 12.0;
+const DUP_LIST_PADDING: f32 = 16.0;
+const DUP_HEADER_HEIGHT: f32 = 32.0;
+
+/// The folder holding `path`, relative to the open folder, or the open
+/// folder's own name for photos directly in it.
+fn relative_folder(state: &Looky, path: &std::path::Path) -> String {
+    let Some(parent) = path.parent() else {
+        return String::new();
+    };
+    match state.folder.as_deref().and_then(|root| parent.strip_prefix(root).ok()) {
+        Some(relative) if !relative.as_os_str().is_empty() => relative.display().to_string(),
+        Some(_) | None => parent
+            .file_name()
+            .map_or_else(|| parent.display().to_string(), |n| n.to_string_lossy().into_owned()),
     }
 }
 
-fn view(state: &Looky) -> Element<'_, Message> {
-    let content = view_inner(state);
-    let in_viewer = state.viewer.current_index.is_some();
-    let screensaver = state.screensaver_active;
-    let menu_open = state.menu_open;
-    KeyListener::new(content, move |key, repeat| {
-        use iced::keyboard::key::Named;
-        use iced::keyboard::Key;
-        // During screensaver, only allow Escape to exit
-        if screensaver {
-            return match &key {
-                Key::Named(Named::Escape) if !repeat => Some(Message::KeyEscape),
-                _ => None,
-            };
-        }
-        match &key {
-            // Arrow/WASD keys allow repeats for smooth panning
-            Key::Named(Named::ArrowLeft) => Some(Message::KeyLeft),
-            Key::Named(Named::ArrowRight) => Some(Message::KeyRight),
-            Key::Named(Named::ArrowUp) => Some(Message::KeyUp),
-            Key::Named(Named::ArrowDown) => Some(Message::KeyDown),
-            Key::Character(c) if matches!(c.as_str(), "a" | "w" | "d" | "s") => {
-                match c.as_str() {
-                    "a" => Some(Message::KeyLeft),
-                    "d" => Some(Message::KeyRight),
-                    "w" => Some(Message::KeyUp),
-                    "s" => Some(Message::KeyDown),
-                    _ => None,
+fn duplicates_list_view(state: &Looky) -> Element<'_, Message> {
+    let cards: Vec<Element<'_, Message>> = state
+        .dup_groups
+        .iter()
+        .enumerate()
+        .map(|(group_idx, group)| {
+            let (label, kind_color) = match &group.match_kind {
+                MatchKind::Exact => (tr("Exact match"), Color::from_rgb(0.9, 0.2, 0.2)),
+                MatchKind::Visual { distance } => {
+                    let _ = distance; // used in display below
+                    (tr("Visual match"), Color::from_rgb(0.9, 0.7, 0.1))
                 }
-            }
-            _ if repeat => None,
-            Key::Named(Named::Space) => Some(Message::ToggleZoom),
-            Key::Named(Named::Enter) => Some(Message::KeyEnter),
-            Key::Named(Named::Escape) => {
-                if menu_open {
-                    Some(Message::ToggleMenu)
-                } else {
-                    Some(Message::KeyEscape)
+            };
+
+            let match_detail = match &group.match_kind {
+                MatchKind::Exact => trf("{} identical files", &[&group.indices.len()]),
+                MatchKind::Visual { distance } => {
+                    trf("{} similar files (distance: {})", &[&group.indices.len(), distance])
                 }
-            }
-            Key::Character(c) if c.as_str() == "i" => {
-                if repeat { return None; }
-                Some(Message::ToggleInfo)
-            }
-            Key::Character(c) if c.as_str() == "f" => {
-                if repeat { return None; }
-                Some(Message::ToggleFullscreen)
-            }
-            Key::Character(c) if c.as_str() == "c" => {
-                if repeat { return None; }
-                Some(Message::CastImage)
-            }
-            _ => None,
-        }
-    })
-    .on_scroll(move |delta, cx, cy| {
-        if screensaver { return None; }
-        if in_viewer {
-            Some(Message::ZoomAdjust(delta, cx, cy))
-        } else {
-            None
-        }
-    })
-    .on_drag(move |dx, dy| {
-        if screensaver { return None; }
-        if in_viewer {
-            Some(Message::ViewerDrag(dx, dy))
-        } else {
-            Some(Message::DragScroll(dx, dy))
-        }
-    })
-    .on_click(move |cx, cy| {
-        if screensaver { return None; }
-        if in_viewer {
-            Some(Message::ViewerClickZoom(cx, cy))
-        } else {
-            None
-        }
-    })
-    .on_right_click(move |cx, cy| {
-        if screensaver { return None; }
-        if in_viewer {
-            Some(Message::ViewerClickUnzoom(cx, cy))
-        } else {
-            None
+            };
+
+            // Thumbnail row for this group
+            let thumb_row: Vec<Element<'_, Message>> = group
+                .indices
+                .iter()
+                .filter_map(|&idx| {
+                    let (_, handle, _) = state.thumbnails.get(idx)?;
+                    let summary = state.dup_summaries.get(&idx);
+                    let filename = summary
+                        .map(|s| s.filename.as_str())
+                        .or_else(|| {
+                            state.image_paths.get(idx)?
+                                .file_name()
+                                .and_then(|n| n.to_str())
+                        })
+                        .unwrap_or_default()
+                        .to_string();
+                    let subtitle = summary
+                        .and_then(|s| s.dimensions)
+                        .map(|(w, h)| format!("{} x {}", w, h))
+                        .unwrap_or_default();
+                    let folder = state
+                        .image_paths
+                        .get(idx)
+                        .map(|path| relative_folder(state, path))
+                        .unwrap_or_default();
+                    Some(
+                        column![
+                            image(handle.clone())
+                                .width(120)
+                                .height(120)
+                                .content_fit(iced::ContentFit::Cover),
+                            text(filename).size(10).wrapping(text::Wrapping::None),
+                            text(folder).size(9).color(label_color()).wrapping(text::Wrapping::None),
+                            text(subtitle).size(9).color(label_color()),
+                        ]
+                        .spacing(2)
+                        .width(130)
+                        .into(),
+                    )
+                })
+                .collect();
+
+            let total: u64 = group
+                .indices
+                .iter()
+                .filter_map(|idx| state.dup_summaries.get(idx))
+                .map(|s| s.file_size)
+                .sum();
+            let sizes = trf(
+                "{} total, {} reclaimable",
+                &[
+                    &metadata::format_file_size(total),
+                    &metadata::format_file_size(group_reclaimable(state, group)),
+                ],
+            );
+
+            let card_content = column![
+                row![
+                    text(label).size(13).color(kind_color),
+                    Space::new().width(Length::Fill),
+                    text(sizes).size(12).color(label_color()),
+                    text(match_detail).size(12).color(label_color()),
+                ]
+                .spacing(8),
+                scrollable(row(thumb_row).spacing(8))
+                    .direction(scrollable::Direction::Horizontal(
+                        scrollable::Scrollbar::default(),
+                    )),
+                row![
+                    button(tr("Compare")).on_press(Message::CompareDuplicates(group_idx)),
+                    button(tr("Keep Largest (Del)"))
+                        .style(button::secondary)
+                        .on_press(Message::KeepLargestOfGroup(group_idx)),
+                ]
+                .spacing(8),
+            ]
+            .spacing(8)
+            .padding(12);
+
+            let focused = state.dup_selected == Some(group_idx);
+            container(card_content)
+                .width(Length::Fill)
+                .height(DUP_CARD_HEIGHT)
+                .style(if focused { dup_card_focused_style } else { container::bordered_box })
+                .into()
+        })
+        .collect();
+
+    let wasted: u64 = state.dup_groups.iter().map(|g| group_reclaimable(state, g)).sum();
+    let order = state.settings.dup_order.unwrap_or_default();
+    let position = dup_position(state).unwrap_or_default();
+    let header = row![
+        text(trf(
+            "{} can be freed across {} groups",
+            &[&metadata::format_file_size(wasted), &state.dup_groups.len()],
+        ))
+        .size(14),
+        Space::new().width(Length::Fill),
+        text(position).size(13).color(label_color()),
+        text(tr("Sort by")).size(13).color(label_color()),
+        iced::widget::pick_list(GroupOrder::ALL, Some(order), Message::SetDupOrder)
+            .text_size(13)
+            .padding([2, 8]),
+    ]
+    .spacing(8)
+    .align_y(iced::Alignment::Center)
+    .height(DUP_HEADER_HEIGHT)
+    .padding([0, 16]);
+
+    let list = scrollable(column(cards).spacing(DUP_CARD_SPACING).padding(DUP_LIST_PADDING))
+        .id(dup_list_scroll_id())
+        .on_scroll(|vp| Message::DupListScrolled(vp.absolute_offset().y))
+        .height(Length::Fill);
+
+    // Below the toolbar overlay
+    let padding = iced::Padding { top: TOOLBAR_HEIGHT, ..iced::Padding::ZERO };
+    container(column![header, list].padding(padding)).into()
+}
+
+/// Start the share server for the open folder, recording any error for the menu.
+fn start_sharing(state: &mut Looky) {
+    let folder_name = state
+        .folder
+        .as_ref()
+        .and_then(|p| p.file_name())
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| tr("Photos").to_string());
+    let options = server::ServerOptions {
+        folder_name,
+        friendly_name: state.settings.share_name.clone(),
+        root_dir: state.folder.clone().unwrap_or_default(),
+        catalog_path: config_dir().map(|d| d.join("catalog.db")),
+        allow_uploads: state.allow_uploads,
+        avif_thumbs: state.settings.share_avif_thumbs,
+        thumb_cache_mb: state.settings.share_thumb_cache_mb,
+        http_workers: state.settings.share_http_workers,
+        interface: state.settings.share_interface.clone(),
+        port: state.settings.share_port,
+    };
+    match server::start_server(state.image_paths.clone(), options) {
+        Ok((handle, url)) => {
+            state.qr_handle = Some(render_qr(&url));
+            state.server_url = Some(url);
+            state.server_handle = Some(handle);
+            state.share_error = None;
+            state.share_started = Some(Instant::now());
         }
-    })
-    .on_pinch(move |scale, cx, cy| {
-        if screensaver { return None; }
-        if in_viewer {
-            Some(Message::PinchZoom(scale, cx, cy))
-        } else {
-            None
+        Err(e) => {
+            log::warn!("Failed to start sharing: {e}");
+            state.share_error = Some(e);
         }
-    })
-    .into()
+    }
 }
 
-fn view_inner(state: &Looky) -> Element<'_, Message> {
-    // Screensaver: no menu overlay
-    if state.screensaver_active {
-        if let Some(index) = state.viewer.current_index {
-            if state.image_paths.get(index).is_some() {
-                let full_handle = state.viewer_cache.get(&index);
-                let thumb_handle = state.thumbnails.get(index).map(|(_, h, _)| h);
-                return viewer_view(
-                    thumb_handle,
-                    full_handle,
-                    index > 0,
-                    index + 1 < state.image_paths.len(),
-                    state.cached_metadata.as_ref().map(|(_, m)| m),
-                    state.viewer.show_info,
-                    state.viewer.zoom_level,
-                    state.viewer_dimensions.get(&index).copied(),
-                    state.viewport_width,
-                    state.viewport_height,
-                    true,
-                );
-            }
-        }
+/// Disconnect every Chromecast and forget any in-flight connections.
+fn stop_casting(state: &mut Looky) {
+    for cast in state.cast_sessions.drain(..) {
+        cast.session.stop();
     }
+    state.cast_devices.clear();
+    state.cast_error = None;
+    state.cast_connecting.clear();
+    state.cast_slideshow_active = false;
+}
 
-    // Build view content (without toolbars)
-    let content: Element<'_, Message> = if let Some(index) = state.viewer.current_index {
-        if state.image_paths.get(index).is_some() {
-            let full_handle = state.viewer_cache.get(&index);
-            let thumb_handle = state.thumbnails.get(index).map(|(_, h, _)| h);
-            viewer_view(
-                thumb_handle,
-                full_handle,
-                index > 0,
-                index + 1 < state.image_paths.len(),
-                state.cached_metadata.as_ref().map(|(_, m)| m),
-                state.viewer.show_info,
-                state.viewer.zoom_level,
-                state.viewer_dimensions.get(&index).copied(),
-                state.viewport_width,
-                state.viewport_height,
-                false,
-            )
-        } else {
-            container(Space::new()).into()
-        }
-    } else if let Some(group_idx) = state.dup_compare {
-        if let Some(group) = state.dup_groups.get(group_idx) {
-            duplicates_compare_view(state, group)
-        } else {
-            container(Space::new()).into()
-        }
-    } else if state.dup_view_active {
-        duplicates_list_view(state)
-    } else if state.loading && state.thumbnails.is_empty() {
-        container(text("Loading...")).center(Length::Fill).into()
-    } else if !state.loading && state.thumbnails.is_empty() {
-        container(text("Open a folder to browse photos"))
-            .center(Length::Fill)
-            .into()
-    } else {
-        let grid = thumbnail_grid(state);
-        scrollable(grid)
-            .id(grid_scroll_id())
-            .on_scroll(|vp| Message::GridScrolled(vp.absolute_offset().y))
-            .height(Length::Fill)
-            .into()
-    };
+/// Stop the share, and any cast session that depends on it.
+fn stop_sharing(state: &mut Looky) {
+    stop_casting(state);
+    if let Some(handle) = state.server_handle.take() {
+        std::thread::spawn(move || handle.stop());
+    }
+    state.server_url = None;
+    state.qr_handle = None;
+    state.qr_overlay_open = false;
+    state.share_started = None;
+}
 
-    // Wrap with menu overlay
-    let layers: Vec<Element<'_, Message>> = vec![content, menu_overlay(state)];
-    iced::widget::Stack::with_children(layers)
-        .width(Length::Fill)
-        .height(Length::Fill)
-        .into()
+/// Time until the share stops itself, whichever of the overall and idle
+/// limits comes first. `None` when sharing is off or unlimited.
+fn share_time_remaining(state: &Looky) -> Option<Duration> {
+    let handle = state.server_handle.as_ref()?;
+    let overall = state
+        .settings
+        .share_time_limit
+        .zip(state.share_started)
+        .map(|(minutes, started)| {
+            Duration::from_secs(minutes * 60).saturating_sub(started.elapsed())
+        });
+    let idle = state
+        .settings
+        .share_idle_limit
+        .map(|minutes| Duration::from_secs(minutes * 60).saturating_sub(handle.idle_for()));
+    match (overall, idle) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
 }
 
-const THUMB_SIZE: f32 = 200.0;
-const THUMB_CELL: f32 = THUMB_SIZE;
-const GRID_PADDING: f32 = 0.0;
+fn format_countdown(d: Duration) -> String {
+    let secs = d.as_secs();
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 {
+        format!("{h}:{m:02}:{s:02}")
+    } else {
+        format!("{m}:{s:02}")
+    }
+}
 
-fn thumbnail_grid(state: &Looky) -> Element<'_, Message> {
-    let thumbnails = &state.thumbnails;
-    let badge_set = &state.dup_badge_set;
-    let selected = state.selected_thumb;
-    let scroll_y = state.grid_scroll_y;
-    let viewport_h = state.viewport_height;
+/// Interface names offered in settings: everything detected now, plus the saved
+/// choice if it has since disappeared so it stays visible and selected.
+fn share_interface_names(state: &Looky) -> Vec<String> {
+    let mut names: Vec<String> = state.share_interfaces.iter().map(|i| i.name.clone()).collect();
+    names.dedup();
+    let missing = state
+        .settings
+        .share_interface
+        .as_ref()
+        .filter(|saved| !names.contains(saved));
+    if let Some(saved) = missing {
+        names.push(saved.clone());
+    }
+    names
+}
 
-    iced::widget::responsive(move |size| {
-        let available = size.width - GRID_PADDING * 2.0;
-        let thumbs_per_row = (available / THUMB_CELL).max(1.0) as usize;
-        let total_rows = (thumbnails.len() + thumbs_per_row - 1) / thumbs_per_row;
+fn settings_view(state: &Looky) -> Element<'_, Message> {
+    let names = share_interface_names(state);
+    let selected = match &state.settings.share_interface {
+        Some(saved) => names.iter().position(|n| n == saved).map(|i| i + 1),
+        None => Some(0),
+    };
 
-        // Determine visible row range (with 1-row buffer above and below)
-        let first_visible_row = (scroll_y / THUMB_CELL).floor().max(0.0) as usize;
-        let visible_row_count = (viewport_h / THUMB_CELL).ceil() as usize + 2;
-        let first_row = first_visible_row.saturating_sub(1);
-        let last_row = (first_row + visible_row_count + 1).min(total_rows);
+    let mut interfaces: Vec<Element<'_, Message>> = vec![radio(
+        tr("Automatic (default route)"),
+        0,
+        selected,
+        Message::SelectShareInterface,
+    )
+    .size(16)
+    .into()];
+    for (i, name) in names.iter().enumerate() {
+        let addrs: Vec<String> = state
+            .share_interfaces
+            .iter()
+            .filter(|iface| &iface.name == name)
+            .map(|iface| iface.ip.to_string())
+            .collect();
+        let label = if addrs.is_empty() {
+            trf("{} (unavailable)", &[name])
+        } else {
+            format!("{name} — {}", addrs.join(", "))
+        };
+        interfaces.push(
+            radio(label, i + 1, selected, Message::SelectShareInterface)
+                .size(16)
+                .into(),
+        );
+    }
 
-        let mut items: Vec<Element<Message>> = Vec::new();
+    let local_url = state.server_handle.as_ref().and_then(|h| h.local_url());
+    let advertised = match (&state.server_url, &local_url) {
+        (Some(url), Some(local)) => trf("Advertising {} and {}", &[url, local]),
+        (Some(url), None) => trf("Advertising {}", &[url]),
+        _ => tr("Not sharing").to_string(),
+    };
 
-        // Top spacer for rows above visible range
-        if first_row > 0 {
-            let spacer_height = first_row as f32 * THUMB_CELL;
-            items.push(
-                Space::new()
-                    .width(Length::Fill)
-                    .height(spacer_height)
-                    .into(),
-            );
-        }
+    let time_limits: Vec<Element<'_, Message>> = SHARE_TIME_LIMITS
+        .iter()
+        .map(|&(minutes, label)| {
+            radio(
+                tr(label),
+                minutes,
+                Some(state.settings.share_time_limit),
+                Message::SelectShareTimeLimit,
+            )
+            .size(16)
+            .into()
+        })
+        .collect();
+    let http_workers: Vec<Element<'_, Message>> = SHARE_HTTP_WORKERS
+        .iter()
+        .map(|&(workers, label)| {
+            radio(
+                tr(label),
+                workers,
+                Some(state.settings.share_http_workers),
+                Message::SelectShareHttpWorkers,
+            )
+            .size(16)
+            .into()
+        })
+        .collect();
+    let thumb_cache_sizes: Vec<Element<'_, Message>> = SHARE_THUMB_CACHE_SIZES
+        .iter()
+        .map(|&(mb, label)| {
+            radio(
+                tr(label),
+                mb,
+                Some(state.settings.share_thumb_cache_mb),
+                Message::SelectShareThumbCacheSize,
+            )
+            .size(16)
+            .into()
+        })
+        .collect();
+    let idle_limits: Vec<Element<'_, Message>> = SHARE_IDLE_LIMITS
+        .iter()
+        .map(|&(minutes, label)| {
+            radio(
+                tr(label),
+                minutes,
+                Some(state.settings.share_idle_limit),
+                Message::SelectShareIdleLimit,
+            )
+            .size(16)
+            .into()
+        })
+        .collect();
 
-        // Render only visible rows
-        for row_idx in first_row..last_row {
-            let start = row_idx * thumbs_per_row;
-            let end = (start + thumbs_per_row).min(thumbnails.len());
-            if start >= thumbnails.len() {
-                break;
-            }
+    let preload_radii: Vec<Element<'_, Message>> = PRELOAD_RADII
+        .iter()
+        .map(|&(radius, label)| {
+            radio(
+                tr(label),
+                radius,
+                Some(state.settings.viewer_preload_radius),
+                Message::SelectPreloadRadius,
+            )
+            .size(16)
+            .into()
+        })
+        .collect();
+    let contact_sheet_grids: Vec<Element<'_, Message>> = CONTACT_SHEET_GRIDS
+        .iter()
+        .map(|&(grid, label)| {
+            radio(
+                tr(label),
+                grid,
+                Some(state.settings.contact_sheet_grid),
+                Message::SelectContactSheetGrid,
+            )
+            .size(16)
+            .into()
+        })
+        .collect();
+    let cache_sizes: Vec<Element<'_, Message>> = VIEWER_CACHE_SIZES
+        .iter()
+        .map(|&(mb, label)| {
+            radio(
+                tr(label),
+                mb,
+                Some(state.settings.viewer_cache_mb),
+                Message::SelectViewerCacheSize,
+            )
+            .size(16)
+            .into()
+        })
+        .collect();
 
-            let row_items: Vec<Element<Message>> = (start..end)
-                .map(|index| {
-                    let (_path, handle, added) = &thumbnails[index];
-                    let age_ms = added.elapsed().as_secs_f32() * 1000.0;
-                    let opacity = (age_ms / THUMB_FADE_MS).min(1.0);
-                    let img = image(handle.clone())
-                        .width(THUMB_SIZE)
-                        .height(THUMB_SIZE)
-                        .content_fit(iced::ContentFit::Cover)
-                        .opacity(opacity);
+    let grid_cache_sizes: Vec<Element<'_, Message>> = GRID_CACHE_SIZES
+        .iter()
+        .map(|&(mb, label)| {
+            radio(
+                tr(label),
+                mb,
+                Some(state.settings.grid_cache_mb),
+                Message::SelectGridCacheSize,
+            )
+            .size(16)
+            .into()
+        })
+        .collect();
 
-                    let thumb_content: Element<'_, Message> =
-                        if badge_set.contains(&index) {
-                            iced::widget::stack![
-                                img,
-                                container(
-                                    container(
-                                        text("DUP").size(11).color(Color::WHITE),
-                                    )
-                                    .padding([2, 6])
-                                    .style(dup_badge_style),
-                                )
-                                .align_right(THUMB_SIZE)
-                                .padding(4),
-                            ]
-                            .into()
-                        } else {
-                            img.into()
-                        };
+    let sharpen_amounts: Vec<Element<'_, Message>> = SHARPEN_AMOUNTS
+        .iter()
+        .map(|&(percent, label)| {
+            radio(
+                tr(label),
+                percent,
+                Some(state.settings.grid_sharpen_percent),
+                Message::SelectGridSharpenAmount,
+            )
+            .size(16)
+            .into()
+        })
+        .collect();
 
-                    let is_selected = selected == Some(index);
-                    let thumb_content: Element<'_, Message> = if is_selected {
-                        iced::widget::stack![
-                            thumb_content,
-                            container(Space::new())
-                                .width(THUMB_SIZE)
-                                .height(THUMB_SIZE)
-                                .style(selection_overlay_style),
-                        ]
-                        .into()
-                    } else {
-                        thumb_content
-                    };
-                    button(thumb_content)
-                        .on_press(Message::ViewImage(index))
-                        .padding(0)
-                        .style(thumb_button_normal)
-                        .into()
-                })
-                .collect();
-            items.push(row(row_items).spacing(0).into());
-        }
+    let grid_layouts: Vec<Element<'_, Message>> = GridLayout::ALL
+        .iter()
+        .map(|&layout| {
+            radio(
+                layout.label(),
+                layout,
+                Some(state.settings.grid_layout.unwrap_or_default()),
+                Message::SelectGridLayout,
+            )
+            .size(16)
+            .into()
+        })
+        .collect();
 
-        // Bottom spacer for rows below visible range
-        if last_row < total_rows {
-            let spacer_height = (total_rows - last_row) as f32 * THUMB_CELL;
-            items.push(
-                Space::new()
-                    .width(Length::Fill)
-                    .height(spacer_height)
-                    .into(),
-            );
-        }
+    let fit_modes: Vec<Element<'_, Message>> = FitMode::ALL
+        .iter()
+        .map(|&mode| {
+            radio(mode.label(), mode, Some(state.viewer.fit_mode), Message::SelectFitMode)
+                .size(16)
+                .into()
+        })
+        .collect();
+    // The field only takes effect once it names a zone
+    let time_zone_saved = state.settings.time_zone.as_deref().unwrap_or_default();
+    let time_zone_hint = if state.time_zone_input.trim() == time_zone_saved {
+        tr("A name such as Europe/Berlin. Leave empty to use the system's.")
+    } else {
+        tr("Not a known time zone")
+    };
+    let scan_depths: Vec<Element<'_, Message>> = SCAN_DEPTHS
+        .iter()
+        .map(|&(depth, label)| {
+            radio(tr(label), depth, Some(state.settings.scan_depth), Message::SelectScanDepth)
+                .size(16)
+                .into()
+        })
+        .collect();
+    let screensaver_recency: Vec<Element<'_, Message>> = SCREENSAVER_RECENCY
+        .iter()
+        .map(|&(strength, label)| {
+            radio(
+                tr(label),
+                strength,
+                Some(state.settings.screensaver_recency),
+                Message::SelectScreensaverRecency,
+            )
+            .size(16)
+            .into()
+        })
+        .collect();
+    let max_zooms: Vec<Element<'_, Message>> = MAX_ZOOM_LEVELS
+        .iter()
+        .map(|&(zoom, label)| {
+            radio(
+                tr(label),
+                zoom,
+                Some(state.settings.viewer_max_zoom),
+                Message::SelectMaxZoom,
+            )
+            .size(16)
+            .into()
+        })
+        .collect();
 
-        column(items).spacing(0).padding(GRID_PADDING).into()
-    })
-    .into()
-}
+    let backgrounds: Vec<Element<'_, Message>> = Background::ALL
+        .iter()
+        .map(|&background| {
+            radio(
+                background.label(),
+                background,
+                Some(state.settings.viewer_background.unwrap_or_default()),
+                Message::SelectViewerBackground,
+            )
+            .size(16)
+            .into()
+        })
+        .collect();
 
-fn thumb_button_normal(_theme: &Theme, _status: button::Status) -> button::Style {
-    button::Style {
-        background: None,
-        border: iced::Border::default(),
-        ..button::Style::default()
+    let languages: Vec<Element<'_, Message>> = std::iter::once(None)
+        .chain(Language::ALL.into_iter().map(Some))
+        .map(|language| {
+            radio(
+                language.map_or(tr("System default"), Language::label),
+                language,
+                Some(state.settings.language),
+                Message::SelectLanguage,
+            )
+            .size(16)
+            .into()
+        })
+        .collect();
+
+    let mut peers: Vec<Element<'_, Message>> = Vec::new();
+    if state.peer_scanning {
+        peers.push(text(tr("Searching...")).size(13).color(label_color()).into());
+    } else {
+        peers.push(button(tr("Find other Looky shares")).on_press(Message::FindPeers).into());
+        for peer in &state.peers {
+            peers.push(text(format!("{}  {}", peer.folder_name, peer.url)).size(13).into());
+        }
     }
-}
 
-fn selection_overlay_style(_theme: &Theme) -> container::Style {
-    container::Style {
-        border: iced::Border {
-            color: Color::WHITE,
-            width: 3.0,
-            ..Default::default()
-        },
-        ..Default::default()
-    }
-}
+    let content = column![
+        text(tr("Sharing")).size(20),
+        section_header(tr("Network interface")),
+        column(interfaces).spacing(8),
+        section_header(tr("Server name")),
+        column![
+            text_input(tr("Looky — folder name"), &state.share_name_input)
+                .on_input(Message::ShareNameChanged)
+                .width(320),
+            text(tr("How TVs and media players list this share"))
+                .size(12)
+                .color(label_color()),
+        ]
+        .spacing(6),
+        section_header(tr("Port")),
+        row![
+            text_input(tr("Automatic"), &state.share_port_input)
+                .on_input(Message::SharePortChanged)
+                .width(120),
+            text(tr("Leave empty to pick a free port")).size(12).color(label_color()),
+        ]
+        .spacing(12)
+        .align_y(iced::Alignment::Center),
+        section_header(tr("Stop sharing")),
+        column(time_limits).spacing(8),
+        section_header(tr("Stop when idle")),
+        column(idle_limits).spacing(8),
+        section_header(tr("Thumbnails")),
+        iced::widget::checkbox(state.settings.share_avif_thumbs)
+            .label(tr("Offer AVIF thumbnails"))
+            .on_toggle(Message::SetShareAvifThumbs)
+            .size(16),
+        text(tr("Browsers get WebP, or JPEG if they can't show it. AVIF is smaller again but slower to make."))
+            .size(12)
+            .color(label_color()),
+        section_header(tr("Requests served at once")),
+        column(http_workers).spacing(8),
+        text(tr("Raise this if TVs downloading full-size photos slow down phones browsing the gallery."))
+            .size(12)
+            .color(label_color()),
+        section_header(tr("Memory for gallery thumbnails")),
+        column(thumb_cache_sizes).spacing(8),
+        text(tr("The least recently viewed are dropped first and made again from the grid's cache."))
+            .size(12)
+            .color(label_color()),
+        section_divider(),
+        text(advertised).size(13),
+        text(tr("Changes apply the next time sharing starts."))
+            .size(12)
+            .color(label_color()),
+        section_header(tr("On this network")),
+        column(peers).spacing(8),
+        section_divider(),
+        text(tr("Viewer")).size(20),
+        section_header(tr("Preload full-size images")),
+        column(preload_radii).spacing(8),
+        text(tr("Automatic works further ahead when photos decode quickly, and less far for large files."))
+            .size(12)
+            .color(label_color()),
+        section_header(tr("Memory for full-size images")),
+        column(cache_sizes).spacing(8),
+        text(tr("Images farthest from the one on screen are dropped first."))
+            .size(12)
+            .color(label_color()),
+        section_header(tr("Image size")),
+        column(fit_modes).spacing(8),
+        section_header(tr("Maximum zoom")),
+        row(max_zooms).spacing(16),
+        section_header(tr("Scroll wheel")),
+        iced::widget::checkbox(state.settings.viewer_wheel_navigates)
+            .label(tr("Scroll to the previous or next photo"))
+            .on_toggle(Message::SetViewerWheelNavigates)
+            .size(16),
+        text(tr("Hold Ctrl while scrolling to zoom. Once zoomed in, scrolling zooms as before."))
+            .size(12)
+            .color(label_color()),
+        section_header(tr("Background")),
+        row(backgrounds).spacing(16),
+        text(tr("Shows through transparent PNG and WebP images."))
+            .size(12)
+            .color(label_color()),
+        section_header(tr("Zoom presets")),
+        column![
+            text_input("100, 200, fit", &state.zoom_presets_input)
+                .on_input(Message::ZoomPresetsChanged)
+                .width(320),
+            text(tr("Percent of actual size, or \"fit\". Keys 1–9 jump to these; Z cycles."))
+                .size(12)
+                .color(label_color()),
+        ]
+        .spacing(6),
+        section_divider(),
+        text(tr("Grid")).size(20),
+        section_header(tr("Layout")),
+        column(grid_layouts).spacing(8),
+        section_header(tr("Badges")),
+        iced::widget::checkbox(!state.settings.grid_hide_ratings)
+            .label(tr("Show ratings and favorites"))
+            .on_toggle(Message::SetGridRatingBadges)
+            .size(16),
+        section_header(tr("Sharpening")),
+        iced::widget::checkbox(state.settings.grid_sharpen)
+            .label(tr("Sharpen thumbnails after shrinking"))
+            .on_toggle(Message::SetGridSharpen)
+            .size(16),
+        column(sharpen_amounts).spacing(8),
+        text(tr("Restores detail lost when photos are shrunk for the grid. Changing it remakes the thumbnails."))
+            .size(12)
+            .color(label_color()),
+        section_header(tr("Memory for thumbnails")),
+        column(grid_cache_sizes).spacing(8),
+        text(tr("Thumbnails far from the visible rows are shrunk and reloaded from disk when scrolled back."))
+            .size(12)
+            .color(label_color()),
+        section_header(tr("Subfolders")),
+        column(scan_depths).spacing(8),
+        text(tr("How deep opening a folder looks for photos. Each folder can choose its own from the menu."))
+            .size(12)
+            .color(label_color()),
+        section_divider(),
+        text(tr("Duplicates")).size(20),
+        iced::widget::checkbox(state.settings.dup_report_capture_pairs)
+            .label(tr("Report RAW or HEIC files and their JPEGs"))
+            .on_toggle(Message::SetDupReportCapturePairs)
+            .size(16),
+        text(tr("Otherwise files with the same name and capture time in the same folder count as one photo saved twice, not as duplicates."))
+            .size(12)
+            .color(label_color()),
+        section_divider(),
+        text(tr("Screensaver")).size(20),
+        section_header(tr("Shuffle")),
+        column(screensaver_recency).spacing(8),
+        text(tr("Recent means the date taken, or when the file was last changed. Older photos still come up now and then."))
+            .size(12)
+            .color(label_color()),
+        section_header(tr("Timing")),
+        iced::widget::checkbox(state.settings.screensaver_dwell_by_aspect)
+            .label(tr("Show panoramas longer and portraits shorter"))
+            .on_toggle(Message::SetScreensaverDwellByAspect)
+            .size(16),
+        section_divider(),
+        text(tr("Contact sheets")).size(20),
+        section_header(tr("Photos per page")),
+        column(contact_sheet_grids).spacing(8),
+        text(tr("Each photo is captioned with its file name and date."))
+            .size(12)
+            .color(label_color()),
+        section_divider(),
+        text(tr("Language")).size(20),
+        column(languages).spacing(8),
+        section_header(tr("Time zone")),
+        column![
+            text_input(tr("System time zone"), &state.time_zone_input)
+                .on_input(Message::TimeZoneChanged)
+                .width(320),
+            text(time_zone_hint).size(12).color(label_color()),
+        ]
+        .spacing(6),
+        section_divider(),
+        text(tr("Accessibility")).size(20),
+        iced::widget::checkbox(state.settings.high_contrast)
+            .label(tr("High contrast"))
+            .on_toggle(Message::SetHighContrast)
+            .size(16),
+        text(tr("White text on black with bright outlines. Tab and Shift+Tab step through the grid, duplicates, menus and settings fields; F10 opens the menu."))
+            .size(12)
+            .color(label_color()),
+    ]
+    .spacing(12)
+    .padding(iced::Padding { top: 64.0, right: 24.0, bottom: 24.0, left: 24.0 })
+    .max_width(560);
 
-fn dup_badge_style(theme: &Theme) -> container::Style {
-    let palette = theme.palette();
-    container::Style {
-        background: Some(iced::Background::Color(palette.danger)),
-        border: iced::Border {
-            radius: 4.0.into(),
-            ..Default::default()
-        },
-        ..Default::default()
-    }
+    scrollable(content).height(Length::Fill).into()
 }
 
-fn duplicates_list_view(state: &Looky) -> Element<'_, Message> {
-    let cards: Vec<Element<'_, Message>> = state
-        .dup_groups
+/// Most card files listed in the import view; the rest are only counted.
+const IMPORT_LIST_LIMIT: usize = 200;
+
+/// Where to import from and to, what's on the card, and the import button.
+fn import_view(state: &Looky) -> Element<'_, Message> {
+    let mut sources: Vec<Element<'_, Message>> = state
+        .import_volumes
         .iter()
-        .enumerate()
-        .map(|(group_idx, group)| {
-            let (label, label_color) = match &group.match_kind {
-                MatchKind::Exact => ("Exact match", Color::from_rgb(0.9, 0.2, 0.2)),
-                MatchKind::Visual { distance } => {
-                    let _ = distance; // used in display below
-                    ("Visual match", Color::from_rgb(0.9, 0.7, 0.1))
-                }
-            };
+        .map(|volume| {
+            let name = volume
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| volume.display().to_string());
+            let selected = state.import_source.as_ref() == Some(volume);
+            button(text(name))
+                .on_press(Message::ImportSourceSelected(Some(volume.clone())))
+                .style(if selected { button::primary } else { button::secondary })
+                .into()
+        })
+        .collect();
+    sources.push(button(tr("Choose Folder...")).on_press(Message::PickImportSource).into());
+    sources.push(
+        button(tr("Refresh"))
+            .on_press(Message::RefreshImportVolumes)
+            .style(button::text)
+            .into(),
+    );
 
-            let match_detail = match &group.match_kind {
-                MatchKind::Exact => format!("{} identical files", group.indices.len()),
-                MatchKind::Visual { distance } => {
-                    format!("{} similar files (distance: {})", group.indices.len(), distance)
-                }
-            };
+    let mut content = column![
+        text(tr("Import Photos")).size(20),
+        section_header(tr("From")),
+        row(sources).spacing(8).wrap(),
+    ]
+    .spacing(12);
+    if state.import_volumes.is_empty() {
+        content = content.push(text(tr("No memory cards found")).size(12).color(label_color()));
+    }
 
-            // Thumbnail row for this group
-            let thumb_row: Vec<Element<'_, Message>> = group
-                .indices
+    if let Some(source) = &state.import_source {
+        content = content.push(text(source.display().to_string()).size(12).color(label_color()));
+        if state.import_scanning {
+            content = content.push(text(tr("Reading the card...")).size(13));
+        } else {
+            let new = state.import_items.iter().filter(|i| !i.already_imported).count();
+            let already = state.import_items.len() - new;
+            content = content.push(
+                text(trf("{} new, {} already imported", &[&new, &already])).size(13),
+            );
+            let mut entries: Vec<Element<'_, Message>> = state
+                .import_items
                 .iter()
-                .filter_map(|&idx| {
-                    let (_, handle, _) = state.thumbnails.get(idx)?;
-                    let summary = state.dup_summaries.get(&idx);
-                    let filename = summary
-                        .map(|s| s.filename.as_str())
-                        .or_else(|| {
-                            state.image_paths.get(idx)?
-                                .file_name()
-                                .and_then(|n| n.to_str())
-                        })
-                        .unwrap_or_default()
-                        .to_string();
-                    let subtitle = summary
-                        .and_then(|s| s.dimensions)
-                        .map(|(w, h)| format!("{} x {}", w, h))
+                .take(IMPORT_LIST_LIMIT)
+                .map(|item| {
+                    let name = item
+                        .path
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
                         .unwrap_or_default();
-                    Some(
-                        column![
-                            image(handle.clone())
-                                .width(120)
-                                .height(120)
-                                .content_fit(iced::ContentFit::Cover),
-                            text(filename).size(10),
-                            text(subtitle).size(9).color(LABEL_COLOR),
-                        ]
-                        .spacing(2)
-                        .width(130)
-                        .into(),
-                    )
+                    let status = if item.already_imported { tr("Already imported") } else { "" };
+                    row![
+                        text(name).size(13).width(Length::Fill),
+                        text(item.day.clone().unwrap_or_default()).size(12).color(label_color()),
+                        text(metadata::format_file_size(item.size)).size(12).color(label_color()),
+                        text(status).size(12).color(label_color()).width(120),
+                    ]
+                    .spacing(12)
+                    .into()
                 })
                 .collect();
+            if let Some(more) = state.import_items.len().checked_sub(IMPORT_LIST_LIMIT).filter(|&n| n > 0) {
+                entries.push(text(trf("and {} more", &[&more])).size(12).color(label_color()).into());
+            }
+            content = content.push(
+                container(scrollable(column(entries).spacing(4).padding(8)).height(240))
+                    .style(container::bordered_box),
+            );
+        }
+    }
 
-            let card_content = column![
+    let dest = import_destination(state);
+    let dest_label = dest
+        .as_ref()
+        .map_or_else(|| tr("No folder chosen").to_string(), |d| d.display().to_string());
+    content = content.push(section_header(tr("To")));
+    content = content.push(
+        row![
+            text(dest_label).size(13).width(Length::Fill),
+            button(tr("Choose...")).on_press(Message::PickImportDestination),
+        ]
+        .spacing(8)
+        .align_y(iced::Alignment::Center),
+    );
+    content = content.push(
+        text(tr("Sorted into year and day folders, e.g. 2024/2024-05-01"))
+            .size(12)
+            .color(label_color()),
+    );
+    if state.import_source.as_ref().is_some_and(|s| state.import_volumes.contains(s)) {
+        content = content.push(
+            iced::widget::checkbox(state.settings.eject_after_import)
+                .label(tr("Eject the card when done"))
+                .on_toggle(Message::SetEjectAfterImport)
+                .size(16),
+        );
+    }
+
+    let new = state.import_items.iter().filter(|i| !i.already_imported).count();
+    let ready = new > 0 && dest.is_some() && !state.import_scanning && state.import_job.is_none();
+    content = content.push(
+        button(text(trn(new, "Import {} Photo", "Import {} Photos")))
+            .on_press_maybe(ready.then_some(Message::StartImport))
+            .style(button::primary),
+    );
+    if let Some(status) = &state.import_status {
+        content = content.push(text(status.as_str()).size(13));
+    }
+
+    let content = content
+        .padding(iced::Padding { top: TOOLBAR_HEIGHT, right: 24.0, bottom: 24.0, left: 24.0 })
+        .max_width(720);
+    scrollable(content).height(Length::Fill).into()
+}
+
+/// Photos Looky moved to its trash, newest first, each with restore and
+/// delete-for-good buttons.
+fn trash_view(state: &Looky) -> Element<'_, Message> {
+    let mut header = row![text(tr("Recently Deleted")).size(20), Space::new().width(Length::Fill)]
+        .align_y(iced::Alignment::Center);
+    if !state.trash_items.is_empty() {
+        header = header.push(button(tr("Empty Trash")).on_press(Message::EmptyTrash));
+    }
+    let mut content = column![header].spacing(12);
+    if let Some(err) = &state.trash_error {
+        content = content.push(text(err.as_str()).size(13).color(Color::from_rgb(0.9, 0.2, 0.2)));
+    }
+    if state.trash_items.is_empty() {
+        content = content.push(text(tr("Nothing has been deleted")).size(14).color(label_color()));
+    }
+    for photo in &state.trash_items {
+        let name = photo
+            .original_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let folder = photo
+            .original_path
+            .parent()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
+        let deleted = std::time::UNIX_EPOCH + Duration::from_secs(photo.deleted_at.max(0) as u64);
+        let details = column![
+            text(name).size(14),
+            text(folder).size(12).color(label_color()),
+            text(trf("Deleted {}", &[&metadata::display_system_time(deleted)]))
+                .size(12)
+                .color(label_color()),
+        ]
+        .spacing(2)
+        .width(Length::Fill);
+        let entry = row![
+            details,
+            button(tr("Restore")).on_press(Message::RestoreTrashed(photo.trashed_path.clone())),
+            button(tr("Delete Permanently"))
+                .on_press(Message::DeleteTrashedForever(photo.trashed_path.clone()))
+                .style(button::danger),
+        ]
+        .spacing(8)
+        .align_y(iced::Alignment::Center);
+        content = content.push(container(entry).padding(12).width(Length::Fill).style(container::bordered_box));
+    }
+    let content = content
+        .padding(iced::Padding { top: TOOLBAR_HEIGHT, right: 24.0, bottom: 24.0, left: 24.0 })
+        .max_width(720);
+    scrollable(content).height(Length::Fill).into()
+}
+
+/// Identical files anywhere in the catalog, grouped by the folders they're
+/// spread over, each folder a click away.
+fn library_duplicates_view(state: &Looky) -> Element<'_, Message> {
+    let wasted: u64 = state.library_dups.iter().map(|o| o.reclaimable).sum();
+    let mut content = column![text(tr("Duplicates Across Library")).size(20)].spacing(12);
+    if state.library_dups.is_empty() {
+        content = content.push(
+            text(tr("No identical files found in the folders scanned so far"))
+                .size(14)
+                .color(label_color()),
+        );
+    } else {
+        content = content.push(
+            text(trf(
+                "{} can be freed across {} sets of folders",
+                &[&metadata::format_file_size(wasted), &state.library_dups.len()],
+            ))
+            .size(14),
+        );
+    }
+    for overlap in &state.library_dups {
+        let mut card = column![].spacing(6);
+        for folder in &overlap.folders {
+            card = card.push(
                 row![
-                    text(label).size(13).color(label_color),
-                    Space::new().width(Length::Fill),
-                    text(match_detail).size(12).color(LABEL_COLOR),
+                    text(folder.display().to_string())
+                        .size(14)
+                        .width(Length::Fill)
+                        .wrapping(text::Wrapping::WordOrGlyph),
+                    button(text(tr("Open")).size(13))
+                        .on_press(Message::FolderSelected(Some(folder.clone())))
+                        .style(button::secondary),
                 ]
-                .spacing(8),
-                scrollable(row(thumb_row).spacing(8))
-                    .direction(scrollable::Direction::Horizontal(
-                        scrollable::Scrollbar::default(),
-                    )),
-                button("Compare").on_press(Message::CompareDuplicates(group_idx)),
+                .spacing(8)
+                .align_y(iced::Alignment::Center),
+            );
+        }
+        card = card.push(
+            text(trf(
+                "{}, {} can be freed",
+                &[
+                    &trn(overlap.copies.len(), "{} photo", "{} photos"),
+                    &metadata::format_file_size(overlap.reclaimable),
+                ],
+            ))
+            .size(12)
+            .color(label_color()),
+        );
+        for copies in overlap.copies.iter().take(LIBRARY_DUPS_SHOWN) {
+            let names: Vec<String> = copies.iter().map(|p| p.display().to_string()).collect();
+            card = card.push(
+                text(names.join("  \u{2194}  "))
+                    .size(12)
+                    .wrapping(text::Wrapping::WordOrGlyph),
+            );
+        }
+        if overlap.copies.len() > LIBRARY_DUPS_SHOWN {
+            let more = overlap.copies.len() - LIBRARY_DUPS_SHOWN;
+            card = card.push(text(trn(more, "and {} more", "and {} more")).size(12).color(label_color()));
+        }
+        content = content.push(container(card).padding(12).width(Length::Fill).style(container::bordered_box));
+    }
+    let content = content
+        .padding(iced::Padding { top: TOOLBAR_HEIGHT, right: 24.0, bottom: 24.0, left: 24.0 })
+        .max_width(900);
+    scrollable(content).height(Length::Fill).into()
+}
+
+fn unreadable_view(state: &Looky) -> Element<'_, Message> {
+    let mut content = column![text(tr("Unreadable Files")).size(20)].spacing(12);
+    if state.unreadable.is_empty() {
+        content = content.push(text(tr("Every photo in this folder could be read")).size(14).color(label_color()));
+    } else {
+        content = content.push(
+            row![
+                text(tr("These files couldn't be decoded. Files still being copied or synced are tried again by themselves."))
+                    .size(14)
+                    .width(Length::Fill)
+                    .wrapping(text::Wrapping::WordOrGlyph),
+                button(text(tr("Try Again")).size(13))
+                    .on_press(Message::RetryUnreadable)
+                    .style(button::secondary),
             ]
             .spacing(8)
-            .padding(12);
-
-            container(card_content)
-                .width(Length::Fill)
-                .style(container::bordered_box)
-                .into()
-        })
-        .collect();
-
-    let list = scrollable(column(cards).spacing(12).padding(16))
-        .id(dup_list_scroll_id())
-        .on_scroll(|vp| Message::DupListScrolled(vp.absolute_offset().y))
-        .height(Length::Fill);
-
-    container(list).into()
+            .align_y(iced::Alignment::Center),
+        );
+    }
+    let mut files: Vec<(&PathBuf, &String)> = state.unreadable.iter().collect();
+    files.sort();
+    for (path, error) in files {
+        let mut card = column![
+            text(path.display().to_string()).size(14).wrapping(text::Wrapping::WordOrGlyph),
+            text(error.as_str()).size(12).color(label_color()).wrapping(text::Wrapping::WordOrGlyph),
+        ]
+        .spacing(4);
+        if state.thumb_retries.get(path).is_some_and(|(_, at)| at.is_some()) {
+            card = card.push(text(tr("Trying again shortly")).size(12).color(label_color()));
+        }
+        content = content.push(container(card).padding(12).width(Length::Fill).style(container::bordered_box));
+    }
+    let content = content
+        .padding(iced::Padding { top: TOOLBAR_HEIGHT, right: 24.0, bottom: 24.0, left: 24.0 })
+        .max_width(900);
+    scrollable(content).height(Length::Fill).into()
 }
 
 fn duplicates_compare_view<'a>(state: &'a Looky, group: &'a DuplicateGroup) -> Element<'a, Message> {
@@ -1720,24 +7599,25 @@ fn duplicates_compare_view<'a>(state: &'a Looky, group: &'a DuplicateGroup) -> E
 
             let mut details: Vec<Element<'_, Message>> = vec![
                 text(filename).size(13).into(),
+                text(relative_folder(state, path)).size(11).color(label_color()).into(),
                 text(format!("{}  {}", dims_text, size_text))
                     .size(11)
-                    .color(LABEL_COLOR)
+                    .color(label_color())
                     .into(),
             ];
             if let Some(date) = info.and_then(|s| s.date_taken.as_deref()) {
                 details.push(
-                    text(format!("Taken: {}", date))
+                    text(trf("Taken: {}", &[&metadata::display_taken(date)]))
                         .size(11)
-                        .color(LABEL_COLOR)
+                        .color(label_color())
                         .into(),
                 );
             }
             if let Some(date) = info.and_then(|s| s.date_modified.as_deref()) {
                 details.push(
-                    text(format!("Modified: {}", date))
+                    text(trf("Modified: {}", &[&metadata::display_modified(date)]))
                         .size(11)
-                        .color(LABEL_COLOR)
+                        .color(label_color())
                         .into(),
                 );
             }
@@ -1772,13 +7652,6 @@ fn viewer_scroll_id() -> iced::widget::Id {
     iced::widget::Id::new("viewer-zoom")
 }
 
-/// Compute the fit-to-screen base size for an image in the viewport.
-/// Returns (fit_w, fit_h) — the size the image would be at zoom 1.0.
-fn fit_size(img_w: u32, img_h: u32, vp_w: f32, vp_h: f32) -> (f32, f32) {
-    let scale = (vp_w / img_w as f32).min(vp_h / img_h as f32);
-    (img_w as f32 * scale, img_h as f32 * scale)
-}
-
 /// Compute the centering padding for the zoomed image inside the scrollable.
 /// The container is max(render_size, viewport_size), so when the image is smaller
 /// than the viewport, padding centers it.
@@ -1796,7 +7669,7 @@ fn center_zoom_scroll(state: &Looky) -> Task<Message> {
 
     let vp_w = state.viewport_width;
     let vp_h = state.viewport_height;
-    let (fit_w, fit_h) = fit_size(img_w, img_h, vp_w, vp_h);
+    let (fit_w, fit_h) = state.viewer.fit_mode.base_size(img_w, img_h, vp_w, vp_h);
     let render_w = fit_w * state.viewer.zoom_level;
     let render_h = fit_h * state.viewer.zoom_level;
     let pad_x = zoom_padding(render_w, vp_w);
@@ -1852,7 +7725,7 @@ fn anchor_zoom_scroll(state: &mut Looky, old_zoom: f32, new_zoom: f32) -> Task<M
 
     let vp_w = state.viewport_width;
     let vp_h = state.viewport_height;
-    let (fit_w, fit_h) = fit_size(img_w, img_h, vp_w, vp_h);
+    let (fit_w, fit_h) = state.viewer.fit_mode.base_size(img_w, img_h, vp_w, vp_h);
 
     let old_render_w = fit_w * old_zoom;
     let old_render_h = fit_h * old_zoom;
@@ -1926,6 +7799,34 @@ fn anchor_zoom_scroll(state: &mut Looky, old_zoom: f32, new_zoom: f32) -> Task<M
     }
 }
 
+/// Animate to a zoom preset, anchored at the viewport centre.
+fn jump_to_zoom_preset(state: &mut Looky, preset: usize) -> Task<Message> {
+    let Some(idx) = state.viewer.current_index else {
+        return Task::none();
+    };
+    if !has_full_image(state, idx) {
+        return Task::none();
+    }
+    let Some(&zoom) = zoom_presets(&state.settings).get(preset) else {
+        return Task::none();
+    };
+    let Some(&(img_w, img_h)) = state.viewer_dimensions.get(&idx) else {
+        return Task::none();
+    };
+    let vp_w = state.viewport_width;
+    let vp_h = state.viewport_height;
+    let target = match zoom {
+        ZoomPreset::Fit => 1.0,
+        ZoomPreset::Percent(percent) => {
+            let (base_w, _) = state.viewer.fit_mode.base_size(img_w, img_h, vp_w, vp_h);
+            percent as f32 / 100.0 * img_w as f32 / base_w
+        }
+    };
+    state.viewer.zoom_to(target, (vp_w / 2.0, vp_h / 2.0));
+    state.viewer.zoom_preset = Some(preset);
+    Task::none()
+}
+
 fn pan_zoom(state: &mut Looky, dx: f32, dy: f32) -> Task<Message> {
     let (ox, oy) = state.viewer.zoom_offset;
     let new_x = (ox + dx).max(0.0);
@@ -1949,7 +7850,11 @@ fn viewer_view<'a>(
     has_next: bool,
     meta: Option<&'a PhotoMetadata>,
     show_info: bool,
+    show_focus: bool,
+    decode_error: Option<&'a str>,
     zoom_level: f32,
+    fit_mode: FitMode,
+    background: Background,
     image_dims: Option<(u32, u32)>,
     viewport_width: f32,
     viewport_height: f32,
@@ -1977,20 +7882,34 @@ fn viewer_view<'a>(
             .into();
     }
 
-    if zoom_level > 1.0 {
-        // Zoomed view: render at zoom_level × fit-to-screen size
+    let focus_areas = meta
+        .map(|m| m.focus_areas.as_slice())
+        .filter(|areas| show_focus && !areas.is_empty());
+
+    // Nothing decoded and nothing coming: show why instead of a gray square
+    let decode_error = decode_error.filter(|_| full_handle.is_none());
+
+    if decode_error.is_none() && (zoom_level > 1.0 || fit_mode != FitMode::Fit) {
+        // Pannable view: render at zoom_level × the fit mode's base size
         let handle = full_handle.or(thumb_handle);
         let image_layer: Element<'a, Message> = if let Some(h) = handle {
             let (img_w, img_h) = image_dims.unwrap_or((800, 600));
             let avail_w = viewport_width;
             let avail_h = viewport_height;
-            let (fit_w, fit_h) = fit_size(img_w, img_h, avail_w, avail_h);
+            let (fit_w, fit_h) = fit_mode.base_size(img_w, img_h, avail_w, avail_h);
             let render_w = fit_w * zoom_level;
             let render_h = fit_h * zoom_level;
             let img = image(h.clone())
                 .content_fit(iced::ContentFit::Fill)
                 .width(render_w)
                 .height(render_h);
+            let img: Element<'a, Message> = match focus_areas {
+                Some(areas) => iced::widget::stack![img, focus_overlay(areas, render_w, render_h)]
+                    .width(render_w)
+                    .height(render_h)
+                    .into(),
+                None => img.into(),
+            };
             container(img)
                 .center_x(render_w.max(avail_w))
                 .center_y(render_h.max(avail_h))
@@ -2012,7 +7931,7 @@ fn viewer_view<'a>(
                 Message::ZoomScrolled(offset.x, offset.y)
             });
 
-        let mut layers: Vec<Element<'_, Message>> = vec![zoom_scroll.into()];
+        let mut layers: Vec<Element<'_, Message>> = vec![backdrop(zoom_scroll, background)];
         if show_info {
             if let Some(m) = meta {
                 layers.push(info_panel(m));
@@ -2025,8 +7944,9 @@ fn viewer_view<'a>(
     }
 
     // Normal (fit-to-screen) view
-    let image_layer: Element<'a, Message> = match (full_handle, thumb_handle) {
-        (Some(full), Some(thumb)) => {
+    let image_layer: Element<'a, Message> = match (decode_error, full_handle, thumb_handle) {
+        (Some(error), _, _) => decode_error_panel(error),
+        (None, Some(full), Some(thumb)) => {
             let thumb_img = image(thumb.clone())
                 .content_fit(iced::ContentFit::Contain)
                 .width(Length::Fill)
@@ -2043,21 +7963,21 @@ fn viewer_view<'a>(
             .height(Length::Fill)
             .into()
         }
-        (Some(full), None) => {
+        (None, Some(full), None) => {
             let full_img = image(full.clone())
                 .content_fit(iced::ContentFit::Contain)
                 .width(Length::Fill)
                 .height(Length::Fill);
             container(full_img).center(Length::Fill).into()
         }
-        (None, Some(thumb)) => {
+        (None, None, Some(thumb)) => {
             let thumb_img = image(thumb.clone())
                 .content_fit(iced::ContentFit::Contain)
                 .width(Length::Fill)
                 .height(Length::Fill);
             container(thumb_img).center(Length::Fill).into()
         }
-        (None, None) => {
+        (None, None, None) => {
             container(Space::new())
                 .center(Length::Fill)
                 .into()
@@ -2113,7 +8033,23 @@ fn viewer_view<'a>(
     .height(Length::Fill)
     .width(Length::Fill);
 
-    let image_with_nav = iced::widget::stack![image_layer, nav_overlay,]
+    // Focus points over the contained image, scaled the same way ContentFit::Contain does
+    let image_layer: Element<'a, Message> = match (focus_areas, image_dims) {
+        (Some(areas), Some((img_w, img_h))) if img_w > 0 && img_h > 0 => {
+            let scale = (viewport_width / img_w as f32).min(viewport_height / img_h as f32);
+            let (render_w, render_h) = (img_w as f32 * scale, img_h as f32 * scale);
+            iced::widget::stack![
+                image_layer,
+                container(focus_overlay(areas, render_w, render_h)).center(Length::Fill),
+            ]
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+        }
+        _ => image_layer,
+    };
+
+    let image_with_nav = iced::widget::stack![backdrop(image_layer, background), nav_overlay,]
         .width(Length::Fill)
         .height(Length::Fill);
 
@@ -2129,8 +8065,291 @@ fn viewer_view<'a>(
         .into()
 }
 
-const LABEL_COLOR: Color = Color::from_rgb(0.5, 0.5, 0.55);
-
+/// Width of the crop and straighten controls beside the photo.
+const EDIT_PANEL_WIDTH: f32 = 280.0;
+/// Space around the photo being edited.
+const EDIT_MARGIN: f32 = 24.0;
+
+/// The photo being cropped and straightened, with its controls.
+fn edit_view<'a>(state: &'a Looky, session: &'a EditSession) -> Element<'a, Message> {
+    let handle = state.viewer_cache.get(&session.index);
+    let photo: Element<'a, Message> = match (handle, edit_frame(state, session)) {
+        (Some(handle), Some((shown_w, shown_h, inner_w, inner_h))) => {
+            let img = image(handle.clone())
+                .content_fit(iced::ContentFit::Fill)
+                .width(shown_w)
+                .height(shown_h)
+                .rotation(iced::Rotation::Floating(iced::Radians(
+                    session.edit.angle.to_radians(),
+                )));
+            let [x, y, w, h] = session.edit.crop;
+            let left = (shown_w - inner_w) / 2.0 + x * inner_w;
+            let top = (shown_h - inner_h) / 2.0 + y * inner_h;
+            let (crop_w, crop_h) = (w * inner_w, h * inner_h);
+            let shade = |width: Length, height: Length| -> Element<'a, Message> {
+                container(Space::new())
+                    .width(width)
+                    .height(height)
+                    .style(|_theme: &Theme| container::Style {
+                        background: Some(iced::Background::Color(Color::from_rgba(
+                            0.0, 0.0, 0.0, 0.6,
+                        ))),
+                        ..Default::default()
+                    })
+                    .into()
+            };
+            let crop_box = container(Space::new())
+                .width(crop_w)
+                .height(crop_h)
+                .style(|_theme: &Theme| container::Style {
+                    border: iced::Border {
+                        color: Color::WHITE,
+                        width: 1.0,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                });
+            let overlay = column![
+                shade(Length::Fill, Length::Fixed(top)),
+                row![
+                    shade(Length::Fixed(left), Length::Fill),
+                    crop_box,
+                    shade(Length::Fill, Length::Fill),
+                ]
+                .height(crop_h),
+                shade(Length::Fill, Length::Fill),
+            ];
+            let framed = iced::widget::stack![img, overlay]
+                .width(shown_w)
+                .height(shown_h);
+            // Keep the turned corners inside the frame
+            let framed = container(framed).width(shown_w).height(shown_h).clip(true);
+            container(framed).center(Length::Fill).into()
+        }
+        _ => container(text(tr("Loading..."))).center(Length::Fill).into(),
+    };
+    let photo = container(photo)
+        .padding(EDIT_MARGIN)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(screensaver_bg_style);
+
+    let aspects: Vec<Element<'a, Message>> = CropAspect::ALL
+        .iter()
+        .map(|&aspect| {
+            radio(aspect.label(), aspect, Some(session.aspect), Message::SelectCropAspect)
+                .size(16)
+                .into()
+        })
+        .collect();
+    let [_, _, crop_w, crop_h] = session.edit.crop;
+    let size_sliders: Element<'a, Message> = match session.aspect {
+        CropAspect::Free => column![
+            text(tr("Width")).size(13),
+            slider(0.05..=1.0, crop_w, Message::EditCropWidth).step(0.01),
+            text(tr("Height")).size(13),
+            slider(0.05..=1.0, crop_h, Message::EditCropHeight).step(0.01),
+        ]
+        .spacing(6)
+        .into(),
+        aspect => {
+            let full = editing_dimensions(state)
+                .and_then(|(w, h)| aspect.crop_size(1.0, w, h))
+                .map_or(1.0, |(w, _)| w);
+            let size = (crop_w / full).clamp(0.05, 1.0);
+            column![
+                text(tr("Size")).size(13),
+                slider(0.05..=1.0, size, Message::EditCropSize).step(0.01),
+            ]
+            .spacing(6)
+            .into()
+        }
+    };
+    let angle = session.edit.angle;
+    let straighten = column![
+        text(trf("Straighten: {}°", &[&format!("{angle:+.1}")])).size(13),
+        slider(-edit::MAX_ANGLE..=edit::MAX_ANGLE, angle, Message::EditStraighten).step(0.1),
+    ]
+    .spacing(6);
+
+    let mut panel = column![
+        text(tr("Crop and Straighten")).size(18),
+        text(tr("Drag the photo to move the crop.")).size(12).color(label_color()),
+        section_divider(),
+        section_header(tr("Aspect ratio")),
+        column(aspects).spacing(6),
+        section_divider(),
+        size_sliders,
+        section_divider(),
+        straighten,
+        section_divider(),
+        row![
+            button(tr("Reset")).on_press(Message::ResetEdit).style(button::secondary),
+            button(tr("Cancel")).on_press(Message::CancelEdit).style(button::secondary),
+            button(tr("Save")).on_press(Message::SaveEdit),
+        ]
+        .spacing(8),
+        button(tr("Save Edited Copy...")).on_press(Message::SaveEditedCopy).style(button::secondary),
+    ]
+    .spacing(10);
+    if let Some(status) = &session.status {
+        panel = panel.push(text(status).size(12).color(label_color()));
+    }
+    let panel = container(scrollable(panel.padding(16)))
+        .width(EDIT_PANEL_WIDTH)
+        .height(Length::Fill)
+        .style(|_theme: &Theme| container::Style {
+            background: Some(chrome_background(0.1, 1.0)),
+            ..Default::default()
+        });
+
+    row![photo, panel].into()
+}
+
+fn decode_error_panel(error: &str) -> Element<'_, Message> {
+    let panel = column![
+        text(tr("Can't display this image")).size(18),
+        text(error).size(13).color(label_color()),
+    ]
+    .spacing(8);
+    container(container(panel).padding(16).max_width(480).style(info_panel_style))
+        .center(Length::Fill)
+        .into()
+}
+
+/// Outline each autofocus area on an image rendered at `width` × `height`.
+fn focus_overlay<'a>(areas: &[FocusArea], width: f32, height: f32) -> Element<'a, Message> {
+    let boxes: Vec<Element<'a, Message>> = areas
+        .iter()
+        .map(|a| {
+            let color = if a.in_focus {
+                Color::from_rgb(1.0, 0.2, 0.2)
+            } else {
+                Color::from_rgba(1.0, 1.0, 1.0, 0.4)
+            };
+            let outline = container(Space::new())
+                .width((a.width * width).max(4.0))
+                .height((a.height * height).max(4.0))
+                .style(move |_theme: &Theme| container::Style {
+                    border: iced::Border {
+                        color,
+                        width: 2.0,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                });
+            container(outline)
+                .padding(iced::Padding {
+                    top: (a.y * height).max(0.0),
+                    left: (a.x * width).max(0.0),
+                    ..Default::default()
+                })
+                .into()
+        })
+        .collect();
+    iced::widget::Stack::with_children(boxes)
+        .width(width)
+        .height(height)
+        .into()
+}
+
+const LABEL_COLOR: Color = Color::from_rgb(0.5, 0.5, 0.55);
+
+/// Colour for secondary text: dimmed, full white in high contrast, or
+/// darker still in the dark room.
+fn label_color() -> Color {
+    if dark_room() {
+        Color::from_rgb(0.18, 0.18, 0.18)
+    } else if high_contrast() {
+        Color::WHITE
+    } else {
+        LABEL_COLOR
+    }
+}
+
+/// Shutter speed, aperture and ISO, as far as they're known.
+fn exposure_summary(meta: &PhotoMetadata) -> Vec<String> {
+    let mut parts = Vec::new();
+    if let Some(ref exp) = meta.exposure_time {
+        parts.push(format!("{}s", exp));
+    }
+    if let Some(ref f) = meta.f_number {
+        parts.push(format!("f/{}", f));
+    }
+    if let Some(ref iso) = meta.iso {
+        parts.push(format!("ISO {}", iso));
+    }
+    parts
+}
+
+/// Height of the bars in the histogram overlay.
+const HISTOGRAM_HEIGHT: f32 = 40.0;
+
+/// A small histogram and the exposure, pinned to the bottom-left corner so
+/// photos can be judged without the full info panel.
+fn histogram_overlay(state: &Looky, index: usize) -> Option<Element<'_, Message>> {
+    if !state.settings.viewer_culling_overlay || state.viewer.show_info {
+        return None;
+    }
+    let mut content = column![].spacing(6);
+    let handle = state.viewer_cache.get(&index);
+    let bins = state
+        .histogram
+        .as_ref()
+        .filter(|(id, _)| handle.is_some_and(|h| h.id() == *id))
+        .map(|(_, bins)| bins);
+    if let Some(bins) = bins {
+        let peak = bins.iter().copied().max().unwrap_or(0).max(1) as f32;
+        let bars: Vec<Element<'_, Message>> = bins
+            .iter()
+            .map(|&n| {
+                let height = (n as f32 / peak * HISTOGRAM_HEIGHT).max(1.0);
+                container(Space::new())
+                    .width(2)
+                    .height(height)
+                    .style(|_theme: &Theme| container::Style {
+                        background: Some(iced::Background::Color(Color::from_rgba(1.0, 1.0, 1.0, 0.8))),
+                        ..Default::default()
+                    })
+                    .into()
+            })
+            .collect();
+        content = content.push(row(bars).height(HISTOGRAM_HEIGHT).align_y(iced::Alignment::End));
+    }
+    let exposure = state
+        .cached_metadata
+        .as_ref()
+        .filter(|(i, _)| *i == index)
+        .map(|(_, meta)| exposure_summary(meta))
+        .unwrap_or_default();
+    if !exposure.is_empty() {
+        content = content.push(
+            text(exposure.join(" \u{00B7} "))
+                .size(12)
+                .color(Color::from_rgba(1.0, 1.0, 1.0, 0.9)),
+        );
+    }
+    if bins.is_none() && exposure.is_empty() {
+        return None;
+    }
+    let panel = container(content).padding([6, 10]).style(|_theme: &Theme| container::Style {
+        background: Some(iced::Background::Color(Color::from_rgba(0.0, 0.0, 0.0, 0.5))),
+        border: iced::Border {
+            radius: 6.0.into(),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    Some(
+        container(panel)
+            .padding(16)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .align_bottom(Length::Fill)
+            .into(),
+    )
+}
+
 fn info_panel(meta: &PhotoMetadata) -> Element<'_, Message> {
     let mut items: Vec<Element<'_, Message>> = Vec::new();
 
@@ -2144,14 +8363,14 @@ fn info_panel(meta: &PhotoMetadata) -> Element<'_, Message> {
     items.push(
         text(metadata::format_file_size(meta.file_size))
             .size(12)
-            .color(LABEL_COLOR)
+            .color(label_color())
             .into(),
     );
     if let Some((w, h)) = meta.dimensions {
         items.push(
             text(format!("{} x {} px", w, h))
                 .size(12)
-                .color(LABEL_COLOR)
+                .color(label_color())
                 .into(),
         );
     }
@@ -2161,10 +8380,10 @@ fn info_panel(meta: &PhotoMetadata) -> Element<'_, Message> {
     if has_dates {
         items.push(section_divider());
         if let Some(ref date) = meta.date_taken {
-            items.push(info_field("Date Taken", date.clone()));
+            items.push(info_field(tr("Date Taken"), metadata::display_taken(date)));
         }
         if let Some(ref date) = meta.date_modified {
-            items.push(info_field("Modified", date.clone()));
+            items.push(info_field(tr("Modified"), metadata::display_modified(date)));
         }
     }
 
@@ -2175,18 +8394,18 @@ fn info_panel(meta: &PhotoMetadata) -> Element<'_, Message> {
         || meta.software.is_some();
     if has_camera {
         items.push(section_divider());
-        items.push(section_header("Camera"));
+        items.push(section_header(tr("Camera")));
         if let Some(ref make) = meta.camera_make {
-            items.push(info_field("Make", make.clone()));
+            items.push(info_field(tr("Make"), make.clone()));
         }
         if let Some(ref model) = meta.camera_model {
-            items.push(info_field("Model", model.clone()));
+            items.push(info_field(tr("Model"), model.clone()));
         }
         if let Some(ref lens) = meta.lens_model {
-            items.push(info_field("Lens", lens.clone()));
+            items.push(info_field(tr("Lens"), lens.clone()));
         }
         if let Some(ref sw) = meta.software {
-            items.push(info_field("Software", sw.clone()));
+            items.push(info_field(tr("Software"), sw.clone()));
         }
     }
 
@@ -2197,38 +8416,29 @@ fn info_panel(meta: &PhotoMetadata) -> Element<'_, Message> {
         || meta.focal_length.is_some();
     if has_exposure {
         items.push(section_divider());
-        items.push(section_header("Exposure"));
+        items.push(section_header(tr("Exposure")));
 
         // Compact exposure summary line: 1/250s  f/2.8  ISO 400
-        let mut summary_parts: Vec<String> = Vec::new();
-        if let Some(ref exp) = meta.exposure_time {
-            summary_parts.push(format!("{}s", exp));
-        }
-        if let Some(ref f) = meta.f_number {
-            summary_parts.push(format!("f/{}", f));
-        }
-        if let Some(ref iso) = meta.iso {
-            summary_parts.push(format!("ISO {}", iso));
-        }
+        let summary_parts = exposure_summary(meta);
         if !summary_parts.is_empty() {
             items.push(text(summary_parts.join("  ")).size(13).into());
         }
 
         if let Some(ref fl) = meta.focal_length {
             let value = match &meta.focal_length_35mm {
-                Some(eq) => format!("{} ({}mm eq.)", fl, eq),
+                Some(eq) => trf("{} ({}mm eq.)", &[fl, eq]),
                 None => fl.clone(),
             };
-            items.push(info_field("Focal length", value));
+            items.push(info_field(tr("Focal length"), value));
         }
         if let Some(ref bias) = meta.exposure_bias {
-            items.push(info_field("Exp. bias", format!("{} EV", bias)));
+            items.push(info_field(tr("Exp. bias"), format!("{} EV", bias)));
         }
         if let Some(ref prog) = meta.exposure_program {
-            items.push(info_field("Program", prog.clone()));
+            items.push(info_field(tr("Program"), prog.clone()));
         }
         if let Some(ref meter) = meta.metering_mode {
-            items.push(info_field("Metering", meter.clone()));
+            items.push(info_field(tr("Metering"), meter.clone()));
         }
     }
 
@@ -2237,15 +8447,15 @@ fn info_panel(meta: &PhotoMetadata) -> Element<'_, Message> {
         meta.flash.is_some() || meta.white_balance.is_some() || meta.color_space.is_some();
     if has_light {
         items.push(section_divider());
-        items.push(section_header("Light & Color"));
+        items.push(section_header(tr("Light & Color")));
         if let Some(ref flash) = meta.flash {
-            items.push(info_field("Flash", flash.clone()));
+            items.push(info_field(tr("Flash"), flash.clone()));
         }
         if let Some(ref wb) = meta.white_balance {
-            items.push(info_field("White balance", wb.clone()));
+            items.push(info_field(tr("White balance"), wb.clone()));
         }
         if let Some(ref cs) = meta.color_space {
-            items.push(info_field("Color space", cs.clone()));
+            items.push(info_field(tr("Color space"), cs.clone()));
         }
     }
 
@@ -2253,12 +8463,12 @@ fn info_panel(meta: &PhotoMetadata) -> Element<'_, Message> {
     let has_gps = meta.gps_latitude.is_some() || meta.gps_altitude.is_some();
     if has_gps {
         items.push(section_divider());
-        items.push(section_header("Location"));
+        items.push(section_header(tr("Location")));
         if let (Some(lat), Some(lon)) = (meta.gps_latitude, meta.gps_longitude) {
-            items.push(info_field("Coordinates", format!("{:.6}, {:.6}", lat, lon)));
+            items.push(info_field(tr("Coordinates"), format!("{:.6}, {:.6}", lat, lon)));
         }
         if let Some(ref alt) = meta.gps_altitude {
-            items.push(info_field("Altitude", alt.clone()));
+            items.push(info_field(tr("Altitude"), alt.clone()));
         }
     }
 
@@ -2267,13 +8477,13 @@ fn info_panel(meta: &PhotoMetadata) -> Element<'_, Message> {
     if has_credits {
         items.push(section_divider());
         if let Some(ref desc) = meta.description {
-            items.push(info_field("Description", desc.clone()));
+            items.push(info_field(tr("Description"), desc.clone()));
         }
         if let Some(ref artist) = meta.artist {
-            items.push(info_field("Artist", artist.clone()));
+            items.push(info_field(tr("Artist"), artist.clone()));
         }
         if let Some(ref cr) = meta.copyright {
-            items.push(info_field("Copyright", cr.clone()));
+            items.push(info_field(tr("Copyright"), cr.clone()));
         }
     }
 
@@ -2289,6 +8499,27 @@ fn info_panel(meta: &PhotoMetadata) -> Element<'_, Message> {
     .into()
 }
 
+/// Fill the viewer background behind `content` with the chosen colour.
+fn backdrop<'a>(
+    content: impl Into<Element<'a, Message>>,
+    background: Background,
+) -> Element<'a, Message> {
+    let color = match background {
+        Background::Theme => return content.into(),
+        Background::Black => Color::BLACK,
+        Background::Gray => Color::from_rgb(0.5, 0.5, 0.5),
+        Background::White => Color::WHITE,
+    };
+    container(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(move |_theme: &Theme| container::Style {
+            background: Some(iced::Background::Color(color)),
+            ..Default::default()
+        })
+        .into()
+}
+
 fn screensaver_bg_style(_theme: &Theme) -> container::Style {
     container::Style {
         background: Some(iced::Background::Color(Color::BLACK)),
@@ -2298,7 +8529,7 @@ fn screensaver_bg_style(_theme: &Theme) -> container::Style {
 
 fn info_panel_style(_theme: &Theme) -> container::Style {
     container::Style {
-        background: Some(iced::Background::Color(Color::from_rgba(0.1, 0.1, 0.1, 0.85))),
+        background: Some(chrome_background(0.1, 0.85)),
         border: iced::Border {
             radius: 8.0.into(),
             ..Default::default()
@@ -2309,12 +8540,12 @@ fn info_panel_style(_theme: &Theme) -> container::Style {
 
 fn hamburger_button_style(_theme: &Theme, status: button::Status) -> button::Style {
     let bg = match status {
-        button::Status::Hovered => Color::from_rgba(0.25, 0.25, 0.25, 0.85),
-        _ => Color::from_rgba(0.15, 0.15, 0.15, 0.85),
+        button::Status::Hovered => chrome_background(0.25, 0.85),
+        _ => chrome_background(0.15, 0.85),
     };
     button::Style {
-        background: Some(iced::Background::Color(bg)),
-        text_color: Color::WHITE,
+        background: Some(bg),
+        text_color: chrome_text(),
         border: iced::Border {
             radius: 20.0.into(),
             ..Default::default()
@@ -2325,7 +8556,7 @@ fn hamburger_button_style(_theme: &Theme, status: button::Status) -> button::Sty
 
 fn menu_container_style(_theme: &Theme) -> container::Style {
     container::Style {
-        background: Some(iced::Background::Color(Color::from_rgba(0.1, 0.1, 0.1, 0.85))),
+        background: Some(chrome_background(0.1, 0.85)),
         border: iced::Border {
             radius: 8.0.into(),
             ..Default::default()
@@ -2343,27 +8574,85 @@ fn menu_item_style(_theme: &Theme, status: button::Status) -> button::Style {
     };
     button::Style {
         background: bg,
-        text_color: Color::WHITE,
+        text_color: chrome_text(),
         border: iced::Border::default(),
         ..Default::default()
     }
 }
 
-fn menu_item(label: &str, msg: Message) -> Element<'_, Message> {
-    button(text(label).width(Length::Fill))
-        .on_press(msg)
-        .style(menu_item_style)
-        .width(Length::Fill)
-        .into()
+/// Highlight for the menu entry picked with the keyboard.
+fn menu_item_focused_style(theme: &Theme, status: button::Status) -> button::Style {
+    let base = menu_item_style(theme, status);
+    button::Style {
+        background: Some(iced::Background::Color(Color::from_rgba(1.0, 1.0, 1.0, 0.15))),
+        border: focus_border(theme),
+        ..base
+    }
+}
+
+/// Menu entries being built, along with the messages of the actionable ones
+/// in order, so the keyboard can step through and trigger them.
+struct MenuBuilder<'a> {
+    items: Vec<Element<'a, Message>>,
+    actions: Vec<Message>,
+    focus: Option<usize>,
+}
+
+impl<'a> MenuBuilder<'a> {
+    fn new(focus: Option<usize>) -> Self {
+        Self {
+            items: Vec::new(),
+            actions: Vec::new(),
+            focus,
+        }
+    }
+
+    /// A full-width entry that sends `msg`.
+    fn item(&mut self, label: &str, msg: Message) {
+        let item = self.button(text(label.to_string()).width(Length::Fill), msg);
+        self.items.push(item.width(Length::Fill).into());
+    }
+
+    /// A menu-styled button for `msg`, registered for keyboard focus. The
+    /// caller places it.
+    fn button(
+        &mut self,
+        content: impl Into<Element<'a, Message>>,
+        msg: Message,
+    ) -> button::Button<'a, Message> {
+        let focused = self.focus == Some(self.actions.len());
+        self.actions.push(msg.clone());
+        button(content).on_press(msg).style(if focused {
+            menu_item_focused_style
+        } else {
+            menu_item_style
+        })
+    }
+
+    /// Anything that isn't a keyboard stop: info text, rules.
+    fn push(&mut self, element: Element<'a, Message>) {
+        self.items.push(element);
+    }
+}
+
+/// Give an icon-only control a name, shown on hover.
+fn named<'a>(control: impl Into<Element<'a, Message>>, name: &str) -> Element<'a, Message> {
+    let label = container(text(name.to_string()).size(12))
+        .padding([2, 6])
+        .style(container::rounded_box);
+    iced::widget::tooltip(control, label, iced::widget::tooltip::Position::Bottom).into()
 }
 
 fn menu_info(content: impl Into<String>) -> Element<'static, Message> {
     text(content.into())
         .size(13)
-        .color(LABEL_COLOR)
+        .color(label_color())
         .into()
 }
 
+/// Height of the toolbar overlay: the 40px buttons and its padding.
+const TOOLBAR_HEIGHT: f32 = 64.0;
+
 fn menu_overlay(state: &Looky) -> Element<'_, Message> {
     let mut items: Vec<Element<'_, Message>> = Vec::new();
 
@@ -2378,14 +8667,59 @@ fn menu_overlay(state: &Looky) -> Element<'_, Message> {
         .padding(0)
         .on_press(Message::ToggleMenu)
         .style(hamburger_button_style);
-    items.push(hamburger.into());
+    let hamburger = named(hamburger, tr("Menu"));
+
+    // Cast button, labelled with the connected device
+    let mut toolbar = row![hamburger].spacing(8);
+    if !state.image_paths.is_empty() {
+        let cast_label = match (state.cast_sessions.as_slice(), state.cast_connecting.first()) {
+            ([], Some(target)) => trf("Connecting to {}...", &[&target.name]),
+            ([], None) => tr("Cast").to_string(),
+            ([cast], _) => cast_status_label(cast),
+            (casts, _) => trf("Casting to {} TVs", &[&casts.len()]),
+        };
+        let cast = button(
+            container(text(cast_label).size(14).line_height(1.0)).center_y(Length::Fill),
+        )
+        .height(40)
+        .padding(iced::Padding { top: 0.0, right: 16.0, bottom: 0.0, left: 16.0 })
+        .on_press(Message::ToggleCastPicker)
+        .style(hamburger_button_style);
+        toolbar = toolbar.push(cast);
+        if !state.cast_sessions.is_empty() {
+            let stop = button(
+                container(text("✕").size(16).line_height(1.0)).center(Length::Fill),
+            )
+            .width(40)
+            .height(40)
+            .padding(0)
+            .on_press(Message::StopCast)
+            .style(hamburger_button_style);
+            toolbar = toolbar.push(named(stop, tr("Stop Cast")));
+        }
+    }
+    if grid_showing(state) {
+        let search = text_input(tr("Search names, cameras, keywords"), &state.search_input)
+            .id(search_id())
+            .on_input(Message::SearchChanged)
+            .size(14)
+            .padding([10, 12])
+            .width(260);
+        toolbar = toolbar.push(search);
+    }
+    items.push(toolbar.into());
 
     if state.menu_open {
-        let menu_items = build_menu_items(state);
+        let menu_items = build_menu_items(state).items;
         let menu = container(column(menu_items).spacing(4).padding(8))
             .style(menu_container_style)
             .max_width(220);
         items.push(menu.into());
+    } else if state.cast_picker_open {
+        let picker = container(column(cast_picker_items(state).items).spacing(4).padding(8))
+            .style(menu_container_style)
+            .max_width(260);
+        items.push(picker.into());
     }
 
     container(column(items).spacing(4))
@@ -2393,9 +8727,239 @@ fn menu_overlay(state: &Looky) -> Element<'_, Message> {
         .into()
 }
 
-fn build_menu_items(state: &Looky) -> Vec<Element<'_, Message>> {
+/// The share's QR code and address filling the window, so guests can scan it
+/// from the sofa. Click or Escape closes it.
+fn qr_overlay(state: &Looky) -> Option<Element<'_, Message>> {
+    if !state.qr_overlay_open {
+        return None;
+    }
+    let (url, qr) = (state.server_url.as_ref()?, state.qr_handle.as_ref()?);
+    let side = (state.viewport_width.min(state.viewport_height) * 0.6).max(160.0);
+    let content = column![
+        image(qr.clone())
+            .width(side)
+            .height(side)
+            .filter_method(image::FilterMethod::Nearest),
+        text(url.as_str()).size(32).color(Color::WHITE),
+        text(tr("Scan with a phone camera. Press Esc to close."))
+            .size(14)
+            .color(label_color()),
+    ]
+    .spacing(16)
+    .align_x(iced::Alignment::Center);
+    let backdrop = container(content)
+        .center(Length::Fill)
+        .style(|_theme: &Theme| container::Style {
+            background: Some(iced::Background::Color(Color::from_rgba(0.0, 0.0, 0.0, 0.9))),
+            ..Default::default()
+        });
+    Some(
+        iced::widget::mouse_area(backdrop)
+            .on_press(Message::ToggleQrOverlay)
+            .into(),
+    )
+}
+
+fn conflict_dialog_open(state: &Looky) -> bool {
+    state.file_jobs.iter().any(FileJob::awaiting_choice)
+}
+
+/// Ask what to do about a photo whose name is taken where it's being copied
+/// or moved, once the rest are done.
+fn conflict_dialog(state: &Looky) -> Option<Element<'_, Message>> {
+    if !conflict_dialog_open(state) {
+        return None;
+    }
+    let job = state.file_jobs.iter().find(|j| j.awaiting_choice())?;
+    let FileJobKind::Transfer(_, dest) = &job.kind else {
+        return None;
+    };
+    let (_, path) = job.conflicts.first()?;
+    let name = path.file_name()?.to_string_lossy().into_owned();
+    let mut content = column![
+        text(tr("File already exists")).size(18),
+        text(trf("{} is already in {}.", &[&name, &dest.display()]))
+            .size(13)
+            .wrapping(text::Wrapping::WordOrGlyph),
+        row![
+            button(tr("Skip"))
+                .on_press(Message::ResolveConflict(OnConflict::Skip))
+                .style(button::secondary),
+            button(tr("Keep Both"))
+                .on_press(Message::ResolveConflict(OnConflict::Rename))
+                .style(button::secondary),
+            button(tr("Replace"))
+                .on_press(Message::ResolveConflict(OnConflict::Overwrite))
+                .style(button::danger),
+        ]
+        .spacing(8),
+    ]
+    .spacing(12);
+    let others = job.conflicts.len() - 1;
+    if others > 0 {
+        content = content.push(
+            iced::widget::checkbox(job.same_for_all)
+                .label(trn(others, "Do the same for {} more", "Do the same for {} more"))
+                .on_toggle(Message::SetConflictSameForAll)
+                .size(16),
+        );
+    }
+    let panel = container(content).padding(20).max_width(440).style(info_panel_style);
+    let backdrop = container(panel)
+        .center(Length::Fill)
+        .style(|_theme: &Theme| container::Style {
+            background: Some(iced::Background::Color(Color::from_rgba(0.0, 0.0, 0.0, 0.5))),
+            ..Default::default()
+        });
+    Some(iced::widget::opaque(backdrop))
+}
+
+fn cast_status_label(cast: &CastConnection) -> String {
+    let name = &cast.session.target.name;
+    match &cast.status {
+        server::cast::CastStatus::Connected => trf("Casting to {}", &[name]),
+        server::cast::CastStatus::Reconnecting => trf("Reconnecting to {}...", &[name]),
+        server::cast::CastStatus::Lost(_) => trf("Lost connection to {}", &[name]),
+    }
+}
+
+fn cast_picker_items(state: &Looky) -> MenuBuilder<'_> {
+    let mut items = MenuBuilder::new(state.menu_focus);
+
+    // Connected devices, each with its own disconnect button
+    for (i, cast) in state.cast_sessions.iter().enumerate() {
+        let disconnect = items.button(text("✕").size(13), Message::StopCastTo(i));
+        let disconnect = named(disconnect, tr("Disconnect"));
+        items.push(
+            row![
+                text(cast_status_label(cast)).size(13).color(label_color()).width(Length::Fill),
+                disconnect,
+            ]
+            .align_y(iced::Alignment::Center)
+            .into(),
+        );
+    }
+    for target in &state.cast_connecting {
+        items.push(menu_info(trf("Connecting to {}...", &[&target.name])));
+    }
+    if !state.cast_sessions.is_empty() {
+        let slideshow_label = if state.cast_slideshow_active {
+            "Stop TV Slideshow"
+        } else {
+            "Slideshow on TV"
+        };
+        items.item(tr(slideshow_label), Message::ToggleCastSlideshow);
+        let stop_label = if state.cast_sessions.len() > 1 {
+            "Stop All"
+        } else {
+            "Stop Cast"
+        };
+        items.item(tr(stop_label), Message::StopCast);
+        items.push(rule::horizontal(1).into());
+    }
+
+    // Devices that can still be added
+    if state.cast_scanning {
+        items.push(menu_info(tr("Searching...")));
+    } else {
+        let mut available = 0;
+        for (i, dev) in state.cast_devices.iter().enumerate() {
+            let busy = state.cast_sessions.iter().any(|c| c.session.target.host == dev.host)
+                || state.cast_connecting.iter().any(|t| t.host == dev.host);
+            if !busy {
+                items.item(&dev.name, Message::CastSelect(i));
+                available += 1;
+            }
+        }
+        if available == 0 && state.cast_sessions.is_empty() {
+            items.push(menu_info(tr("No devices found")));
+        } else if available == 0 {
+            items.push(menu_info(tr("No other devices found")));
+        }
+        items.item(tr("Search Again"), Message::StartCastScan);
+    }
+    if let Some(err) = &state.cast_error {
+        items.push(
+            text(err.as_str())
+                .size(12)
+                .color(Color::from_rgb(0.9, 0.2, 0.2))
+                .into(),
+        );
+    }
+    items
+}
+
+/// Messages of the open menu's (or cast picker's) entries, in keyboard order.
+fn menu_actions(state: &Looky) -> Vec<Message> {
+    if state.menu_open {
+        build_menu_items(state).actions
+    } else if state.cast_picker_open {
+        cast_picker_items(state).actions
+    } else {
+        Vec::new()
+    }
+}
+
+fn move_menu_focus(state: &mut Looky, step: isize) {
+    let count = menu_actions(state).len() as isize;
+    if count == 0 {
+        return;
+    }
+    let next = match state.menu_focus {
+        Some(i) => (i as isize + step).rem_euclid(count),
+        None if step < 0 => count - 1,
+        None => 0,
+    };
+    state.menu_focus = Some(next as usize);
+}
+
+fn move_dup_selection(state: &mut Looky, step: isize) -> Task<Message> {
+    let count = state.dup_groups.len() as isize;
+    if count == 0 {
+        return Task::none();
+    }
+    let next = match state.dup_selected {
+        Some(i) => (i as isize + step).clamp(0, count - 1),
+        None => 0,
+    };
+    state.dup_selected = Some(next as usize);
+    scroll_to_dup_selection(state)
+}
+
+/// Scroll the duplicates list just far enough to show the selected group.
+fn scroll_to_dup_selection(state: &mut Looky) -> Task<Message> {
+    let Some(group_idx) = state.dup_selected else {
+        return Task::none();
+    };
+    let top = DUP_LIST_PADDING + group_idx as f32 * (DUP_CARD_HEIGHT + DUP_CARD_SPACING);
+    let bottom = top + DUP_CARD_HEIGHT + DUP_LIST_PADDING;
+    // The list sits below the toolbar and the header row
+    let visible = (state.viewport_height - TOOLBAR_HEIGHT - DUP_HEADER_HEIGHT).max(DUP_CARD_HEIGHT);
+    let y = if top - DUP_LIST_PADDING < state.dup_scroll_y {
+        top - DUP_LIST_PADDING
+    } else if bottom > state.dup_scroll_y + visible {
+        bottom - visible
+    } else {
+        return Task::none();
+    };
+    state.dup_scroll_y = y;
+    use iced::widget::operation::AbsoluteOffset;
+    iced::widget::operation::scroll_to(dup_list_scroll_id(), AbsoluteOffset { x: None, y: Some(y) })
+}
+
+fn build_menu_items(state: &Looky) -> MenuBuilder<'_> {
     if state.viewer.current_index.is_some() {
         viewer_menu_items(state)
+    } else if state.settings_view_active {
+        settings_menu_items(state)
+    } else if state.trash_view_active {
+        trash_menu_items(state)
+    } else if state.import_view_active {
+        import_menu_items(state)
+    } else if state.library_dups_view_active {
+        library_duplicates_menu_items(state)
+    } else if state.unreadable_view_active {
+        unreadable_menu_items(state)
     } else if state.dup_compare.is_some() {
         compare_menu_items(state)
     } else if state.dup_view_active {
@@ -2405,38 +8969,70 @@ fn build_menu_items(state: &Looky) -> Vec<Element<'_, Message>> {
     }
 }
 
-fn grid_menu_items(state: &Looky) -> Vec<Element<'_, Message>> {
-    let mut items: Vec<Element<'_, Message>> = Vec::new();
+fn grid_menu_items(state: &Looky) -> MenuBuilder<'_> {
+    let mut items = MenuBuilder::new(state.menu_focus);
 
     // Open Folder
-    items.push(menu_item("Open Folder", Message::OpenFolder));
+    items.item(tr("Open Folder"), Message::OpenFolder);
+    if state.folder.is_some() {
+        items.item(tr("Refresh (F5)"), Message::RefreshFolder);
+    }
+    if let Some(dir) = state.grid_filter.subfolder.as_ref() {
+        items.item(tr("Exclude This Folder"), Message::ExcludeSubfolder);
+        if let Some(cat) = state.catalog.as_ref() {
+            let label = if cat.dup_excluded_folders().contains(dir) {
+                "Include in Duplicate Scans"
+            } else {
+                "Leave Out of Duplicate Scans"
+            };
+            items.item(tr(label), Message::ToggleSubfolderInDupScans);
+        }
+    }
+    if state.folder.is_some() && state.catalog.is_some() {
+        let depth = scan_depth(state);
+        let label = SCAN_DEPTHS.iter().find(|&&(d, _)| d == depth).map_or("All subfolders", |&(_, l)| l);
+        items.item(&trf("Scan: {}", &[&tr(label)]), Message::CycleFolderScanDepth);
+    }
+    items.item(tr("New Window (Ctrl+N)"), Message::NewWindow);
+    items.item(tr("Import from Card"), Message::ShowImport);
     items.push(rule::horizontal(1).into());
 
-    // Find Duplicates / Scanning / Scan for new
-    if !state.image_paths.is_empty() {
-        if state.dup_scanning {
-            let scanned = state.dup_total - state.dup_pending.len();
-            items.push(menu_info(format!(
-                "Scanning {} / {}...",
-                scanned, state.dup_total
-            )));
-            items.push(menu_item("Cancel", Message::CancelDupScan));
+    // Find Duplicates (progress is in the status bar while scanning)
+    if !state.image_paths.is_empty() && !state.dup_scanning {
+        items.item(tr("Find Duplicates"), Message::FindDuplicates);
+    }
+
+    if !state.image_paths.is_empty() && !state.library_checked {
+        items.item(tr("Check Against Library"), Message::CheckAgainstLibrary);
+    }
+    if !state.grid_shown.is_empty() && state.export_job.is_none() {
+        items.item(tr("Export Gallery"), Message::ExportGallery);
+    }
+    if let Some(status) = &state.export_status {
+        items.push(menu_info(status.as_str()));
+    }
+    if !state.grid_shown.is_empty() && state.contact_sheet_job.is_none() {
+        items.item(tr("Export Contact Sheet"), Message::ExportContactSheet);
+    }
+    if let Some(status) = &state.contact_sheet_status {
+        items.push(menu_info(status.as_str()));
+    }
+
+    // Unreadable files, and whether duplicate scans skip them
+    if !state.unreadable.is_empty() {
+        let count = state.unreadable.len();
+        items.item(&trn(count, "{} unreadable file", "{} unreadable files"), Message::ShowUnreadable);
+        let skip_label = if state.settings.dup_skip_unreadable {
+            "Include Unreadable in Scan"
         } else {
-            items.push(menu_item("Find Duplicates", Message::FindDuplicates));
-        }
+            "Skip Unreadable in Scan"
+        };
+        items.item(tr(skip_label), Message::ToggleSkipUnreadable);
     }
 
     // Duplicates (N) button
     if !state.dup_groups.is_empty() {
-        items.push(
-            button(
-                text(format!("Duplicates ({})", state.dup_groups.len())).width(Length::Fill),
-            )
-            .on_press(Message::ShowDuplicatesView)
-            .style(menu_item_style)
-            .width(Length::Fill)
-            .into(),
-        );
+        items.item(&trf("Duplicates ({})", &[&state.dup_groups.len()]), Message::ShowDuplicatesView);
     }
 
     // Screensaver
@@ -2446,7 +9042,15 @@ fn grid_menu_items(state: &Looky) -> Vec<Element<'_, Message>> {
         } else {
             "Screensaver"
         };
-        items.push(menu_item(ss_label, Message::ToggleScreensaver));
+        items.item(tr(ss_label), Message::ToggleScreensaver);
+        items.item(tr("Random Photo (R)"), Message::RandomPhoto);
+    }
+    if !state.grid_shown.is_empty() {
+        items.item(tr("Cull Photos"), Message::StartCulling);
+    }
+    let rejected = rejected_indices(state).len();
+    if rejected > 0 {
+        items.item(&trn(rejected, "Move {} Reject to Trash", "Move {} Rejects to Trash"), Message::TrashRejected);
     }
 
     items.push(rule::horizontal(1).into());
@@ -2458,73 +9062,80 @@ fn grid_menu_items(state: &Looky) -> Vec<Element<'_, Message>> {
         } else {
             "Share"
         };
-        items.push(menu_item(share_label, Message::ToggleSharing));
-    }
-
-    // Cast controls (only when sharing)
-    if state.server_handle.is_some() {
-        if let Some(name) = &state.cast_target_name {
-            items.push(menu_info(format!("TV: {name}")));
-            items.push(menu_item("Stop Cast", Message::StopCast));
-        } else if state.cast_scanning {
-            items.push(menu_info("Scanning...".to_string()));
-        } else if !state.cast_devices.is_empty() {
-            for (i, dev) in state.cast_devices.iter().enumerate() {
-                items.push(
-                    button(text(dev.name.as_str()).width(Length::Fill))
-                        .on_press(Message::CastSelect(i))
-                        .style(menu_item_style)
-                        .width(Length::Fill)
-                        .into(),
-                );
-            }
+        items.item(tr(share_label), Message::ToggleSharing);
+        let uploads_label = if state.allow_uploads {
+            "Disallow Uploads"
         } else {
-            items.push(menu_item("Cast to TV", Message::StartCastScan));
-        }
-        if let Some(err) = &state.cast_error {
-            items.push(
-                text(err.as_str())
-                    .size(12)
-                    .color(Color::from_rgb(0.9, 0.2, 0.2))
-                    .into(),
-            );
+            "Allow Uploads"
+        };
+        items.item(tr(uploads_label), Message::ToggleUploads);
+    }
+    if let Some(err) = &state.share_error {
+        items.push(
+            text(err.as_str())
+                .size(12)
+                .color(Color::from_rgb(0.9, 0.2, 0.2))
+                .into(),
+        );
+    }
+
+    if state.selected_thumb.is_some() {
+        if share::AVAILABLE {
+            items.item(tr("Share..."), Message::SharePhoto);
         }
+        items.item(tr("Move to Trash (Del)"), Message::TrashPhoto);
+    }
+    if let Some(err) = &state.share_sheet_error {
+        items.push(menu_info(err.as_str()));
     }
+    if state.catalog.is_some() {
+        items.item(tr("Recently Deleted"), Message::ShowTrash);
+        items.item(tr("Duplicates Across Library"), Message::ShowLibraryDuplicates);
+    }
+    items.item(tr("Settings"), Message::ShowSettings);
 
     items.push(rule::horizontal(1).into());
 
-    // Photo count
-    if !state.image_paths.is_empty() {
-        let count_text = if state.loading {
-            format!(
-                "{} / {} photos",
-                state.thumbnails.len(),
-                state.image_paths.len()
-            )
-        } else {
-            format!("{} photos", state.image_paths.len())
-        };
-        items.push(menu_info(count_text));
-    }
-
     // Folder path or server URL + QR
     if let (Some(url), Some(qr)) = (&state.server_url, &state.qr_handle) {
-        items.push(
+        let link = items.button(
             text(url.as_str())
                 .size(13)
-                .color(LABEL_COLOR)
-                .wrapping(text::Wrapping::WordOrGlyph)
+                .wrapping(text::Wrapping::WordOrGlyph),
+            Message::OpenShareUrl,
+        );
+        let copy_label = if state.share_url_copied { "Copied" } else { "Copy" };
+        let copy = items.button(text(tr(copy_label)).size(13), Message::CopyShareUrl);
+        items.push(
+            row![named(link.width(Length::Fill), tr("Open in browser")), copy]
+                .spacing(4)
+                .align_y(iced::Alignment::Center)
                 .into(),
         );
-        items.push(image(qr.clone()).width(80).height(80).into());
+        if let Some(local) = state.server_handle.as_ref().and_then(|h| h.local_url()) {
+            items.push(text(local).size(13).color(label_color()).into());
+        }
+        if let Some(left) = share_time_remaining(state) {
+            items.push(
+                text(trf("Stops in {}", &[&format_countdown(left)]))
+                    .size(13)
+                    .color(label_color())
+                    .into(),
+            );
+        }
+        let enlarge = button(image(qr.clone()).width(80).height(80))
+            .padding(0)
+            .on_press(Message::ToggleQrOverlay)
+            .style(button::text);
+        items.push(named(enlarge, tr("Show QR code large (Q)")));
     } else {
         items.push(
             text(match &state.folder {
                 Some(p) => p.display().to_string(),
-                None => "No folder selected".into(),
+                None => tr("No folder selected").into(),
             })
             .size(13)
-            .color(LABEL_COLOR)
+            .color(label_color())
             .wrapping(text::Wrapping::WordOrGlyph)
             .into(),
         );
@@ -2533,24 +9144,104 @@ fn grid_menu_items(state: &Looky) -> Vec<Element<'_, Message>> {
     items
 }
 
-fn viewer_menu_items(state: &Looky) -> Vec<Element<'_, Message>> {
-    let mut items: Vec<Element<'_, Message>> = Vec::new();
+fn viewer_menu_items(state: &Looky) -> MenuBuilder<'_> {
+    let mut items = MenuBuilder::new(state.menu_focus);
 
-    items.push(menu_item("Back", Message::BackToGrid));
+    items.item(tr("Back"), Message::BackToGrid);
 
     let info_label = if state.viewer.show_info {
         "Hide Info"
     } else {
         "Info"
     };
-    items.push(menu_item(info_label, Message::ToggleInfo));
+    items.item(tr(info_label), Message::ToggleInfo);
+
+    let has_focus_data = state
+        .cached_metadata
+        .as_ref()
+        .is_some_and(|(i, m)| Some(*i) == state.viewer.current_index && !m.focus_areas.is_empty());
+    if has_focus_data {
+        let focus_label = if state.viewer.show_focus {
+            "Hide AF Points (K)"
+        } else {
+            "Show AF Points (K)"
+        };
+        items.item(tr(focus_label), Message::ToggleFocusPoints);
+    }
+    let culling_label = if state.settings.viewer_culling_overlay {
+        "Hide Histogram (O)"
+    } else {
+        "Histogram (O)"
+    };
+    items.item(tr(culling_label), Message::ToggleCullingOverlay);
 
     let fs_label = if state.fullscreen {
         "Window"
     } else {
         "Fullscreen"
     };
-    items.push(menu_item(fs_label, Message::ToggleFullscreen));
+    items.item(tr(fs_label), Message::ToggleFullscreen);
+    let dark_room_label = if dark_room() {
+        "Leave Dark Room (L)"
+    } else {
+        "Dark Room (L)"
+    };
+    items.item(tr(dark_room_label), Message::ToggleDarkRoom);
+
+    let flip_partner = state.viewer.flip_partner;
+    let mark_label = if flip_partner.is_some() && flip_partner == state.viewer.current_index {
+        "Unmark A/B (M)"
+    } else {
+        "Mark for A/B (M)"
+    };
+    items.item(tr(mark_label), Message::ToggleFlipMark);
+    if flip_partner.is_some_and(|p| Some(p) != state.viewer.current_index) {
+        items.item(tr("Flip A/B (B)"), Message::FlipCompare);
+    }
+    items.item(tr("Random Photo (R)"), Message::RandomPhoto);
+
+    let fit_mode = state.viewer.fit_mode;
+    let fit_label = trf("View: {}", &[&fit_mode.label()]);
+    items.item(&fit_label, Message::SelectFitMode(fit_mode.next()));
+    let orientation = state
+        .viewer
+        .current_index
+        .and_then(|i| state.image_paths.get(i))
+        .and_then(|path| thumbnail::orientation_override(path));
+    let orientation_label = ORIENTATION_OVERRIDES
+        .iter()
+        .find(|&&(o, _)| o == orientation)
+        .map_or("As tagged", |&(_, label)| label);
+    items.item(
+        &trf("Rotation: {}", &[&tr(orientation_label)]),
+        Message::CycleOrientationOverride,
+    );
+    items.item(tr("Crop and Straighten (E)"), Message::StartEdit);
+    let rating = state
+        .viewer
+        .current_index
+        .and_then(|i| state.image_paths.get(i))
+        .and_then(|path| state.ratings.get(path))
+        .copied()
+        .unwrap_or_default();
+    let stars = match rating.stars {
+        0 => tr("None").to_string(),
+        n => "\u{2605}".repeat(n as usize),
+    };
+    items.item(&trf("Rating: {} (T)", &[&stars]), Message::CycleRating);
+    let favorite_label = if rating.favorite {
+        "Unfavorite (H)"
+    } else {
+        "Favorite (H)"
+    };
+    items.item(tr(favorite_label), Message::ToggleFavorite);
+    if share::AVAILABLE {
+        items.item(tr("Share..."), Message::SharePhoto);
+    }
+    if let Some(err) = &state.share_sheet_error {
+        items.push(menu_info(err.as_str()));
+    }
+    items.item(tr("Move to Trash (Del)"), Message::TrashPhoto);
 
     items.push(rule::horizontal(1).into());
 
@@ -2567,10 +9258,19 @@ fn viewer_menu_items(state: &Looky) -> Vec<Element<'_, Message>> {
                 index + 1,
                 state.image_paths.len()
             )));
+            let partner_name = state
+                .viewer
+                .flip_partner
+                .filter(|&p| p != index)
+                .and_then(|p| state.image_paths.get(p))
+                .and_then(|p| p.file_name());
+            if let Some(name) = partner_name {
+                items.push(menu_info(trf("A/B with {}", &[&name.to_string_lossy()])));
+            }
             if state.viewer.zoom_level > 1.0 {
-                items.push(menu_info(format!(
+                items.push(menu_info(trf(
                     "Zoom: {}%",
-                    (state.viewer.zoom_level * 100.0) as u32
+                    &[&((state.viewer.zoom_level * 100.0) as u32)],
                 )));
             }
         }
@@ -2579,31 +9279,74 @@ fn viewer_menu_items(state: &Looky) -> Vec<Element<'_, Message>> {
     items
 }
 
-fn dup_list_menu_items(state: &Looky) -> Vec<Element<'_, Message>> {
-    let mut items: Vec<Element<'_, Message>> = Vec::new();
-    items.push(menu_item("Back", Message::BackFromDuplicates));
+fn settings_menu_items(state: &Looky) -> MenuBuilder<'_> {
+    let mut items = MenuBuilder::new(state.menu_focus);
+    items.item(tr("Back"), Message::BackFromSettings);
+    items
+}
+
+fn trash_menu_items(state: &Looky) -> MenuBuilder<'_> {
+    let mut items = MenuBuilder::new(state.menu_focus);
+    items.item(tr("Back"), Message::BackFromTrash);
+    if !state.trash_items.is_empty() {
+        items.push(rule::horizontal(1).into());
+        items.item(tr("Empty Trash"), Message::EmptyTrash);
+    }
+    items
+}
+
+fn library_duplicates_menu_items(state: &Looky) -> MenuBuilder<'_> {
+    let mut items = MenuBuilder::new(state.menu_focus);
+    items.item(tr("Back"), Message::BackFromLibraryDuplicates);
+    items
+}
+
+fn unreadable_menu_items(state: &Looky) -> MenuBuilder<'_> {
+    let mut items = MenuBuilder::new(state.menu_focus);
+    items.item(tr("Back"), Message::BackFromUnreadable);
+    if !state.unreadable.is_empty() {
+        items.item(tr("Try Again"), Message::RetryUnreadable);
+    }
+    items
+}
+
+fn import_menu_items(state: &Looky) -> MenuBuilder<'_> {
+    let mut items = MenuBuilder::new(state.menu_focus);
+    items.item(tr("Back"), Message::BackFromImport);
+    items
+}
+
+/// "Group 14 of 372" for the selected or compared duplicate group.
+fn dup_position(state: &Looky) -> Option<String> {
+    let group_idx = state.dup_compare.or(state.dup_selected)?;
+    Some(trf("Group {} of {}", &[&(group_idx + 1), &state.dup_groups.len()]))
+}
+
+fn dup_list_menu_items(state: &Looky) -> MenuBuilder<'_> {
+    let mut items = MenuBuilder::new(state.menu_focus);
+    items.item(tr("Back"), Message::BackFromDuplicates);
     items.push(rule::horizontal(1).into());
-    items.push(menu_info(format!(
-        "{} duplicate groups found",
-        state.dup_groups.len()
-    )));
+    items.push(menu_info(trf("{} duplicate groups found", &[&state.dup_groups.len()])));
     items
 }
 
-fn compare_menu_items(state: &Looky) -> Vec<Element<'_, Message>> {
-    let mut items: Vec<Element<'_, Message>> = Vec::new();
-    items.push(menu_item("Back", Message::BackFromCompare));
+fn compare_menu_items(state: &Looky) -> MenuBuilder<'_> {
+    let mut items = MenuBuilder::new(state.menu_focus);
+    items.item(tr("Back"), Message::BackFromCompare);
     items.push(rule::horizontal(1).into());
 
     if let Some(group_idx) = state.dup_compare {
         if let Some(group) = state.dup_groups.get(group_idx) {
             let (label, label_color) = match &group.match_kind {
-                MatchKind::Exact => ("Exact match", Color::from_rgb(0.9, 0.2, 0.2)),
-                MatchKind::Visual { .. } => ("Visual match", Color::from_rgb(0.9, 0.7, 0.1)),
+                MatchKind::Exact => (tr("Exact match"), Color::from_rgb(0.9, 0.2, 0.2)),
+                MatchKind::Visual { .. } => (tr("Visual match"), Color::from_rgb(0.9, 0.7, 0.1)),
             };
             items.push(text(label).size(13).color(label_color).into());
         }
     }
+    if let Some(position) = dup_position(state) {
+        items.push(menu_info(position));
+    }
 
     items
 }
@@ -2611,7 +9354,7 @@ fn compare_menu_items(state: &Looky) -> Vec<Element<'_, Message>> {
 fn section_header(label: &str) -> Element<'_, Message> {
     text(label.to_string())
         .size(11)
-        .color(LABEL_COLOR)
+        .color(label_color())
         .into()
 }
 
@@ -2623,7 +9366,7 @@ fn section_divider<'a>() -> Element<'a, Message> {
 
 fn info_field(label: &str, value: String) -> Element<'_, Message> {
     row![
-        text(label.to_string()).size(12).color(LABEL_COLOR).width(90),
+        text(label.to_string()).size(12).color(label_color()).width(90),
         text(value).size(12),
     ]
     .spacing(8)
@@ -2660,27 +9403,105 @@ fn render_qr(url: &str) -> image::Handle {
     image::Handle::from_rgba(img_size, img_size, pixels)
 }
 
-fn theme(_state: &Looky) -> Theme {
-    Theme::Dark
+/// Open `url` in the default browser, without waiting for it.
+fn open_url(url: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+    if let Err(e) = command.arg(url).spawn() {
+        log::warn!("Failed to open {}: {}", url, e);
+    }
 }
 
 async fn pick_folder() -> Option<PathBuf> {
+    pick_folder_titled(tr("Select a photo folder")).await
+}
+
+async fn pick_folder_titled(title: &str) -> Option<PathBuf> {
     rfd::AsyncFileDialog::new()
-        .set_title("Select a photo folder")
+        .set_title(title)
         .pick_folder()
         .await
         .map(|handle| handle.path().to_path_buf())
 }
 
-async fn scan_folder(folder: PathBuf) -> Vec<PathBuf> {
+async fn scan_folder(folder: PathBuf, depth: Option<u32>) -> Vec<PathBuf> {
+    find_images(folder, depth)
+}
+
+/// Levels of subfolders to scan in the open folder: its own choice, or the
+/// default from the settings.
+fn scan_depth(state: &Looky) -> Option<u32> {
+    match state.folder.as_deref() {
+        Some(folder) => scan_depth_for(state.catalog.as_ref(), &state.settings, folder),
+        None => state.settings.scan_depth,
+    }
+}
+
+pub fn scan_depth_for(
+    catalog: Option<&Catalog>,
+    settings: &Settings,
+    folder: &std::path::Path,
+) -> Option<u32> {
+    catalog
+        .and_then(|cat| cat.get_scan_depth(folder))
+        .unwrap_or(settings.scan_depth)
+}
+
+/// Files that keep the folder they're in, and everything below it, out of
+/// scans: Android's marker for thumbnail and cache folders, and our own.
+const EXCLUDE_MARKERS: [&str; 2] = [".nomedia", ".lookyignore"];
+
+fn is_excluded(dir: &std::path::Path) -> bool {
+    EXCLUDE_MARKERS.iter().any(|marker| dir.join(marker).exists())
+}
+
+/// Whether `path` is in an excluded folder below `root`. `root` itself is
+/// never excluded, as it was opened on purpose.
+fn in_excluded_folder(root: Option<&std::path::Path>, path: &std::path::Path) -> bool {
+    let Some(root) = root else {
+        return false;
+    };
+    path.ancestors()
+        .skip(1)
+        .take_while(|dir| *dir != root && dir.starts_with(root))
+        .any(is_excluded)
+}
+
+/// Whether `path` is no more than `depth` levels of subfolders below `root`,
+/// as [`find_images`] would reach it.
+fn within_depth(root: Option<&std::path::Path>, depth: Option<u32>, path: &std::path::Path) -> bool {
+    let (Some(root), Some(depth)) = (root, depth) else {
+        return true;
+    };
+    path.strip_prefix(root)
+        .is_ok_and(|relative| relative.components().count() <= depth as usize + 1)
+}
+
+/// Whether `path` lies under any of `folders`.
+pub fn in_any_folder(folders: &[PathBuf], path: &std::path::Path) -> bool {
+    folders.iter().any(|folder| path.starts_with(folder))
+}
+
+/// Every image under `folder` down to `depth` levels of subfolders (all of
+/// them if `None`), sorted by path, leaving out excluded subfolders.
+pub fn find_images(folder: PathBuf, depth: Option<u32>) -> Vec<PathBuf> {
     let mut paths = Vec::new();
-    let mut stack = vec![folder];
-    while let Some(dir) = stack.pop() {
+    let mut stack = vec![(folder, 0)];
+    while let Some((dir, level)) = stack.pop() {
         if let Ok(entries) = std::fs::read_dir(&dir) {
             for entry in entries.flatten() {
                 let path = entry.path();
                 if path.is_dir() {
-                    stack.push(path);
+                    if depth.is_none_or(|depth| level < depth) && !is_excluded(&path) {
+                        stack.push((path, level + 1));
+                    }
                 } else if is_image_file(&path) {
                     paths.push(path);
                 }
@@ -2691,7 +9512,7 @@ async fn scan_folder(folder: PathBuf) -> Vec<PathBuf> {
     paths
 }
 
-fn is_image_file(path: &std::path::Path) -> bool {
+pub fn is_image_file(path: &std::path::Path) -> bool {
     match path.extension().and_then(|e| e.to_str()) {
         Some(ext) => matches!(
             ext.to_lowercase().as_str(),
@@ -1,19 +1,146 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 
 use rusqlite::{Connection, Result, params};
 
-use crate::metadata::FileSummary;
+use crate::edit::PhotoEdit;
+use crate::metadata::{FileSummary, PhotoInfo};
+
+/// Bump when `PhotoInfo` gains fields, so rows indexed before are read again.
+const PHOTO_INFO_VERSION: i64 = 3;
+
+/// Columns added to `images` since it was first created, for catalogs made
+/// by older versions.
+const ADDED_COLUMNS: [(&str, &str); 7] = [
+    ("camera_make", "TEXT"),
+    ("camera_model", "TEXT"),
+    ("lens_model", "TEXT"),
+    ("info_version", "INTEGER"),
+    ("orientation", "INTEGER"),
+    ("description", "TEXT"),
+    ("keywords", "TEXT"),
+];
+
+/// Columns added to `folder_sessions` after its first release.
+const SESSION_ADDED_COLUMNS: [(&str, &str); 1] = [("last_viewed_path", "TEXT")];
+const RATING_ADDED_COLUMNS: [(&str, &str); 1] = [("flag", "INTEGER NOT NULL DEFAULT 0")];
+
+/// Separates keywords in the `keywords` column.
+const KEYWORD_SEPARATOR: char = ';';
+
+/// Statements run on any catalog and the time they took, for the debug
+/// overlay.
+static QUERIES: AtomicU64 = AtomicU64::new(0);
+static QUERY_NANOS: AtomicU64 = AtomicU64::new(0);
+
+fn count_query(_sql: &str, took: Duration) {
+    QUERIES.fetch_add(1, Ordering::Relaxed);
+    QUERY_NANOS.fetch_add(took.as_nanos() as u64, Ordering::Relaxed);
+}
+
+/// Statements run since startup, and how long they took in all.
+pub fn query_stats() -> (u64, Duration) {
+    (
+        QUERIES.load(Ordering::Relaxed),
+        Duration::from_nanos(QUERY_NANOS.load(Ordering::Relaxed)),
+    )
+}
 
 pub struct Catalog {
     conn: Connection,
 }
 
+/// A photo Looky moved to its trash, see [`crate::trash`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrashedPhoto {
+    /// Where the file is now, inside the trash folder.
+    pub trashed_path: PathBuf,
+    pub original_path: PathBuf,
+    /// Seconds since the Unix epoch.
+    pub deleted_at: i64,
+}
+
+/// Stars, a favourite mark and a pick or reject flag given to a photo while
+/// culling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rating {
+    /// 0 (unrated) to 5.
+    pub stars: u8,
+    pub favorite: bool,
+    pub flag: Flag,
+}
+
+/// A first-pass verdict on a photo, quicker to give than stars.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Flag {
+    #[default]
+    None,
+    Pick,
+    Reject,
+}
+
+impl Flag {
+    pub const ALL: [Flag; 3] = [Flag::Pick, Flag::Reject, Flag::None];
+
+    pub fn label(self) -> &'static str {
+        crate::i18n::tr(match self {
+            Flag::None => "Unflagged",
+            Flag::Pick => "Picks",
+            Flag::Reject => "Rejects",
+        })
+    }
+
+    /// Value stored in the `flag` column.
+    fn to_sql(self) -> i64 {
+        match self {
+            Flag::None => 0,
+            Flag::Pick => 1,
+            Flag::Reject => -1,
+        }
+    }
+
+    fn from_sql(value: i64) -> Self {
+        match value {
+            1 => Flag::Pick,
+            -1 => Flag::Reject,
+            _ => Flag::None,
+        }
+    }
+}
+
+impl std::fmt::Display for Flag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl Rating {
+    pub const MAX_STARS: u8 = 5;
+
+    pub fn is_unrated(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Where the user left off in a folder, restored when it's reopened.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FolderSession {
+    pub grid_scroll_y: f32,
+    pub selected: Option<PathBuf>,
+    /// The photo open in the viewer, if any.
+    pub viewing: Option<PathBuf>,
+    /// The photo last shown in the viewer, even after it was closed.
+    pub last_viewed: Option<PathBuf>,
+}
+
 impl Catalog {
     pub fn open(db_path: &Path) -> Result<Self> {
         if let Some(parent) = db_path.parent() {
             let _ = std::fs::create_dir_all(parent);
         }
-        let conn = Connection::open(db_path)?;
+        let mut conn = Connection::open(db_path)?;
+        conn.profile(Some(count_query));
         let catalog = Catalog { conn };
         catalog.init_schema()?;
         Ok(catalog)
@@ -31,15 +158,94 @@ impl Catalog {
                 date_taken TEXT,
                 date_modified TEXT,
                 content_hash BLOB,
-                perceptual_hash BLOB
+                perceptual_hash BLOB,
+                camera_make TEXT,
+                camera_model TEXT,
+                lens_model TEXT,
+                info_version INTEGER,
+                orientation INTEGER,
+                description TEXT,
+                keywords TEXT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_images_content_hash ON images(content_hash);
+
+            CREATE TABLE IF NOT EXISTS folder_sessions (
+                folder TEXT PRIMARY KEY,
+                grid_scroll_y REAL NOT NULL,
+                selected_path TEXT,
+                viewing_path TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS dup_scans (
+                folder TEXT PRIMARY KEY,
+                paused INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS scan_depths (
+                folder TEXT PRIMARY KEY,
+                depth INTEGER
             );
 
-            CREATE INDEX IF NOT EXISTS idx_images_content_hash ON images(content_hash);",
-        )
+            CREATE TABLE IF NOT EXISTS dup_excluded_folders (
+                folder TEXT PRIMARY KEY
+            );
+
+            CREATE TABLE IF NOT EXISTS trash (
+                trashed_path TEXT PRIMARY KEY,
+                original_path TEXT NOT NULL,
+                deleted_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS orientation_overrides (
+                path TEXT PRIMARY KEY,
+                orientation INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS edits (
+                path TEXT PRIMARY KEY,
+                crop_x REAL NOT NULL,
+                crop_y REAL NOT NULL,
+                crop_width REAL NOT NULL,
+                crop_height REAL NOT NULL,
+                angle REAL NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS ratings (
+                path TEXT PRIMARY KEY,
+                stars INTEGER NOT NULL,
+                favorite INTEGER NOT NULL
+            );",
+        )?;
+        self.add_missing_columns()
+    }
+
+    fn add_missing_columns(&self) -> Result<()> {
+        self.add_columns("images", &ADDED_COLUMNS)?;
+        self.add_columns("folder_sessions", &SESSION_ADDED_COLUMNS)?;
+        self.add_columns("ratings", &RATING_ADDED_COLUMNS)
+    }
+
+    fn add_columns(&self, table: &str, columns: &[(&str, &str)]) -> Result<()> {
+        let existing: Vec<String> = {
+            let mut stmt = self
+                .conn
+                .prepare(&format!("SELECT name FROM pragma_table_info('{}')", table))?;
+            stmt.query_map([], |row| row.get(0))?
+                .collect::<Result<_>>()?
+        };
+        for (name, kind) in columns {
+            if !existing.iter().any(|c| c == name) {
+                self.conn
+                    .execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, name, kind), [])?;
+            }
+        }
+        Ok(())
     }
 
     /// Returns cached hashes if the path exists in DB and file_size + mtime still match.
-    pub fn get_hashes(&self, path: &Path) -> Option<([u8; 32], Vec<u8>)> {
+    /// The content hash is `None` if it was skipped as not needed.
+    pub fn get_hashes(&self, path: &Path) -> Option<(Option<[u8; 32]>, Vec<u8>)> {
         let path_str = path.to_string_lossy();
         let (disk_size, disk_mtime) = file_size_and_mtime(path)?;
 
@@ -63,14 +269,12 @@ impl Catalog {
             if db_size != disk_size as i64 || db_mtime != disk_mtime {
                 return None;
             }
-            let ch = content_hash?;
             let ph = perceptual_hash?;
-            if ch.len() != 32 {
-                return None;
-            }
-            let mut arr = [0u8; 32];
-            arr.copy_from_slice(&ch);
-            Some((arr, ph))
+            let ch = match content_hash {
+                Some(ch) => Some(<[u8; 32]>::try_from(ch.as_slice()).ok()?),
+                None => None,
+            };
+            Some((ch, ph))
         })
     }
 
@@ -80,7 +284,7 @@ impl Catalog {
         path: &Path,
         file_size: u64,
         mtime_ns: i64,
-        content_hash: &[u8; 32],
+        content_hash: Option<&[u8; 32]>,
         perceptual_hash: &[u8],
     ) {
         let path_str = path.to_string_lossy();
@@ -96,12 +300,101 @@ impl Catalog {
                 path_str.as_ref(),
                 file_size as i64,
                 mtime_ns,
-                &content_hash[..],
+                content_hash.map(|h| &h[..]),
                 perceptual_hash,
             ],
         );
     }
 
+    /// The cached SHA-256 of a file, if it was hashed in full and hasn't
+    /// changed since.
+    pub fn get_content_hash(&self, path: &Path) -> Option<[u8; 32]> {
+        let path_str = path.to_string_lossy();
+        let (disk_size, disk_mtime) = file_size_and_mtime(path)?;
+        let (db_size, db_mtime, content_hash): (i64, i64, Option<Vec<u8>>) = self
+            .conn
+            .prepare_cached("SELECT file_size, mtime_ns, content_hash FROM images WHERE path = ?1")
+            .ok()?
+            .query_row(params![path_str.as_ref()], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .ok()?;
+        if db_size != disk_size as i64 || db_mtime != disk_mtime {
+            return None;
+        }
+        <[u8; 32]>::try_from(content_hash?.as_slice()).ok()
+    }
+
+    /// Whether a catalogued file outside `folder` has this SHA-256, i.e. the
+    /// photo is already in the library.
+    pub fn has_content_hash_outside(&self, content_hash: &[u8; 32], folder: &Path) -> bool {
+        let prefix = folder.join("").to_string_lossy().into_owned();
+        self.conn
+            .prepare_cached(
+                "SELECT 1 FROM images
+                 WHERE content_hash = ?1 AND substr(path, 1, length(?2)) != ?2
+                 LIMIT 1",
+            )
+            .and_then(|mut stmt| stmt.exists(params![&content_hash[..], prefix]))
+            .unwrap_or(false)
+    }
+
+    /// Catalogued files that share their SHA-256 with another, in any
+    /// folder ever scanned, as the file size and the paths of each set.
+    pub fn same_content_files(&self) -> Vec<(u64, Vec<PathBuf>)> {
+        let mut stmt = match self.conn.prepare(
+            "SELECT content_hash, file_size, path FROM images
+             WHERE content_hash IN (
+                SELECT content_hash FROM images WHERE content_hash IS NOT NULL
+                GROUP BY content_hash HAVING COUNT(*) > 1
+             )
+             ORDER BY content_hash, path",
+        ) {
+            Ok(s) => s,
+            Err(_) => return Vec::new(),
+        };
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, Vec<u8>>(0)?,
+                    row.get::<_, i64>(1)?,
+                    PathBuf::from(row.get::<_, String>(2)?),
+                ))
+            })
+            .map(|rows| rows.filter_map(|r| r.ok()).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        let mut sets: Vec<(Vec<u8>, u64, Vec<PathBuf>)> = Vec::new();
+        for (hash, size, path) in rows {
+            match sets.last_mut() {
+                Some((last, _, paths)) if *last == hash => paths.push(path),
+                _ => sets.push((hash, size.max(0) as u64, vec![path])),
+            }
+        }
+        sets.into_iter().map(|(_, size, paths)| (size, paths)).collect()
+    }
+
+    /// Record just the SHA-256 of a file, e.g. one copied in by an import,
+    /// leaving any other cached info alone.
+    pub fn insert_content_hash(
+        &self,
+        path: &Path,
+        file_size: u64,
+        mtime_ns: i64,
+        content_hash: &[u8; 32],
+    ) {
+        let path_str = path.to_string_lossy();
+        let _ = self.conn.execute(
+            "INSERT INTO images (path, file_size, mtime_ns, content_hash)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(path) DO UPDATE SET
+                file_size = excluded.file_size,
+                mtime_ns = excluded.mtime_ns,
+                content_hash = excluded.content_hash",
+            params![path_str.as_ref(), file_size as i64, mtime_ns, &content_hash[..]],
+        );
+    }
+
     /// Returns a cached FileSummary if the path exists and size+mtime match.
     pub fn get_file_summary(&self, path: &Path) -> Option<FileSummary> {
         let path_str = path.to_string_lossy();
@@ -183,6 +476,372 @@ impl Catalog {
         );
     }
 
+    /// Returns the indexed info if the path is in the DB, size + mtime still
+    /// match, and it was indexed by this version.
+    pub fn get_photo_info(&self, path: &Path) -> Option<PhotoInfo> {
+        let path_str = path.to_string_lossy();
+        let (disk_size, disk_mtime) = file_size_and_mtime(path)?;
+
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT file_size, mtime_ns, info_version, camera_make, camera_model, lens_model,
+                        width, height, orientation, description, keywords
+                 FROM images WHERE path = ?1",
+            )
+            .ok()?;
+
+        stmt.query_row(params![path_str.as_ref()], |row| {
+            let db_size: i64 = row.get(0)?;
+            let db_mtime: i64 = row.get(1)?;
+            let version: Option<i64> = row.get(2)?;
+            let width: Option<u32> = row.get(6)?;
+            let height: Option<u32> = row.get(7)?;
+            let keywords: Option<String> = row.get(10)?;
+            let info = PhotoInfo {
+                camera_make: row.get(3)?,
+                camera_model: row.get(4)?,
+                lens_model: row.get(5)?,
+                dimensions: width.zip(height),
+                orientation: row.get(8)?,
+                description: row.get(9)?,
+                keywords: keywords
+                    .map(|k| k.split(KEYWORD_SEPARATOR).map(str::to_string).collect())
+                    .unwrap_or_default(),
+            };
+            Ok((db_size, db_mtime, version, info))
+        })
+        .ok()
+        .and_then(|(db_size, db_mtime, version, info)| {
+            if db_size != disk_size as i64 || db_mtime != disk_mtime {
+                return None;
+            }
+            (version? >= PHOTO_INFO_VERSION).then_some(info)
+        })
+    }
+
+    /// Insert or update the indexed info for a path. Hashes stored for an
+    /// older version of the file are dropped rather than carried over.
+    pub fn insert_photo_info(&self, path: &Path, file_size: u64, mtime_ns: i64, info: &PhotoInfo) {
+        let path_str = path.to_string_lossy();
+        let (width, height) = match info.dimensions {
+            Some((w, h)) => (Some(w), Some(h)),
+            None => (None, None),
+        };
+        let keywords = (!info.keywords.is_empty())
+            .then(|| info.keywords.join(&KEYWORD_SEPARATOR.to_string()));
+        let _ = self.conn.execute(
+            "INSERT INTO images (path, file_size, mtime_ns, camera_make, camera_model, lens_model,
+                                 width, height, orientation, description, keywords, info_version)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+             ON CONFLICT(path) DO UPDATE SET
+                content_hash = CASE WHEN file_size = excluded.file_size AND mtime_ns = excluded.mtime_ns
+                    THEN content_hash END,
+                perceptual_hash = CASE WHEN file_size = excluded.file_size AND mtime_ns = excluded.mtime_ns
+                    THEN perceptual_hash END,
+                file_size = excluded.file_size,
+                mtime_ns = excluded.mtime_ns,
+                camera_make = excluded.camera_make,
+                camera_model = excluded.camera_model,
+                lens_model = excluded.lens_model,
+                width = excluded.width,
+                height = excluded.height,
+                orientation = excluded.orientation,
+                description = excluded.description,
+                keywords = excluded.keywords,
+                info_version = excluded.info_version",
+            params![
+                path_str.as_ref(),
+                file_size as i64,
+                mtime_ns,
+                info.camera_make.as_deref(),
+                info.camera_model.as_deref(),
+                info.lens_model.as_deref(),
+                width,
+                height,
+                info.orientation,
+                info.description.as_deref(),
+                keywords,
+                PHOTO_INFO_VERSION,
+            ],
+        );
+    }
+
+    pub fn get_session(&self, folder: &Path) -> Option<FolderSession> {
+        let folder_str = folder.to_string_lossy();
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT grid_scroll_y, selected_path, viewing_path, last_viewed_path
+                 FROM folder_sessions WHERE folder = ?1",
+            )
+            .ok()?;
+        stmt.query_row(params![folder_str.as_ref()], |row| {
+            let grid_scroll_y: f64 = row.get(0)?;
+            let selected: Option<String> = row.get(1)?;
+            let viewing: Option<String> = row.get(2)?;
+            let last_viewed: Option<String> = row.get(3)?;
+            Ok(FolderSession {
+                grid_scroll_y: grid_scroll_y as f32,
+                selected: selected.map(PathBuf::from),
+                viewing: viewing.map(PathBuf::from),
+                last_viewed: last_viewed.map(PathBuf::from),
+            })
+        })
+        .ok()
+    }
+
+    pub fn save_session(&self, folder: &Path, session: &FolderSession) {
+        let folder_str = folder.to_string_lossy();
+        let _ = self.conn.execute(
+            "INSERT INTO folder_sessions
+                (folder, grid_scroll_y, selected_path, viewing_path, last_viewed_path)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(folder) DO UPDATE SET
+                grid_scroll_y = excluded.grid_scroll_y,
+                selected_path = excluded.selected_path,
+                viewing_path = excluded.viewing_path,
+                last_viewed_path = excluded.last_viewed_path",
+            params![
+                folder_str.as_ref(),
+                f64::from(session.grid_scroll_y),
+                session.selected.as_ref().map(|p| p.to_string_lossy().into_owned()),
+                session.viewing.as_ref().map(|p| p.to_string_lossy().into_owned()),
+                session.last_viewed.as_ref().map(|p| p.to_string_lossy().into_owned()),
+            ],
+        );
+    }
+
+    /// Whether a duplicate scan of `folder` was left unfinished, and if so
+    /// whether it was paused.
+    pub fn get_dup_scan(&self, folder: &Path) -> Option<bool> {
+        let folder_str = folder.to_string_lossy();
+        self.conn
+            .query_row(
+                "SELECT paused FROM dup_scans WHERE folder = ?1",
+                params![folder_str.as_ref()],
+                |row| row.get(0),
+            )
+            .ok()
+    }
+
+    /// Remember a duplicate scan of `folder` is under way, to pick it up
+    /// again if the folder is closed before it finishes.
+    pub fn save_dup_scan(&self, folder: &Path, paused: bool) {
+        let folder_str = folder.to_string_lossy();
+        let _ = self.conn.execute(
+            "INSERT INTO dup_scans (folder, paused) VALUES (?1, ?2)
+             ON CONFLICT(folder) DO UPDATE SET paused = excluded.paused",
+            params![folder_str.as_ref(), paused],
+        );
+    }
+
+    pub fn clear_dup_scan(&self, folder: &Path) {
+        let folder_str = folder.to_string_lossy();
+        let _ = self
+            .conn
+            .execute("DELETE FROM dup_scans WHERE folder = ?1", params![folder_str.as_ref()]);
+    }
+
+    /// How many levels of subfolders to scan in `folder`, if set for it:
+    /// `Some(None)` for all of them.
+    pub fn get_scan_depth(&self, folder: &Path) -> Option<Option<u32>> {
+        let folder_str = folder.to_string_lossy();
+        self.conn
+            .query_row(
+                "SELECT depth FROM scan_depths WHERE folder = ?1",
+                params![folder_str.as_ref()],
+                |row| row.get(0),
+            )
+            .ok()
+    }
+
+    pub fn set_scan_depth(&self, folder: &Path, depth: Option<u32>) {
+        let folder_str = folder.to_string_lossy();
+        let _ = self.conn.execute(
+            "INSERT INTO scan_depths (folder, depth) VALUES (?1, ?2)
+             ON CONFLICT(folder) DO UPDATE SET depth = excluded.depth",
+            params![folder_str.as_ref(), depth],
+        );
+    }
+
+    /// Folders whose photos duplicate scans leave out, though they can still
+    /// be browsed.
+    pub fn dup_excluded_folders(&self) -> Vec<PathBuf> {
+        let mut stmt = match self.conn.prepare("SELECT folder FROM dup_excluded_folders") {
+            Ok(s) => s,
+            Err(_) => return Vec::new(),
+        };
+        stmt.query_map([], |row| Ok(PathBuf::from(row.get::<_, String>(0)?)))
+            .map(|rows| rows.filter_map(|r| r.ok()).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn set_dup_excluded(&self, folder: &Path, excluded: bool) {
+        let folder_str = folder.to_string_lossy();
+        let sql = if excluded {
+            "INSERT OR IGNORE INTO dup_excluded_folders (folder) VALUES (?1)"
+        } else {
+            "DELETE FROM dup_excluded_folders WHERE folder = ?1"
+        };
+        let _ = self.conn.execute(sql, params![folder_str.as_ref()]);
+    }
+
+    pub fn add_trashed(&self, photo: &TrashedPhoto) {
+        let _ = self.conn.execute(
+            "INSERT OR REPLACE INTO trash (trashed_path, original_path, deleted_at)
+             VALUES (?1, ?2, ?3)",
+            params![
+                photo.trashed_path.to_string_lossy().as_ref(),
+                photo.original_path.to_string_lossy().as_ref(),
+                photo.deleted_at,
+            ],
+        );
+    }
+
+    /// Everything in the trash, most recently deleted first.
+    pub fn trashed(&self) -> Vec<TrashedPhoto> {
+        let mut stmt = match self.conn.prepare(
+            "SELECT trashed_path, original_path, deleted_at FROM trash
+             ORDER BY deleted_at DESC",
+        ) {
+            Ok(s) => s,
+            Err(_) => return Vec::new(),
+        };
+        stmt.query_map([], |row| {
+            Ok(TrashedPhoto {
+                trashed_path: PathBuf::from(row.get::<_, String>(0)?),
+                original_path: PathBuf::from(row.get::<_, String>(1)?),
+                deleted_at: row.get(2)?,
+            })
+        })
+        .map(|rows| rows.filter_map(|r| r.ok()).collect())
+        .unwrap_or_default()
+    }
+
+    /// Forget a trashed photo once it's restored or deleted for good.
+    pub fn remove_trashed(&self, trashed_path: &Path) {
+        let _ = self.conn.execute(
+            "DELETE FROM trash WHERE trashed_path = ?1",
+            params![trashed_path.to_string_lossy().as_ref()],
+        );
+    }
+
+    /// Every file shown with an orientation other than its own tag's.
+    pub fn orientation_overrides(&self) -> Vec<(PathBuf, u32)> {
+        let mut stmt = match self.conn.prepare("SELECT path, orientation FROM orientation_overrides") {
+            Ok(s) => s,
+            Err(_) => return Vec::new(),
+        };
+        stmt.query_map([], |row| Ok((PathBuf::from(row.get::<_, String>(0)?), row.get(1)?)))
+            .map(|rows| rows.filter_map(|r| r.ok()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Show `path` with `orientation` instead of its tag's, or go back to
+    /// the tag for `None`.
+    pub fn set_orientation_override(&self, path: &Path, orientation: Option<u32>) {
+        let path_str = path.to_string_lossy();
+        let _ = match orientation {
+            Some(orientation) => self.conn.execute(
+                "INSERT INTO orientation_overrides (path, orientation) VALUES (?1, ?2)
+                 ON CONFLICT(path) DO UPDATE SET orientation = excluded.orientation",
+                params![path_str.as_ref(), orientation],
+            ),
+            None => self.conn.execute(
+                "DELETE FROM orientation_overrides WHERE path = ?1",
+                params![path_str.as_ref()],
+            ),
+        };
+    }
+
+    /// Every edited photo, with its crop and straighten.
+    pub fn edits(&self) -> Vec<(PathBuf, PhotoEdit)> {
+        let mut stmt = match self
+            .conn
+            .prepare("SELECT path, crop_x, crop_y, crop_width, crop_height, angle FROM edits")
+        {
+            Ok(s) => s,
+            Err(_) => return Vec::new(),
+        };
+        stmt.query_map([], |row| {
+            let edit = PhotoEdit {
+                crop: [row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?],
+                angle: row.get(5)?,
+            };
+            Ok((PathBuf::from(row.get::<_, String>(0)?), edit))
+        })
+        .map(|rows| rows.filter_map(|r| r.ok()).collect())
+        .unwrap_or_default()
+    }
+
+    /// Keep `edit` for `path`, or drop its edit for `None`.
+    pub fn set_edit(&self, path: &Path, edit: Option<&PhotoEdit>) {
+        let path_str = path.to_string_lossy();
+        let _ = match edit {
+            Some(edit) => {
+                let [x, y, width, height] = edit.crop;
+                self.conn.execute(
+                    "INSERT OR REPLACE INTO edits (path, crop_x, crop_y, crop_width, crop_height, angle)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![path_str.as_ref(), x, y, width, height, edit.angle],
+                )
+            }
+            None => self
+                .conn
+                .execute("DELETE FROM edits WHERE path = ?1", params![path_str.as_ref()]),
+        };
+    }
+
+    /// Every rated, favourite or flagged photo.
+    pub fn ratings(&self) -> Vec<(PathBuf, Rating)> {
+        let mut stmt = match self.conn.prepare("SELECT path, stars, favorite, flag FROM ratings") {
+            Ok(s) => s,
+            Err(_) => return Vec::new(),
+        };
+        stmt.query_map([], |row| {
+            let rating = Rating {
+                stars: row.get(1)?,
+                favorite: row.get(2)?,
+                flag: Flag::from_sql(row.get(3)?),
+            };
+            Ok((PathBuf::from(row.get::<_, String>(0)?), rating))
+        })
+        .map(|rows| rows.filter_map(|r| r.ok()).collect())
+        .unwrap_or_default()
+    }
+
+    /// Keep `rating` for `path`, dropping the row once it's back to unrated.
+    pub fn set_rating(&self, path: &Path, rating: Rating) {
+        let path_str = path.to_string_lossy();
+        let _ = if rating.is_unrated() {
+            self.conn
+                .execute("DELETE FROM ratings WHERE path = ?1", params![path_str.as_ref()])
+        } else {
+            self.conn.execute(
+                "INSERT OR REPLACE INTO ratings (path, stars, favorite, flag) VALUES (?1, ?2, ?3, ?4)",
+                params![path_str.as_ref(), rating.stars, rating.favorite, rating.flag.to_sql()],
+            )
+        };
+    }
+
+    /// Follow a file moved from `from` to `to`, keeping its info, hashes,
+    /// rating, edit and orientation override.
+    pub fn move_path(&self, from: &Path, to: &Path) {
+        let (from, to) = (from.to_string_lossy(), to.to_string_lossy());
+        for table in ["images", "ratings", "edits", "orientation_overrides"] {
+            // Anything already recorded at the destination was overwritten
+            let _ = self.conn.execute(
+                &format!("DELETE FROM {} WHERE path = ?1", table),
+                params![to.as_ref()],
+            );
+            let _ = self.conn.execute(
+                &format!("UPDATE {} SET path = ?2 WHERE path = ?1", table),
+                params![from.as_ref(), to.as_ref()],
+            );
+        }
+    }
+
     /// Remove rows whose paths no longer exist on disk.
     pub fn prune_missing(&self) {
         let paths: Vec<String> = {
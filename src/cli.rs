@@ -0,0 +1,223 @@
+//! Subcommands that run without a window, for scheduled jobs on a NAS:
+//!
+//! - `looky scan <folder>` fills the thumbnail cache and the catalog's
+//!   hashes, so opening the folder or scanning it for duplicates later is
+//!   quick.
+//! - `looky dupes <folder> [--json]` finds duplicates as the app does and
+//!   prints them.
+//!
+//! Anything else on the command line starts the app as usual.
+
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+
+use crate::app;
+use crate::catalog::{self, Catalog};
+use crate::duplicates::{self, DuplicateGroup, ImageHashes, MatchKind};
+use crate::metadata;
+use crate::server::api::json_escape;
+use crate::settings::Settings;
+use crate::thumbnail;
+
+/// Photos handled between progress lines.
+const BATCH_SIZE: usize = 64;
+
+const USAGE: &str = "Usage:
+  looky                        open the app
+  looky scan <folder>          cache thumbnails and hashes for <folder>
+  looky dupes <folder> [--json]
+                               print the duplicates in <folder>";
+
+/// Run the subcommand in `args` (without the program name) and return the
+/// exit code, or `None` if there isn't one and the app should start.
+pub fn run(args: &[OsString]) -> Option<i32> {
+    let command = args.first()?.to_str()?;
+    let rest = &args[1..];
+    let result = match command {
+        "scan" => folder_arg(rest).and_then(|(folder, _)| scan(&folder)),
+        "dupes" => folder_arg(rest).and_then(|(folder, json)| dupes(&folder, json)),
+        "help" | "--help" | "-h" => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        _ => return None,
+    };
+    Some(match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("looky: {e}");
+            1
+        }
+    })
+}
+
+/// The folder argument, and whether `--json` was given.
+fn folder_arg(args: &[OsString]) -> Result<(PathBuf, bool), String> {
+    let json = args.iter().any(|a| a == "--json");
+    let mut folders = args.iter().filter(|a| *a != "--json").map(Path::new);
+    match (folders.next(), folders.next()) {
+        (Some(folder), None) if folder.is_dir() => Ok((folder.to_path_buf(), json)),
+        (Some(folder), None) => Err(format!("{} is not a folder", folder.display())),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+/// Thumbnails for every photo under `folder`, then its hashes.
+fn scan(folder: &Path) -> Result<(), String> {
+    let catalog = open_catalog()?;
+    let settings = Settings::load();
+    app::apply_sharpening(&settings);
+    let depth = app::scan_depth_for(Some(&catalog), &settings, folder);
+    let paths = app::find_images(folder.to_path_buf(), depth);
+    eprintln!("{} photos in {}", paths.len(), folder.display());
+
+    let mut unreadable = 0;
+    for (n, batch) in paths.chunks(BATCH_SIZE).enumerate() {
+        let results = thumbnail::generate_thumbnails_parallel(batch, app::GRID_THUMBNAIL_PIXELS);
+        unreadable += results.iter().filter(|(_, r)| r.is_err()).count();
+        progress("Thumbnails", n * BATCH_SIZE + batch.len(), paths.len());
+    }
+
+    let items: Vec<(usize, PathBuf)> = paths.iter().cloned().enumerate().collect();
+    let hashes = hash_all(&catalog, &items);
+    println!(
+        "Scanned {} photos: {} thumbnails cached, {} hashed, {} unreadable",
+        paths.len(),
+        paths.len() - unreadable,
+        hashes.len(),
+        unreadable
+    );
+    Ok(())
+}
+
+/// Hash `folder` as a duplicate scan in the app does, and print the groups.
+fn dupes(folder: &Path, json: bool) -> Result<(), String> {
+    let catalog = open_catalog()?;
+    let settings = Settings::load();
+    let depth = app::scan_depth_for(Some(&catalog), &settings, folder);
+    let excluded = catalog.dup_excluded_folders();
+    let mut paths = app::find_images(folder.to_path_buf(), depth);
+    paths.retain(|path| !app::in_any_folder(&excluded, path));
+    let items: Vec<(usize, PathBuf)> = paths.iter().cloned().enumerate().collect();
+    let hashes = hash_all(&catalog, &items);
+    let mut groups = duplicates::find_duplicates(&hashes, app::VISUAL_DUP_THRESHOLD);
+    if !settings.dup_report_capture_pairs {
+        groups = duplicates::without_capture_sets(
+            groups,
+            |i| paths.get(i).cloned(),
+            |i| metadata::read_file_summary(paths.get(i)?).date_taken,
+        );
+    }
+    for group in &mut groups {
+        group.indices.sort();
+    }
+    groups.sort_by_key(|g| g.indices[0]);
+
+    let mut out = std::io::stdout().lock();
+    let written = if json {
+        write_json(&mut out, folder, &paths, &groups)
+    } else {
+        write_text(&mut out, &paths, &groups)
+    };
+    written.map_err(|e| e.to_string())
+}
+
+fn open_catalog() -> Result<Catalog, String> {
+    app::open_catalog().ok_or_else(|| "couldn't open the catalog".to_string())
+}
+
+/// Hashes for `items`, from the catalog where it has them and computed and
+/// stored otherwise. Only files that could be exact copies get a content
+/// hash, as in the app.
+fn hash_all(catalog: &Catalog, items: &[(usize, PathBuf)]) -> Vec<(usize, ImageHashes)> {
+    let candidates: HashSet<usize> = duplicates::exact_candidates(items);
+    let mut hashes = Vec::new();
+    let mut pending = Vec::new();
+    for (i, path) in items {
+        let cached = catalog
+            .get_hashes(path)
+            .filter(|(content_hash, _)| content_hash.is_some() || !candidates.contains(i));
+        match cached {
+            Some((content_hash, perceptual_hash)) => hashes.push((
+                *i,
+                ImageHashes {
+                    content_hash,
+                    perceptual_hash,
+                },
+            )),
+            None => pending.push((*i, path.clone(), candidates.contains(i))),
+        }
+    }
+
+    let cached = hashes.len();
+    for (n, batch) in pending.chunks(BATCH_SIZE).enumerate() {
+        let results: Vec<(usize, PathBuf, Option<ImageHashes>)> = batch
+            .par_iter()
+            .map(|(i, path, with_content)| (*i, path.clone(), duplicates::compute_hashes(path, *with_content)))
+            .collect();
+        for (i, path, h) in results {
+            let Some(h) = h else { continue };
+            if let Some((file_size, mtime_ns)) = catalog::file_size_and_mtime_for(&path) {
+                catalog.insert_hashes(&path, file_size, mtime_ns, h.content_hash.as_ref(), &h.perceptual_hash);
+            }
+            hashes.push((i, h));
+        }
+        progress("Hashes", cached + n * BATCH_SIZE + batch.len(), items.len());
+    }
+    hashes
+}
+
+fn progress(label: &str, done: usize, total: usize) {
+    eprintln!("{label}: {done} / {total}");
+}
+
+fn write_text(out: &mut impl Write, paths: &[PathBuf], groups: &[DuplicateGroup]) -> std::io::Result<()> {
+    for group in groups {
+        match group.match_kind {
+            MatchKind::Exact => writeln!(out, "exact")?,
+            MatchKind::Visual { distance } => writeln!(out, "similar (distance {distance})")?,
+        }
+        for &i in &group.indices {
+            writeln!(out, "  {}", paths[i].display())?;
+        }
+    }
+    Ok(())
+}
+
+/// `{"folder": ..., "groups": [{"match": "exact", "distance": 0,
+/// "files": [{"path": ..., "size": ...}]}]}`
+fn write_json(
+    out: &mut impl Write,
+    folder: &Path,
+    paths: &[PathBuf],
+    groups: &[DuplicateGroup],
+) -> std::io::Result<()> {
+    write!(out, "{{\"folder\":\"{}\",\"groups\":[", json_escape(&folder.to_string_lossy()))?;
+    for (n, group) in groups.iter().enumerate() {
+        let (kind, distance) = match group.match_kind {
+            MatchKind::Exact => ("exact", 0),
+            MatchKind::Visual { distance } => ("visual", distance),
+        };
+        if n > 0 {
+            write!(out, ",")?;
+        }
+        write!(out, "{{\"match\":\"{kind}\",\"distance\":{distance},\"files\":[")?;
+        for (m, &i) in group.indices.iter().enumerate() {
+            let size = std::fs::metadata(&paths[i]).map(|m| m.len()).unwrap_or(0);
+            if m > 0 {
+                write!(out, ",")?;
+            }
+            write!(
+                out,
+                "{{\"path\":\"{}\",\"size\":{size}}}",
+                json_escape(&paths[i].to_string_lossy())
+            )?;
+        }
+        write!(out, "]}}")?;
+    }
+    writeln!(out, "]}}")
+}
@@ -0,0 +1,254 @@
+//! Printable PDF contact sheets: pages of small photos in a grid, each
+//! captioned with its file name and date, for proofing and binders.
+//!
+//! The PDF is written by hand. Photos go in as JPEG streams the viewer
+//! decodes itself, and captions use the built-in Helvetica, so nothing needs
+//! embedding beyond the images.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+
+use crate::i18n::trf;
+use crate::metadata;
+use crate::thumbnail;
+
+/// Longest side of each photo, enough for a sharp print at small sizes.
+const PHOTO_SIZE: u32 = 800;
+const PHOTO_QUALITY: u8 = 80;
+
+/// A4 in points.
+const PAGE_WIDTH: f32 = 595.28;
+const PAGE_HEIGHT: f32 = 841.89;
+const MARGIN: f32 = 36.0;
+const HEADER_HEIGHT: f32 = 28.0;
+const TITLE_SIZE: f32 = 14.0;
+const CAPTION_SIZE: f32 = 7.0;
+/// Room under each photo for two caption lines.
+const CAPTION_HEIGHT: f32 = 20.0;
+const GAP: f32 = 6.0;
+/// Average Helvetica character width, as a fraction of the font size.
+const CHAR_WIDTH: f32 = 0.55;
+
+/// A photo ready to place on a sheet.
+#[derive(Debug, Clone)]
+pub struct SheetPhoto {
+    pub jpeg: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    pub name: String,
+    /// When it was taken, or else last modified.
+    pub date: Option<String>,
+}
+
+/// Shrink and caption each `(n, path)`. Photos that can't be decoded are
+/// left out.
+pub fn prepare_batch(items: &[(usize, PathBuf)]) -> Vec<(usize, SheetPhoto)> {
+    items
+        .par_iter()
+        .filter_map(|(n, path)| match prepare_photo(path) {
+            Some(photo) => Some((*n, photo)),
+            None => {
+                log::warn!("Failed to add {} to the contact sheet", path.display());
+                None
+            }
+        })
+        .collect()
+}
+
+fn prepare_photo(path: &Path) -> Option<SheetPhoto> {
+    let jpeg = thumbnail::display_jpeg_bytes(path, PHOTO_SIZE, PHOTO_QUALITY)?;
+    let (width, height) = image::ImageReader::with_format(io::Cursor::new(&jpeg), image::ImageFormat::Jpeg)
+        .into_dimensions()
+        .ok()?;
+    let summary = metadata::read_file_summary(path);
+    let date = summary
+        .date_taken
+        .or(summary.date_modified)
+        .map(|date| date.get(..16).map(str::to_string).unwrap_or(date));
+    Some(SheetPhoto {
+        jpeg,
+        width,
+        height,
+        name: summary.filename,
+        date,
+    })
+}
+
+/// Write `photos`, in order, to a PDF at `dest` with `columns` × `rows`
+/// photos per page and `title` at the top of each page.
+pub fn write_pdf(dest: &Path, title: &str, columns: usize, rows: usize, photos: &[SheetPhoto]) -> io::Result<()> {
+    let per_page = (columns * rows).max(1);
+    let pages = photos.len().div_ceil(per_page).max(1);
+    // Objects: catalog, page tree and font, then a page and its content
+    // stream for each page, then the images.
+    let page_id = |page: usize| 4 + page * 2;
+    let image_id = |i: usize| 4 + pages * 2 + i;
+
+    let mut pdf = PdfWriter::new(File::create(dest)?, image_id(photos.len()));
+    pdf.raw(b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n")?;
+    pdf.object(1, b"<< /Type /Catalog /Pages 2 0 R >>")?;
+    let kids: Vec<String> = (0..pages).map(|page| format!("{} 0 R", page_id(page))).collect();
+    pdf.object(
+        2,
+        format!("<< /Type /Pages /Kids [{}] /Count {pages} >>", kids.join(" ")).as_bytes(),
+    )?;
+    pdf.object(
+        3,
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>",
+    )?;
+
+    let cell_width = (PAGE_WIDTH - 2.0 * MARGIN) / columns.max(1) as f32;
+    let cell_height = (PAGE_HEIGHT - 2.0 * MARGIN - HEADER_HEIGHT) / rows.max(1) as f32;
+    let box_width = cell_width - GAP;
+    let box_height = (cell_height - CAPTION_HEIGHT - GAP).max(1.0);
+    let caption_chars = (box_width / (CAPTION_SIZE * CHAR_WIDTH)) as usize;
+    let grid_top = PAGE_HEIGHT - MARGIN - HEADER_HEIGHT;
+
+    for page in 0..pages {
+        let first = page * per_page;
+        let on_page = &photos[first.min(photos.len())..(first + per_page).min(photos.len())];
+        let mut content = String::new();
+        content.push_str(&format!(
+            "BT /F1 {TITLE_SIZE} Tf {MARGIN} {:.2} Td ({}) Tj ET\n",
+            PAGE_HEIGHT - MARGIN - TITLE_SIZE,
+            pdf_text(title, 60),
+        ));
+        let page_label = trf("Page {} of {}", &[&(page + 1), &pages]);
+        content.push_str(&format!(
+            "0.4 g BT /F1 {CAPTION_SIZE} Tf {:.2} {:.2} Td ({}) Tj ET 0 g\n",
+            PAGE_WIDTH - MARGIN - page_label.chars().count() as f32 * CAPTION_SIZE * CHAR_WIDTH,
+            PAGE_HEIGHT - MARGIN - TITLE_SIZE,
+            pdf_text(&page_label, 40),
+        ));
+        let mut images = String::new();
+        for (slot, photo) in on_page.iter().enumerate() {
+            let i = first + slot;
+            let left = MARGIN + (slot % columns.max(1)) as f32 * cell_width + GAP / 2.0;
+            let top = grid_top - (slot / columns.max(1)) as f32 * cell_height - GAP / 2.0;
+            let box_bottom = top - box_height;
+
+            // Fit within the box, centred
+            let scale = (box_width / photo.width.max(1) as f32).min(box_height / photo.height.max(1) as f32);
+            let (w, h) = (photo.width as f32 * scale, photo.height as f32 * scale);
+            let x = left + (box_width - w) / 2.0;
+            let y = box_bottom + (box_height - h) / 2.0;
+            content.push_str(&format!("q {w:.2} 0 0 {h:.2} {x:.2} {y:.2} cm /Im{i} Do Q\n"));
+            images.push_str(&format!("/Im{i} {} 0 R ", image_id(i)));
+
+            content.push_str(&format!(
+                "BT /F1 {CAPTION_SIZE} Tf {left:.2} {:.2} Td ({}) Tj ET\n",
+                box_bottom - 8.0,
+                pdf_text(&photo.name, caption_chars),
+            ));
+            if let Some(date) = &photo.date {
+                content.push_str(&format!(
+                    "0.4 g BT /F1 {CAPTION_SIZE} Tf {left:.2} {:.2} Td ({}) Tj ET 0 g\n",
+                    box_bottom - 16.0,
+                    pdf_text(date, caption_chars),
+                ));
+            }
+        }
+
+        pdf.object(
+            page_id(page),
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] \
+                 /Resources << /Font << /F1 3 0 R >> /XObject << {images}>> >> /Contents {} 0 R >>",
+                page_id(page) + 1,
+            )
+            .as_bytes(),
+        )?;
+        pdf.stream(page_id(page) + 1, "", content.as_bytes())?;
+    }
+
+    for (i, photo) in photos.iter().enumerate() {
+        pdf.stream(
+            image_id(i),
+            &format!(
+                "/Type /XObject /Subtype /Image /Width {} /Height {} \
+                 /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /DCTDecode",
+                photo.width, photo.height,
+            ),
+            &photo.jpeg,
+        )?;
+    }
+    pdf.finish()
+}
+
+/// `text` as a PDF string literal body in WinAnsi, cut to `max_chars`.
+/// Characters outside Latin-1 become "?".
+fn pdf_text(text: &str, max_chars: usize) -> String {
+    let mut chars: Vec<char> = text.chars().collect();
+    if chars.len() > max_chars {
+        chars.truncate(max_chars.saturating_sub(3));
+        chars.extend(['.', '.', '.']);
+    }
+    let mut out = String::new();
+    for c in chars {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            '\u{A0}'..='\u{FF}' => out.push_str(&format!("\\{:03o}", c as u32)),
+            _ => out.push('?'),
+        }
+    }
+    out
+}
+
+/// Writes numbered objects and keeps their offsets for the cross-reference
+/// table. Objects `1..count` must all be written before `finish`.
+struct PdfWriter {
+    out: BufWriter<File>,
+    offsets: Vec<u64>,
+    pos: u64,
+}
+
+impl PdfWriter {
+    fn new(file: File, count: usize) -> Self {
+        Self {
+            out: BufWriter::new(file),
+            offsets: vec![0; count],
+            pos: 0,
+        }
+    }
+
+    fn raw(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.out.write_all(bytes)?;
+        self.pos += bytes.len() as u64;
+        Ok(())
+    }
+
+    fn object(&mut self, id: usize, body: &[u8]) -> io::Result<()> {
+        self.offsets[id] = self.pos;
+        self.raw(format!("{id} 0 obj\n").as_bytes())?;
+        self.raw(body)?;
+        self.raw(b"\nendobj\n")
+    }
+
+    fn stream(&mut self, id: usize, dict: &str, data: &[u8]) -> io::Result<()> {
+        self.offsets[id] = self.pos;
+        self.raw(format!("{id} 0 obj\n<< {dict} /Length {} >>\nstream\n", data.len()).as_bytes())?;
+        self.raw(data)?;
+        self.raw(b"\nendstream\nendobj\n")
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        let xref = self.pos;
+        let count = self.offsets.len();
+        let mut table = format!("xref\n0 {count}\n0000000000 65535 f \n");
+        for offset in &self.offsets[1..] {
+            table.push_str(&format!("{offset:010} 00000 n \n"));
+        }
+        table.push_str(&format!(
+            "trailer\n<< /Size {count} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n"
+        ));
+        self.raw(table.as_bytes())?;
+        self.out.flush()
+    }
+}
@@ -0,0 +1,267 @@
+//! Full-size image decoding for the viewer, on dedicated threads, most
+//! urgent first.
+//!
+//! After every navigation the viewer hands over the whole set of images it
+//! wants. Queued work that is no longer wanted is dropped, and a decode
+//! already running for it stops at its next checkpoint, so holding an arrow
+//! key doesn't leave a backlog of stale decodes in front of the current image.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use iced::futures::channel::oneshot;
+
+use crate::edit;
+use crate::thumbnail;
+
+const WORKERS: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DecodeKind {
+    /// Screen-sized stand-in, at most this many pixels on the long edge.
+    Preview(u32),
+    Full,
+    /// The finished image scaled to fit the screen, at most this many pixels
+    /// on the long edge, for the screensaver, which never zooms.
+    Fitted(u32),
+}
+
+/// Decode order; lower sorts first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Current,
+    /// This many images ahead in the direction of travel.
+    Ahead(usize),
+    /// This many images behind.
+    Behind(usize),
+}
+
+pub struct Request {
+    pub index: usize,
+    pub path: PathBuf,
+    pub kind: DecodeKind,
+    pub priority: Priority,
+}
+
+#[derive(Debug, Clone)]
+pub struct Decoded {
+    pub index: usize,
+    pub kind: DecodeKind,
+    pub rgba: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    /// How long the decode itself took, not counting time queued.
+    pub elapsed: Duration,
+}
+
+/// A full-size or fitted decode that failed, with the decoder's error message.
+#[derive(Debug, Clone)]
+pub struct DecodeFailed {
+    pub index: usize,
+    pub error: String,
+}
+
+pub type DecodeResult = Result<Decoded, DecodeFailed>;
+
+struct Job {
+    request: Request,
+    tx: oneshot::Sender<DecodeResult>,
+}
+
+struct Running {
+    index: usize,
+    kind: DecodeKind,
+    cancelled: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct Queue {
+    pending: Vec<Job>,
+    running: Vec<Running>,
+    /// Set when the scheduler is dropped; workers exit instead of waiting.
+    shutdown: bool,
+}
+
+type Shared = Arc<(Mutex<Queue>, Condvar)>;
+
+pub struct DecodeScheduler {
+    shared: Shared,
+}
+
+impl DecodeScheduler {
+    pub fn new() -> Self {
+        let shared: Shared = Arc::default();
+        for i in 0..WORKERS {
+            let shared = Arc::clone(&shared);
+            let spawned = std::thread::Builder::new()
+                .name(format!("looky-decode-{i}"))
+                .spawn(move || worker(shared));
+            if let Err(e) = spawned {
+                log::warn!("Failed to spawn decode worker: {}", e);
+            }
+        }
+        Self { shared }
+    }
+
+    /// Decodes waiting and running.
+    pub fn queue_lengths(&self) -> (usize, usize) {
+        let queue = self.shared.0.lock().unwrap();
+        (queue.pending.len(), queue.running.len())
+    }
+
+    /// Replace the set of wanted decodes. Anything queued or running that
+    /// isn't in `wanted` is cancelled (its receiver resolves to an error);
+    /// wanted work already queued takes its new priority. Returns receivers
+    /// only for requests that weren't already queued or running.
+    pub fn schedule(&self, wanted: Vec<Request>) -> Vec<oneshot::Receiver<DecodeResult>> {
+        let (lock, cond) = &*self.shared;
+        let mut queue = lock.lock().unwrap();
+        let is_wanted = |index: usize, kind: DecodeKind| {
+            wanted.iter().any(|r| r.index == index && r.kind == kind)
+        };
+
+        queue.pending.retain(|job| is_wanted(job.request.index, job.request.kind));
+        for running in &queue.running {
+            if !is_wanted(running.index, running.kind) {
+                running.cancelled.store(true, Ordering::Relaxed);
+            }
+        }
+
+        let mut receivers = Vec::new();
+        for request in wanted {
+            let same = |index: usize, kind: DecodeKind| index == request.index && kind == request.kind;
+            let in_flight = queue
+                .running
+                .iter()
+                .any(|r| same(r.index, r.kind) && !r.cancelled.load(Ordering::Relaxed));
+            if in_flight {
+                continue;
+            }
+            match queue.pending.iter_mut().find(|j| same(j.request.index, j.request.kind)) {
+                Some(job) => job.request.priority = request.priority,
+                None => {
+                    let (tx, rx) = oneshot::channel();
+                    queue.pending.push(Job { request, tx });
+                    receivers.push(rx);
+                }
+            }
+        }
+        cond.notify_all();
+        receivers
+    }
+}
+
+impl Drop for DecodeScheduler {
+    fn drop(&mut self) {
+        let (lock, cond) = &*self.shared;
+        let mut queue = lock.lock().unwrap();
+        queue.shutdown = true;
+        queue.pending.clear();
+        for running in &queue.running {
+            running.cancelled.store(true, Ordering::Relaxed);
+        }
+        cond.notify_all();
+    }
+}
+
+impl Default for DecodeScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn worker(shared: Shared) {
+    let (lock, cond) = &*shared;
+    loop {
+        let (job, cancelled) = {
+            let mut queue = lock.lock().unwrap();
+            while queue.pending.is_empty() && !queue.shutdown {
+                queue = cond.wait(queue).unwrap();
+            }
+            if queue.shutdown {
+                return;
+            }
+            let next = queue
+                .pending
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, j)| j.request.priority)
+                .map(|(i, _)| i)
+                .unwrap_or(0);
+            let job = queue.pending.remove(next);
+            let cancelled = Arc::new(AtomicBool::new(false));
+            queue.running.push(Running {
+                index: job.request.index,
+                kind: job.request.kind,
+                cancelled: Arc::clone(&cancelled),
+            });
+            (job, cancelled)
+        };
+
+        let Job { request, tx } = job;
+        log::debug!("decode: [{}] {:?} start", request.index, request.kind);
+        let started = Instant::now();
+        let result = decode(&request.path, request.kind, &cancelled);
+        let elapsed = started.elapsed();
+
+        lock.lock().unwrap().running.retain(|r| !Arc::ptr_eq(&r.cancelled, &cancelled));
+        if cancelled.load(Ordering::Relaxed) {
+            log::debug!("decode: [{}] {:?} cancelled", request.index, request.kind);
+            continue;
+        }
+        // Dropping the sender means there's nothing to show, e.g. no preview
+        // for a non-JPEG; a failed full decode reports why.
+        match result {
+            Ok(Some(rgba)) => {
+                let (width, height) = rgba.dimensions();
+                let _ = tx.send(Ok(Decoded {
+                    index: request.index,
+                    kind: request.kind,
+                    rgba: rgba.into_raw(),
+                    width,
+                    height,
+                    elapsed,
+                }));
+            }
+            Ok(None) => {}
+            Err(error) => {
+                let _ = tx.send(Err(DecodeFailed {
+                    index: request.index,
+                    error,
+                }));
+            }
+        }
+    }
+}
+
+/// Decode and orient an image, giving up early once `cancelled` is set.
+/// `Ok(None)` when cancelled or there's no preview to be had.
+fn decode(
+    path: &Path,
+    kind: DecodeKind,
+    cancelled: &AtomicBool,
+) -> Result<Option<image::RgbaImage>, String> {
+    let is_cancelled = || cancelled.load(Ordering::Relaxed);
+    if is_cancelled() {
+        return Ok(None);
+    }
+    match kind {
+        DecodeKind::Preview(max_size) => return Ok(thumbnail::decode_screen_sized(path, max_size)),
+        DecodeKind::Fitted(max_size) => return thumbnail::decode_fitted(path, max_size).map(Some),
+        DecodeKind::Full => {}
+    }
+    let img = image::open(path).map_err(|e| {
+        log::warn!("Failed to open {}: {}", path.display(), e);
+        e.to_string()
+    })?;
+    if is_cancelled() {
+        return Ok(None);
+    }
+    let upright = thumbnail::upright_rgba(img, thumbnail::read_orientation(path));
+    if is_cancelled() {
+        return Ok(None);
+    }
+    Ok(Some(edit::apply_for(path, image::DynamicImage::ImageRgba8(upright)).into_rgba8()))
+}
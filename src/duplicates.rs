@@ -1,13 +1,20 @@
 use std::collections::{HashMap, HashSet};
+use std::io::{Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
 
 use image_hasher::{HashAlg, HasherConfig};
 use rayon::prelude::*;
 use sha2::{Digest, Sha256};
 
+/// Bytes read from each end of a file to tell same-size files apart before
+/// hashing them in full.
+const SAMPLE_BYTES: u64 = 64 * 1024;
+
 #[derive(Debug, Clone)]
 pub struct ImageHashes {
-    pub content_hash: [u8; 32],
+    /// SHA-256 of the whole file. Only computed for files that could be an
+    /// exact copy of another, see [`exact_candidates`].
+    pub content_hash: Option<[u8; 32]>,
     pub perceptual_hash: Vec<u8>,
 }
 
@@ -23,15 +30,110 @@ pub struct DuplicateGroup {
     pub indices: Vec<usize>,
 }
 
-/// Compute SHA-256 and perceptual hash for a single image.
-pub fn compute_hashes(path: &Path) -> Option<ImageHashes> {
-    let file_bytes = std::fs::read(path).ok()?;
+impl DuplicateGroup {
+    /// Bytes freed by keeping only the largest file, with `size_of` giving
+    /// each image's file size. Images of unknown size count as empty.
+    pub fn reclaimable(&self, size_of: impl Fn(usize) -> Option<u64>) -> u64 {
+        let sizes: Vec<u64> = self.indices.iter().map(|&i| size_of(i).unwrap_or(0)).collect();
+        sizes.iter().sum::<u64>() - sizes.iter().max().copied().unwrap_or(0)
+    }
+}
+
+/// How the duplicates list orders its groups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GroupOrder {
+    /// By where the group's first photo is in the folder.
+    #[default]
+    Folder,
+    /// Most reclaimable space first.
+    SpaceFreed,
+    /// Groups with copies in the same folders together, by folder path.
+    Folders,
+}
+
+impl GroupOrder {
+    pub const ALL: [GroupOrder; 3] = [GroupOrder::Folder, GroupOrder::SpaceFreed, GroupOrder::Folders];
+
+    pub fn label(self) -> &'static str {
+        crate::i18n::tr(match self {
+            GroupOrder::Folder => "Folder order",
+            GroupOrder::SpaceFreed => "Space freed",
+            GroupOrder::Folders => "Where the copies are",
+        })
+    }
+
+    /// Name used in the settings file.
+    pub fn key(self) -> &'static str {
+        match self {
+            GroupOrder::Folder => "folder",
+            GroupOrder::SpaceFreed => "space",
+            GroupOrder::Folders => "folders",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|o| o.key() == key)
+    }
+}
+
+impl std::fmt::Display for GroupOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// Identical files spread over the same set of folders, from the catalog
+/// rather than one folder's scan.
+#[derive(Debug, Clone)]
+pub struct FolderOverlap {
+    /// The folders holding the copies, sorted.
+    pub folders: Vec<PathBuf>,
+    /// Each photo's copies, sorted by path.
+    pub copies: Vec<Vec<PathBuf>>,
+    /// Bytes freed by keeping one copy of each photo.
+    pub reclaimable: u64,
+}
+
+/// Group sets of identical files, given as `(file size, paths)`, by the
+/// folders they're spread over, most space to free first.
+pub fn group_by_folders(sets: Vec<(u64, Vec<PathBuf>)>) -> Vec<FolderOverlap> {
+    let mut by_folders: HashMap<Vec<PathBuf>, FolderOverlap> = HashMap::new();
+    for (size, mut paths) in sets {
+        paths.sort();
+        let mut folders: Vec<PathBuf> = paths
+            .iter()
+            .map(|p| p.parent().unwrap_or(Path::new("")).to_path_buf())
+            .collect();
+        folders.sort();
+        folders.dedup();
+        let overlap = by_folders.entry(folders.clone()).or_insert_with(|| FolderOverlap {
+            folders,
+            copies: Vec::new(),
+            reclaimable: 0,
+        });
+        overlap.reclaimable += size * (paths.len() as u64 - 1);
+        overlap.copies.push(paths);
+    }
+    let mut overlaps: Vec<FolderOverlap> = by_folders.into_values().collect();
+    for overlap in &mut overlaps {
+        overlap.copies.sort();
+    }
+    overlaps.sort_by(|a, b| b.reclaimable.cmp(&a.reclaimable).then_with(|| a.folders.cmp(&b.folders)));
+    overlaps
+}
 
-    // SHA-256 content hash
-    let content_hash: [u8; 32] = Sha256::digest(&file_bytes).into();
+/// Compute the perceptual hash for a single image, and its SHA-256 if
+/// `with_content` is set.
+pub fn compute_hashes(path: &Path, with_content: bool) -> Option<ImageHashes> {
+    let (content_hash, img) = if with_content {
+        let file_bytes = std::fs::read(path).ok()?;
+        let content_hash: [u8; 32] = Sha256::digest(&file_bytes).into();
+        (Some(content_hash), image::load_from_memory(&file_bytes).ok()?)
+    } else {
+        (None, image::open(path).ok()?)
+    };
 
     // Perceptual hash (dHash 8x8 gradient)
-    let img = image::load_from_memory(&file_bytes).ok()?;
     let hasher = HasherConfig::new()
         .hash_alg(HashAlg::Gradient)
         .hash_size(8, 8)
@@ -45,14 +147,77 @@ pub fn compute_hashes(path: &Path) -> Option<ImageHashes> {
     })
 }
 
-/// Compute hashes for a batch of (index, path) pairs in parallel.
-pub fn compute_hashes_batch(items: &[(usize, PathBuf)]) -> Vec<(usize, Option<ImageHashes>)> {
+/// Compute hashes for a batch of (index, path, with_content) in parallel.
+pub fn compute_hashes_batch(items: &[(usize, PathBuf, bool)]) -> Vec<(usize, Option<ImageHashes>)> {
     items
         .par_iter()
-        .map(|(idx, path)| (*idx, compute_hashes(path)))
+        .map(|(idx, path, with_content)| (*idx, compute_hashes(path, *with_content)))
         .collect()
 }
 
+/// SHA-256 of each of `items`' whole file, `None` where it can't be read.
+pub fn content_hash_batch(items: &[(usize, PathBuf)]) -> Vec<(usize, Option<[u8; 32]>)> {
+    items
+        .par_iter()
+        .map(|(idx, path)| {
+            let hash = std::fs::read(path).ok().map(|bytes| Sha256::digest(&bytes).into());
+            (*idx, hash)
+        })
+        .collect()
+}
+
+/// The `items` that could be exact copies of one another: files sharing a
+/// size whose first and last 64 KB match too. Only these need a full
+/// content hash; everything else is unique without reading it all.
+pub fn exact_candidates(items: &[(usize, PathBuf)]) -> HashSet<usize> {
+    let sizes: Vec<(usize, &Path, u64)> = items
+        .par_iter()
+        .filter_map(|(idx, path)| Some((*idx, path.as_path(), std::fs::metadata(path).ok()?.len())))
+        .collect();
+    let mut by_size: HashMap<u64, Vec<(usize, &Path)>> = HashMap::new();
+    for (idx, path, size) in sizes {
+        by_size.entry(size).or_default().push((idx, path));
+    }
+
+    let same_size: Vec<(usize, &Path, u64)> = by_size
+        .into_iter()
+        .filter(|(_, files)| files.len() > 1)
+        .flat_map(|(size, files)| files.into_iter().map(move |(idx, path)| (idx, path, size)))
+        .collect();
+    let samples: Vec<((u64, [u8; 32]), usize)> = same_size
+        .par_iter()
+        .filter_map(|&(idx, path, size)| Some(((size, sample_hash(path, size)?), idx)))
+        .collect();
+    let mut by_sample: HashMap<(u64, [u8; 32]), Vec<usize>> = HashMap::new();
+    for (key, idx) in samples {
+        by_sample.entry(key).or_default().push(idx);
+    }
+
+    by_sample
+        .into_values()
+        .filter(|indices| indices.len() > 1)
+        .flatten()
+        .collect()
+}
+
+/// SHA-256 of the first and last `SAMPLE_BYTES` of a file, or of all of it
+/// if it's no bigger than that.
+fn sample_hash(path: &Path, size: u64) -> Option<[u8; 32]> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    if size <= SAMPLE_BYTES * 2 {
+        std::io::copy(&mut file, &mut hasher).ok()?;
+    } else {
+        let mut buf = vec![0u8; SAMPLE_BYTES as usize];
+        file.read_exact(&mut buf).ok()?;
+        hasher.update(&buf);
+        file.seek(SeekFrom::End(-(SAMPLE_BYTES as i64))).ok()?;
+        file.read_exact(&mut buf).ok()?;
+        hasher.update(&buf);
+    }
+    Some(hasher.finalize().into())
+}
+
 /// Find duplicate groups from a set of hashes.
 /// `threshold` is the max hamming distance for visual matches.
 pub fn find_duplicates(hashes: &[(usize, ImageHashes)], threshold: u32) -> Vec<DuplicateGroup> {
@@ -61,7 +226,9 @@ pub fn find_duplicates(hashes: &[(usize, ImageHashes)], threshold: u32) -> Vec<D
     // Phase 1: Exact matches by SHA-256
     let mut sha_groups: HashMap<[u8; 32], Vec<usize>> = HashMap::new();
     for (idx, h) in hashes {
-        sha_groups.entry(h.content_hash).or_default().push(*idx);
+        if let Some(content_hash) = h.content_hash {
+            sha_groups.entry(content_hash).or_default().push(*idx);
+        }
     }
 
     let mut exact_matched: HashSet<usize> = HashSet::new();
@@ -156,6 +323,49 @@ pub fn find_duplicates(hashes: &[(usize, ImageHashes)], threshold: u32) -> Vec<D
     groups
 }
 
+/// Whether `indices` are all one capture saved in several formats, such as a
+/// RAW or HEIC file and the JPEG the camera wrote beside it: same folder, same
+/// name apart from the extension, and no two different capture times.
+pub fn is_capture_set(
+    indices: &[usize],
+    path_of: impl Fn(usize) -> Option<PathBuf>,
+    taken_of: impl Fn(usize) -> Option<String>,
+) -> bool {
+    let capture = |i: usize| {
+        let path = path_of(i)?;
+        let stem = path.file_stem()?.to_ascii_lowercase();
+        Some((path.parent().map(Path::to_path_buf), stem))
+    };
+    let Some((&first, rest)) = indices.split_first() else {
+        return false;
+    };
+    let Some(first) = capture(first) else {
+        return false;
+    };
+    if !rest.iter().all(|&i| capture(i).as_ref() == Some(&first)) {
+        return false;
+    }
+    let mut taken = indices.iter().filter_map(|&i| taken_of(i));
+    let first_taken = taken.next();
+    taken.all(|t| Some(&t) == first_taken.as_ref())
+}
+
+/// Drop visual matches that are just one capture in several formats, see
+/// [`is_capture_set`]. Exact copies are kept, whatever their names.
+pub fn without_capture_sets(
+    groups: Vec<DuplicateGroup>,
+    path_of: impl Fn(usize) -> Option<PathBuf>,
+    taken_of: impl Fn(usize) -> Option<String>,
+) -> Vec<DuplicateGroup> {
+    groups
+        .into_iter()
+        .filter(|g| {
+            matches!(g.match_kind, MatchKind::Exact)
+                || !is_capture_set(&g.indices, &path_of, &taken_of)
+        })
+        .collect()
+}
+
 /// Get the set of all indices that appear in any duplicate group, for O(1) badge lookup.
 pub fn duplicate_indices(groups: &[DuplicateGroup]) -> HashSet<usize> {
     let mut set = HashSet::new();
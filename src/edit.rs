@@ -0,0 +1,238 @@
+//! Non-destructive crop and straighten. Edits live in the catalog and are
+//! applied wherever a photo is decoded for display or export; the file
+//! itself is never touched.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, RwLock};
+
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+use rayon::prelude::*;
+
+/// Straightening range either way, in degrees.
+pub const MAX_ANGLE: f32 = 45.0;
+/// Smallest crop side, as a fraction of the photo.
+const MIN_CROP: f32 = 0.05;
+
+/// A crop and straighten, in the coordinates of the photo turned upright.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhotoEdit {
+    /// Left, top, width and height of the crop, as fractions of the largest
+    /// rectangle the straightened photo fills.
+    pub crop: [f32; 4],
+    /// Degrees clockwise.
+    pub angle: f32,
+}
+
+impl Default for PhotoEdit {
+    fn default() -> Self {
+        Self {
+            crop: [0.0, 0.0, 1.0, 1.0],
+            angle: 0.0,
+        }
+    }
+}
+
+impl PhotoEdit {
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The crop resized to `width` × `height` around its centre, kept inside
+    /// the photo.
+    pub fn with_crop_size(self, width: f32, height: f32) -> Self {
+        let [x, y, w, h] = self.crop;
+        let (width, height) = (width.clamp(MIN_CROP, 1.0), height.clamp(MIN_CROP, 1.0));
+        let left = (x + w / 2.0 - width / 2.0).clamp(0.0, 1.0 - width);
+        let top = (y + h / 2.0 - height / 2.0).clamp(0.0, 1.0 - height);
+        Self {
+            crop: [left, top, width, height],
+            ..self
+        }
+    }
+
+    /// The crop moved by `dx`, `dy`, kept inside the photo.
+    pub fn moved(self, dx: f32, dy: f32) -> Self {
+        let [x, y, w, h] = self.crop;
+        Self {
+            crop: [(x + dx).clamp(0.0, 1.0 - w), (y + dy).clamp(0.0, 1.0 - h), w, h],
+            ..self
+        }
+    }
+
+    /// The edited photo's long edge as a fraction of the long edge of the
+    /// upright `width` × `height` photo it's cut from.
+    pub fn kept_fraction(&self, width: u32, height: u32) -> f32 {
+        let (fw, fh) = (width as f32, height as f32);
+        let k = inner_scale(self.angle, fw, fh);
+        let [_, _, w, h] = self.crop;
+        (w * k * fw).max(h * k * fh) / fw.max(fh)
+    }
+
+    /// Size of what [`apply`](Self::apply) makes of an upright `width` ×
+    /// `height` photo.
+    pub fn output_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let (fw, fh) = (width as f32, height as f32);
+        let [x, y, w, h] = self.crop;
+        if self.angle == 0.0 {
+            let left = (x * fw).round() as u32;
+            let top = (y * fh).round() as u32;
+            let crop_w = ((w * fw).round() as u32).clamp(1, width - left.min(width - 1));
+            let crop_h = ((h * fh).round() as u32).clamp(1, height - top.min(height - 1));
+            return (crop_w, crop_h);
+        }
+        let k = inner_scale(self.angle, fw, fh);
+        ((w * k * fw).round().max(1.0) as u32, (h * k * fh).round().max(1.0) as u32)
+    }
+
+    /// Straighten then crop `img`, which must already be upright.
+    pub fn apply(&self, img: DynamicImage) -> DynamicImage {
+        let (width, height) = img.dimensions();
+        let (fw, fh) = (width as f32, height as f32);
+        let [x, y, w, h] = self.crop;
+        if self.angle == 0.0 {
+            let left = (x * fw).round() as u32;
+            let top = (y * fh).round() as u32;
+            let crop_w = ((w * fw).round() as u32).clamp(1, width - left.min(width - 1));
+            let crop_h = ((h * fh).round() as u32).clamp(1, height - top.min(height - 1));
+            return img.crop_imm(left, top, crop_w, crop_h);
+        }
+
+        let src = img.to_rgba8();
+        let k = inner_scale(self.angle, fw, fh);
+        let (cx, cy) = (fw / 2.0, fh / 2.0);
+        let left = cx - k * fw / 2.0 + x * k * fw;
+        let top = cy - k * fh / 2.0 + y * k * fh;
+        let out_w = (w * k * fw).round().max(1.0) as u32;
+        let out_h = (h * k * fh).round().max(1.0) as u32;
+        let (step_x, step_y) = (w * k * fw / out_w as f32, h * k * fh / out_h as f32);
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+
+        let mut out = RgbaImage::new(out_w, out_h);
+        out.par_chunks_mut(out_w as usize * 4).enumerate().for_each(|(row, pixels)| {
+            let dy = top + (row as f32 + 0.5) * step_y - cy;
+            for (col, pixel) in pixels.chunks_exact_mut(4).enumerate() {
+                let dx = left + (col as f32 + 0.5) * step_x - cx;
+                // Undo the clockwise turn to find where this came from
+                let sx = cx + dx * cos + dy * sin;
+                let sy = cy - dx * sin + dy * cos;
+                pixel.copy_from_slice(&bilinear(&src, sx - 0.5, sy - 0.5).0);
+            }
+        });
+        DynamicImage::ImageRgba8(out)
+    }
+}
+
+/// How much of a `width` × `height` photo turned `angle` degrees still
+/// fills a rectangle of the same shape around its centre.
+pub fn inner_scale(angle: f32, width: f32, height: f32) -> f32 {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let (sin, cos) = (sin.abs(), cos.abs());
+    (width / (width * cos + height * sin)).min(height / (width * sin + height * cos))
+}
+
+fn bilinear(img: &RgbaImage, x: f32, y: f32) -> Rgba<u8> {
+    let max_x = (img.width() - 1) as f32;
+    let max_y = (img.height() - 1) as f32;
+    let (x, y) = (x.clamp(0.0, max_x), y.clamp(0.0, max_y));
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let (x0, y0) = (x0 as u32, y0 as u32);
+    let (x1, y1) = ((x0 + 1).min(max_x as u32), (y0 + 1).min(max_y as u32));
+    let (a, b, c, d) = (
+        img.get_pixel(x0, y0),
+        img.get_pixel(x1, y0),
+        img.get_pixel(x0, y1),
+        img.get_pixel(x1, y1),
+    );
+    let mut out = [0u8; 4];
+    for (i, channel) in out.iter_mut().enumerate() {
+        let top = a[i] as f32 * (1.0 - fx) + b[i] as f32 * fx;
+        let bottom = c[i] as f32 * (1.0 - fx) + d[i] as f32 * fx;
+        *channel = (top * (1.0 - fy) + bottom * fy).round() as u8;
+    }
+    Rgba(out)
+}
+
+/// Shapes the crop can be held to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CropAspect {
+    #[default]
+    Free,
+    /// The photo's own shape.
+    Original,
+    /// Width to height.
+    Ratio(u32, u32),
+}
+
+impl CropAspect {
+    pub const ALL: [CropAspect; 6] = [
+        CropAspect::Free,
+        CropAspect::Original,
+        CropAspect::Ratio(1, 1),
+        CropAspect::Ratio(4, 5),
+        CropAspect::Ratio(3, 2),
+        CropAspect::Ratio(16, 9),
+    ];
+
+    pub fn label(self) -> String {
+        match self {
+            CropAspect::Free => crate::i18n::tr("Free").to_string(),
+            CropAspect::Original => crate::i18n::tr("Original").to_string(),
+            CropAspect::Ratio(w, h) => format!("{w}:{h}"),
+        }
+    }
+
+    /// Width and height of the crop, as fractions of the photo, for `size`
+    /// of the largest one of this shape. `None` for a free crop.
+    pub fn crop_size(self, size: f32, photo_width: u32, photo_height: u32) -> Option<(f32, f32)> {
+        let ratio = match self {
+            CropAspect::Free => return None,
+            CropAspect::Original => return Some((size, size)),
+            CropAspect::Ratio(w, h) => w as f32 / h as f32,
+        };
+        let photo_ratio = photo_width as f32 / photo_height.max(1) as f32;
+        let (w, h) = if ratio >= photo_ratio {
+            (1.0, photo_ratio / ratio)
+        } else {
+            (ratio / photo_ratio, 1.0)
+        };
+        Some((w * size, h * size))
+    }
+}
+
+/// Edits by file, shared by every window and the share server.
+static EDITS: LazyLock<RwLock<HashMap<PathBuf, PhotoEdit>>> = LazyLock::new(Default::default);
+
+/// Show `path` with `edit`, or as it is for `None`.
+pub fn set_edit(path: &Path, edit: Option<PhotoEdit>) {
+    let mut edits = EDITS.write().unwrap();
+    match edit {
+        Some(edit) => edits.insert(path.to_path_buf(), edit),
+        None => edits.remove(path),
+    };
+}
+
+pub fn edit_for(path: &Path) -> Option<PhotoEdit> {
+    EDITS.read().unwrap().get(path).copied()
+}
+
+/// `img`, already upright, with `path`'s edit applied.
+pub fn apply_for(path: &Path, img: DynamicImage) -> DynamicImage {
+    match edit_for(path) {
+        Some(edit) => edit.apply(img),
+        None => img,
+    }
+}
+
+/// Write `path` turned upright with `edit` applied to `dest` as a JPEG.
+pub fn save_copy(path: &Path, edit: PhotoEdit, dest: &Path) -> Result<(), String> {
+    let img = image::open(path).map_err(|e| e.to_string())?;
+    let orientation = crate::thumbnail::read_orientation(path);
+    let img = edit.apply(crate::thumbnail::apply_orientation(img, orientation));
+    let file = std::fs::File::create(dest).map_err(|e| e.to_string())?;
+    let mut writer = std::io::BufWriter::new(file);
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut writer, 92)
+        .encode_image(&img.to_rgb8())
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,81 @@
+//! Writing photos out as a static web gallery that can be published
+//! anywhere: thumbnails, display-size images and an `index.html` that looks
+//! and behaves like the shared gallery.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+
+use crate::i18n::trn;
+use crate::server::http::{self, html_escape};
+use crate::thumbnail;
+
+const THUMBS_DIR: &str = "thumbs";
+const IMAGES_DIR: &str = "images";
+
+/// File name of the `n`th photo in both the thumbnail and image folders.
+fn file_name(n: usize) -> String {
+    format!("{n:05}.jpg")
+}
+
+/// Write the thumbnail and display-size image of each `(n, path)` into
+/// `dest`. Returns the photos written, as their number and original name.
+pub fn export_batch(dest: &Path, items: &[(usize, PathBuf)]) -> Vec<(usize, String)> {
+    items
+        .par_iter()
+        .filter_map(|(n, path)| match export_photo(dest, *n, path) {
+            Ok(()) => {
+                let name = path.file_name()?.to_string_lossy().into_owned();
+                Some((*n, name))
+            }
+            Err(e) => {
+                log::warn!("Failed to export {}: {}", path.display(), e);
+                None
+            }
+        })
+        .collect()
+}
+
+fn export_photo(dest: &Path, n: usize, path: &Path) -> io::Result<()> {
+    let display = thumbnail::display_jpeg_bytes(path, http::DISPLAY_DEFAULT_SIZE, http::DISPLAY_QUALITY)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "couldn't decode"))?;
+    let thumb = thumbnail::thumbnail_jpeg_bytes(path, http::THUMB_MAX_SIZE, http::THUMB_QUALITY);
+    std::fs::create_dir_all(dest.join(THUMBS_DIR))?;
+    std::fs::create_dir_all(dest.join(IMAGES_DIR))?;
+    std::fs::write(dest.join(IMAGES_DIR).join(file_name(n)), display)?;
+    std::fs::write(dest.join(THUMBS_DIR).join(file_name(n)), thumb)
+}
+
+/// Write `index.html` listing `photos`, in order, titled `title`.
+pub fn write_index(dest: &Path, title: &str, photos: &[(usize, String)]) -> io::Result<()> {
+    let mut thumbs_html = String::new();
+    for (n, name) in photos {
+        let name = html_escape(name);
+        let file = file_name(*n);
+        thumbs_html.push_str(&format!(
+            r#"<a href="{IMAGES_DIR}/{file}" title="{name}"><img src="{THUMBS_DIR}/{file}" loading="lazy" alt="{name}"></a>"#,
+        ));
+    }
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html><head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title}</title>
+{style}
+</head><body>
+<div class="header">
+  <h1>{title}</h1>
+  <span class="count">{count}</span>
+</div>
+<div class="grid" id="grid">{thumbs_html}</div>
+{lightbox}
+</body></html>"#,
+        title = html_escape(title),
+        count = trn(photos.len(), "{} photo", "{} photos"),
+        style = http::GALLERY_STYLE,
+        lightbox = http::LIGHTBOX_SCRIPT,
+    );
+    std::fs::write(dest.join("index.html"), html)
+}
@@ -0,0 +1,153 @@
+//! Copying and moving photos between folders, shared by imports, the trash
+//! and batch actions on a grid selection.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::catalog::TrashedPhoto;
+
+/// What a batch transfer does with each file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transfer {
+    Copy,
+    Move,
+}
+
+/// `path`'s name in `dir`, numbered `name-1.ext`, `name-2.ext`... if a file
+/// already has it.
+pub fn unique_target(dir: &Path, path: &Path) -> PathBuf {
+    let mut target = dir.join(path.file_name().unwrap_or_default());
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    let mut n = 1;
+    while target.exists() {
+        target = dir.join(format!("{stem}-{n}{ext}"));
+        n += 1;
+    }
+    target
+}
+
+/// Rename, or copy and delete when `to` is on another drive.
+///
+/// The copy lands under a hidden name beside `to` and is renamed over it once
+/// complete, so a failed copy never touches a file already at `to`. If `from`
+/// can't be removed afterwards both copies are kept and the error returned.
+pub fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    match std::fs::rename(from, to) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {}
+        result => return result,
+    }
+    let dir = to.parent().unwrap_or(Path::new("."));
+    let tmp = dir.join(format!(".{}.part", uuid::Uuid::new_v4()));
+    let copied = std::fs::copy(from, &tmp).and_then(|_| std::fs::rename(&tmp, to));
+    if let Err(e) = copied {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e);
+    }
+    std::fs::remove_file(from)
+}
+
+/// What to do when a file's name is already taken in the destination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnConflict {
+    Skip,
+    Overwrite,
+    /// Keep both, numbering the new one as [`unique_target`] does.
+    Rename,
+}
+
+/// How transferring one file went.
+#[derive(Debug, Clone)]
+pub enum Outcome {
+    /// The file is now at this path.
+    Done(PathBuf),
+    Skipped,
+    /// Its name is taken in the destination and no choice was given.
+    Conflict,
+    /// Moved into Looky's trash, see [`crate::trash`].
+    Trashed(TrashedPhoto),
+    Failed(String),
+}
+
+/// Copy or move each of `items` into `dest`. Names already taken there are
+/// handled per `on_conflict`, or reported as conflicts for `None`. Once
+/// `cancelled` is set the rest are skipped.
+pub fn transfer(
+    items: &[(usize, PathBuf)],
+    dest: &Path,
+    op: Transfer,
+    on_conflict: Option<OnConflict>,
+    cancelled: &AtomicBool,
+) -> Vec<(usize, Outcome)> {
+    items
+        .iter()
+        .map(|(index, path)| {
+            if cancelled.load(Ordering::Relaxed) {
+                return (*index, Outcome::Skipped);
+            }
+            (*index, transfer_one(path, dest, op, on_conflict))
+        })
+        .collect()
+}
+
+/// Move each of `items` into the trash at `trash_dir`, skipping the rest
+/// once `cancelled` is set.
+pub fn trash(items: &[(usize, PathBuf)], trash_dir: &Path, cancelled: &AtomicBool) -> Vec<(usize, Outcome)> {
+    items
+        .iter()
+        .map(|(index, path)| {
+            if cancelled.load(Ordering::Relaxed) {
+                return (*index, Outcome::Skipped);
+            }
+            let outcome = match crate::trash::move_to_trash(trash_dir, path) {
+                Ok(photo) => Outcome::Trashed(photo),
+                Err(e) => {
+                    log::warn!("Failed to move {} to the trash: {}", path.display(), e);
+                    Outcome::Failed(e.to_string())
+                }
+            };
+            (*index, outcome)
+        })
+        .collect()
+}
+
+fn transfer_one(path: &Path, dest: &Path, op: Transfer, on_conflict: Option<OnConflict>) -> Outcome {
+    let Some(name) = path.file_name() else {
+        return Outcome::Failed("not a file".to_string());
+    };
+    let mut target = dest.join(name);
+    if target.exists() {
+        // Onto itself: a copy gets a new name and a move has nothing to do
+        let same_file = same_file(path, &target);
+        match (on_conflict, same_file, op) {
+            (_, true, Transfer::Move) | (Some(OnConflict::Skip), ..) => return Outcome::Skipped,
+            (_, true, Transfer::Copy) | (Some(OnConflict::Rename), ..) => {
+                target = unique_target(dest, path)
+            }
+            (None, ..) => return Outcome::Conflict,
+            (Some(OnConflict::Overwrite), ..) => {}
+        }
+    }
+    let result = match op {
+        Transfer::Copy => std::fs::copy(path, &target).map(|_| ()),
+        Transfer::Move => move_file(path, &target),
+    };
+    match result {
+        Ok(()) => Outcome::Done(target),
+        Err(e) => {
+            log::warn!("Failed to transfer {} to {}: {}", path.display(), dest.display(), e);
+            Outcome::Failed(e.to_string())
+        }
+    }
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
@@ -0,0 +1,138 @@
+//! Copies, moves, exports and trashing on dedicated threads, so a long job
+//! neither blocks the UI nor holds up the executor other work runs on.
+//!
+//! Jobs hand over their work a batch at a time, each tagged with the job's
+//! id. Batches run first come, first served, so a second job starts as soon
+//! as a worker is free rather than waiting for the first to finish.
+//! Cancelling a job drops its queued batches and tells the one running to
+//! stop at its next file.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+
+use iced::futures::channel::oneshot;
+
+const WORKERS: usize = 2;
+
+/// Identifies a job's batches in the queue.
+pub type JobId = u64;
+
+struct Work {
+    job: JobId,
+    cancelled: Arc<AtomicBool>,
+    run: Box<dyn FnOnce(&AtomicBool) + Send>,
+}
+
+#[derive(Default)]
+struct Queue {
+    pending: Vec<Work>,
+    /// Cancel flags of the jobs with a batch running.
+    running: Vec<(JobId, Arc<AtomicBool>)>,
+    /// Set when the queue is dropped; workers exit once nothing is pending.
+    shutdown: bool,
+}
+
+type Shared = Arc<(Mutex<Queue>, Condvar)>;
+
+pub struct FileQueue {
+    shared: Shared,
+    next_id: JobId,
+}
+
+impl FileQueue {
+    pub fn new() -> Self {
+        let shared: Shared = Arc::default();
+        for i in 0..WORKERS {
+            let shared = Arc::clone(&shared);
+            let spawned = std::thread::Builder::new()
+                .name(format!("looky-files-{i}"))
+                .spawn(move || worker(shared));
+            if let Err(e) = spawned {
+                log::warn!("Failed to spawn file worker: {}", e);
+            }
+        }
+        Self { shared, next_id: 0 }
+    }
+
+    /// A fresh id for a new job.
+    pub fn new_job(&mut self) -> JobId {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    /// Queue a batch for `job`. `run` gets a flag that's set once the job is
+    /// cancelled and should stop between files when it is. The receiver
+    /// resolves to `run`'s result, or an error if the job was cancelled
+    /// before the batch started.
+    pub fn submit<T: Send + 'static>(
+        &self,
+        job: JobId,
+        run: impl FnOnce(&AtomicBool) -> T + Send + 'static,
+    ) -> oneshot::Receiver<T> {
+        let (tx, rx) = oneshot::channel();
+        let (lock, cond) = &*self.shared;
+        lock.lock().unwrap().pending.push(Work {
+            job,
+            cancelled: Arc::new(AtomicBool::new(false)),
+            run: Box::new(move |cancelled| {
+                let _ = tx.send(run(cancelled));
+            }),
+        });
+        cond.notify_one();
+        rx
+    }
+
+    /// Batches waiting and running, across all jobs.
+    pub fn queue_lengths(&self) -> (usize, usize) {
+        let queue = self.shared.0.lock().unwrap();
+        (queue.pending.len(), queue.running.len())
+    }
+
+    /// Drop `job`'s queued batches and stop the one running.
+    pub fn cancel(&self, job: JobId) {
+        let mut queue = self.shared.0.lock().unwrap();
+        queue.pending.retain(|w| w.job != job);
+        for (running, cancelled) in &queue.running {
+            if *running == job {
+                cancelled.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+impl Drop for FileQueue {
+    fn drop(&mut self) {
+        let (lock, cond) = &*self.shared;
+        lock.lock().unwrap().shutdown = true;
+        cond.notify_all();
+    }
+}
+
+impl Default for FileQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn worker(shared: Shared) {
+    let (lock, cond) = &*shared;
+    loop {
+        let work = {
+            let mut queue = lock.lock().unwrap();
+            while queue.pending.is_empty() && !queue.shutdown {
+                queue = cond.wait(queue).unwrap();
+            }
+            if queue.pending.is_empty() {
+                return;
+            }
+            let work = queue.pending.remove(0);
+            queue.running.push((work.job, Arc::clone(&work.cancelled)));
+            work
+        };
+
+        let Work { job, cancelled, run } = work;
+        log::debug!("files: job {} batch start", job);
+        run(&cancelled);
+        lock.lock().unwrap().running.retain(|(_, c)| !Arc::ptr_eq(c, &cancelled));
+    }
+}
@@ -0,0 +1,207 @@
+//! Narrowing the grid to part of a scanned folder.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::catalog::Flag;
+use crate::metadata::PhotoInfo;
+
+/// How far from 1:1 a photo can be and still count as square.
+const SQUARE_TOLERANCE: f32 = 0.03;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    Landscape,
+    Portrait,
+    Square,
+}
+
+impl Orientation {
+    pub const ALL: [Orientation; 3] = [
+        Orientation::Landscape,
+        Orientation::Portrait,
+        Orientation::Square,
+    ];
+
+    pub fn label(self) -> &'static str {
+        crate::i18n::tr(match self {
+            Orientation::Landscape => "Landscape",
+            Orientation::Portrait => "Portrait",
+            Orientation::Square => "Square",
+        })
+    }
+
+    /// The orientation of a photo `aspect` (width ÷ height) wide.
+    pub fn of(aspect: f32) -> Self {
+        if (aspect - 1.0).abs() <= SQUARE_TOLERANCE {
+            Orientation::Square
+        } else if aspect > 1.0 {
+            Orientation::Landscape
+        } else {
+            Orientation::Portrait
+        }
+    }
+}
+
+impl std::fmt::Display for Orientation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// An aspect ratio typed as "2:1", "16/9", "3x2" or "1.5", as width ÷ height.
+pub fn parse_aspect(input: &str) -> Option<f32> {
+    let input = input.trim();
+    let ratio = match input.split_once([':', '/', 'x', '×']) {
+        Some((w, h)) => w.trim().parse::<f32>().ok()? / h.trim().parse::<f32>().ok()?,
+        None => input.parse().ok()?,
+    };
+    (ratio.is_finite() && ratio > 0.0).then_some(ratio)
+}
+
+/// What the grid shows out of the folder's images. The default shows all of them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GridFilter {
+    /// Only images under this subfolder of the open folder.
+    pub subfolder: Option<PathBuf>,
+    /// Only photos from this camera, as named by [`PhotoInfo::camera`].
+    pub camera: Option<String>,
+    pub lens: Option<String>,
+    pub orientation: Option<Orientation>,
+    /// Limits on width ÷ height, e.g. a minimum of 2 for panoramas.
+    pub min_aspect: Option<f32>,
+    pub max_aspect: Option<f32>,
+    /// Lowercase text to look for in file names and indexed info.
+    pub search: Option<String>,
+    /// Only photos not already in the library. The app applies this, as
+    /// only it knows which those are.
+    pub new_only: bool,
+    /// Only photos flagged this way. Also applied by the app, which holds
+    /// the ratings.
+    pub flag: Option<Flag>,
+}
+
+impl GridFilter {
+    pub fn is_active(&self) -> bool {
+        self.subfolder.is_some() || self.new_only || self.flag.is_some() || self.uses_info()
+    }
+
+    /// Whether anything besides the subfolder is filtered on, i.e. the
+    /// result depends on indexed info.
+    pub fn uses_info(&self) -> bool {
+        self.camera.is_some()
+            || self.lens.is_some()
+            || self.orientation.is_some()
+            || self.min_aspect.is_some()
+            || self.max_aspect.is_some()
+            || self.search.is_some()
+    }
+
+    pub fn in_subfolder(&self, path: &Path) -> bool {
+        self.subfolder.as_ref().is_none_or(|dir| path.starts_with(dir))
+    }
+
+    /// Photos not indexed yet only pass when nothing needs their info.
+    pub fn matches(&self, path: &Path, info: Option<&PhotoInfo>) -> bool {
+        if !self.in_subfolder(path) {
+            return false;
+        }
+        let camera_ok = self
+            .camera
+            .as_ref()
+            .is_none_or(|camera| info.and_then(PhotoInfo::camera).as_ref() == Some(camera));
+        let lens_ok = self
+            .lens
+            .as_ref()
+            .is_none_or(|lens| info.and_then(|i| i.lens_model.as_ref()) == Some(lens));
+        let aspect = info.and_then(PhotoInfo::aspect_ratio);
+        let shape_ok = match (self.orientation, self.min_aspect, self.max_aspect) {
+            (None, None, None) => true,
+            (orientation, min, max) => aspect.is_some_and(|aspect| {
+                orientation.is_none_or(|o| Orientation::of(aspect) == o)
+                    && min.is_none_or(|min| aspect >= min)
+                    && max.is_none_or(|max| aspect <= max)
+            }),
+        };
+        let search_ok = self
+            .search
+            .as_ref()
+            .is_none_or(|query| matches_search(path, info, query));
+        camera_ok && lens_ok && shape_ok && search_ok
+    }
+
+    /// Indices of the `paths` this filter lets through, in order.
+    pub fn apply(&self, paths: &[PathBuf], infos: &HashMap<PathBuf, PhotoInfo>) -> Vec<usize> {
+        paths
+            .iter()
+            .enumerate()
+            .filter(|(_, path)| self.matches(path, infos.get(*path)))
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// Whether `query` (lowercase) is in the file name or, once the photo is
+/// indexed, its camera, lens, description or keywords.
+fn matches_search(path: &Path, info: Option<&PhotoInfo>, query: &str) -> bool {
+    let has = |text: &str| text.to_lowercase().contains(query);
+    if path.file_name().is_some_and(|name| has(&name.to_string_lossy())) {
+        return true;
+    }
+    info.is_some_and(|info| {
+        info.camera().is_some_and(|camera| has(&camera))
+            || info.lens_model.as_deref().is_some_and(has)
+            || info.description.as_deref().is_some_and(has)
+            || info.keywords.iter().any(|keyword| has(keyword))
+    })
+}
+
+/// The cameras and lenses seen in a folder, for the filter dropdowns, and
+/// whether any photo's size is known so it can be filtered by shape.
+#[derive(Debug, Clone, Default)]
+pub struct FilterChoices {
+    pub cameras: BTreeSet<String>,
+    pub lenses: BTreeSet<String>,
+    pub has_dimensions: bool,
+}
+
+impl FilterChoices {
+    pub fn add(&mut self, info: &PhotoInfo) {
+        if let Some(camera) = info.camera() {
+            self.cameras.insert(camera);
+        }
+        if let Some(lens) = &info.lens_model {
+            self.lenses.insert(lens.clone());
+        }
+        self.has_dimensions |= info.dimensions.is_some();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cameras.is_empty() && self.lenses.is_empty() && !self.has_dimensions
+    }
+}
+
+/// The folders directly inside `dir` that hold any of `paths`, at any depth.
+pub fn child_folders(paths: &[PathBuf], dir: &Path) -> Vec<PathBuf> {
+    let children: BTreeSet<PathBuf> = paths
+        .iter()
+        .filter_map(|path| {
+            let rest = path.parent()?.strip_prefix(dir).ok()?;
+            let first = rest.components().next()?;
+            Some(dir.join(first))
+        })
+        .collect();
+    children.into_iter().collect()
+}
+
+/// `dir` and each folder above it up to `root`, outermost first, for a
+/// breadcrumb trail.
+pub fn breadcrumbs(root: &Path, dir: &Path) -> Vec<PathBuf> {
+    let mut trail: Vec<PathBuf> = dir
+        .ancestors()
+        .take_while(|ancestor| ancestor.starts_with(root))
+        .map(Path::to_path_buf)
+        .collect();
+    trail.reverse();
+    trail
+}
@@ -0,0 +1,282 @@
+//! Autofocus point extraction from EXIF maker notes.
+//!
+//! Each camera brand stores AF data in its own maker note layout. We parse the
+//! handful of tags that carry focus area geometry for Canon, Nikon, Sony,
+//! Fujifilm and Panasonic, and fall back to the standard EXIF SubjectArea /
+//! SubjectLocation tags (used by most phones). Areas are returned as fractions
+//! of the displayed (orientation-corrected) image so the viewer can scale them
+//! to any render size.
+
+/// A focus area as fractions (0.0–1.0) of the oriented image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FocusArea {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// Whether the camera reported this point as achieving focus. Points that
+    /// were merely selected or available are drawn dimmer.
+    pub in_focus: bool,
+}
+
+/// Size of the box drawn for brands that only record a focus point, as a
+/// fraction of the image's short side.
+const POINT_SIZE: f32 = 0.04;
+
+/// Read focus areas from parsed EXIF, already transformed for `orientation`.
+pub fn read_focus_areas(exif: &exif::Exif, orientation: u32) -> Vec<FocusArea> {
+    let make = exif
+        .get_field(exif::Tag::Make, exif::In::PRIMARY)
+        .map(|f| f.display_value().to_string().trim_matches('"').to_lowercase())
+        .unwrap_or_default();
+
+    let from_maker_note = maker_note(exif).and_then(|(note, offset)| {
+        let buf = exif.buf();
+        let le = exif.little_endian();
+        if make.starts_with("canon") {
+            canon_af_info(buf, offset, le)
+        } else if make.starts_with("nikon") {
+            nikon_af_info(note)
+        } else if make.starts_with("sony") {
+            sony_focus_location(buf, note, offset, le)
+        } else if make.starts_with("fujifilm") {
+            fuji_focus_pixel(note, image_size(exif)?)
+        } else if make.starts_with("panasonic") {
+            panasonic_af_point(buf, note, offset, le)
+        } else {
+            None
+        }
+    });
+
+    let areas = from_maker_note
+        .or_else(|| subject_area(exif))
+        .unwrap_or_default();
+    areas
+        .into_iter()
+        .filter(|a| a.width > 0.0 && a.height > 0.0)
+        .filter(|a| (0.0..=1.0).contains(&(a.x + a.width / 2.0)))
+        .filter(|a| (0.0..=1.0).contains(&(a.y + a.height / 2.0)))
+        .map(|a| orient(a, orientation))
+        .collect()
+}
+
+/// The raw MakerNote bytes and their offset within the TIFF buffer.
+fn maker_note(exif: &exif::Exif) -> Option<(&[u8], usize)> {
+    let field = exif.get_field(exif::Tag::MakerNote, exif::In::PRIMARY)?;
+    match &field.value {
+        exif::Value::Undefined(bytes, offset) => Some((bytes.as_slice(), *offset as usize)),
+        _ => None,
+    }
+}
+
+fn image_size(exif: &exif::Exif) -> Option<(f32, f32)> {
+    let dim = |tag| {
+        exif.get_field(tag, exif::In::PRIMARY)
+            .and_then(|f| f.value.get_uint(0))
+            .filter(|&v| v > 0)
+    };
+    let w = dim(exif::Tag::PixelXDimension)?;
+    let h = dim(exif::Tag::PixelYDimension)?;
+    Some((w as f32, h as f32))
+}
+
+/// Standard EXIF subject area: a point, circle or rectangle centered on the subject.
+fn subject_area(exif: &exif::Exif) -> Option<Vec<FocusArea>> {
+    let (img_w, img_h) = image_size(exif)?;
+    let field = exif
+        .get_field(exif::Tag::SubjectArea, exif::In::PRIMARY)
+        .or_else(|| exif.get_field(exif::Tag::SubjectLocation, exif::In::PRIMARY))?;
+    let v: Vec<f32> = (0..4)
+        .map_while(|i| field.value.get_uint(i))
+        .map(|v| v as f32)
+        .collect();
+    let (w, h) = match v.len() {
+        2 => {
+            let side = img_w.min(img_h) * POINT_SIZE;
+            (side, side)
+        }
+        3 => (v[2], v[2]),
+        4 => (v[2], v[3]),
+        _ => return None,
+    };
+    Some(vec![centered(v[0], v[1], w, h, img_w, img_h, true)])
+}
+
+/// Canon AFInfo2 (tag 0x0026): per-point sizes and center offsets, plus an
+/// in-focus bitmask. Y offsets grow upward from the image center.
+fn canon_af_info(buf: &[u8], offset: usize, le: bool) -> Option<Vec<FocusArea>> {
+    let (count, value_offset) = ifd_entry(buf, offset, le, 0x0026)?;
+    let word = |i: usize| read_u16(buf, value_offset + i * 2, le);
+    let words = count.min(buf.len().saturating_sub(value_offset) / 2);
+    if words < 8 {
+        return None;
+    }
+    let n = word(2)? as usize;
+    let (mut img_w, mut img_h) = (word(6)? as f32, word(7)? as f32);
+    if img_w == 0.0 || img_h == 0.0 {
+        (img_w, img_h) = (word(4)? as f32, word(5)? as f32);
+    }
+    if n == 0 || img_w == 0.0 || img_h == 0.0 || words < 8 + 4 * n + n.div_ceil(16) {
+        return None;
+    }
+
+    let focused = |i: usize| word(8 + 4 * n + i / 16).is_some_and(|w| w & (1 << (i % 16)) != 0);
+    let areas = (0..n)
+        .filter_map(|i| {
+            let w = word(8 + i)? as f32;
+            let h = word(8 + n + i)? as f32;
+            let x = word(8 + 2 * n + i)? as i16 as f32;
+            let y = word(8 + 3 * n + i)? as i16 as f32;
+            Some(centered(img_w / 2.0 + x, img_h / 2.0 - y, w, h, img_w, img_h, focused(i)))
+        })
+        .collect();
+    Some(areas)
+}
+
+/// Nikon AFInfo2 (tag 0x00B7): the contrast-detect (live view) AF area. Phase-detect
+/// points are stored as indices into per-body grids, which we don't map.
+fn nikon_af_info(note: &[u8]) -> Option<Vec<FocusArea>> {
+    // "Nikon\0" + version + padding, then an embedded TIFF header
+    if !note.starts_with(b"Nikon\0") || note.len() < 18 {
+        return None;
+    }
+    let tiff = &note[10..];
+    let le = &tiff[..2] == b"II";
+    let ifd = read_u32(tiff, 4, le)? as usize;
+    let (count, value_offset) = ifd_entry(tiff, ifd, le, 0x00B7)?;
+    let data = tiff.get(value_offset..value_offset + count)?;
+
+    let base = match data.get(..4)? {
+        b"0100" | b"0101" if data.get(4).is_some_and(|&c| c != 0) => 0x10,
+        b"0400" => 0x3E,
+        _ => return None,
+    };
+    let field = |i: usize| read_u16(data, base + i * 2, le).map(f32::from);
+    let (img_w, img_h) = (field(0)?, field(1)?);
+    let (x, y, w, h) = (field(2)?, field(3)?, field(4)?, field(5)?);
+    if img_w == 0.0 || img_h == 0.0 {
+        return None;
+    }
+    Some(vec![centered(x, y, w, h, img_w, img_h, true)])
+}
+
+/// Sony FocusLocation (tag 0x2027): image width, height, then the focus point.
+fn sony_focus_location(buf: &[u8], note: &[u8], offset: usize, le: bool) -> Option<Vec<FocusArea>> {
+    let ifd = if note.starts_with(b"SONY DSC ") || note.starts_with(b"SONY CAM ") {
+        offset + 12
+    } else {
+        offset
+    };
+    let (count, value_offset) = ifd_entry(buf, ifd, le, 0x2027)?;
+    if count < 4 {
+        return None;
+    }
+    let v = |i: usize| read_u16(buf, value_offset + i * 2, le).map(f32::from);
+    point_area(v(2)?, v(3)?, v(0)?, v(1)?)
+}
+
+/// Fujifilm FocusPixel (tag 0x1031): the focus point in full-image pixels.
+fn fuji_focus_pixel(note: &[u8], (img_w, img_h): (f32, f32)) -> Option<Vec<FocusArea>> {
+    // Fujifilm maker notes are always little-endian with offsets relative to the note
+    if !note.starts_with(b"FUJIFILM") {
+        return None;
+    }
+    let ifd = read_u32(note, 8, true)? as usize;
+    let (count, value_offset) = ifd_entry(note, ifd, true, 0x1031)?;
+    if count < 2 {
+        return None;
+    }
+    let x = read_u16(note, value_offset, true)? as f32;
+    let y = read_u16(note, value_offset + 2, true)? as f32;
+    point_area(x, y, img_w, img_h)
+}
+
+/// Panasonic AFPointPosition (tag 0x004D): two rationals giving the point as
+/// fractions of the frame.
+fn panasonic_af_point(buf: &[u8], note: &[u8], offset: usize, le: bool) -> Option<Vec<FocusArea>> {
+    if !note.starts_with(b"Panasonic\0") {
+        return None;
+    }
+    let (count, value_offset) = ifd_entry(buf, offset + 12, le, 0x004D)?;
+    if count < 2 {
+        return None;
+    }
+    let rational = |at: usize| {
+        let num = read_u32(buf, at, le)? as f32;
+        let den = read_u32(buf, at + 4, le)? as f32;
+        (den != 0.0).then(|| num / den)
+    };
+    point_area(rational(value_offset)?, rational(value_offset + 8)?, 1.0, 1.0)
+}
+
+/// A fixed-size box around a single focus point.
+fn point_area(x: f32, y: f32, img_w: f32, img_h: f32) -> Option<Vec<FocusArea>> {
+    if img_w <= 0.0 || img_h <= 0.0 {
+        return None;
+    }
+    let side = img_w.min(img_h) * POINT_SIZE;
+    Some(vec![centered(x, y, side, side, img_w, img_h, true)])
+}
+
+fn centered(cx: f32, cy: f32, w: f32, h: f32, img_w: f32, img_h: f32, in_focus: bool) -> FocusArea {
+    FocusArea {
+        x: (cx - w / 2.0) / img_w,
+        y: (cy - h / 2.0) / img_h,
+        width: w / img_w,
+        height: h / img_h,
+        in_focus,
+    }
+}
+
+/// Map an area on the stored image to the same area after the EXIF orientation
+/// transform, matching `thumbnail::apply_orientation`.
+fn orient(a: FocusArea, orientation: u32) -> FocusArea {
+    let FocusArea { x, y, width: w, height: h, in_focus } = a;
+    let (x, y, w, h) = match orientation {
+        2 => (1.0 - x - w, y, w, h),
+        3 => (1.0 - x - w, 1.0 - y - h, w, h),
+        4 => (x, 1.0 - y - h, w, h),
+        5 => (y, x, h, w),
+        6 => (1.0 - y - h, x, h, w),
+        7 => (1.0 - y - h, 1.0 - x - w, h, w),
+        8 => (y, 1.0 - x - w, h, w),
+        _ => (x, y, w, h),
+    };
+    FocusArea { x, y, width: w, height: h, in_focus }
+}
+
+/// Find `tag` in the IFD at `ifd`, returning its component count and the
+/// offset of its value (inline values point into the entry itself).
+fn ifd_entry(buf: &[u8], ifd: usize, le: bool, tag: u16) -> Option<(usize, usize)> {
+    let entries = read_u16(buf, ifd, le)? as usize;
+    (0..entries).find_map(|i| {
+        let entry = ifd + 2 + i * 12;
+        if read_u16(buf, entry, le)? != tag {
+            return None;
+        }
+        let unit = match read_u16(buf, entry + 2, le)? {
+            1 | 2 | 6 | 7 => 1,
+            3 | 8 => 2,
+            4 | 9 | 11 => 4,
+            5 | 10 | 12 => 8,
+            _ => return None,
+        };
+        let count = read_u32(buf, entry + 4, le)? as usize;
+        let value_offset = if count * unit <= 4 {
+            entry + 8
+        } else {
+            read_u32(buf, entry + 8, le)? as usize
+        };
+        Some((count, value_offset))
+    })
+}
+
+fn read_u16(buf: &[u8], at: usize, le: bool) -> Option<u16> {
+    let b: [u8; 2] = buf.get(at..at + 2)?.try_into().ok()?;
+    Some(if le { u16::from_le_bytes(b) } else { u16::from_be_bytes(b) })
+}
+
+fn read_u32(buf: &[u8], at: usize, le: bool) -> Option<u32> {
+    let b: [u8; 4] = buf.get(at..at + 4)?.try_into().ok()?;
+    Some(if le { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) })
+}
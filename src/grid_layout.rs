@@ -0,0 +1,175 @@
+//! Where each thumbnail sits in the grid: fixed squares, or justified rows
+//! that keep each photo's shape and stretch to fill the width.
+
+use std::ops::Range;
+
+/// How grid thumbnails are laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GridLayout {
+    /// Equal squares, cropped to the middle of each photo.
+    #[default]
+    Square,
+    /// Rows of equal height with the whole photo in each cell.
+    Justified,
+}
+
+impl GridLayout {
+    pub const ALL: [GridLayout; 2] = [GridLayout::Square, GridLayout::Justified];
+
+    pub fn label(self) -> &'static str {
+        crate::i18n::tr(match self {
+            GridLayout::Square => "Square crops",
+            GridLayout::Justified => "Whole photos in justified rows",
+        })
+    }
+
+    /// Name used in the settings file.
+    pub fn key(self) -> &'static str {
+        match self {
+            GridLayout::Square => "square",
+            GridLayout::Justified => "justified",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|l| l.key() == key)
+    }
+}
+
+/// One row of cells, by grid position.
+#[derive(Debug, Clone, Copy)]
+pub struct GridRow {
+    pub start: usize,
+    pub end: usize,
+    pub top: f32,
+    pub height: f32,
+}
+
+/// Rows and cell widths for every thumbnail in the grid.
+#[derive(Debug, Default)]
+pub struct Geometry {
+    pub rows: Vec<GridRow>,
+    /// Width of the cell at each grid position.
+    pub widths: Vec<f32>,
+}
+
+impl Geometry {
+    /// `count` squares of side `cell`, as many to a row as fit in `width`.
+    pub fn square(count: usize, width: f32, cell: f32) -> Self {
+        let per_row = (width / cell).max(1.0) as usize;
+        let rows = (0..count.div_ceil(per_row))
+            .map(|r| GridRow {
+                start: r * per_row,
+                end: ((r + 1) * per_row).min(count),
+                top: r as f32 * cell,
+                height: cell,
+            })
+            .collect();
+        Self {
+            rows,
+            widths: vec![cell; count],
+        }
+    }
+
+    /// Cells of the given width/height `aspects`, packed into rows as near
+    /// `target_height` tall as the breaks allow and scaled so each row spans
+    /// `width` exactly. The last row keeps the target height rather than
+    /// stretching.
+    pub fn justified(aspects: &[f32], width: f32, target_height: f32) -> Self {
+        let width = width.max(1.0);
+        let mut geometry = Self {
+            rows: Vec::new(),
+            widths: Vec::with_capacity(aspects.len()),
+        };
+        let mut start = 0;
+        let mut row_aspect = 0.0;
+        for (i, &aspect) in aspects.iter().enumerate() {
+            if row_aspect + aspect < width / target_height {
+                row_aspect += aspect;
+                continue;
+            }
+            // Over the width: end the row with this photo, or just before
+            // it, whichever comes out nearer the target height
+            let with = width / (row_aspect + aspect);
+            let without = width / row_aspect;
+            if i > start && without - target_height < target_height - with {
+                geometry.push_row(aspects, start..i, without);
+                start = i;
+                row_aspect = aspect;
+            } else {
+                geometry.push_row(aspects, start..i + 1, with);
+                start = i + 1;
+                row_aspect = 0.0;
+            }
+        }
+        if start < aspects.len() {
+            let height = target_height.min(width / row_aspect);
+            geometry.push_row(aspects, start..aspects.len(), height);
+        }
+        geometry
+    }
+
+    fn push_row(&mut self, aspects: &[f32], cells: Range<usize>, height: f32) {
+        let top = self.height();
+        self.widths.extend(aspects[cells.clone()].iter().map(|a| a * height));
+        self.rows.push(GridRow {
+            start: cells.start,
+            end: cells.end,
+            top,
+            height,
+        });
+    }
+
+    pub fn height(&self) -> f32 {
+        self.rows.last().map_or(0.0, |r| r.top + r.height)
+    }
+
+    /// Index of the row holding grid position `position`.
+    pub fn row_of(&self, position: usize) -> Option<usize> {
+        let r = self.rows.partition_point(|r| r.end <= position);
+        (r < self.rows.len()).then_some(r)
+    }
+
+    /// Rows overlapping the band from `top` to `bottom`.
+    pub fn rows_between(&self, top: f32, bottom: f32) -> Range<usize> {
+        let first = self.rows.partition_point(|r| r.top + r.height <= top);
+        let last = self.rows.partition_point(|r| r.top < bottom);
+        first..last.max(first)
+    }
+
+    /// Grid positions in the rows overlapping `top` to `bottom`.
+    pub fn positions_between(&self, top: f32, bottom: f32) -> Range<usize> {
+        let rows = self.rows_between(top, bottom);
+        match (self.rows.get(rows.start), rows.end.checked_sub(1).and_then(|r| self.rows.get(r))) {
+            (Some(first), Some(last)) if !rows.is_empty() => first.start..last.end,
+            _ => 0..0,
+        }
+    }
+
+    /// The position `delta` rows above or below `position` whose cell is
+    /// nearest it horizontally, clamped to the first and last rows.
+    pub fn vertical_step(&self, position: usize, delta: isize) -> usize {
+        let Some(from) = self.row_of(position) else {
+            return position;
+        };
+        let to = (from as isize + delta).clamp(0, self.rows.len() as isize - 1) as usize;
+        if to == from {
+            return if delta < 0 { self.rows[from].start } else { self.rows[from].end - 1 };
+        }
+        let centre = self.left(position) + self.widths[position] / 2.0;
+        let row = self.rows[to];
+        (row.start..row.end)
+            .min_by(|&a, &b| {
+                let da = (self.left(a) + self.widths[a] / 2.0 - centre).abs();
+                let db = (self.left(b) + self.widths[b] / 2.0 - centre).abs();
+                da.total_cmp(&db)
+            })
+            .unwrap_or(row.start)
+    }
+
+    /// Left edge of the cell at `position` within its row.
+    fn left(&self, position: usize) -> f32 {
+        let start = self.row_of(position).map_or(position, |r| self.rows[r].start);
+        self.widths[start..position].iter().sum()
+    }
+}
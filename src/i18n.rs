@@ -0,0 +1,560 @@
+//! Translations for user-facing text.
+//!
+//! Strings are looked up by their English text, gettext-style, so call sites
+//! stay readable and anything without a translation falls back to English.
+//! Placeholders are `{}` and are filled in order by [`trf`] and [`trn`].
+
+use std::cell::Cell;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::German];
+
+    /// The language's own name for itself, so it can be found in any locale.
+    pub fn label(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+        }
+    }
+
+    /// ISO 639-1 code, also the name used in the settings file.
+    pub fn key(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::German => "de",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|l| l.key() == key)
+    }
+
+    /// The language from the POSIX locale variables, e.g. `de_AT.UTF-8`.
+    pub fn system() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| {
+                let code = value.split(['_', '-', '.', '@']).next()?.to_lowercase();
+                Self::from_key(&code)
+            })
+            .unwrap_or_default()
+    }
+
+    /// The first language in an HTTP `Accept-Language` header we have,
+    /// e.g. `de-CH,de;q=0.9,en;q=0.8`. Quality values are taken to be in
+    /// order, as browsers send them.
+    pub fn from_accept_language(header: &str) -> Option<Self> {
+        header.split(',').find_map(|part| {
+            let tag = part.split(';').next()?.trim();
+            Self::from_key(&tag.split('-').next()?.to_lowercase())
+        })
+    }
+}
+
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+thread_local! {
+    /// Language for work on this thread only, e.g. a web request.
+    static OVERRIDE: Cell<Option<Language>> = const { Cell::new(None) };
+}
+
+/// Run `f` with text on this thread in `language` rather than the app's,
+/// for serving a client that asked for another one.
+pub fn with_language<T>(language: Option<Language>, f: impl FnOnce() -> T) -> T {
+    let previous = OVERRIDE.replace(language);
+    let result = f();
+    OVERRIDE.set(previous);
+    result
+}
+
+/// Switch every window (and the web gallery) to `language`.
+pub fn set_language(language: Language) {
+    let index = Language::ALL.iter().position(|&l| l == language).unwrap_or(0);
+    CURRENT.store(index as u8, Ordering::Relaxed);
+}
+
+pub fn language() -> Language {
+    if let Some(language) = OVERRIDE.get() {
+        return language;
+    }
+    Language::ALL
+        .get(CURRENT.load(Ordering::Relaxed) as usize)
+        .copied()
+        .unwrap_or_default()
+}
+
+/// `text` in the current language.
+pub fn tr(text: &'static str) -> &'static str {
+    let translated = match language() {
+        Language::English => None,
+        Language::German => german(text),
+    };
+    translated.unwrap_or(text)
+}
+
+/// `text` in the current language with each `{}` replaced by the next of `args`.
+pub fn trf(text: &'static str, args: &[&dyn Display]) -> String {
+    let mut args = args.iter();
+    let mut parts = tr(text).split("{}");
+    let mut out = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        if let Some(arg) = args.next() {
+            out.push_str(&arg.to_string());
+        }
+        out.push_str(part);
+    }
+    out
+}
+
+/// `one` or `other` depending on `n`, with `n` filled in. Both of the
+/// supported languages only distinguish singular from plural.
+pub fn trn(n: usize, one: &'static str, other: &'static str) -> String {
+    trf(if n == 1 { one } else { other }, &[&n])
+}
+
+fn german(text: &str) -> Option<&'static str> {
+    Some(match text {
+        // Months, as dates are written
+        "Jan" => "Jan.",
+        "Feb" => "Feb.",
+        "Mar" => "März",
+        "Apr" => "Apr.",
+        "May" => "Mai",
+        "Jun" => "Juni",
+        "Jul" => "Juli",
+        "Aug" => "Aug.",
+        "Sep" => "Sept.",
+        "Oct" => "Okt.",
+        "Nov" => "Nov.",
+        "Dec" => "Dez.",
+        // Grid and status bar
+        "Loading..." => "Wird geladen...",
+        "Open a folder to browse photos" => "Öffne einen Ordner, um Fotos anzusehen",
+        "Scanning folder" => "Ordner wird durchsucht",
+        "Thumbnails" => "Vorschaubilder",
+        "Hashing for duplicates" => "Duplikatsuche",
+        "Duplicate scan paused" => "Duplikatsuche pausiert",
+        "{} total, {} reclaimable" => "{} gesamt, {} freigebbar",
+        "{} can be freed across {} groups" => "{} können in {} Gruppen freigegeben werden",
+        "Duplicates Across Library" => "Duplikate in der Bibliothek",
+        "No identical files found in the folders scanned so far" => {
+            "Keine identischen Dateien in den bisher durchsuchten Ordnern gefunden"
+        }
+        "{} can be freed across {} sets of folders" => {
+            "{} können in {} Ordnergruppen freigegeben werden"
+        }
+        "{}, {} can be freed" => "{}, {} können freigegeben werden",
+        "Open" => "Öffnen",
+        "Sort by" => "Sortieren nach",
+        "Move to Trash (Del)" => "In den Papierkorb (Entf)",
+        "Recently Deleted" => "Zuletzt gelöscht",
+        "Empty Trash" => "Papierkorb leeren",
+        "Nothing has been deleted" => "Nichts gelöscht",
+        "Deleted {}" => "Gelöscht am {}",
+        "Restore" => "Wiederherstellen",
+        "Delete Permanently" => "Endgültig löschen",
+        "Couldn't restore: {}" => "Wiederherstellen fehlgeschlagen: {}",
+        "Couldn't delete: {}" => "Löschen fehlgeschlagen: {}",
+        "Import from Card" => "Von Speicherkarte importieren",
+        "Check Against Library" => "Mit Bibliothek abgleichen",
+        "Export Gallery" => "Galerie exportieren",
+        "Exporting gallery" => "Galerie-Export",
+        "Select where to save the gallery" => "Speicherort für die Galerie wählen",
+        "Gallery saved to {}" => "Galerie gespeichert in {}",
+        "Couldn't save the gallery: {}" => "Galerie konnte nicht gespeichert werden: {}",
+        "Export Contact Sheet" => "Kontaktabzug exportieren",
+        "Save contact sheet" => "Kontaktabzug speichern",
+        "Making contact sheet" => "Kontaktabzug wird erstellt",
+        "Contact sheet saved to {}" => "Kontaktabzug gespeichert in {}",
+        "Couldn't save the contact sheet: {}" => "Kontaktabzug konnte nicht gespeichert werden: {}",
+        "Contact sheets" => "Kontaktabzüge",
+        "Photos per page" => "Fotos pro Seite",
+        "3 × 4 (12 per page)" => "3 × 4 (12 pro Seite)",
+        "4 × 5 (20 per page)" => "4 × 5 (20 pro Seite)",
+        "5 × 7 (35 per page)" => "5 × 7 (35 pro Seite)",
+        "6 × 8 (48 per page)" => "6 × 8 (48 pro Seite)",
+        "Each photo is captioned with its file name and date." => "Jedes Foto wird mit Dateiname und Datum beschriftet.",
+        "Checking against library" => "Abgleich mit Bibliothek",
+        "New only" => "Nur neue",
+        "IMPORTED" => "IMPORTIERT",
+        "Import Photos" => "Fotos importieren",
+        "From" => "Von",
+        "To" => "Nach",
+        "Choose Folder..." => "Ordner wählen...",
+        "Share..." => "Teilen...",
+        // Batch actions on a selection
+        "{} selected" => "{} ausgewählt",
+        "Copy to..." => "Kopieren nach...",
+        "Move to..." => "Verschieben nach...",
+        "Move to Trash" => "In den Papierkorb",
+        "Select where to copy the photos" => "Ziel zum Kopieren der Fotos wählen",
+        "Select where to move the photos" => "Ziel zum Verschieben der Fotos wählen",
+        "Copied {} photo" => "{} Foto kopiert",
+        "Copied {} photos" => "{} Fotos kopiert",
+        "Moved {} photo" => "{} Foto verschoben",
+        "Moved {} photos" => "{} Fotos verschoben",
+        "Moved {} photo to the trash" => "{} Foto in den Papierkorb verschoben",
+        "Moved {} photos to the trash" => "{} Fotos in den Papierkorb verschoben",
+        "{} failed: {}" => "{} fehlgeschlagen: {}",
+        "{} skipped" => "{} übersprungen",
+        "Copying" => "Kopieren",
+        "Moving" => "Verschieben",
+        "Moving to the trash" => "In den Papierkorb verschieben",
+        "Waiting for a choice" => "Wartet auf eine Auswahl",
+        "File already exists" => "Datei existiert bereits",
+        "{} is already in {}." => "{} ist bereits in {} vorhanden.",
+        "Skip" => "Überspringen",
+        "Keep Both" => "Beide behalten",
+        "Replace" => "Ersetzen",
+        "Do the same for {} more" => "Für {} weitere genauso verfahren",
+        "Couldn't share: {}" => "Teilen fehlgeschlagen: {}",
+        "Offer AVIF thumbnails" => "AVIF-Vorschaubilder anbieten",
+        "Memory for gallery thumbnails" => "Speicher für Galerie-Vorschaubilder",
+        "Requests served at once" => "Gleichzeitig bearbeitete Anfragen",
+        "Show QR code large (Q)" => "QR-Code groß anzeigen (Q)",
+        "Copy" => "Kopieren",
+        "Copied" => "Kopiert",
+        "Open in browser" => "Im Browser öffnen",
+        "Shuffle" => "Zufallsreihenfolge",
+        "Timing" => "Anzeigedauer",
+        "Show panoramas longer and portraits shorter" => "Panoramen länger und Hochformate kürzer zeigen",
+        "Paused" => "Angehalten",
+        "Automatic works further ahead when photos decode quickly, and less far for large files." => "Automatisch lädt weiter voraus, wenn Fotos schnell dekodiert werden, und weniger weit bei großen Dateien.",
+        "Every photo equally" => "Alle Fotos gleich oft",
+        "Somewhat favor recent photos" => "Neuere Fotos etwas bevorzugen",
+        "Strongly favor recent photos" => "Neuere Fotos stark bevorzugen",
+        "Recent means the date taken, or when the file was last changed. Older photos still come up now and then." => "Neu heißt nach Aufnahmedatum oder letzter Änderung der Datei. Ältere Fotos erscheinen trotzdem ab und zu.",
+        "Scan with a phone camera. Press Esc to close." => "Mit der Handykamera scannen. Esc schließt.",
+        "4 at a time" => "4 gleichzeitig",
+        "8 at a time" => "8 gleichzeitig",
+        "16 at a time" => "16 gleichzeitig",
+        "32 at a time" => "32 gleichzeitig",
+        "Raise this if TVs downloading full-size photos slow down phones browsing the gallery." => "Erhöhen, wenn Fernseher beim Laden großer Fotos die Galerie auf Handys ausbremsen.",
+        "The least recently viewed are dropped first and made again from the grid's cache." => "Die am längsten nicht angesehenen werden zuerst verworfen und bei Bedarf aus dem Raster-Cache neu erzeugt.",
+        "Browsers get WebP, or JPEG if they can't show it. AVIF is smaller again but slower to make." => "Browser erhalten WebP oder, falls sie es nicht anzeigen können, JPEG. AVIF ist noch kleiner, aber langsamer zu erzeugen.",
+        "Choose..." => "Wählen...",
+        "Refresh" => "Aktualisieren",
+        "Refresh (F5)" => "Aktualisieren (F5)",
+        "Time zone" => "Zeitzone",
+        "System time zone" => "Zeitzone des Systems",
+        "A name such as Europe/Berlin. Leave empty to use the system's." => {
+            "Ein Name wie Europe/Berlin. Leer lassen, um die des Systems zu verwenden."
+        }
+        "Not a known time zone" => "Keine bekannte Zeitzone",
+        "Scan: {}" => "Durchsuchen: {}",
+        "All subfolders" => "Alle Unterordner",
+        "This folder only" => "Nur dieser Ordner",
+        "One level of subfolders" => "Eine Ebene Unterordner",
+        "Two levels of subfolders" => "Zwei Ebenen Unterordner",
+        "Subfolders" => "Unterordner",
+        "How deep opening a folder looks for photos. Each folder can choose its own from the menu." => {
+            "Wie tief beim Öffnen eines Ordners nach Fotos gesucht wird. Jeder Ordner kann im Menü seine eigene Tiefe wählen."
+        }
+        "Exclude This Folder" => "Diesen Ordner ausschließen",
+        "Couldn't exclude the folder: {}" => "Ordner konnte nicht ausgeschlossen werden: {}",
+        "The folder is up to date" => "Der Ordner ist aktuell",
+        "{} added, {} removed" => "{} hinzugefügt, {} entfernt",
+        "No memory cards found" => "Keine Speicherkarten gefunden",
+        "Reading the card..." => "Karte wird gelesen...",
+        "{} new, {} already imported" => "{} neu, {} bereits importiert",
+        "Already imported" => "Bereits importiert",
+        "and {} more" => "und {} weitere",
+        "No folder chosen" => "Kein Ordner gewählt",
+        "Sorted into year and day folders, e.g. 2024/2024-05-01" => "In Jahres- und Tagesordner sortiert, z. B. 2024/2024-05-01",
+        "Eject the card when done" => "Karte danach auswerfen",
+        "Import {} Photo" => "{} Foto importieren",
+        "Import {} Photos" => "{} Fotos importieren",
+        "Imported {} photo" => "{} Foto importiert",
+        "Imported {} photos" => "{} Fotos importiert",
+        "Imported {} photos, {} failed" => "{} Fotos importiert, {} fehlgeschlagen",
+        "Import cancelled after {} of {} photos" => "Import nach {} von {} Fotos abgebrochen",
+        "Card ejected" => "Karte ausgeworfen",
+        "Couldn't eject the card: {}" => "Karte konnte nicht ausgeworfen werden: {}",
+        "Importing" => "Import",
+        "Cancelling import" => "Import wird abgebrochen",
+        "Select a card or folder to import from" => "Karte oder Ordner zum Importieren wählen",
+        "Select where to import to" => "Importziel wählen",
+        "Folder order" => "Ordnerreihenfolge",
+        "Where the copies are" => "Ort der Kopien",
+        "Space freed" => "Freigegebener Speicher",
+        "Pause" => "Pausieren",
+        "Resume" => "Fortsetzen",
+        "Reading photo info" => "Fotodaten werden gelesen",
+        "All cameras" => "Alle Kameras",
+        "All lenses" => "Alle Objektive",
+        "All shapes" => "Alle Formate",
+        "All flags" => "Alle Markierungen",
+        "Picks" => "Ausgewählte",
+        "Rejects" => "Abgelehnte",
+        "Unflagged" => "Nicht markiert",
+        "Landscape" => "Querformat",
+        "Portrait" => "Hochformat",
+        "Square" => "Quadratisch",
+        "Aspect" => "Seitenverhältnis",
+        "Narrowest width:height, e.g. 2:1 for panoramas" => {
+            "Schmalstes Breite:Höhe, z. B. 2:1 für Panoramen"
+        }
+        "Widest width:height, e.g. 4:5" => "Breitestes Breite:Höhe, z. B. 4:5",
+        "Search names, cameras, keywords" => "Namen, Kameras, Stichwörter suchen",
+        "{} photo" => "{} Foto",
+        "{} photos" => "{} Fotos",
+        "{} of {} photos" => "{} von {} Fotos",
+        "about {} left" => "noch etwa {}",
+        "\u{26A0} UNREADABLE" => "\u{26A0} UNLESBAR",
+        "Select a photo folder" => "Fotoordner auswählen",
+        "Photos" => "Fotos",
+        // Duplicates
+        "Exact match" => "Exakte Übereinstimmung",
+        "Visual match" => "Visuelle Übereinstimmung",
+        "{} identical files" => "{} identische Dateien",
+        "{} similar files (distance: {})" => "{} ähnliche Dateien (Abstand: {})",
+        "Compare" => "Vergleichen",
+        "Taken: {}" => "Aufgenommen: {}",
+        "Modified: {}" => "Geändert: {}",
+        "{} duplicate groups found" => "{} Duplikatgruppen gefunden",
+        // Menus
+        "Open Folder" => "Ordner öffnen",
+        "New Window (Ctrl+N)" => "Neues Fenster (Strg+N)",
+        "Find Duplicates" => "Duplikate suchen",
+        "{} unreadable file" => "{} unlesbare Datei",
+        "{} unreadable files" => "{} unlesbare Dateien",
+        "Unreadable Files" => "Unlesbare Dateien",
+        "Every photo in this folder could be read" => "Alle Fotos in diesem Ordner konnten gelesen werden",
+        "These files couldn't be decoded. Files still being copied or synced are tried again by themselves." => {
+            "Diese Dateien konnten nicht dekodiert werden. Dateien, die noch kopiert oder synchronisiert werden, werden von selbst erneut versucht."
+        }
+        "Trying again shortly" => "Wird gleich erneut versucht",
+        "Try Again" => "Erneut versuchen",
+        "Include Unreadable in Scan" => "Unlesbare in Suche einbeziehen",
+        "Skip Unreadable in Scan" => "Unlesbare bei Suche überspringen",
+        "Report RAW or HEIC files and their JPEGs" => "RAW- oder HEIC-Dateien und ihre JPEGs melden",
+        "Otherwise files with the same name and capture time in the same folder count as one photo saved twice, not as duplicates." => {
+            "Sonst gelten Dateien mit gleichem Namen und Aufnahmezeitpunkt im selben Ordner als ein zweifach gespeichertes Foto, nicht als Duplikate."
+        }
+        "Duplicates ({})" => "Duplikate ({})",
+        "Duplicates" => "Duplikate",
+        "Group {} of {}" => "Gruppe {} von {}",
+        "Keep Largest (Del)" => "Größte behalten (Entf)",
+        "Include in Duplicate Scans" => "In Duplikatsuche einbeziehen",
+        "Leave Out of Duplicate Scans" => "Von Duplikatsuche ausnehmen",
+        "Find duplicates again to include this folder" => "Duplikate erneut suchen, um diesen Ordner einzubeziehen",
+        "Screensaver" => "Bildschirmschoner",
+        "Stop Screensaver" => "Bildschirmschoner beenden",
+        "Share" => "Freigeben",
+        "Stop Sharing" => "Freigabe beenden",
+        "Allow Uploads" => "Uploads erlauben",
+        "Disallow Uploads" => "Uploads verbieten",
+        "Settings" => "Einstellungen",
+        "Stops in {}" => "Endet in {}",
+        "No folder selected" => "Kein Ordner ausgewählt",
+        "Back" => "Zurück",
+        "Info" => "Info",
+        "Hide Info" => "Info ausblenden",
+        "Show AF Points (K)" => "AF-Punkte anzeigen (K)",
+        "Hide AF Points (K)" => "AF-Punkte ausblenden (K)",
+        "Dark Room (L)" => "Dunkelkammer (L)",
+        "Leave Dark Room (L)" => "Dunkelkammer verlassen (L)",
+        "As tagged" => "Laut EXIF",
+        "Ignore tag" => "EXIF ignorieren",
+        "90° clockwise" => "90° im Uhrzeigersinn",
+        "90° counterclockwise" => "90° gegen den Uhrzeigersinn",
+        "Fullscreen" => "Vollbild",
+        "Window" => "Fenster",
+        "Mark for A/B (M)" => "Für A/B markieren (M)",
+        "Unmark A/B (M)" => "A/B-Markierung aufheben (M)",
+        "Flip A/B (B)" => "A/B wechseln (B)",
+        "View: {}" => "Ansicht: {}",
+        "Rotation: {}" => "Drehung: {}",
+        "Crop and Straighten (E)" => "Zuschneiden und Ausrichten (E)",
+        "Rating: {} (T)" => "Bewertung: {} (T)",
+        "Cull Photos" => "Fotos aussortieren",
+        "Move {} Reject to Trash" => "{} abgelehntes in den Papierkorb",
+        "Move {} Rejects to Trash" => "{} abgelehnte in den Papierkorb",
+        "That was the last photo" => "Das war das letzte Foto",
+        "{} picked, {} rejected" => "{} ausgewählt, {} abgelehnt",
+        "Done" => "Fertig",
+        "Not flagged" => "Nicht markiert",
+        "Picked" => "Ausgewählt",
+        "Rejected" => "Abgelehnt",
+        "P pick \u{00B7} X reject \u{00B7} U clear \u{00B7} Esc stop" => {
+            "P auswählen \u{00B7} X ablehnen \u{00B7} U zurücksetzen \u{00B7} Esc beenden"
+        }
+        "Random Photo (R)" => "Zufälliges Foto (R)",
+        "Resume at {}" => "Weiter bei {}",
+        "None" => "Keine",
+        "Favorite (H)" => "Favorit (H)",
+        "Unfavorite (H)" => "Kein Favorit mehr (H)",
+        // Crop and straighten
+        "Crop and Straighten" => "Zuschneiden und Ausrichten",
+        "Drag the photo to move the crop." => "Foto ziehen, um den Ausschnitt zu verschieben.",
+        "Aspect ratio" => "Seitenverhältnis",
+        "Free" => "Frei",
+        "Original" => "Original",
+        "Width" => "Breite",
+        "Height" => "Höhe",
+        "Size" => "Größe",
+        "Straighten: {}°" => "Ausrichten: {}°",
+        "Reset" => "Zurücksetzen",
+        "Save" => "Speichern",
+        "Save Edited Copy..." => "Bearbeitete Kopie speichern...",
+        "Save edited copy" => "Bearbeitete Kopie speichern",
+        "Saved to {}" => "Gespeichert unter {}",
+        "Couldn't save the copy: {}" => "Die Kopie konnte nicht gespeichert werden: {}",
+        "A/B with {}" => "A/B mit {}",
+        "Zoom: {}%" => "Zoom: {} %",
+        // Casting
+        "Cast" => "Streamen",
+        "Connecting to {}..." => "Verbinde mit {}...",
+        "Casting to {}" => "Streamt auf {}",
+        "Casting to {} TVs" => "Streamt auf {} Fernseher",
+        "Reconnecting to {}..." => "Erneut verbinden mit {}...",
+        "Lost connection to {}" => "Verbindung zu {} verloren",
+        "Slideshow on TV" => "Diashow auf dem Fernseher",
+        "Stop TV Slideshow" => "TV-Diashow beenden",
+        "Stop Cast" => "Streamen beenden",
+        "Stop All" => "Alle beenden",
+        "Searching..." => "Suche läuft...",
+        "No devices found" => "Keine Geräte gefunden",
+        "No other devices found" => "Keine weiteren Geräte gefunden",
+        "Search Again" => "Erneut suchen",
+        "Couldn't start sharing: {}" => "Freigabe konnte nicht gestartet werden: {}",
+        "Couldn't connect to {}: {}" => "Verbindung zu {} fehlgeschlagen: {}",
+        // Viewer
+        "Can't display this image" => "Dieses Bild kann nicht angezeigt werden",
+        "Fit" => "Einpassen",
+        "Fit Width" => "Breite einpassen",
+        "Fit Height" => "Höhe einpassen",
+        "Fill" => "Füllen",
+        "Theme" => "Design",
+        "Black" => "Schwarz",
+        "Gray" => "Grau",
+        "White" => "Weiß",
+        // Info panel
+        "Date Taken" => "Aufnahme",
+        "Modified" => "Geändert",
+        "Camera" => "Kamera",
+        "Make" => "Hersteller",
+        "Model" => "Modell",
+        "Lens" => "Objektiv",
+        "Exposure" => "Belichtung",
+        "Histogram (O)" => "Histogramm (O)",
+        "Hide Histogram (O)" => "Histogramm ausblenden (O)",
+        "Focal length" => "Brennweite",
+        "{} ({}mm eq.)" => "{} ({} mm KB)",
+        "Exp. bias" => "Korrektur",
+        "Program" => "Programm",
+        "Metering" => "Messung",
+        "Light & Color" => "Licht & Farbe",
+        "Flash" => "Blitz",
+        "White balance" => "Weißabgleich",
+        "Color space" => "Farbraum",
+        "Location" => "Ort",
+        "Coordinates" => "Koordinaten",
+        "Altitude" => "Höhe",
+        "Description" => "Beschreibung",
+        "Artist" => "Urheber",
+        // Settings
+        "Sharing" => "Freigabe",
+        "Network interface" => "Netzwerkschnittstelle",
+        "Automatic (default route)" => "Automatisch (Standardroute)",
+        "{} (unavailable)" => "{} (nicht verfügbar)",
+        "Server name" => "Servername",
+        "Looky — folder name" => "Looky — Ordnername",
+        "How TVs and media players list this share" => {
+            "So zeigen Fernseher und Mediaplayer diese Freigabe an"
+        }
+        "Port" => "Port",
+        "Automatic" => "Automatisch",
+        "Leave empty to pick a free port" => "Leer lassen, um einen freien Port zu wählen",
+        "Stop sharing" => "Freigabe beenden",
+        "Never" => "Nie",
+        "After 30 minutes" => "Nach 30 Minuten",
+        "After 2 hours" => "Nach 2 Stunden",
+        "After 8 hours" => "Nach 8 Stunden",
+        "Stop when idle" => "Bei Inaktivität beenden",
+        "After 10 idle minutes" => "Nach 10 Minuten ohne Zugriff",
+        "After 30 idle minutes" => "Nach 30 Minuten ohne Zugriff",
+        "After 1 idle hour" => "Nach 1 Stunde ohne Zugriff",
+        "Advertising {}" => "Angeboten unter {}",
+        "Advertising {} and {}" => "Angeboten unter {} und {}",
+        "Not sharing" => "Keine Freigabe aktiv",
+        "Changes apply the next time sharing starts." => {
+            "Änderungen gelten ab der nächsten Freigabe."
+        }
+        "On this network" => "In diesem Netzwerk",
+        "Find other Looky shares" => "Andere Looky-Freigaben suchen",
+        "Viewer" => "Betrachter",
+        "Preload full-size images" => "Bilder in voller Größe vorladen",
+        "1 image each way" => "1 Bild in jede Richtung",
+        "3 images each way" => "3 Bilder in jede Richtung",
+        "6 images each way" => "6 Bilder in jede Richtung",
+        "12 images each way" => "12 Bilder in jede Richtung",
+        "Memory for full-size images" => "Speicher für Bilder in voller Größe",
+        "Images farthest from the one on screen are dropped first." => {
+            "Bilder, die am weitesten vom angezeigten entfernt sind, werden zuerst verworfen."
+        }
+        "Image size" => "Bildgröße",
+        "Maximum zoom" => "Maximaler Zoom",
+        "Background" => "Hintergrund",
+        "Shows through transparent PNG and WebP images." => {
+            "Scheint durch transparente PNG- und WebP-Bilder."
+        }
+        "Zoom presets" => "Zoomstufen",
+        "Percent of actual size, or \"fit\". Keys 1–9 jump to these; Z cycles." => {
+            "Prozent der Originalgröße oder \"fit\". Tasten 1–9 springen dorthin; Z wechselt durch."
+        }
+        "Grid" => "Raster",
+        "Layout" => "Anordnung",
+        "Badges" => "Markierungen",
+        "Scroll wheel" => "Mausrad",
+        "Scroll to the previous or next photo" => "Zum vorherigen oder nächsten Foto blättern",
+        "Hold Ctrl while scrolling to zoom. Once zoomed in, scrolling zooms as before." => {
+            "Zum Zoomen beim Scrollen Strg gedrückt halten. Vergrößert zoomt das Mausrad wie bisher."
+        }
+        "Show ratings and favorites" => "Bewertungen und Favoriten anzeigen",
+        "Sharpening" => "Schärfen",
+        "Sharpen thumbnails after shrinking" => "Vorschaubilder nach dem Verkleinern schärfen",
+        "Light" => "Leicht",
+        "Medium" => "Mittel",
+        "Strong" => "Stark",
+        "Restores detail lost when photos are shrunk for the grid. Changing it remakes the thumbnails." => {
+            "Stellt Details wieder her, die beim Verkleinern für das Raster verloren gehen. Eine Änderung erstellt die Vorschaubilder neu."
+        }
+        "Square crops" => "Quadratische Ausschnitte",
+        "Whole photos in justified rows" => "Ganze Fotos in Blocksatz-Zeilen",
+        "Memory for thumbnails" => "Speicher für Vorschaubilder",
+        "Thumbnails far from the visible rows are shrunk and reloaded from disk when scrolled back." => {
+            "Vorschaubilder weit außerhalb der sichtbaren Zeilen werden verkleinert und beim Zurückscrollen neu geladen."
+        }
+        "Language" => "Sprache",
+        "Accessibility" => "Barrierefreiheit",
+        "High contrast" => "Hoher Kontrast",
+        "White text on black with bright outlines. Tab and Shift+Tab step through the grid, duplicates, menus and settings fields; F10 opens the menu." => {
+            "Weiße Schrift auf Schwarz mit hellen Rahmen. Tab und Umschalt+Tab wechseln durch Raster, Duplikate, Menüs und Eingabefelder; F10 öffnet das Menü."
+        }
+        "Menu" => "Menü",
+        "Cancel" => "Abbrechen",
+        "Disconnect" => "Trennen",
+        "System default" => "Systemstandard",
+        // Web gallery
+        "Prev" => "Zurück",
+        "Next" => "Weiter",
+        "Page {} of {}" => "Seite {} von {}",
+        "Upload" => "Hochladen",
+        "Uploading {} / {}..." => "Lade hoch: {} / {}...",
+        "{} upload(s) failed" => "{} Upload(s) fehlgeschlagen",
+        "Uploaded {}" => "{} hochgeladen",
+        _ => return None,
+    })
+}
@@ -0,0 +1,125 @@
+//! Copying photos off a camera card into the library, sorted into year and
+//! day folders.
+
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+
+use crate::metadata;
+
+/// A photo found on the card.
+#[derive(Debug, Clone)]
+pub struct ImportItem {
+    pub path: PathBuf,
+    pub size: u64,
+    /// The day it was taken, or else last modified, as "YYYY-MM-DD".
+    pub day: Option<String>,
+    pub content_hash: [u8; 32],
+    /// Its content hash is already in the catalog.
+    pub already_imported: bool,
+}
+
+/// Mounted volumes that look like camera cards, i.e. have a DCIM folder.
+pub fn find_volumes() -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = Vec::new();
+    if cfg!(target_os = "macos") {
+        roots.push(PathBuf::from("/Volumes"));
+    } else if cfg!(windows) {
+        return ('D'..='Z')
+            .map(|letter| PathBuf::from(format!("{letter}:\\")))
+            .filter(|drive| drive.join("DCIM").is_dir())
+            .collect();
+    } else {
+        roots.push(PathBuf::from("/media"));
+        roots.push(PathBuf::from("/mnt"));
+        if let Ok(user) = std::env::var("USER") {
+            roots.push(Path::new("/media").join(&user));
+            roots.push(Path::new("/run/media").join(&user));
+        }
+    }
+    let mut volumes: Vec<PathBuf> = roots
+        .iter()
+        .filter_map(|root| std::fs::read_dir(root).ok())
+        .flat_map(|entries| entries.flatten().map(|entry| entry.path()))
+        .filter(|path| path.join("DCIM").is_dir())
+        .collect();
+    volumes.sort();
+    volumes.dedup();
+    volumes
+}
+
+/// The photos under `source`'s DCIM folder, or all of `source` if it has
+/// none, with their content hashes. Reads every file in full.
+pub async fn scan_source(source: PathBuf) -> Vec<ImportItem> {
+    let dcim = source.join("DCIM");
+    let root = if dcim.is_dir() { dcim } else { source };
+    let mut paths = Vec::new();
+    let mut stack = vec![root];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                stack.push(path);
+            } else if crate::app::is_image_file(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort();
+    paths
+        .into_par_iter()
+        .filter_map(|path| {
+            let bytes = std::fs::read(&path).ok()?;
+            let content_hash: [u8; 32] = Sha256::digest(&bytes).into();
+            let summary = metadata::read_file_summary(&path);
+            let day = summary
+                .date_taken
+                .or(summary.date_modified)
+                .and_then(|date| date.get(..10).map(str::to_string));
+            Some(ImportItem {
+                path,
+                size: bytes.len() as u64,
+                day,
+                content_hash,
+                already_imported: false,
+            })
+        })
+        .collect()
+}
+
+/// Copy `item` into `dest`/YYYY/YYYY-MM-DD, or `dest`/Undated, renaming it
+/// if a different file already has its name there. Returns the new path.
+pub fn import_item(item: &ImportItem, dest: &Path) -> std::io::Result<PathBuf> {
+    let dir = match item.day.as_deref().and_then(|day| Some((day.get(..4)?, day))) {
+        Some((year, day)) => dest.join(year).join(day),
+        None => dest.join("Undated"),
+    };
+    std::fs::create_dir_all(&dir)?;
+    let target = crate::file_ops::unique_target(&dir, &item.path);
+    std::fs::copy(&item.path, &target)?;
+    Ok(target)
+}
+
+/// Eject the card mounted at `volume`.
+pub async fn eject(volume: PathBuf) -> Result<(), String> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = std::process::Command::new("diskutil");
+        command.arg("eject");
+        command
+    } else if cfg!(windows) {
+        return Err("ejecting isn't supported on Windows".to_string());
+    } else {
+        let mut command = std::process::Command::new("gio");
+        command.args(["mount", "--eject"]);
+        command
+    };
+    match command.arg(&volume).status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("eject exited with {status}")),
+        Err(e) => Err(e.to_string()),
+    }
+}
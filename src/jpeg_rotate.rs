@@ -0,0 +1,858 @@
+//! Lossless JPEG rotation and flipping, for clients that ignore the EXIF
+//! orientation tag.
+//!
+//! As jpegtran does, the compressed 8×8 blocks are rearranged and their DCT
+//! coefficients transposed or negated rather than decoded to pixels, so the
+//! picture loses nothing. A flip can only move whole MCUs, so a partial MCU
+//! column or row on the edge that ends up at the start is trimmed away, a
+//! few pixels at most. Only baseline and extended Huffman JPEGs with 8-bit
+//! samples are handled; anything else returns `None` for the caller to
+//! re-encode.
+
+/// Natural (row-major) index of each coefficient in zigzag order.
+#[rustfmt::skip]
+const UNZIGZAG: [usize; 64] = [
+     0,  1,  8, 16,  9,  2,  3, 10,
+    17, 24, 32, 25, 18, 11,  4,  5,
+    12, 19, 26, 33, 40, 48, 41, 34,
+    27, 20, 13,  6,  7, 14, 21, 28,
+    35, 42, 49, 56, 57, 50, 43, 36,
+    29, 22, 15, 23, 30, 37, 44, 51,
+    58, 59, 52, 45, 38, 31, 39, 46,
+    53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+// Huffman tables from Annex K of the JPEG standard. Between them they code
+// every symbol an 8-bit image can produce, so the output always uses them.
+const LUMA_DC_LENGTHS: [u8; 16] = [0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0];
+const CHROMA_DC_LENGTHS: [u8; 16] = [0, 3, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0];
+const DC_VALUES: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+const LUMA_AC_LENGTHS: [u8; 16] = [0, 2, 1, 3, 3, 2, 4, 3, 5, 5, 4, 4, 0, 0, 1, 0x7D];
+#[rustfmt::skip]
+const LUMA_AC_VALUES: [u8; 162] = [
+    0x01, 0x02, 0x03, 0x00, 0x04, 0x11, 0x05, 0x12, 0x21, 0x31, 0x41, 0x06, 0x13, 0x51, 0x61, 0x07,
+    0x22, 0x71, 0x14, 0x32, 0x81, 0x91, 0xA1, 0x08, 0x23, 0x42, 0xB1, 0xC1, 0x15, 0x52, 0xD1, 0xF0,
+    0x24, 0x33, 0x62, 0x72, 0x82, 0x09, 0x0A, 0x16, 0x17, 0x18, 0x19, 0x1A, 0x25, 0x26, 0x27, 0x28,
+    0x29, 0x2A, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3A, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49,
+    0x4A, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5A, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69,
+    0x6A, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7A, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89,
+    0x8A, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9A, 0xA2, 0xA3, 0xA4, 0xA5, 0xA6, 0xA7,
+    0xA8, 0xA9, 0xAA, 0xB2, 0xB3, 0xB4, 0xB5, 0xB6, 0xB7, 0xB8, 0xB9, 0xBA, 0xC2, 0xC3, 0xC4, 0xC5,
+    0xC6, 0xC7, 0xC8, 0xC9, 0xCA, 0xD2, 0xD3, 0xD4, 0xD5, 0xD6, 0xD7, 0xD8, 0xD9, 0xDA, 0xE1, 0xE2,
+    0xE3, 0xE4, 0xE5, 0xE6, 0xE7, 0xE8, 0xE9, 0xEA, 0xF1, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7, 0xF8,
+    0xF9, 0xFA,
+];
+const CHROMA_AC_LENGTHS: [u8; 16] = [0, 2, 1, 2, 4, 4, 3, 4, 7, 5, 4, 4, 0, 1, 2, 0x77];
+#[rustfmt::skip]
+const CHROMA_AC_VALUES: [u8; 162] = [
+    0x00, 0x01, 0x02, 0x03, 0x11, 0x04, 0x05, 0x21, 0x31, 0x06, 0x12, 0x41, 0x51, 0x07, 0x61, 0x71,
+    0x13, 0x22, 0x32, 0x81, 0x08, 0x14, 0x42, 0x91, 0xA1, 0xB1, 0xC1, 0x09, 0x23, 0x33, 0x52, 0xF0,
+    0x15, 0x62, 0x72, 0xD1, 0x0A, 0x16, 0x24, 0x34, 0xE1, 0x25, 0xF1, 0x17, 0x18, 0x19, 0x1A, 0x26,
+    0x27, 0x28, 0x29, 0x2A, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3A, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48,
+    0x49, 0x4A, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5A, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68,
+    0x69, 0x6A, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7A, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87,
+    0x88, 0x89, 0x8A, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9A, 0xA2, 0xA3, 0xA4, 0xA5,
+    0xA6, 0xA7, 0xA8, 0xA9, 0xAA, 0xB2, 0xB3, 0xB4, 0xB5, 0xB6, 0xB7, 0xB8, 0xB9, 0xBA, 0xC2, 0xC3,
+    0xC4, 0xC5, 0xC6, 0xC7, 0xC8, 0xC9, 0xCA, 0xD2, 0xD3, 0xD4, 0xD5, 0xD6, 0xD7, 0xD8, 0xD9, 0xDA,
+    0xE2, 0xE3, 0xE4, 0xE5, 0xE6, 0xE7, 0xE8, 0xE9, 0xEA, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7, 0xF8,
+    0xF9, 0xFA,
+];
+
+type Block = [i16; 64];
+
+struct Component {
+    id: u8,
+    h: usize,
+    v: usize,
+    table: usize,
+    /// Blocks in rows, padded out to whole MCUs.
+    blocks: Vec<Block>,
+    blocks_wide: usize,
+    blocks_high: usize,
+}
+
+struct Image {
+    width: usize,
+    height: usize,
+    components: Vec<Component>,
+    /// Quantization tables in natural order, by table number.
+    tables: [Option<[u16; 64]>; 4],
+    /// APPn and COM segments, kept as they were apart from the orientation.
+    extra: Vec<(u8, Vec<u8>)>,
+}
+
+impl Image {
+    fn max_h(&self) -> usize {
+        self.components.iter().map(|c| c.h).max().unwrap_or(1)
+    }
+
+    fn max_v(&self) -> usize {
+        self.components.iter().map(|c| c.v).max().unwrap_or(1)
+    }
+}
+
+/// `data`, a JPEG with EXIF orientation `orientation`, turned upright and
+/// tagged as upright. `None` if it isn't a JPEG this can transform.
+pub fn upright(data: &[u8], orientation: u32) -> Option<Vec<u8>> {
+    let mut image = parse(data)?;
+    let ops: &[Op] = match orientation {
+        2 => &[Op::FlipH],
+        3 => &[Op::FlipH, Op::FlipV],
+        4 => &[Op::FlipV],
+        5 => &[Op::Transpose],
+        6 => &[Op::Transpose, Op::FlipH],
+        7 => &[Op::Transpose, Op::FlipH, Op::FlipV],
+        8 => &[Op::Transpose, Op::FlipV],
+        _ => &[],
+    };
+    for op in ops {
+        match op {
+            Op::FlipH => flip_h(&mut image)?,
+            Op::FlipV => flip_v(&mut image)?,
+            Op::Transpose => transpose(&mut image),
+        }
+    }
+    encode(&image)
+}
+
+enum Op {
+    FlipH,
+    FlipV,
+    Transpose,
+}
+
+// --- Reading ---
+
+fn parse(data: &[u8]) -> Option<Image> {
+    if data.get(..2)? != [0xFF, 0xD8] {
+        return None;
+    }
+    let mut image = Image {
+        width: 0,
+        height: 0,
+        components: Vec::new(),
+        tables: [None; 4],
+        extra: Vec::new(),
+    };
+    let mut dc_tables: [Option<Huffman>; 4] = Default::default();
+    let mut ac_tables: [Option<Huffman>; 4] = Default::default();
+    let mut restart_interval = 0;
+    let mut pos = 2;
+    loop {
+        if *data.get(pos)? != 0xFF {
+            return None;
+        }
+        while *data.get(pos + 1)? == 0xFF {
+            pos += 1;
+        }
+        let marker = data[pos + 1];
+        pos += 2;
+        match marker {
+            0xD9 => break,
+            0x01 | 0xD0..=0xD7 => continue,
+            _ => {}
+        }
+        let len = usize::from(u16::from_be_bytes([*data.get(pos)?, *data.get(pos + 1)?]));
+        let segment = data.get(pos + 2..pos + len)?;
+        pos += len;
+        match marker {
+            0xE0..=0xEF | 0xFE => {
+                let mut segment = segment.to_vec();
+                if marker == 0xE1 {
+                    reset_exif_orientation(&mut segment);
+                }
+                image.extra.push((marker, segment));
+            }
+            0xDB => read_quant_tables(segment, &mut image.tables)?,
+            0xC0 | 0xC1 => read_frame(segment, &mut image)?,
+            0xC4 => read_huffman_tables(segment, &mut dc_tables, &mut ac_tables)?,
+            0xDD => {
+                restart_interval = usize::from(u16::from_be_bytes([*segment.first()?, *segment.get(1)?]));
+            }
+            0xDA => {
+                pos = read_scan(data, pos, segment, &mut image, &dc_tables, &ac_tables, restart_interval)?;
+            }
+            // Progressive, lossless, arithmetic coding or a height set later
+            0xC2 | 0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF | 0xDC => return None,
+            _ => {}
+        }
+    }
+    (!image.components.is_empty()).then_some(image)
+}
+
+/// Set the orientation in an APP1 Exif segment to 1, if it has one.
+fn reset_exif_orientation(segment: &mut [u8]) -> Option<()> {
+    let tiff = segment.strip_prefix(b"Exif\0\0")?;
+    let big_endian = match tiff.get(..2)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+    let u16_at = |tiff: &[u8], at: usize| -> Option<u16> {
+        let bytes = [*tiff.get(at)?, *tiff.get(at + 1)?];
+        Some(if big_endian { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) })
+    };
+    let bytes = [*tiff.get(4)?, *tiff.get(5)?, *tiff.get(6)?, *tiff.get(7)?];
+    let ifd = if big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) } as usize;
+    let count = usize::from(u16_at(tiff, ifd)?);
+    let entry = (0..count)
+        .map(|i| ifd + 2 + i * 12)
+        .find(|&entry| u16_at(tiff, entry) == Some(0x0112))?;
+    let value = if big_endian { 1u16.to_be_bytes() } else { 1u16.to_le_bytes() };
+    let at = 6 + entry + 8;
+    segment.get_mut(at..at + 2)?.copy_from_slice(&value);
+    Some(())
+}
+
+fn read_quant_tables(mut segment: &[u8], tables: &mut [Option<[u16; 64]>; 4]) -> Option<()> {
+    while let Some(&info) = segment.first() {
+        let (wide, id) = (info >> 4 != 0, usize::from(info & 15));
+        let size = if wide { 128 } else { 64 };
+        let values = segment.get(1..1 + size)?;
+        let mut table = [0u16; 64];
+        for (k, &natural) in UNZIGZAG.iter().enumerate() {
+            table[natural] = if wide {
+                u16::from_be_bytes([values[2 * k], values[2 * k + 1]])
+            } else {
+                u16::from(values[k])
+            };
+        }
+        *tables.get_mut(id)? = Some(table);
+        segment = &segment[1 + size..];
+    }
+    Some(())
+}
+
+fn read_frame(segment: &[u8], image: &mut Image) -> Option<()> {
+    if *segment.first()? != 8 || !image.components.is_empty() {
+        return None;
+    }
+    image.height = usize::from(u16::from_be_bytes([*segment.get(1)?, *segment.get(2)?]));
+    image.width = usize::from(u16::from_be_bytes([*segment.get(3)?, *segment.get(4)?]));
+    let count = usize::from(*segment.get(5)?);
+    if image.width == 0 || image.height == 0 || count == 0 {
+        return None;
+    }
+    for i in 0..count {
+        let spec = segment.get(6 + i * 3..9 + i * 3)?;
+        let (h, v) = (usize::from(spec[1] >> 4), usize::from(spec[1] & 15));
+        if !(1..=4).contains(&h) || !(1..=4).contains(&v) || spec[2] > 3 {
+            return None;
+        }
+        image.components.push(Component {
+            id: spec[0],
+            h,
+            v,
+            table: usize::from(spec[2]),
+            blocks: Vec::new(),
+            blocks_wide: 0,
+            blocks_high: 0,
+        });
+    }
+    let (mcu_w, mcu_h) = (8 * image.max_h(), 8 * image.max_v());
+    let (mcus_x, mcus_y) = (image.width.div_ceil(mcu_w), image.height.div_ceil(mcu_h));
+    for c in &mut image.components {
+        c.blocks_wide = mcus_x * c.h;
+        c.blocks_high = mcus_y * c.v;
+        c.blocks = vec![[0; 64]; c.blocks_wide * c.blocks_high];
+    }
+    Some(())
+}
+
+/// A Huffman table for decoding, in the form of the standard's F.2.2.3.
+struct Huffman {
+    max_code: [i32; 17],
+    offset: [i32; 17],
+    values: Vec<u8>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8], values: &[u8]) -> Self {
+        let mut max_code = [-1; 17];
+        let mut offset = [0; 17];
+        let (mut code, mut k) = (0i32, 0i32);
+        for len in 1..=16 {
+            let n = i32::from(lengths[len - 1]);
+            offset[len] = k - code;
+            code += n;
+            k += n;
+            if n > 0 {
+                max_code[len] = code - 1;
+            }
+            code <<= 1;
+        }
+        Self {
+            max_code,
+            offset,
+            values: values.to_vec(),
+        }
+    }
+
+    fn decode(&self, bits: &mut BitReader) -> Option<u8> {
+        let mut code = 0i32;
+        for len in 1..=16 {
+            code = (code << 1) | bits.bit() as i32;
+            if code <= self.max_code[len] {
+                return self.values.get(usize::try_from(code + self.offset[len]).ok()?).copied();
+            }
+        }
+        None
+    }
+}
+
+fn read_huffman_tables(
+    mut segment: &[u8],
+    dc: &mut [Option<Huffman>; 4],
+    ac: &mut [Option<Huffman>; 4],
+) -> Option<()> {
+    while let Some(&info) = segment.first() {
+        let lengths = segment.get(1..17)?;
+        let count: usize = lengths.iter().map(|&n| usize::from(n)).sum();
+        let values = segment.get(17..17 + count)?;
+        let table = Some(Huffman::new(lengths, values));
+        match info >> 4 {
+            0 => *dc.get_mut(usize::from(info & 15))? = table,
+            1 => *ac.get_mut(usize::from(info & 15))? = table,
+            _ => return None,
+        }
+        segment = &segment[17 + count..];
+    }
+    Some(())
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    byte: u8,
+    left: u8,
+}
+
+impl BitReader<'_> {
+    /// The next bit; zeros once a marker ends the data.
+    fn bit(&mut self) -> u8 {
+        if self.left == 0 {
+            self.byte = match (self.data.get(self.pos), self.data.get(self.pos + 1)) {
+                (Some(0xFF), Some(0x00)) => {
+                    self.pos += 2;
+                    0xFF
+                }
+                (Some(0xFF), _) | (None, _) => 0,
+                (Some(&byte), _) => {
+                    self.pos += 1;
+                    byte
+                }
+            };
+            self.left = 8;
+        }
+        self.left -= 1;
+        (self.byte >> self.left) & 1
+    }
+
+    fn bits(&mut self, n: u8) -> i32 {
+        (0..n).fold(0, |acc, _| (acc << 1) | i32::from(self.bit()))
+    }
+
+    /// Skip to the byte after the restart marker that should come next.
+    fn restart(&mut self) -> Option<()> {
+        self.left = 0;
+        while self.data.get(self.pos) == Some(&0xFF) && self.data.get(self.pos + 1) == Some(&0xFF) {
+            self.pos += 1;
+        }
+        match (self.data.get(self.pos), self.data.get(self.pos + 1)) {
+            (Some(0xFF), Some(0xD0..=0xD7)) => {
+                self.pos += 2;
+                Some(())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// `value`'s `size` low bits as the signed number they code (F.2.2.1).
+fn extend(value: i32, size: u8) -> i32 {
+    if size == 0 || value >= 1 << (size - 1) {
+        value
+    } else {
+        value - (1 << size) + 1
+    }
+}
+
+/// Decode one scan into `image`'s blocks and return where its data ends.
+fn read_scan(
+    data: &[u8],
+    start: usize,
+    header: &[u8],
+    image: &mut Image,
+    dc_tables: &[Option<Huffman>; 4],
+    ac_tables: &[Option<Huffman>; 4],
+    restart_interval: usize,
+) -> Option<usize> {
+    let count = usize::from(*header.first()?);
+    let mut scan = Vec::with_capacity(count);
+    for i in 0..count {
+        let spec = header.get(1 + i * 2..3 + i * 2)?;
+        let index = image.components.iter().position(|c| c.id == spec[0])?;
+        let dc = dc_tables.get(usize::from(spec[1] >> 4))?.as_ref()?;
+        let ac = ac_tables.get(usize::from(spec[1] & 15))?.as_ref()?;
+        scan.push((index, dc, ac));
+    }
+    if header.get(1 + count * 2..4 + count * 2)? != [0, 63, 0] {
+        return None;
+    }
+
+    // Each unit is an MCU, or one block of the component in a scan of one.
+    let mut units: Vec<Vec<(usize, usize)>> = Vec::new();
+    if let [(index, ..)] = scan[..] {
+        let c = &image.components[index];
+        let across = (image.width * c.h).div_ceil(image.max_h()).div_ceil(8);
+        let down = (image.height * c.v).div_ceil(image.max_v()).div_ceil(8);
+        for row in 0..down {
+            units.extend((0..across).map(|col| vec![(index, row * c.blocks_wide + col)]));
+        }
+    } else {
+        let (mcus_x, mcus_y) = (
+            image.components[0].blocks_wide / image.components[0].h,
+            image.components[0].blocks_high / image.components[0].v,
+        );
+        for my in 0..mcus_y {
+            for mx in 0..mcus_x {
+                let mut unit = Vec::new();
+                for &(index, ..) in &scan {
+                    let c = &image.components[index];
+                    for by in 0..c.v {
+                        for bx in 0..c.h {
+                            unit.push((index, (my * c.v + by) * c.blocks_wide + mx * c.h + bx));
+                        }
+                    }
+                }
+                units.push(unit);
+            }
+        }
+    }
+
+    let mut bits = BitReader {
+        data,
+        pos: start,
+        byte: 0,
+        left: 0,
+    };
+    let mut predictions = vec![0i32; image.components.len()];
+    for (n, unit) in units.iter().enumerate() {
+        if restart_interval > 0 && n > 0 && n % restart_interval == 0 {
+            bits.restart()?;
+            predictions.fill(0);
+        }
+        for &(index, block) in unit {
+            let (_, dc, ac) = scan.iter().find(|(i, ..)| *i == index)?;
+            let coefficients = image.components[index].blocks.get_mut(block)?;
+            let size = dc.decode(&mut bits)?;
+            predictions[index] += extend(bits.bits(size), size);
+            coefficients[0] = i16::try_from(predictions[index]).ok()?;
+            let mut k = 1;
+            while k < 64 {
+                let symbol = ac.decode(&mut bits)?;
+                let (run, size) = (usize::from(symbol >> 4), symbol & 15);
+                if size == 0 {
+                    if run != 15 {
+                        break;
+                    }
+                    k += 16;
+                    continue;
+                }
+                k += run;
+                let natural = *UNZIGZAG.get(k)?;
+                coefficients[natural] = i16::try_from(extend(bits.bits(size), size)).ok()?;
+                k += 1;
+            }
+        }
+    }
+
+    // The scan's data runs to the next marker that isn't a restart
+    let mut pos = bits.pos;
+    while pos + 1 < data.len() {
+        if data[pos] == 0xFF && !matches!(data[pos + 1], 0x00 | 0xD0..=0xD7 | 0xFF) {
+            return Some(pos);
+        }
+        pos += 1;
+    }
+    None
+}
+
+// --- Transforming ---
+
+/// Mirror left to right, first trimming a partial MCU column off the right.
+fn flip_h(image: &mut Image) -> Option<()> {
+    let mcu_w = 8 * image.max_h();
+    let mcus_x = image.width / mcu_w;
+    if mcus_x == 0 {
+        return None;
+    }
+    image.width = mcus_x * mcu_w;
+    for c in &mut image.components {
+        let wide = mcus_x * c.h;
+        let mut blocks = Vec::with_capacity(wide * c.blocks_high);
+        for row in c.blocks.chunks(c.blocks_wide) {
+            blocks.extend(row[..wide].iter().rev().map(|block| negate_odd(block, |u, _| u)));
+        }
+        c.blocks = blocks;
+        c.blocks_wide = wide;
+    }
+    Some(())
+}
+
+/// Mirror top to bottom, first trimming a partial MCU row off the bottom.
+fn flip_v(image: &mut Image) -> Option<()> {
+    let mcu_h = 8 * image.max_v();
+    let mcus_y = image.height / mcu_h;
+    if mcus_y == 0 {
+        return None;
+    }
+    image.height = mcus_y * mcu_h;
+    for c in &mut image.components {
+        let high = mcus_y * c.v;
+        let mut blocks = Vec::with_capacity(c.blocks_wide * high);
+        for row in c.blocks.chunks(c.blocks_wide).take(high).rev() {
+            blocks.extend(row.iter().map(|block| negate_odd(block, |_, v| v)));
+        }
+        c.blocks = blocks;
+        c.blocks_high = high;
+    }
+    Some(())
+}
+
+/// Swap rows and columns, which needs no trimming.
+fn transpose(image: &mut Image) {
+    std::mem::swap(&mut image.width, &mut image.height);
+    for c in &mut image.components {
+        let mut blocks = vec![[0; 64]; c.blocks.len()];
+        for y in 0..c.blocks_high {
+            for x in 0..c.blocks_wide {
+                blocks[x * c.blocks_high + y] = transposed(&c.blocks[y * c.blocks_wide + x]);
+            }
+        }
+        c.blocks = blocks;
+        std::mem::swap(&mut c.blocks_wide, &mut c.blocks_high);
+        std::mem::swap(&mut c.h, &mut c.v);
+    }
+    for table in image.tables.iter_mut().flatten() {
+        let original = *table;
+        for (i, value) in table.iter_mut().enumerate() {
+            *value = original[(i % 8) * 8 + i / 8];
+        }
+    }
+}
+
+/// `block` with the coefficients whose `freq(u, v)` is odd negated, which
+/// mirrors the 8×8 pixels it codes along that axis.
+fn negate_odd(block: &Block, freq: impl Fn(usize, usize) -> usize) -> Block {
+    let mut out = *block;
+    for (i, value) in out.iter_mut().enumerate() {
+        if freq(i % 8, i / 8) % 2 == 1 {
+            *value = -*value;
+        }
+    }
+    out
+}
+
+fn transposed(block: &Block) -> Block {
+    let mut out = [0; 64];
+    for (i, value) in out.iter_mut().enumerate() {
+        *value = block[(i % 8) * 8 + i / 8];
+    }
+    out
+}
+
+// --- Writing ---
+
+fn segment(out: &mut Vec<u8>, marker: u8, body: &[u8]) {
+    out.extend([0xFF, marker]);
+    out.extend(((body.len() + 2) as u16).to_be_bytes());
+    out.extend(body);
+}
+
+/// Code and length for each symbol a Huffman table codes.
+fn huffman_codes(lengths: &[u8; 16], values: &[u8]) -> [Option<(u16, u8)>; 256] {
+    let mut codes = [None; 256];
+    let (mut code, mut k) = (0u16, 0);
+    for (len, &n) in (1..=16).zip(lengths) {
+        for &value in &values[k..k + usize::from(n)] {
+            codes[usize::from(value)] = Some((code, len));
+            code += 1;
+        }
+        k += usize::from(n);
+        code <<= 1;
+    }
+    codes
+}
+
+#[derive(Default)]
+struct BitWriter {
+    out: Vec<u8>,
+    acc: u32,
+    filled: u8,
+}
+
+impl BitWriter {
+    fn put(&mut self, value: u16, len: u8) {
+        for i in (0..len).rev() {
+            self.acc = (self.acc << 1) | u32::from((value >> i) & 1);
+            self.filled += 1;
+            if self.filled == 8 {
+                self.push_byte();
+            }
+        }
+    }
+
+    fn push_byte(&mut self) {
+        let byte = self.acc as u8;
+        self.out.push(byte);
+        if byte == 0xFF {
+            self.out.push(0x00);
+        }
+        self.acc = 0;
+        self.filled = 0;
+    }
+
+    /// Pad the last byte with ones, as the standard asks.
+    fn finish(mut self) -> Vec<u8> {
+        while self.filled != 0 {
+            self.put(1, 1);
+        }
+        self.out
+    }
+}
+
+/// Bits needed for `value`'s magnitude, and those bits as coded (F.1.2.1).
+fn magnitude(value: i32) -> (u8, u16) {
+    let size = (32 - value.unsigned_abs().leading_zeros()) as u8;
+    let bits = if value < 0 { value - 1 } else { value };
+    (size, (bits & ((1 << size) - 1)) as u16)
+}
+
+fn encode(image: &Image) -> Option<Vec<u8>> {
+    let blocks_per_mcu: usize = image.components.iter().map(|c| c.h * c.v).sum();
+    if image.components.len() > 4 || (image.components.len() > 1 && blocks_per_mcu > 10) {
+        return None;
+    }
+    let width = u16::try_from(image.width).ok()?;
+    let height = u16::try_from(image.height).ok()?;
+    let mut out = vec![0xFF, 0xD8];
+    for (marker, body) in &image.extra {
+        segment(&mut out, *marker, body);
+    }
+
+    let mut wide_tables = false;
+    for (id, table) in image.tables.iter().enumerate() {
+        let Some(table) = table else {
+            continue;
+        };
+        let wide = table.iter().any(|&q| q > 255);
+        wide_tables |= wide;
+        let mut body = vec![(u8::from(wide) << 4) | id as u8];
+        for &natural in &UNZIGZAG {
+            if wide {
+                body.extend(table[natural].to_be_bytes());
+            } else {
+                body.push(table[natural] as u8);
+            }
+        }
+        segment(&mut out, 0xDB, &body);
+    }
+
+    let mut frame = vec![8];
+    frame.extend(height.to_be_bytes());
+    frame.extend(width.to_be_bytes());
+    frame.push(image.components.len() as u8);
+    for c in &image.components {
+        frame.extend([c.id, ((c.h << 4) | c.v) as u8, c.table as u8]);
+    }
+    // Baseline only allows 8-bit quantization tables
+    segment(&mut out, if wide_tables { 0xC1 } else { 0xC0 }, &frame);
+
+    let standard = [
+        (0x00, &LUMA_DC_LENGTHS, &DC_VALUES[..]),
+        (0x10, &LUMA_AC_LENGTHS, &LUMA_AC_VALUES[..]),
+        (0x01, &CHROMA_DC_LENGTHS, &DC_VALUES[..]),
+        (0x11, &CHROMA_AC_LENGTHS, &CHROMA_AC_VALUES[..]),
+    ];
+    for (info, lengths, values) in standard {
+        let mut body = vec![info];
+        body.extend(lengths);
+        body.extend(values);
+        segment(&mut out, 0xC4, &body);
+    }
+    let luma = (huffman_codes(&LUMA_DC_LENGTHS, &DC_VALUES), huffman_codes(&LUMA_AC_LENGTHS, &LUMA_AC_VALUES));
+    let chroma = (
+        huffman_codes(&CHROMA_DC_LENGTHS, &DC_VALUES),
+        huffman_codes(&CHROMA_AC_LENGTHS, &CHROMA_AC_VALUES),
+    );
+
+    let mut scan = vec![image.components.len() as u8];
+    for (i, c) in image.components.iter().enumerate() {
+        scan.extend([c.id, if i == 0 { 0x00 } else { 0x11 }]);
+    }
+    scan.extend([0, 63, 0]);
+    segment(&mut out, 0xDA, &scan);
+
+    let mut bits = BitWriter::default();
+    let mut predictions = vec![0i32; image.components.len()];
+    let mut write_block = |bits: &mut BitWriter, index: usize, block: &Block| -> Option<()> {
+        let (dc, ac) = if index == 0 { &luma } else { &chroma };
+        let diff = i32::from(block[0]) - predictions[index];
+        predictions[index] = i32::from(block[0]);
+        let (size, value) = magnitude(diff);
+        let (code, len) = dc[usize::from(size)]?;
+        bits.put(code, len);
+        bits.put(value, size);
+        let mut run = 0;
+        for &natural in &UNZIGZAG[1..] {
+            let coefficient = i32::from(block[natural]);
+            if coefficient == 0 {
+                run += 1;
+                continue;
+            }
+            while run > 15 {
+                let (code, len) = ac[0xF0]?;
+                bits.put(code, len);
+                run -= 16;
+            }
+            let (size, value) = magnitude(coefficient);
+            let (code, len) = ac[(run << 4) | usize::from(size)]?;
+            bits.put(code, len);
+            bits.put(value, size);
+            run = 0;
+        }
+        if run > 0 {
+            let (code, len) = ac[0x00]?;
+            bits.put(code, len);
+        }
+        Some(())
+    };
+
+    if let [c] = &image.components[..] {
+        // A scan of one component covers only the blocks the image reaches
+        let (across, down) = (image.width.div_ceil(8), image.height.div_ceil(8));
+        for row in 0..down {
+            for col in 0..across {
+                write_block(&mut bits, 0, &c.blocks[row * c.blocks_wide + col])?;
+            }
+        }
+    } else {
+        let first = &image.components[0];
+        let (mcus_x, mcus_y) = (first.blocks_wide / first.h, first.blocks_high / first.v);
+        for my in 0..mcus_y {
+            for mx in 0..mcus_x {
+                for (index, c) in image.components.iter().enumerate() {
+                    for by in 0..c.v {
+                        for bx in 0..c.h {
+                            let block = &c.blocks[(my * c.v + by) * c.blocks_wide + mx * c.h + bx];
+                            write_block(&mut bits, index, block)?;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    out.extend(bits.finish());
+    out.extend([0xFF, 0xD9]);
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thumbnail::upright_rgba;
+    use image::{GenericImageView, RgbImage};
+
+    /// A gradient with some detail, which the image crate encodes without
+    /// chroma subsampling, so in 8×8 MCUs.
+    fn unsubsampled_jpeg(width: u32, height: u32) -> Vec<u8> {
+        let img = RgbImage::from_fn(width, height, |x, y| {
+            image::Rgb([(x * 255 / width) as u8, (y * 255 / height) as u8, ((x * 7 + y * 13) % 256) as u8])
+        });
+        let mut data = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut data, 90)
+            .encode_image(&img)
+            .unwrap();
+        data
+    }
+
+    /// A 4:2:0 JPEG, in 16×16 MCUs as most cameras write them, built from
+    /// made-up coefficients since nothing here encodes one.
+    fn subsampled_jpeg(width: usize, height: usize) -> Vec<u8> {
+        let (mcus_x, mcus_y) = (width.div_ceil(16), height.div_ceil(16));
+        let component = |id: u8, h: usize, v: usize, table: usize| {
+            let (blocks_wide, blocks_high) = (mcus_x * h, mcus_y * v);
+            let blocks = (0..blocks_wide * blocks_high)
+                .map(|n| {
+                    let mut block = [0; 64];
+                    let seed = n as i32 * 37 + i32::from(id) * 11;
+                    block[0] = (seed * 53 % 801 - 400) as i16;
+                    for (k, &natural) in UNZIGZAG[1..6].iter().enumerate() {
+                        block[natural] = ((seed + k as i32 * 17) * 29 % 61 - 30) as i16;
+                    }
+                    block
+                })
+                .collect();
+            Component { id, h, v, table, blocks, blocks_wide, blocks_high }
+        };
+        let image = Image {
+            width,
+            height,
+            components: vec![component(1, 2, 2, 0), component(2, 1, 1, 1), component(3, 1, 1, 1)],
+            tables: [Some([2; 64]), Some([3; 64]), None, None],
+            extra: Vec::new(),
+        };
+        encode(&image).unwrap()
+    }
+
+    /// Turn `data` to every orientation but 1 and check each against the
+    /// decoded photo turned upright, after trimming the partial MCUs a flip
+    /// drops from the source.
+    fn check_all_orientations(data: &[u8], (mcu_w, mcu_h): (u32, u32)) {
+        let source = image::load_from_memory(data).unwrap();
+        let (width, height) = source.dimensions();
+        for orientation in 2..=8 {
+            let turned = upright(data, orientation).expect("transformable JPEG");
+            let actual = image::load_from_memory(&turned).unwrap().to_rgba8();
+
+            let kept_w = if matches!(orientation, 2 | 3 | 7 | 8) { width / mcu_w * mcu_w } else { width };
+            let kept_h = if matches!(orientation, 3 | 4 | 6 | 7) { height / mcu_h * mcu_h } else { height };
+            let expected = upright_rgba(source.crop_imm(0, 0, kept_w, kept_h), orientation);
+            assert_eq!(actual.dimensions(), expected.dimensions(), "orientation {orientation}");
+
+            // Upsampled chroma along a trimmed edge still blends in the
+            // dropped MCU in `expected`, so leave the outermost pixels out
+            let trimmed = (kept_w, kept_h) != (width, height);
+            let (out_w, out_h) = actual.dimensions();
+            let inside = |x: u32, y: u32| !trimmed || (x > 0 && y > 0 && x + 1 < out_w && y + 1 < out_h);
+            let diffs: Vec<u8> = actual
+                .enumerate_pixels()
+                .filter(|&(x, y, _)| inside(x, y))
+                .flat_map(|(x, y, p)| p.0.into_iter().zip(expected.get_pixel(x, y).0).map(|(a, b)| a.abs_diff(b)))
+                .collect();
+            let mean = diffs.iter().map(|&d| f64::from(d)).sum::<f64>() / diffs.len() as f64;
+            assert!(mean < 1.0, "orientation {orientation}: mean difference {mean}");
+        }
+    }
+
+    #[test]
+    fn turns_mcu_aligned_jpeg() {
+        check_all_orientations(&unsubsampled_jpeg(32, 24), (8, 8));
+    }
+
+    #[test]
+    fn trims_partial_mcus() {
+        check_all_orientations(&unsubsampled_jpeg(37, 21), (8, 8));
+    }
+
+    #[test]
+    fn turns_subsampled_jpeg() {
+        check_all_orientations(&subsampled_jpeg(32, 48), (16, 16));
+    }
+
+    #[test]
+    fn trims_partial_subsampled_mcus() {
+        check_all_orientations(&subsampled_jpeg(37, 29), (16, 16));
+    }
+}
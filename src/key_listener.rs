@@ -33,13 +33,16 @@ struct State {
     pinch_last_distance: Option<f32>,
     /// True after a pinch ends, to prevent the remaining finger from triggering drag.
     was_pinching: bool,
+    /// Modifier keys currently held, for scroll callbacks.
+    modifiers: keyboard::Modifiers,
 }
 
 pub struct KeyListener<'a, Message, Theme = iced::Theme, Renderer = iced::Renderer> {
     content: Element<'a, Message, Theme, Renderer>,
-    on_key_press: Box<dyn Fn(keyboard::Key, bool) -> Option<Message> + 'a>,
-    /// Called on scroll events with (delta, cursor_x, cursor_y).
-    on_scroll: Option<Box<dyn Fn(f32, f32, f32) -> Option<Message> + 'a>>,
+    /// Called on key press with (key, modifiers, repeat).
+    on_key_press: Box<dyn Fn(keyboard::Key, keyboard::Modifiers, bool) -> Option<Message> + 'a>,
+    /// Called on scroll events with (delta, cursor_x, cursor_y, modifiers).
+    on_scroll: Option<Box<dyn Fn(f32, f32, f32, keyboard::Modifiers) -> Option<Message> + 'a>>,
     /// Called on mouse drag with (dx, dy). Return Some to consume the event.
     on_drag: Option<Box<dyn Fn(f32, f32) -> Option<Message> + 'a>>,
     /// Called on left click (press+release without drag) with (cursor_x, cursor_y).
@@ -53,7 +56,7 @@ pub struct KeyListener<'a, Message, Theme = iced::Theme, Renderer = iced::Render
 impl<'a, Message, Theme, Renderer> KeyListener<'a, Message, Theme, Renderer> {
     pub fn new(
         content: impl Into<Element<'a, Message, Theme, Renderer>>,
-        on_key_press: impl Fn(keyboard::Key, bool) -> Option<Message> + 'a,
+        on_key_press: impl Fn(keyboard::Key, keyboard::Modifiers, bool) -> Option<Message> + 'a,
     ) -> Self {
         Self {
             content: content.into(),
@@ -68,7 +71,7 @@ impl<'a, Message, Theme, Renderer> KeyListener<'a, Message, Theme, Renderer> {
 
     pub fn on_scroll(
         mut self,
-        f: impl Fn(f32, f32, f32) -> Option<Message> + 'a,
+        f: impl Fn(f32, f32, f32, keyboard::Modifiers) -> Option<Message> + 'a,
     ) -> Self {
         self.on_scroll = Some(Box::new(f));
         self
@@ -180,6 +183,9 @@ where
                     state.last_pos = Some(pos);
                 }
             }
+            Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                state.modifiers = *modifiers;
+            }
             Event::Mouse(mouse::Event::CursorMoved { .. }) => {
                 if state.pressed {
                     if let (Some(press), Some(pos)) = (state.press_pos, cursor.position()) {
@@ -223,7 +229,7 @@ where
                         .position()
                         .map(|p| (p.x, p.y))
                         .unwrap_or((0.0, 0.0));
-                    if let Some(message) = on_scroll(y, cx, cy) {
+                    if let Some(message) = on_scroll(y, cx, cy, state.modifiers) {
                         shell.publish(message);
                         return;
                     }
@@ -351,8 +357,8 @@ where
                     }
                 }
             }
-            Event::Keyboard(keyboard::Event::KeyPressed { key, repeat, .. }) => {
-                if let Some(message) = (self.on_key_press)(key.clone(), *repeat) {
+            Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, repeat, .. }) => {
+                if let Some(message) = (self.on_key_press)(key.clone(), *modifiers, *repeat) {
                     shell.publish(message);
                 }
             }
@@ -1,14 +1,36 @@
 mod app;
 mod catalog;
+mod cli;
+mod contact_sheet;
+mod decoder;
 mod duplicates;
+mod edit;
+mod export;
+mod file_ops;
+mod file_queue;
+mod filter;
+mod focus;
+mod grid_layout;
+mod i18n;
+mod import;
+mod jpeg_rotate;
 mod key_listener;
 mod metadata;
 mod server;
+mod settings;
+mod share;
+mod slideshow;
+mod status;
 mod thumbnail;
+mod trash;
 mod viewer;
 mod watcher;
 
 fn main() -> iced::Result {
     env_logger::init();
+    let args: Vec<std::ffi::OsString> = std::env::args_os().skip(1).collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
     app::run()
 }
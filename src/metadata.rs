@@ -1,4 +1,10 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use rayon::prelude::*;
+
+use crate::focus::{self, FocusArea};
+use crate::i18n::{self, Language, tr};
 
 pub struct PhotoMetadata {
     pub filename: String,
@@ -34,6 +40,8 @@ pub struct PhotoMetadata {
     pub gps_latitude: Option<f64>,
     pub gps_longitude: Option<f64>,
     pub gps_altitude: Option<String>,
+    // Autofocus
+    pub focus_areas: Vec<FocusArea>,
 }
 
 pub fn read_metadata(path: &Path) -> PhotoMetadata {
@@ -82,6 +90,7 @@ pub fn read_metadata(path: &Path) -> PhotoMetadata {
         gps_latitude: e.and_then(|d| d.gps_latitude),
         gps_longitude: e.and_then(|d| d.gps_longitude),
         gps_altitude: e.and_then(|d| d.gps_altitude.clone()),
+        focus_areas: e.map(|d| d.focus_areas.clone()).unwrap_or_default(),
     }
 }
 
@@ -109,6 +118,7 @@ struct ExifData {
     gps_latitude: Option<f64>,
     gps_longitude: Option<f64>,
     gps_altitude: Option<String>,
+    focus_areas: Vec<FocusArea>,
 }
 
 fn read_exif(path: &Path) -> Option<ExifData> {
@@ -121,10 +131,14 @@ fn read_exif(path: &Path) -> Option<ExifData> {
             .map(|f| f.display_value().to_string())
     };
 
+    let orientation = exif
+        .get_field(exif::Tag::Orientation, exif::In::PRIMARY)
+        .and_then(|f| f.value.get_uint(0));
+    // Focus areas follow the image as shown
+    let shown_orientation = crate::thumbnail::orientation_override(path).or(orientation);
+
     Some(ExifData {
-        orientation: exif
-            .get_field(exif::Tag::Orientation, exif::In::PRIMARY)
-            .and_then(|f| f.value.get_uint(0)),
+        orientation,
         date_taken: get_str(exif::Tag::DateTimeOriginal),
         camera_make: get_str(exif::Tag::Make),
         camera_model: get_str(exif::Tag::Model),
@@ -153,6 +167,7 @@ fn read_exif(path: &Path) -> Option<ExifData> {
         gps_latitude: parse_gps_coord(&exif, exif::Tag::GPSLatitude, exif::Tag::GPSLatitudeRef),
         gps_longitude: parse_gps_coord(&exif, exif::Tag::GPSLongitude, exif::Tag::GPSLongitudeRef),
         gps_altitude: get_str(exif::Tag::GPSAltitude),
+        focus_areas: focus::read_focus_areas(&exif, shown_orientation.unwrap_or(1)),
     })
 }
 
@@ -177,6 +192,127 @@ fn parse_gps_coord(exif: &exif::Exif, coord_tag: exif::Tag, ref_tag: exif::Tag)
     Some(coord)
 }
 
+/// What the catalog indexes about each photo, so the grid can be filtered
+/// without opening every file again.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PhotoInfo {
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub lens_model: Option<String>,
+    /// Pixel size as stored, before `orientation` is applied.
+    pub dimensions: Option<(u32, u32)>,
+    pub orientation: Option<u32>,
+    pub description: Option<String>,
+    /// Tags set in Windows Explorer or Lightroom's Windows export.
+    pub keywords: Vec<String>,
+}
+
+impl PhotoInfo {
+    /// Width and height as the photo is shown, i.e. turned upright.
+    pub fn display_size(&self) -> Option<(u32, u32)> {
+        let (w, h) = self.dimensions?;
+        // Orientations 5–8 are rotated a quarter turn
+        if matches!(self.orientation, Some(5..=8)) {
+            Some((h, w))
+        } else {
+            Some((w, h))
+        }
+    }
+
+    /// Displayed width ÷ height.
+    pub fn aspect_ratio(&self) -> Option<f32> {
+        let (w, h) = self.display_size()?;
+        (h > 0).then(|| w as f32 / h as f32)
+    }
+
+    /// Make and model as one name, e.g. "Canon EOS R5". Many models
+    /// already start with the make, so it isn't repeated.
+    pub fn camera(&self) -> Option<String> {
+        match (self.camera_make.as_deref(), self.camera_model.as_deref()) {
+            (Some(make), Some(model)) => {
+                let brand = make.split_whitespace().next().unwrap_or(make);
+                if model.to_lowercase().starts_with(&brand.to_lowercase()) {
+                    Some(model.to_string())
+                } else {
+                    Some(format!("{} {}", make, model))
+                }
+            }
+            (make, model) => make.or(model).map(str::to_string),
+        }
+    }
+}
+
+pub fn read_photo_info(path: &Path) -> PhotoInfo {
+    let dimensions = image::image_dimensions(path).ok();
+    let exif = std::fs::File::open(path).ok().and_then(|file| {
+        let mut reader = std::io::BufReader::new(file);
+        exif::Reader::new().read_from_container(&mut reader).ok()
+    });
+    let Some(exif) = exif else {
+        return PhotoInfo {
+            dimensions,
+            ..PhotoInfo::default()
+        };
+    };
+    let uint_field = |tag| {
+        exif.get_field(tag, exif::In::PRIMARY)
+            .and_then(|f| f.value.get_uint(0))
+    };
+    // Formats the image crate can't open (RAW) still record their size in EXIF
+    let exif_dimensions = uint_field(exif::Tag::PixelXDimension)
+        .zip(uint_field(exif::Tag::PixelYDimension));
+    PhotoInfo {
+        camera_make: ascii_field(&exif, exif::Tag::Make),
+        camera_model: ascii_field(&exif, exif::Tag::Model),
+        lens_model: ascii_field(&exif, exif::Tag::LensModel),
+        dimensions: dimensions.or(exif_dimensions),
+        orientation: uint_field(exif::Tag::Orientation),
+        description: ascii_field(&exif, exif::Tag::ImageDescription),
+        keywords: xp_keywords(&exif),
+    }
+}
+
+pub fn read_photo_info_batch(paths: &[PathBuf]) -> Vec<(PathBuf, PhotoInfo)> {
+    paths
+        .par_iter()
+        .map(|path| (path.clone(), read_photo_info(path)))
+        .collect()
+}
+
+/// An ASCII field as plain text, without the quoting `display_value` adds
+/// or the padding some cameras leave in.
+fn ascii_field(exif: &exif::Exif, tag: exif::Tag) -> Option<String> {
+    let field = exif.get_field(tag, exif::In::PRIMARY)?;
+    let exif::Value::Ascii(values) = &field.value else {
+        return None;
+    };
+    let text = String::from_utf8_lossy(values.first()?);
+    let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// The Windows XPKeywords field: UTF-16, semicolon-separated.
+fn xp_keywords(exif: &exif::Exif) -> Vec<String> {
+    const XP_KEYWORDS: exif::Tag = exif::Tag(exif::Context::Tiff, 0x9c9e);
+    let Some(field) = exif.get_field(XP_KEYWORDS, exif::In::PRIMARY) else {
+        return Vec::new();
+    };
+    let exif::Value::Byte(bytes) = &field.value else {
+        return Vec::new();
+    };
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+        .trim_end_matches('\0')
+        .split(';')
+        .map(str::trim)
+        .filter(|keyword| !keyword.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
 /// Lightweight summary for duplicate comparison — avoids full EXIF parse.
 #[derive(Debug, Clone)]
 pub struct FileSummary {
@@ -214,7 +350,68 @@ pub fn read_file_summary(path: &Path) -> FileSummary {
     }
 }
 
-fn format_system_time(time: std::time::SystemTime) -> String {
+/// Time zone chosen in the settings over the system's.
+static TIME_ZONE: RwLock<Option<jiff::tz::TimeZone>> = RwLock::new(None);
+
+/// Show dates in the IANA zone `name` rather than the system's, or in the
+/// system's again with `None`. Returns false, changing nothing, if the zone
+/// isn't known.
+pub fn set_time_zone(name: Option<&str>) -> bool {
+    let zone = match name {
+        Some(name) => match jiff::tz::TimeZone::get(name) {
+            Ok(zone) => Some(zone),
+            Err(_) => return false,
+        },
+        None => None,
+    };
+    *TIME_ZONE.write().unwrap() = zone;
+    true
+}
+
+fn local_zone() -> jiff::tz::TimeZone {
+    TIME_ZONE.read().unwrap().clone().unwrap_or_else(jiff::tz::TimeZone::system)
+}
+
+/// `time` in the local time zone, written the way the current language
+/// writes dates.
+pub fn display_system_time(time: std::time::SystemTime) -> String {
+    jiff::Timestamp::try_from(time)
+        .map(|t| display_date(t.to_zoned(local_zone()).datetime()))
+        .unwrap_or_else(|_| format_system_time(time))
+}
+
+/// A date as [`format_system_time`] writes it (UTC), shown in the local time
+/// zone and the current language.
+pub fn display_modified(date: &str) -> String {
+    jiff::civil::DateTime::strptime("%Y-%m-%d %H:%M:%S", date)
+        .and_then(|utc| utc.to_zoned(jiff::tz::TimeZone::UTC))
+        .map(|utc| display_date(utc.with_time_zone(local_zone()).datetime()))
+        .unwrap_or_else(|_| date.to_string())
+}
+
+/// An EXIF date taken, e.g. `2024:03:05 14:32:10`, or the same in ISO 8601,
+/// in the current language. EXIF dates are the camera's local time, so
+/// there's no zone to convert.
+pub fn display_taken(date: &str) -> String {
+    ["%Y:%m:%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"]
+        .into_iter()
+        .find_map(|format| jiff::civil::DateTime::strptime(format, date).ok())
+        .map_or_else(|| date.to_string(), display_date)
+}
+
+fn display_date(date: jiff::civil::DateTime) -> String {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let month = tr(MONTHS[date.month() as usize - 1]);
+    let time = format!("{:02}:{:02}:{:02}", date.hour(), date.minute(), date.second());
+    match i18n::language() {
+        Language::English => format!("{month} {}, {}, {time}", date.day(), date.year()),
+        Language::German => format!("{}. {month} {}, {time}", date.day(), date.year()),
+    }
+}
+
+pub fn format_system_time(time: std::time::SystemTime) -> String {
     let duration = time
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap_or_default();
@@ -0,0 +1,186 @@
+//! JSON API for scripts and other apps on the LAN.
+//!
+//! Built by hand rather than pulling in serde — the payloads are small and flat.
+
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use crate::metadata;
+use crate::thumbnail;
+
+pub const DEFAULT_PER_PAGE: usize = 100;
+pub const MAX_PER_PAGE: usize = 1000;
+
+/// `/api/images`: one page of the shared image list, leaving out the
+/// `removed` ones.
+pub fn images_json(image_paths: &[PathBuf], removed: &HashSet<usize>, page: usize, per_page: usize) -> String {
+    let per_page = per_page.clamp(1, MAX_PER_PAGE);
+    let shown: Vec<(usize, &PathBuf)> = image_paths
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !removed.contains(i))
+        .collect();
+    let total = shown.len();
+    let start = page.saturating_mul(per_page).min(total);
+    let end = (start + per_page).min(total);
+
+    let items: Vec<String> = shown[start..end]
+        .iter()
+        .map(|&(i, path)| image_summary_json(i, path))
+        .collect();
+
+    JsonObject::new()
+        .num("total", total)
+        .num("page", page)
+        .num("per_page", per_page)
+        .raw("images", &format!("[{}]", items.join(",")))
+        .finish()
+}
+
+fn image_summary_json(index: usize, path: &Path) -> String {
+    let id = super::photo_id(path);
+    let v = thumbnail::version_token(path);
+    let fs_meta = std::fs::metadata(path).ok();
+    let size = fs_meta.as_ref().map(|m| m.len());
+    let modified = fs_meta
+        .as_ref()
+        .and_then(|m| m.modified().ok())
+        .map(metadata::format_system_time);
+    JsonObject::new()
+        .num("index", index)
+        .str("id", Some(&id))
+        .str("name", Some(&file_name(path)))
+        .opt_num("size", size)
+        .str("date_modified", modified.as_deref())
+        .str("image_url", Some(&format!("/api/image/{id}?v={v}")))
+        .str("original_url", Some(&format!("/original/{id}?v={v}")))
+        .str("thumb_url", Some(&format!("/thumb/{id}?v={v}")))
+        .finish()
+}
+
+/// `/api/meta/{i}`: full metadata for one image.
+pub fn meta_json(index: usize, path: &Path) -> String {
+    let m = metadata::read_metadata(path);
+    JsonObject::new()
+        .num("index", index)
+        .str("name", Some(&m.filename))
+        .num("size", m.file_size)
+        .opt_num("width", m.dimensions.map(|(w, _)| w))
+        .opt_num("height", m.dimensions.map(|(_, h)| h))
+        .opt_num("orientation", m.orientation)
+        .str("date_taken", m.date_taken.as_deref())
+        .str("date_modified", m.date_modified.as_deref())
+        .str("date_taken_display", m.date_taken.as_deref().map(metadata::display_taken).as_deref())
+        .str("date_modified_display", m.date_modified.as_deref().map(metadata::display_modified).as_deref())
+        .str("camera_make", m.camera_make.as_deref())
+        .str("camera_model", m.camera_model.as_deref())
+        .str("lens_model", m.lens_model.as_deref())
+        .str("software", m.software.as_deref())
+        .str("exposure_time", m.exposure_time.as_deref())
+        .str("f_number", m.f_number.as_deref())
+        .str("iso", m.iso.as_deref())
+        .str("focal_length", m.focal_length.as_deref())
+        .str("focal_length_35mm", m.focal_length_35mm.as_deref())
+        .str("exposure_bias", m.exposure_bias.as_deref())
+        .str("exposure_program", m.exposure_program.as_deref())
+        .str("metering_mode", m.metering_mode.as_deref())
+        .str("flash", m.flash.as_deref())
+        .str("white_balance", m.white_balance.as_deref())
+        .str("color_space", m.color_space.as_deref())
+        .str("artist", m.artist.as_deref())
+        .str("copyright", m.copyright.as_deref())
+        .str("description", m.description.as_deref())
+        .opt_num("gps_latitude", m.gps_latitude)
+        .opt_num("gps_longitude", m.gps_longitude)
+        .str("gps_altitude", m.gps_altitude.as_deref())
+        .finish()
+}
+
+/// `{"error": "..."}` body for API error responses.
+pub fn error_json(message: &str) -> String {
+    JsonObject::new().str("error", Some(message)).finish()
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Minimal builder for flat JSON objects. `None` values are written as `null`.
+pub struct JsonObject {
+    out: String,
+}
+
+impl JsonObject {
+    pub fn new() -> Self {
+        Self { out: String::from("{") }
+    }
+
+    fn key(&mut self, key: &str) {
+        if self.out.len() > 1 {
+            self.out.push(',');
+        }
+        self.out.push('"');
+        self.out.push_str(&json_escape(key));
+        self.out.push_str("\":");
+    }
+
+    pub fn str(mut self, key: &str, value: Option<&str>) -> Self {
+        self.key(key);
+        match value {
+            Some(v) => {
+                self.out.push('"');
+                self.out.push_str(&json_escape(v));
+                self.out.push('"');
+            }
+            None => self.out.push_str("null"),
+        }
+        self
+    }
+
+    pub fn num(self, key: &str, value: impl Display) -> Self {
+        self.raw(key, &value.to_string())
+    }
+
+    pub fn opt_num<T: Display>(self, key: &str, value: Option<T>) -> Self {
+        match value {
+            Some(v) => self.num(key, v),
+            None => self.raw(key, "null"),
+        }
+    }
+
+    pub fn bool(self, key: &str, value: bool) -> Self {
+        self.raw(key, if value { "true" } else { "false" })
+    }
+
+    /// Insert an already-encoded JSON value.
+    pub fn raw(mut self, key: &str, json: &str) -> Self {
+        self.key(key);
+        self.out.push_str(json);
+        self
+    }
+
+    pub fn finish(mut self) -> String {
+        self.out.push('}');
+        self.out
+    }
+}
+
+/// Escape a string for embedding in a JSON string literal.
+pub(crate) fn json_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
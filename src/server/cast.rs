@@ -1,5 +1,5 @@
 use std::net::IpAddr;
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc, Mutex};
 use std::time::{Duration, Instant};
 
 use rust_cast::channels::media::{Media, StreamType};
@@ -18,6 +18,15 @@ pub struct CastTarget {
     pub port: u16,
 }
 
+/// Connection state reported by the worker thread.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CastStatus {
+    Connected,
+    Reconnecting,
+    /// The last reconnect failed; the next load tries again.
+    Lost(String),
+}
+
 /// Handle to a Chromecast session backed by a dedicated worker thread.
 ///
 /// The worker thread owns the TLS connection and auto-reconnects when it
@@ -26,6 +35,7 @@ pub struct CastTarget {
 #[derive(Clone)]
 pub struct CastSession {
     tx: mpsc::Sender<CastCommand>,
+    status: Arc<Mutex<CastStatus>>,
     pub target: CastTarget,
 }
 
@@ -49,15 +59,24 @@ impl CastSession {
         let (device, transport_id, session_id) = connect_device(&target)?;
 
         let (tx, rx) = mpsc::channel();
-        let worker_target = target.clone();
+        let status = Arc::new(Mutex::new(CastStatus::Connected));
+        let worker = Worker {
+            target: target.clone(),
+            status: Arc::clone(&status),
+        };
         std::thread::Builder::new()
             .name("cast-worker".into())
             .spawn(move || {
-                cast_worker(device, transport_id, session_id, worker_target, rx);
+                cast_worker(device, transport_id, session_id, worker, rx);
             })
             .map_err(|e| format!("Spawn cast worker: {e}"))?;
 
-        Ok(Self { tx, target })
+        Ok(Self { tx, status, target })
+    }
+
+    /// Current connection state, as last reported by the worker.
+    pub fn status(&self) -> CastStatus {
+        self.status.lock().unwrap().clone()
     }
 
     /// Queue an image load on the Chromecast. Returns immediately.
@@ -77,6 +96,29 @@ impl CastSession {
 // Worker thread
 // ---------------------------------------------------------------------------
 
+/// What the worker knows about its session besides the connection itself.
+struct Worker {
+    target: CastTarget,
+    status: Arc<Mutex<CastStatus>>,
+}
+
+impl Worker {
+    fn set_status(&self, status: CastStatus) {
+        *self.status.lock().unwrap() = status;
+    }
+
+    /// Open a fresh connection, reporting progress through the status.
+    fn reconnect(&self) -> Result<(CastDevice<'static>, String, String), String> {
+        self.set_status(CastStatus::Reconnecting);
+        let result = connect_device(&self.target);
+        match &result {
+            Ok(_) => self.set_status(CastStatus::Connected),
+            Err(e) => self.set_status(CastStatus::Lost(e.clone())),
+        }
+        result
+    }
+}
+
 /// Open TLS, connect receiver, launch Default Media Receiver, connect to app.
 fn connect_device(target: &CastTarget) -> Result<(CastDevice<'static>, String, String), String> {
     let device: CastDevice<'static> =
@@ -141,15 +183,15 @@ fn load_or_reconnect(
     device: &mut CastDevice<'static>,
     transport_id: &mut String,
     session_id: &mut String,
-    target: &CastTarget,
+    worker: &Worker,
     url: &str,
 ) -> bool {
     if load_media(device, transport_id, session_id, url).is_ok() {
         return true;
     }
 
-    log::info!("Cast connection lost, reconnecting to '{}'...", target.name);
-    match connect_device(target) {
+    log::info!("Cast connection lost, reconnecting to '{}'...", worker.target.name);
+    match worker.reconnect() {
         Ok((d, tid, sid)) => {
             *device = d;
             *transport_id = tid;
@@ -172,7 +214,7 @@ fn cast_worker(
     mut device: CastDevice<'static>,
     mut transport_id: String,
     mut session_id: String,
-    target: CastTarget,
+    worker: Worker,
     rx: mpsc::Receiver<CastCommand>,
 ) {
     let mut last_ping = Instant::now();
@@ -184,10 +226,10 @@ fn cast_worker(
                     &mut device,
                     &mut transport_id,
                     &mut session_id,
-                    &target,
+                    &worker,
                     &url,
                 ) {
-                    log::info!("Cast to '{}': {url}", target.name);
+                    log::info!("Cast to '{}': {url}", worker.target.name);
                 }
                 last_ping = Instant::now();
             }
@@ -201,7 +243,7 @@ fn cast_worker(
                 if last_ping.elapsed() >= HEARTBEAT_INTERVAL {
                     if device.heartbeat.ping().is_err() {
                         log::debug!("Cast heartbeat ping failed, proactive reconnect");
-                        match connect_device(&target) {
+                        match worker.reconnect() {
                             Ok((d, tid, sid)) => {
                                 device = d;
                                 transport_id = tid;
@@ -1,18 +1,39 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::net::SocketAddr;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::gena::Service;
+use super::icon::{ICON_SIZES, IconFormat};
+use super::photo_info::{self, PhotoInfo, PhotoInfoIndex};
+use super::search::Criteria;
+use crate::thumbnail;
 
 /// Generate the UPnP device description XML.
-pub fn device_xml(device_uuid: &str, folder_name: &str, addr: SocketAddr) -> String {
+pub fn device_xml(device_uuid: &str, friendly_name: &str, addr: SocketAddr) -> String {
+    let mut icons = String::new();
+    for format in [IconFormat::Png, IconFormat::Jpeg] {
+        for size in ICON_SIZES {
+            icons.push_str(&format!(
+                "\n      <icon><mimetype>{}</mimetype><width>{size}</width><height>{size}</height><depth>24</depth><url>/dlna/icon/{size}.{}</url></icon>",
+                format.mime(),
+                format.extension()
+            ));
+        }
+    }
+    let friendly_name = xml_escape(friendly_name);
     format!(
         r#"<?xml version="1.0" encoding="UTF-8"?>
 <root xmlns="urn:schemas-upnp-org:device-1-0">
   <specVersion><major>1</major><minor>0</minor></specVersion>
   <device>
     <deviceType>urn:schemas-upnp-org:device:MediaServer:1</deviceType>
-    <friendlyName>Looky — {folder_name}</friendlyName>
+    <friendlyName>{friendly_name}</friendlyName>
     <manufacturer>Looky</manufacturer>
     <modelName>Looky Photo Server</modelName>
     <UDN>uuid:{device_uuid}</UDN>
+    <iconList>{icons}
+    </iconList>
     <serviceList>
       <service>
         <serviceType>urn:schemas-upnp-org:service:ContentDirectory:1</serviceType>
@@ -56,6 +77,21 @@ pub fn content_directory_scpd() -> &'static str {
         <argument><name>UpdateID</name><direction>out</direction><relatedStateVariable>A_ARG_TYPE_UpdateID</relatedStateVariable></argument>
       </argumentList>
     </action>
+    <action>
+      <name>Search</name>
+      <argumentList>
+        <argument><name>ContainerID</name><direction>in</direction><relatedStateVariable>A_ARG_TYPE_ObjectID</relatedStateVariable></argument>
+        <argument><name>SearchCriteria</name><direction>in</direction><relatedStateVariable>A_ARG_TYPE_SearchCriteria</relatedStateVariable></argument>
+        <argument><name>Filter</name><direction>in</direction><relatedStateVariable>A_ARG_TYPE_Filter</relatedStateVariable></argument>
+        <argument><name>StartingIndex</name><direction>in</direction><relatedStateVariable>A_ARG_TYPE_Index</relatedStateVariable></argument>
+        <argument><name>RequestedCount</name><direction>in</direction><relatedStateVariable>A_ARG_TYPE_Count</relatedStateVariable></argument>
+        <argument><name>SortCriteria</name><direction>in</direction><relatedStateVariable>A_ARG_TYPE_SortCriteria</relatedStateVariable></argument>
+        <argument><name>Result</name><direction>out</direction><relatedStateVariable>A_ARG_TYPE_Result</relatedStateVariable></argument>
+        <argument><name>NumberReturned</name><direction>out</direction><relatedStateVariable>A_ARG_TYPE_Count</relatedStateVariable></argument>
+        <argument><name>TotalMatches</name><direction>out</direction><relatedStateVariable>A_ARG_TYPE_Count</relatedStateVariable></argument>
+        <argument><name>UpdateID</name><direction>out</direction><relatedStateVariable>A_ARG_TYPE_UpdateID</relatedStateVariable></argument>
+      </argumentList>
+    </action>
     <action>
       <name>GetSystemUpdateID</name>
       <argumentList>
@@ -81,6 +117,7 @@ pub fn content_directory_scpd() -> &'static str {
     <stateVariable sendEventsAttribute="no"><name>A_ARG_TYPE_BrowseFlag</name><dataType>string</dataType><allowedValueList><allowedValue>BrowseMetadata</allowedValue><allowedValue>BrowseDirectChildren</allowedValue></allowedValueList></stateVariable>
     <stateVariable sendEventsAttribute="no"><name>A_ARG_TYPE_Filter</name><dataType>string</dataType></stateVariable>
     <stateVariable sendEventsAttribute="no"><name>A_ARG_TYPE_SortCriteria</name><dataType>string</dataType></stateVariable>
+    <stateVariable sendEventsAttribute="no"><name>A_ARG_TYPE_SearchCriteria</name><dataType>string</dataType></stateVariable>
     <stateVariable sendEventsAttribute="no"><name>A_ARG_TYPE_Index</name><dataType>ui4</dataType></stateVariable>
     <stateVariable sendEventsAttribute="no"><name>A_ARG_TYPE_Count</name><dataType>ui4</dataType></stateVariable>
     <stateVariable sendEventsAttribute="no"><name>A_ARG_TYPE_UpdateID</name><dataType>ui4</dataType></stateVariable>
@@ -140,14 +177,37 @@ pub fn connection_manager_scpd() -> &'static str {
 }
 
 /// Handle a SOAP action on ContentDirectory.
-pub fn handle_content_directory(body: &str, addr: SocketAddr, image_paths: &[std::path::PathBuf]) -> String {
+pub fn handle_content_directory(body: &str, library: &Library) -> String {
     let action = extract_soap_action(body);
     match action.as_deref() {
-        Some("Browse") => handle_browse(body, addr, image_paths),
-        Some("GetSystemUpdateID") => soap_response("GetSystemUpdateID", "<Id>1</Id>"),
-        Some("GetSearchCapabilities") => soap_response("GetSearchCapabilities", "<SearchCaps></SearchCaps>"),
-        Some("GetSortCapabilities") => soap_response("GetSortCapabilities", "<SortCaps></SortCaps>"),
-        _ => soap_response("Browse", "<Result></Result><NumberReturned>0</NumberReturned><TotalMatches>0</TotalMatches><UpdateID>1</UpdateID>"),
+        Some("Browse") => handle_browse(body, library),
+        Some("GetSystemUpdateID") => {
+            soap_response("GetSystemUpdateID", &format!("<Id>{}</Id>", library.update_id))
+        }
+        Some("Search") => handle_search(body, library),
+        Some("GetSearchCapabilities") => soap_response(
+            "GetSearchCapabilities",
+            &format!("<SearchCaps>{}</SearchCaps>", SEARCH_CAPABILITIES.join(",")),
+        ),
+        Some("GetSortCapabilities") => soap_response(
+            "GetSortCapabilities",
+            &format!("<SortCaps>{}</SortCaps>", SORT_CAPABILITIES.join(",")),
+        ),
+        _ => browse_response(library, "", 0, 0),
+    }
+}
+
+const SOURCE_PROTOCOL_INFO: &str = "http-get:*:image/jpeg:*,http-get:*:image/png:*,http-get:*:image/gif:*,http-get:*:image/bmp:*,http-get:*:image/webp:*";
+
+/// Current values of a service's evented state variables, for GENA NOTIFYs.
+pub fn evented_variables(service: Service, system_update_id: u32) -> Vec<(&'static str, String)> {
+    match service {
+        Service::ContentDirectory => vec![("SystemUpdateID", system_update_id.to_string())],
+        Service::ConnectionManager => vec![
+            ("SourceProtocolInfo", SOURCE_PROTOCOL_INFO.to_string()),
+            ("SinkProtocolInfo", String::new()),
+            ("CurrentConnectionIDs", "0".to_string()),
+        ],
     }
 }
 
@@ -157,7 +217,7 @@ pub fn handle_connection_manager(body: &str) -> String {
     match action.as_deref() {
         Some("GetProtocolInfo") => soap_response(
             "GetProtocolInfo",
-            "<Source>http-get:*:image/jpeg:*,http-get:*:image/png:*,http-get:*:image/gif:*,http-get:*:image/bmp:*,http-get:*:image/webp:*</Source><Sink></Sink>",
+            &format!("<Source>{SOURCE_PROTOCOL_INFO}</Source><Sink></Sink>"),
         ),
         Some("GetCurrentConnectionIDs") => {
             soap_response("GetCurrentConnectionIDs", "<ConnectionIDs>0</ConnectionIDs>")
@@ -190,7 +250,7 @@ fn extract_soap_action(body: &str) -> Option<String> {
 /// Max items per browse page when client sends RequestedCount=0 (meaning "all").
 const BROWSE_PAGE_SIZE: usize = 200;
 
-fn handle_browse(body: &str, addr: SocketAddr, image_paths: &[std::path::PathBuf]) -> String {
+fn handle_browse(body: &str, library: &Library) -> String {
     let object_id = extract_xml_value(body, "ObjectID").unwrap_or_else(|| "0".to_string());
     let browse_flag = extract_xml_value(body, "BrowseFlag").unwrap_or_else(|| "BrowseDirectChildren".to_string());
     let starting_index: usize = extract_xml_value(body, "StartingIndex")
@@ -199,111 +259,643 @@ fn handle_browse(body: &str, addr: SocketAddr, image_paths: &[std::path::PathBuf
     let requested_count: usize = extract_xml_value(body, "RequestedCount")
         .and_then(|s| s.parse().ok())
         .unwrap_or(0);
-
-    let total = image_paths.len();
+    let sort = parse_sort_criteria(&extract_xml_value(body, "SortCriteria").unwrap_or_default());
+    let filter = Filter::parse(&extract_xml_value(body, "Filter").unwrap_or_default());
 
     log::debug!(
-        "DLNA Browse: flag={browse_flag} object_id={object_id} start={starting_index} count={requested_count} total={total}"
+        "DLNA Browse: flag={browse_flag} object_id={object_id} start={starting_index} count={requested_count} sort={sort:?} total={}",
+        library.image_paths.len()
     );
 
+    let tree = FolderTree::build(library);
+
     if browse_flag == "BrowseMetadata" {
-        if object_id == "0" {
-            // Root container metadata
-            let didl = format!(
-                r#"<DIDL-Lite xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/"><container id="0" parentID="-1" restricted="1" childCount="{total}"><dc:title>Photos</dc:title><upnp:class>object.container.storageFolder</upnp:class></container></DIDL-Lite>"#
-            );
-            let escaped = xml_escape(&didl);
-            return soap_response(
-                "Browse",
-                &format!("<Result>{escaped}</Result><NumberReturned>1</NumberReturned><TotalMatches>1</TotalMatches><UpdateID>1</UpdateID>"),
-            );
+        if let Some(container) = container_info(library, &tree, &object_id) {
+            return browse_response(library, &build_didl_container(&container, &filter), 1, 1);
         }
 
         // Individual item metadata
         if let Ok(idx) = object_id.parse::<usize>() {
-            if let Some(path) = image_paths.get(idx) {
-                let item = build_didl_item_full(idx, path, addr);
-                let didl = format!(
-                    r#"<DIDL-Lite xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/">{item}</DIDL-Lite>"#
-                );
-                let escaped = xml_escape(&didl);
-                return soap_response(
-                    "Browse",
-                    &format!("<Result>{escaped}</Result><NumberReturned>1</NumberReturned><TotalMatches>1</TotalMatches><UpdateID>1</UpdateID>"),
-                );
+            if let Some(path) = library.image_paths.get(idx).filter(|_| library.is_shared(idx)) {
+                let parent_id = container_id(&tree.dir_of(path));
+                let item = item_didl(library, idx, &parent_id, &filter);
+                return browse_response(library, &item, 1, 1);
             }
         }
 
         // Unknown object ID — return empty
-        return soap_response(
-            "Browse",
-            "<Result>&lt;DIDL-Lite xmlns=&quot;urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/&quot;/&gt;</Result><NumberReturned>0</NumberReturned><TotalMatches>0</TotalMatches><UpdateID>1</UpdateID>",
-        );
+        return browse_response(library, "", 0, 0);
     }
 
-    // BrowseDirectChildren of root
+    let Some(mut children) = container_children(library, &tree, &object_id) else {
+        return browse_response(library, "", 0, 0);
+    };
+    sort_children(library, &mut children, &sort);
+    let total = children.len();
     let count = if requested_count == 0 || requested_count > BROWSE_PAGE_SIZE { BROWSE_PAGE_SIZE } else { requested_count };
     let end = (starting_index + count).min(total);
-    let slice = starting_index..end;
-    let number_returned = slice.len();
+    let start = starting_index.min(end);
 
     let mut didl_items = String::new();
-    for i in slice {
-        if let Some(path) = image_paths.get(i) {
-            didl_items.push_str(&build_didl_item(i, path, addr));
+    for child in &children[start..end] {
+        match child {
+            Child::Container(info) => didl_items.push_str(&build_didl_container(info, &filter)),
+            Child::Item(idx) => didl_items.push_str(&item_didl(library, *idx, &object_id, &filter)),
+        }
+    }
+
+    browse_response(library, &didl_items, end - start, total)
+}
+
+/// Properties that Search criteria can refer to.
+const SEARCH_CAPABILITIES: [&str; 4] = ["dc:title", "upnp:class", "@id", "@parentID"];
+
+const PHOTO_CLASS: &str = "object.item.imageItem.photo";
+
+/// Search the images under `ContainerID`. Only items are returned; containers
+/// never match.
+fn handle_search(body: &str, library: &Library) -> String {
+    let container = extract_xml_value(body, "ContainerID").unwrap_or_else(|| "0".to_string());
+    let raw_criteria = extract_xml_value(body, "SearchCriteria").unwrap_or_default();
+    let starting_index: usize = extract_xml_value(body, "StartingIndex")
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0);
+    let requested_count: usize = extract_xml_value(body, "RequestedCount")
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0);
+    let sort = parse_sort_criteria(&extract_xml_value(body, "SortCriteria").unwrap_or_default());
+    let filter = Filter::parse(&extract_xml_value(body, "Filter").unwrap_or_default());
+
+    let criteria_text = xml_unescape(&raw_criteria);
+    log::debug!("DLNA Search: container={container} criteria={criteria_text} start={starting_index} count={requested_count}");
+
+    let criteria = match Criteria::parse(&criteria_text) {
+        Ok(c) => c,
+        Err(e) => {
+            log::debug!("DLNA Search: bad criteria {criteria_text:?}: {e}");
+            return search_response(library, "", 0, 0);
         }
+    };
+
+    let tree = FolderTree::build(library);
+    let Some(candidates) = container_items(library, &tree, &container) else {
+        return search_response(library, "", 0, 0);
+    };
+    let mut matches: Vec<Child> = candidates
+        .into_iter()
+        .filter(|&idx| {
+            let Some(path) = library.image_paths.get(idx) else {
+                return false;
+            };
+            criteria.matches(&|property: &str| match property {
+                "dc:title" => Some(file_title(path)),
+                "upnp:class" => Some(PHOTO_CLASS.to_string()),
+                "@id" => Some(idx.to_string()),
+                "@parentID" => Some(container_id(&tree.dir_of(path))),
+                _ => None,
+            })
+        })
+        .map(Child::Item)
+        .collect();
+    sort_children(library, &mut matches, &sort);
+
+    let total = matches.len();
+    let count = if requested_count == 0 || requested_count > BROWSE_PAGE_SIZE { BROWSE_PAGE_SIZE } else { requested_count };
+    let end = (starting_index + count).min(total);
+    let start = starting_index.min(end);
+
+    let mut didl_items = String::new();
+    for child in &matches[start..end] {
+        let Child::Item(idx) = *child else {
+            continue;
+        };
+        let parent_id = container_id(&tree.dir_of(&library.image_paths[idx]));
+        didl_items.push_str(&item_didl(library, idx, &parent_id, &filter));
+    }
+
+    search_response(library, &didl_items, end - start, total)
+}
+
+fn item_didl(library: &Library, index: usize, parent_id: &str, filter: &Filter) -> String {
+    let Some(path) = library.image_paths.get(index) else {
+        return String::new();
+    };
+    let info = library
+        .photo_info
+        .lock()
+        .unwrap()
+        .get(library.image_paths, index)
+        .cloned()
+        .unwrap_or_default();
+    build_didl_item(index, path, parent_id, &info, filter, library.addr)
+}
+
+/// Fields accepted in SortCriteria.
+const SORT_CAPABILITIES: [&str; 2] = ["dc:title", "dc:date"];
+
+/// Parse `+dc:date,-dc:title` into `(field, descending)` pairs, dropping
+/// fields we can't sort by.
+fn parse_sort_criteria(raw: &str) -> Vec<(String, bool)> {
+    raw.split(',')
+        .filter_map(|part| {
+            let part = part.trim();
+            let (descending, field) = match part.strip_prefix('-') {
+                Some(field) => (true, field),
+                None => (false, part.strip_prefix('+').unwrap_or(part)),
+            };
+            SORT_CAPABILITIES
+                .contains(&field)
+                .then(|| (field.to_string(), descending))
+        })
+        .collect()
+}
+
+/// Order children by the sort keys, keeping containers ahead of items.
+/// Objects without a value for a key sort after those with one.
+fn sort_children(library: &Library, children: &mut [Child], sort: &[(String, bool)]) {
+    if sort.is_empty() {
+        return;
+    }
+    let dates: Vec<Option<String>> = if sort.iter().any(|(field, _)| field == "dc:date") {
+        library.photo_info.lock().unwrap().dates(library.image_paths).to_vec()
+    } else {
+        Vec::new()
+    };
+    let value = |child: &Child, field: &str| -> Option<String> {
+        match (child, field) {
+            (Child::Container(info), "dc:title") => Some(info.title.to_lowercase()),
+            (Child::Item(idx), "dc:title") => {
+                library.image_paths.get(*idx).map(|p| file_title(p).to_lowercase())
+            }
+            (Child::Item(idx), "dc:date") => dates.get(*idx).cloned().flatten(),
+            _ => None,
+        }
+    };
+    children.sort_by(|a, b| {
+        let kind = matches!(b, Child::Container(_)).cmp(&matches!(a, Child::Container(_)));
+        sort.iter().fold(kind, |ord, (field, descending)| {
+            ord.then_with(|| match (value(a, field), value(b, field)) {
+                (Some(x), Some(y)) if *descending => y.cmp(&x),
+                (Some(x), Some(y)) => x.cmp(&y),
+                (x, y) => x.is_none().cmp(&y.is_none()),
+            })
+        })
+    });
+}
+
+/// Every image under a container, including those in nested containers.
+fn container_items(library: &Library, tree: &FolderTree, id: &str) -> Option<Vec<usize>> {
+    if let Some(date) = parse_date_id(id) {
+        let by_month = group_by_date(library);
+        let mut items: Vec<usize> = match date {
+            (None, _) => by_month.values().flat_map(|m| m.values().flatten().copied()).collect(),
+            (Some(year), None) => by_month.get(&year)?.values().flatten().copied().collect(),
+            (Some(year), Some(month)) => by_month.get(&year)?.get(&month)?.clone(),
+        };
+        items.sort_unstable();
+        return Some(items);
+    }
+    let dir = dir_for_container_id(id)?;
+    if !tree.folders.contains_key(&dir) {
+        return None;
+    }
+    Some(
+        library
+            .image_paths
+            .iter()
+            .enumerate()
+            .filter(|(idx, path)| library.is_shared(*idx) && tree.dir_of(path).starts_with(&dir))
+            .map(|(idx, _)| idx)
+            .collect(),
+    )
+}
+
+/// Everything the ContentDirectory serves.
+pub struct Library<'a> {
+    pub addr: SocketAddr,
+    /// The shared folder; its subfolders become containers.
+    pub root: &'a Path,
+    pub image_paths: &'a [PathBuf],
+    pub photo_info: &'a Mutex<PhotoInfoIndex>,
+    /// Indices of images deleted since the share started. Indices stay
+    /// stable, so these are hidden rather than removed from `image_paths`.
+    pub removed: &'a HashSet<usize>,
+    /// SystemUpdateID reported with every response.
+    pub update_id: u32,
+}
+
+impl Library<'_> {
+    fn is_shared(&self, index: usize) -> bool {
+        index < self.image_paths.len() && !self.removed.contains(&index)
+    }
+}
+
+/// A container as it appears in DIDL-Lite.
+struct ContainerInfo {
+    id: String,
+    parent_id: String,
+    title: String,
+    child_count: usize,
+}
+
+enum Child {
+    Container(ContainerInfo),
+    Item(usize),
+}
+
+/// Object IDs: "0" is the root, "dir:a/b" a subfolder, "date", "date:2024"
+/// and "date:2024-07" the virtual date tree. Items use their image index.
+const DATE_ROOT_ID: &str = "date";
+
+fn container_info(library: &Library, tree: &FolderTree, id: &str) -> Option<ContainerInfo> {
+    if let Some(date) = parse_date_id(id) {
+        let by_month = group_by_date(library);
+        let (parent_id, title, child_count) = match date {
+            (None, _) => ("0".to_string(), "By Date".to_string(), by_month.len()),
+            (Some(year), None) => (DATE_ROOT_ID.to_string(), year.to_string(), by_month.get(&year)?.len()),
+            (Some(year), Some(month)) => (
+                date_id(Some(year), None),
+                photo_info::month_name(month).to_string(),
+                by_month.get(&year)?.get(&month)?.len(),
+            ),
+        };
+        return Some(ContainerInfo {
+            id: id.to_string(),
+            parent_id,
+            title,
+            child_count,
+        });
+    }
+
+    let dir = dir_for_container_id(id)?;
+    let node = tree.folders.get(&dir)?;
+    let (parent_id, title, extra) = match dir.parent() {
+        Some(parent) => (
+            container_id(parent),
+            dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+            0,
+        ),
+        // The root also holds the "By Date" container.
+        None => ("-1".to_string(), "Photos".to_string(), 1),
+    };
+    Some(ContainerInfo {
+        id: id.to_string(),
+        parent_id,
+        title,
+        child_count: node.child_count() + extra,
+    })
+}
+
+fn container_children(library: &Library, tree: &FolderTree, id: &str) -> Option<Vec<Child>> {
+    if let Some(date) = parse_date_id(id) {
+        let by_month = group_by_date(library);
+        let children = match date {
+            (None, _) => by_month
+                .iter()
+                .rev()
+                .map(|(&year, months)| {
+                    Child::Container(ContainerInfo {
+                        id: date_id(Some(year), None),
+                        parent_id: id.to_string(),
+                        title: year.to_string(),
+                        child_count: months.len(),
+                    })
+                })
+                .collect(),
+            (Some(year), None) => by_month
+                .get(&year)?
+                .iter()
+                .map(|(&month, items)| {
+                    Child::Container(ContainerInfo {
+                        id: date_id(Some(year), Some(month)),
+                        parent_id: id.to_string(),
+                        title: photo_info::month_name(month).to_string(),
+                        child_count: items.len(),
+                    })
+                })
+                .collect(),
+            (Some(year), Some(month)) => by_month
+                .get(&year)?
+                .get(&month)?
+                .iter()
+                .map(|&idx| Child::Item(idx))
+                .collect(),
+        };
+        return Some(children);
     }
 
+    // Folders list their subfolders first, then the images directly inside.
+    let dir = dir_for_container_id(id)?;
+    let node = tree.folders.get(&dir)?;
+    let mut children = Vec::with_capacity(node.child_count() + 1);
+    if dir.as_os_str().is_empty() {
+        children.push(Child::Container(ContainerInfo {
+            id: DATE_ROOT_ID.to_string(),
+            parent_id: id.to_string(),
+            title: "By Date".to_string(),
+            child_count: group_by_date(library).len(),
+        }));
+    }
+    for sub in &node.subdirs {
+        let child_count = tree.folders.get(sub).map(FolderNode::child_count).unwrap_or(0);
+        children.push(Child::Container(ContainerInfo {
+            id: container_id(sub),
+            parent_id: id.to_string(),
+            title: sub.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+            child_count,
+        }));
+    }
+    children.extend(node.items.iter().map(|&idx| Child::Item(idx)));
+    Some(children)
+}
+
+/// Image indices grouped by year, then month. Undated images are left out.
+fn group_by_date(library: &Library) -> BTreeMap<u16, BTreeMap<u8, Vec<usize>>> {
+    let mut photo_info = library.photo_info.lock().unwrap();
+    let dates = photo_info.dates(library.image_paths);
+    let mut by_month: BTreeMap<u16, BTreeMap<u8, Vec<usize>>> = BTreeMap::new();
+    for (idx, date) in dates.iter().enumerate().filter(|(idx, _)| library.is_shared(*idx)) {
+        if let Some((year, month)) = date.as_deref().and_then(photo_info::year_month) {
+            by_month.entry(year).or_default().entry(month).or_default().push(idx);
+        }
+    }
+    by_month
+}
+
+fn date_id(year: Option<u16>, month: Option<u8>) -> String {
+    match (year, month) {
+        (Some(y), Some(m)) => format!("{DATE_ROOT_ID}:{y:04}-{m:02}"),
+        (Some(y), None) => format!("{DATE_ROOT_ID}:{y:04}"),
+        _ => DATE_ROOT_ID.to_string(),
+    }
+}
+
+/// `(year, month)` for a date container ID; `None` for any other ID.
+fn parse_date_id(id: &str) -> Option<(Option<u16>, Option<u8>)> {
+    if id == DATE_ROOT_ID {
+        return Some((None, None));
+    }
+    let rest = id.strip_prefix(DATE_ROOT_ID)?.strip_prefix(':')?;
+    match rest.split_once('-') {
+        Some((y, m)) => Some((Some(y.parse().ok()?), Some(m.parse().ok()?))),
+        None => Some((Some(rest.parse().ok()?), None)),
+    }
+}
+
+/// Wrap DIDL-Lite fragments in a Browse SOAP response.
+fn browse_response(library: &Library, didl_items: &str, number_returned: usize, total: usize) -> String {
+    didl_response("Browse", didl_items, number_returned, total, library.update_id)
+}
+
+fn search_response(library: &Library, didl_items: &str, number_returned: usize, total: usize) -> String {
+    didl_response("Search", didl_items, number_returned, total, library.update_id)
+}
+
+fn didl_response(action: &str, didl_items: &str, number_returned: usize, total: usize, update_id: u32) -> String {
     let didl = format!(
         r#"<DIDL-Lite xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/">{didl_items}</DIDL-Lite>"#
     );
     let escaped = xml_escape(&didl);
     soap_response(
-        "Browse",
-        &format!("<Result>{escaped}</Result><NumberReturned>{number_returned}</NumberReturned><TotalMatches>{total}</TotalMatches><UpdateID>1</UpdateID>"),
+        action,
+        &format!("<Result>{escaped}</Result><NumberReturned>{number_returned}</NumberReturned><TotalMatches>{total}</TotalMatches><UpdateID>{update_id}</UpdateID>"),
     )
 }
 
-/// Lightweight item for BrowseDirectChildren listings (no disk I/O for dimensions).
-fn build_didl_item(index: usize, path: &Path, addr: SocketAddr) -> String {
-    let title = xml_escape(&file_title(path));
-    let mime = mime_for_path(path);
-    let filename = url_filename(path);
-    let image_url = format!("http://{addr}/image/{index}/{filename}");
-    let thumb_url = format!("http://{addr}/thumb/{index}/thumb_{index}.jpg");
-    let dlna_pn = dlna_profile_for_mime(mime);
-    let dlna_features = format!(
-        "{dlna_pn}DLNA.ORG_OP=01;DLNA.ORG_CI=0;DLNA.ORG_FLAGS=00D00000000000000000000000000000"
-    );
-    let thumb_features = "DLNA.ORG_PN=JPEG_TN;DLNA.ORG_OP=01;DLNA.ORG_CI=1;DLNA.ORG_FLAGS=00D00000000000000000000000000000";
+/// The shared folder's directory layout, keyed by path relative to the root
+/// (the root itself is the empty path).
+struct FolderTree<'a> {
+    root: &'a Path,
+    folders: BTreeMap<PathBuf, FolderNode>,
+}
+
+#[derive(Default)]
+struct FolderNode {
+    subdirs: BTreeSet<PathBuf>,
+    items: Vec<usize>,
+}
+
+impl FolderNode {
+    fn child_count(&self) -> usize {
+        self.subdirs.len() + self.items.len()
+    }
+}
+
+impl<'a> FolderTree<'a> {
+    fn build(library: &Library<'a>) -> Self {
+        let mut tree = Self {
+            root: library.root,
+            folders: BTreeMap::new(),
+        };
+        tree.folders.insert(PathBuf::new(), FolderNode::default());
+        for (idx, path) in library.image_paths.iter().enumerate() {
+            if !library.is_shared(idx) {
+                continue;
+            }
+            let dir = tree.dir_of(path);
+            tree.folders.entry(dir.clone()).or_default().items.push(idx);
+            // Link each ancestor to its child so empty intermediate folders still appear.
+            let mut child = dir;
+            while let Some(parent) = child.parent() {
+                let parent = parent.to_path_buf();
+                let node = tree.folders.entry(parent.clone()).or_default();
+                if !node.subdirs.insert(child) {
+                    break;
+                }
+                child = parent;
+            }
+        }
+        tree
+    }
+
+    /// Directory containing `path`, relative to the root. Images outside the
+    /// root are listed at the top level.
+    fn dir_of(&self, path: &Path) -> PathBuf {
+        path.parent()
+            .and_then(|p| p.strip_prefix(self.root).ok())
+            .map(Path::to_path_buf)
+            .unwrap_or_default()
+    }
+}
+
+/// Container ID for a folder relative to the root, with `/` separators.
+fn container_id(dir: &Path) -> String {
+    if dir.as_os_str().is_empty() {
+        return "0".to_string();
+    }
+    let parts: Vec<String> = dir
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    format!("dir:{}", parts.join("/"))
+}
+
+fn dir_for_container_id(id: &str) -> Option<PathBuf> {
+    if id == "0" {
+        return Some(PathBuf::new());
+    }
+    let rel = id.strip_prefix("dir:")?;
+    Some(rel.split('/').filter(|p| !p.is_empty() && *p != "..").collect())
+}
+
+fn build_didl_container(info: &ContainerInfo, filter: &Filter) -> String {
+    let id = xml_escape(&info.id);
+    let parent_id = xml_escape(&info.parent_id);
+    let title = xml_escape(&info.title);
+    let child_count = if filter.allows("@childCount") || filter.allows("container@childCount") {
+        format!(r#" childCount="{}""#, info.child_count)
+    } else {
+        String::new()
+    };
     format!(
-        r#"<item id="{index}" parentID="0" restricted="1"><dc:title>{title}</dc:title><upnp:class>object.item.imageItem.photo</upnp:class><res protocolInfo="http-get:*:{mime}:{dlna_features}">{image_url}</res><res protocolInfo="http-get:*:image/jpeg:{thumb_features}">{thumb_url}</res></item>"#
+        r#"<container id="{id}" parentID="{parent_id}" restricted="1"{child_count}><dc:title>{title}</dc:title><upnp:class>object.container.storageFolder</upnp:class></container>"#
     )
 }
 
-/// Full item with resolution and size for BrowseMetadata on a single item.
-fn build_didl_item_full(index: usize, path: &Path, addr: SocketAddr) -> String {
+/// DIDL item for one image. `dc:date` and the stored size come from the
+/// photo info index, but every listing still stats and canonicalizes the
+/// file for its version token and reads its orientation to describe the
+/// upright copy `/image/` sends. Neither is cached, since an edit or a new
+/// orientation override changes them mid-share.
+fn build_didl_item(
+    index: usize,
+    path: &Path,
+    parent_id: &str,
+    info: &PhotoInfo,
+    filter: &Filter,
+    addr: SocketAddr,
+) -> String {
+    let parent_id = xml_escape(parent_id);
     let title = xml_escape(&file_title(path));
-    let mime = mime_for_path(path);
+    let date = match &info.date {
+        Some(date) if filter.allows("dc:date") => format!("<dc:date>{}</dc:date>", xml_escape(date)),
+        _ => String::new(),
+    };
+    if !filter.allows("res") {
+        return format!(
+            r#"<item id="{index}" parentID="{parent_id}" restricted="1"><dc:title>{title}</dc:title>{date}<upnp:class>{PHOTO_CLASS}</upnp:class></item>"#
+        );
+    }
+
+    let resolution_attr = |dims: Option<(u32, u32)>| match dims {
+        Some((w, h)) if filter.allows("res@resolution") => format!(r#" resolution="{w}x{h}""#),
+        _ => String::new(),
+    };
+
+    // Rotated and edited photos are sent as an upright JPEG re-encoded on
+    // first request, so its length isn't known yet and the size is left out
+    let upright = thumbnail::needs_upright(path);
+    let dimensions = match info.dimensions {
+        Some(d) if upright => Some(thumbnail::upright_size(path, d)),
+        d => d,
+    };
+
+    // The original first, labelled with a profile only if it actually fits one.
+    let mime = if upright { "image/jpeg" } else { mime_for_path(path) };
     let filename = url_filename(path);
-    let image_url = format!("http://{addr}/image/{index}/{filename}");
-    let thumb_url = format!("http://{addr}/thumb/{index}/thumb_{index}.jpg");
-    let size_attr = std::fs::metadata(path)
-        .map(|m| format!(r#" size="{}""#, m.len()))
-        .unwrap_or_default();
-    let resolution_attr = image::image_dimensions(path)
-        .map(|(w, h)| format!(r#" resolution="{w}x{h}""#))
-        .unwrap_or_default();
-    let dlna_pn = dlna_profile_for_mime(mime);
-    let dlna_features = format!(
-        "{dlna_pn}DLNA.ORG_OP=01;DLNA.ORG_CI=0;DLNA.ORG_FLAGS=00D00000000000000000000000000000"
+    // Versioned, so a renderer's cache doesn't outlive an edit or an index
+    // that now belongs to another photo
+    let id = super::photo_id(path);
+    let v = thumbnail::version_token(path);
+    let image_url = format!("http://{addr}/image/{id}/{filename}?v={v}");
+    let size_attr = if filter.allows("res@size") && info.file_size > 0 && !upright {
+        format!(r#" size="{}""#, info.file_size)
+    } else {
+        String::new()
+    };
+    let original_fits = |max: u32| dimensions.is_some_and(|(w, h)| w.max(h) <= max);
+    let original_pn = match mime {
+        "image/jpeg" if original_fits(JPEG_PROFILES[0].max_size) => "DLNA.ORG_PN=JPEG_LRG;",
+        "image/png" if original_fits(PNG_LRG_MAX_SIZE) => "DLNA.ORG_PN=PNG_LRG;",
+        _ => "",
+    };
+    let mut res = format!(
+        r#"<res protocolInfo="http-get:*:{mime}:{original_pn}{DLNA_FEATURES}"{size_attr}{}>{image_url}</res>"#,
+        resolution_attr(dimensions)
     );
-    let thumb_features = "DLNA.ORG_PN=JPEG_TN;DLNA.ORG_OP=01;DLNA.ORG_CI=1;DLNA.ORG_FLAGS=00D00000000000000000000000000000";
+
+    // Then resized JPEGs for each profile smaller than the original, largest
+    // first. The thumbnail is always offered.
+    for profile in &JPEG_PROFILES {
+        let smaller = dimensions.is_none_or(|(w, h)| w.max(h) > profile.max_size);
+        if !smaller && profile.name != "JPEG_TN" {
+            continue;
+        }
+        let ci = if profile.name == "JPEG_TN" { 1 } else { 0 };
+        let url = format!("http://{addr}/dlna/res/{id}/{}.jpg?v={v}", profile.name);
+        let dims = dimensions.map(|d| fit_within(d, profile.max_size));
+        res.push_str(&format!(
+            r#"<res protocolInfo="http-get:*:image/jpeg:DLNA.ORG_PN={};DLNA.ORG_OP=01;DLNA.ORG_CI={ci};DLNA.ORG_FLAGS=00D00000000000000000000000000000"{}>{url}</res>"#,
+            profile.name,
+            resolution_attr(dims)
+        ));
+    }
+
     format!(
-        r#"<item id="{index}" parentID="0" restricted="1"><dc:title>{title}</dc:title><upnp:class>object.item.imageItem.photo</upnp:class><res protocolInfo="http-get:*:{mime}:{dlna_features}"{size_attr}{resolution_attr}>{image_url}</res><res protocolInfo="http-get:*:image/jpeg:{thumb_features}">{thumb_url}</res></item>"#
+        r#"<item id="{index}" parentID="{parent_id}" restricted="1"><dc:title>{title}</dc:title>{date}<upnp:class>{PHOTO_CLASS}</upnp:class>{res}</item>"#
     )
 }
 
+/// A DLNA JPEG media profile and the longest edge it allows.
+pub struct JpegProfile {
+    pub name: &'static str,
+    pub max_size: u32,
+}
+
+/// JPEG profiles offered as resized variants, largest first. Many TVs refuse
+/// (or crawl through) originals beyond these limits.
+pub const JPEG_PROFILES: [JpegProfile; 4] = [
+    JpegProfile { name: "JPEG_LRG", max_size: 4096 },
+    JpegProfile { name: "JPEG_MED", max_size: 1024 },
+    JpegProfile { name: "JPEG_SM", max_size: 640 },
+    JpegProfile { name: "JPEG_TN", max_size: 160 },
+];
+
+const PNG_LRG_MAX_SIZE: u32 = 4096;
+
+const DLNA_FEATURES: &str = "DLNA.ORG_OP=01;DLNA.ORG_CI=0;DLNA.ORG_FLAGS=00D00000000000000000000000000000";
+
+pub fn jpeg_profile(name: &str) -> Option<&'static JpegProfile> {
+    JPEG_PROFILES.iter().find(|p| p.name == name)
+}
+
+/// Scale `(w, h)` down so the longer edge is at most `max`.
+fn fit_within((w, h): (u32, u32), max: u32) -> (u32, u32) {
+    let long = w.max(h);
+    if long <= max {
+        return (w, h);
+    }
+    let scale = |v: u32| ((u64::from(v) * u64::from(max) + u64::from(long) / 2) / u64::from(long)).max(1) as u32;
+    (scale(w), scale(h))
+}
+
+/// The Browse/Search `Filter` argument: which optional properties to include.
+/// `id`, `parentID`, `restricted`, `dc:title` and `upnp:class` are always sent.
+struct Filter {
+    all: bool,
+    properties: Vec<String>,
+}
+
+impl Filter {
+    fn parse(raw: &str) -> Self {
+        let raw = raw.trim();
+        Self {
+            all: raw.is_empty() || raw == "*",
+            properties: raw.split(',').map(|p| p.trim().to_string()).collect(),
+        }
+    }
+
+    fn allows(&self, property: &str) -> bool {
+        if self.all {
+            return true;
+        }
+        self.properties.iter().any(|p| {
+            // Asking for any attribute of an element implies the element itself.
+            p == property || p.strip_prefix(property).is_some_and(|rest| rest.starts_with('@'))
+        })
+    }
+}
+
 fn url_filename(path: &Path) -> String {
     let name = path.file_name()
         .map(|n| n.to_string_lossy().to_string())
@@ -323,14 +915,6 @@ fn url_filename(path: &Path) -> String {
     encoded
 }
 
-fn dlna_profile_for_mime(mime: &str) -> &'static str {
-    match mime {
-        "image/jpeg" => "DLNA.ORG_PN=JPEG_LRG;",
-        "image/png" => "DLNA.ORG_PN=PNG_LRG;",
-        _ => "",
-    }
-}
-
 fn soap_response(action: &str, inner: &str) -> String {
     format!(
         r#"<?xml version="1.0" encoding="UTF-8"?>
@@ -355,6 +939,15 @@ fn xml_escape(s: &str) -> String {
         .replace('"', "&quot;")
 }
 
+/// Undo the entity escaping of a SOAP argument (SearchCriteria quotes arrive as `&quot;`).
+fn xml_unescape(s: &str) -> String {
+    s.replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
 fn file_title(path: &Path) -> String {
     path.file_stem()
         .map(|s| s.to_string_lossy().to_string())
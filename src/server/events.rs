@@ -0,0 +1,99 @@
+//! Server-Sent Events for live gallery updates.
+//!
+//! Each `/events` request hands its raw socket writer to the hub instead of
+//! occupying an HTTP worker, so any number of browsers can stay subscribed.
+//! Every connection is written from its own thread, so a client that stops
+//! reading never holds up the sender. A client is dropped once a write to it
+//! fails, stays stuck longer than [`WRITE_TIMEOUT`], or its backlog fills.
+
+use std::io::Write;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+type Writer = Box<dyn Write + Send + 'static>;
+
+/// Events queued for one client before it's given up on.
+const BACKLOG: usize = 64;
+
+/// How long one write may block before the client is given up on.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct Client {
+    tx: SyncSender<Arc<str>>,
+    /// When the write in progress started; `None` between writes.
+    writing_since: Arc<Mutex<Option<Instant>>>,
+}
+
+impl Client {
+    fn stalled(&self) -> bool {
+        self.writing_since
+            .lock()
+            .unwrap()
+            .is_some_and(|since| since.elapsed() > WRITE_TIMEOUT)
+    }
+}
+
+#[derive(Default)]
+pub struct EventHub {
+    clients: Mutex<Vec<Client>>,
+}
+
+impl EventHub {
+    /// Register the connection and write the SSE response head to it.
+    pub fn subscribe(&self, writer: Writer) {
+        let head = "HTTP/1.1 200 OK\r\n\
+                    Content-Type: text/event-stream\r\n\
+                    Cache-Control: no-cache\r\n\
+                    Connection: keep-alive\r\n\
+                    \r\n\
+                    retry: 3000\n\n";
+        let (tx, rx) = mpsc::sync_channel(BACKLOG);
+        let writing_since = Arc::default();
+        let since = Arc::clone(&writing_since);
+        let spawned = std::thread::Builder::new()
+            .name("looky-events".into())
+            .spawn(move || write_events(writer, rx, &since));
+        if let Err(e) = spawned {
+            log::warn!("Failed to spawn event writer: {}", e);
+            return;
+        }
+        if tx.try_send(Arc::from(head)).is_ok() {
+            self.clients.lock().unwrap().push(Client { tx, writing_since });
+        }
+    }
+
+    /// Send a named event with a single-line data payload to every client.
+    pub fn broadcast(&self, event: &str, data: &str) {
+        self.send_raw(&format!("event: {event}\ndata: {data}\n\n"));
+    }
+
+    /// Send an SSE comment so idle connections stay open and dead ones get pruned.
+    pub fn keepalive(&self) {
+        self.send_raw(":\n\n");
+    }
+
+    /// Drop all connections (used on shutdown).
+    pub fn close_all(&self) {
+        self.clients.lock().unwrap().clear();
+    }
+
+    /// Queue `msg` for every client without waiting on any of them.
+    fn send_raw(&self, msg: &str) {
+        let msg: Arc<str> = Arc::from(msg);
+        let mut clients = self.clients.lock().unwrap();
+        clients.retain(|client| !client.stalled() && client.tx.try_send(Arc::clone(&msg)).is_ok());
+    }
+}
+
+/// Write queued messages to one client until it fails or the hub drops it.
+fn write_events(mut writer: Writer, rx: Receiver<Arc<str>>, writing_since: &Mutex<Option<Instant>>) {
+    for msg in rx {
+        *writing_since.lock().unwrap() = Some(Instant::now());
+        let written = writer.write_all(msg.as_bytes()).and_then(|_| writer.flush());
+        *writing_since.lock().unwrap() = None;
+        if written.is_err() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! UPnP GENA eventing: SUBSCRIBE / renew / UNSUBSCRIBE tracking and NOTIFY
+//! delivery to subscriber callback URLs.
+//!
+//! NOTIFYs are sent from short-lived threads so a slow or unreachable
+//! subscriber never holds up an HTTP worker.
+
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Longest subscription we grant; clients renew before it runs out.
+pub const MAX_TIMEOUT: Duration = Duration::from_secs(1800);
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Service {
+    ContentDirectory,
+    ConnectionManager,
+}
+
+impl Service {
+    /// Map an `eventSubURL` from the device description to its service.
+    pub fn from_event_path(path: &str) -> Option<Self> {
+        match path {
+            "/dlna/event/content" => Some(Self::ContentDirectory),
+            "/dlna/event/connection" => Some(Self::ConnectionManager),
+            _ => None,
+        }
+    }
+}
+
+struct Subscription {
+    sid: String,
+    service: Service,
+    callbacks: Vec<String>,
+    expires: Instant,
+    seq: u32,
+}
+
+#[derive(Default)]
+pub struct Subscriptions {
+    subs: Mutex<Vec<Subscription>>,
+}
+
+impl Subscriptions {
+    /// Register a subscriber and return its SID.
+    pub fn subscribe(&self, service: Service, callbacks: Vec<String>, timeout: Duration) -> String {
+        let sid = format!("uuid:{}", uuid::Uuid::new_v4());
+        let mut subs = self.subs.lock().unwrap();
+        subs.retain(|s| s.expires > Instant::now());
+        subs.push(Subscription {
+            sid: sid.clone(),
+            service,
+            callbacks,
+            expires: Instant::now() + timeout,
+            seq: 0,
+        });
+        log::debug!("GENA subscribe {sid} to {service:?}");
+        sid
+    }
+
+    /// Extend a live subscription. Returns false if the SID is unknown or expired.
+    pub fn renew(&self, sid: &str, timeout: Duration) -> bool {
+        let mut subs = self.subs.lock().unwrap();
+        subs.retain(|s| s.expires > Instant::now());
+        match subs.iter_mut().find(|s| s.sid == sid) {
+            Some(sub) => {
+                sub.expires = Instant::now() + timeout;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn unsubscribe(&self, sid: &str) -> bool {
+        let mut subs = self.subs.lock().unwrap();
+        let before = subs.len();
+        subs.retain(|s| s.sid != sid);
+        subs.len() != before
+    }
+
+    /// Send evented variables to one subscriber (the initial event after SUBSCRIBE).
+    pub fn notify_one(&self, sid: &str, properties: &[(&str, String)]) {
+        let mut subs = self.subs.lock().unwrap();
+        if let Some(sub) = subs.iter_mut().find(|s| s.sid == sid) {
+            send(sub, properties);
+        }
+    }
+
+    /// Send evented variables to every live subscriber of `service`.
+    pub fn notify(&self, service: Service, properties: &[(&str, String)]) {
+        let mut subs = self.subs.lock().unwrap();
+        subs.retain(|s| s.expires > Instant::now());
+        for sub in subs.iter_mut().filter(|s| s.service == service) {
+            send(sub, properties);
+        }
+    }
+}
+
+/// Parse a `CALLBACK` header: one or more `<url>`s, tried in order.
+pub fn parse_callbacks(header: &str) -> Vec<String> {
+    header
+        .split('<')
+        .filter_map(|part| part.split_once('>').map(|(url, _)| url.trim().to_string()))
+        .filter(|url| url.starts_with("http://"))
+        .collect()
+}
+
+/// Parse a `TIMEOUT` header (`Second-1800` or `Second-infinite`), capped at [`MAX_TIMEOUT`].
+pub fn parse_timeout(header: Option<&str>) -> Duration {
+    header
+        .and_then(|h| h.trim().strip_prefix("Second-"))
+        .and_then(|secs| secs.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(MAX_TIMEOUT)
+        .min(MAX_TIMEOUT)
+}
+
+fn send(sub: &mut Subscription, properties: &[(&str, String)]) {
+    let seq = sub.seq;
+    // SEQ wraps to 1, not 0; 0 is reserved for the initial event.
+    sub.seq = sub.seq.checked_add(1).unwrap_or(1);
+    let body = property_set_xml(properties);
+    let sid = sub.sid.clone();
+    let callbacks = sub.callbacks.clone();
+    let _ = std::thread::Builder::new()
+        .name("looky-gena".into())
+        .spawn(move || {
+            for url in &callbacks {
+                match post_notify(url, &sid, seq, &body) {
+                    Ok(()) => return,
+                    Err(e) => log::debug!("GENA NOTIFY to {url} failed: {e}"),
+                }
+            }
+        });
+}
+
+fn property_set_xml(properties: &[(&str, String)]) -> String {
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="utf-8"?><e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0">"#,
+    );
+    for (name, value) in properties {
+        xml.push_str(&format!("<e:property><{name}>{value}</{name}></e:property>"));
+    }
+    xml.push_str("</e:propertyset>");
+    xml
+}
+
+fn post_notify(url: &str, sid: &str, seq: u32, body: &str) -> Result<(), String> {
+    let rest = url.strip_prefix("http://").ok_or("Not an http URL")?;
+    let (host, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let host_port = if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{host}:80")
+    };
+    let addr = host_port
+        .to_socket_addrs()
+        .map_err(|e| e.to_string())?
+        .next()
+        .ok_or("Unresolvable callback host")?;
+
+    let mut stream = TcpStream::connect_timeout(&addr, NOTIFY_TIMEOUT).map_err(|e| e.to_string())?;
+    let _ = stream.set_read_timeout(Some(NOTIFY_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(NOTIFY_TIMEOUT));
+    let request = format!(
+        "NOTIFY {path} HTTP/1.1\r\n\
+         HOST: {host}\r\n\
+         CONTENT-TYPE: text/xml; charset=\"utf-8\"\r\n\
+         NT: upnp:event\r\n\
+         NTS: upnp:propchange\r\n\
+         SID: {sid}\r\n\
+         SEQ: {seq}\r\n\
+         CONTENT-LENGTH: {}\r\n\
+         CONNECTION: close\r\n\
+         \r\n\
+         {body}",
+        body.len()
+    );
+    stream.write_all(request.as_bytes()).map_err(|e| e.to_string())?;
+
+    let mut status = [0u8; 12];
+    stream.read_exact(&mut status).map_err(|e| e.to_string())?;
+    match &status[9..12] {
+        b"200" => Ok(()),
+        code => Err(format!("HTTP {}", String::from_utf8_lossy(code))),
+    }
+}
@@ -1,29 +1,93 @@
-use std::collections::HashMap;
+use std::io::Read;
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use image::GenericImageView;
-
+use super::api;
 use super::dlna;
+use super::gena;
+use super::icon;
+use super::thumb_cache::ThumbCache;
+use super::upload;
 use super::ServerState;
+use crate::i18n::{self, Language, tr, trf, trn};
+use crate::metadata;
 use crate::thumbnail;
 
 const THUMBS_PER_PAGE: usize = 60;
-const THUMB_MAX_SIZE: u32 = 400;
-const THUMB_QUALITY: u8 = 80;
+pub const THUMB_MAX_SIZE: u32 = 400;
+pub const THUMB_QUALITY: u8 = 80;
 const CAST_MAX_SIZE: u32 = 1920;
-const CAST_QUALITY: u8 = 90;
+/// Widths `/display/` will render; requests snap to the nearest one so the
+/// disk cache doesn't fill with one-off sizes.
+const DISPLAY_SIZES: [u32; 6] = [640, 1024, 1600, 1920, 2560, 3840];
+pub const DISPLAY_DEFAULT_SIZE: u32 = 1600;
+pub const DISPLAY_QUALITY: u8 = 88;
+/// JPEG quality for full-size images re-encoded to turn them upright.
+const UPRIGHT_QUALITY: u8 = 92;
 const DLNA_TRANSFER_INTERACTIVE: &str = "transferMode.dlna.org: Interactive";
 const DLNA_CONTENT_FEATURES: &str = "contentFeatures.dlna.org: DLNA.ORG_OP=01;DLNA.ORG_CI=0;DLNA.ORG_FLAGS=00D00000000000000000000000000000";
+const EVENTS_KEEPALIVE: Duration = Duration::from_secs(15);
 
 type HttpResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
+/// Encoded thumbnails by image index and format, shared by the workers.
+type SharedThumbCache = Arc<Mutex<ThumbCache<(usize, ThumbFormat)>>>;
+
+/// Encodings `/thumb/` can be served in, picked from the request's Accept
+/// header so browsers get the smallest one they can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum ThumbFormat {
+    Jpeg,
+    Webp,
+    Avif,
+}
+
+impl ThumbFormat {
+    /// The best format `accept` allows. AVIF only when the share offers it,
+    /// and JPEG for anything that doesn't ask, such as DLNA renderers.
+    fn negotiate(accept: Option<&str>, allow_avif: bool) -> Self {
+        let accepts = |mime: &str| {
+            accept.is_some_and(|accept| {
+                accept
+                    .split(',')
+                    .any(|part| part.split(';').next().is_some_and(|m| m.trim().eq_ignore_ascii_case(mime)))
+            })
+        };
+        if allow_avif && accepts("image/avif") {
+            ThumbFormat::Avif
+        } else if accepts("image/webp") {
+            ThumbFormat::Webp
+        } else {
+            ThumbFormat::Jpeg
+        }
+    }
+
+    fn mime(self) -> &'static str {
+        match self {
+            ThumbFormat::Jpeg => "image/jpeg",
+            ThumbFormat::Webp => "image/webp",
+            ThumbFormat::Avif => "image/avif",
+        }
+    }
+
+    fn encode(self, path: &std::path::Path) -> Vec<u8> {
+        match self {
+            ThumbFormat::Jpeg => thumbnail::thumbnail_jpeg_bytes(path, THUMB_MAX_SIZE, THUMB_QUALITY),
+            ThumbFormat::Webp => thumbnail::thumbnail_webp_bytes(path, THUMB_MAX_SIZE, THUMB_QUALITY),
+            ThumbFormat::Avif => thumbnail::thumbnail_avif_bytes(path, THUMB_MAX_SIZE, THUMB_QUALITY),
+        }
+    }
+}
 
 pub fn run(server: tiny_http::Server, state: Arc<ServerState>) {
-    let thumb_cache: Arc<Mutex<HashMap<usize, Vec<u8>>>> = Arc::new(Mutex::new(HashMap::new()));
+    let thumb_cache: SharedThumbCache = Arc::new(Mutex::new(ThumbCache::new(state.thumb_cache_bytes)));
     let server = Arc::new(server);
 
-    let workers: Vec<_> = (0..4)
+    // Each worker blocks until its response is fully sent, so a TV pulling
+    // originals holds one for a while; more workers keep thumbnails flowing.
+    let workers: Vec<_> = (0..state.http_workers)
         .map(|i| {
             let server = Arc::clone(&server);
             let state = Arc::clone(&state);
@@ -45,8 +109,17 @@ pub fn run(server: tiny_http::Server, state: Arc<ServerState>) {
                         let method = request.method().to_string();
 
                         log::debug!("HTTP {} {}", method, url);
-
-                        let result = route(request, &method, &url, &state, &cache);
+                        *state.last_request.lock().unwrap() = std::time::Instant::now();
+
+                        // Pages in the language the browser asks for
+                        let language = request
+                            .headers()
+                            .iter()
+                            .find(|h| h.field.equiv("Accept-Language"))
+                            .and_then(|h| Language::from_accept_language(h.value.as_str()));
+                        let result = i18n::with_language(language, || {
+                            route(request, &method, &url, &state, &cache)
+                        });
 
                         if let Err(e) = result {
                             log::debug!("HTTP response error: {}", e);
@@ -57,6 +130,17 @@ pub fn run(server: tiny_http::Server, state: Arc<ServerState>) {
         })
         .collect();
 
+    // Keep live-update connections open while the workers serve requests.
+    let mut last_keepalive = std::time::Instant::now();
+    while !state.shutdown.load(Ordering::Relaxed) {
+        std::thread::sleep(Duration::from_millis(500));
+        if last_keepalive.elapsed() >= EVENTS_KEEPALIVE {
+            state.events.keepalive();
+            last_keepalive = std::time::Instant::now();
+        }
+    }
+    state.events.close_all();
+
     for w in workers {
         let _ = w.join();
     }
@@ -67,42 +151,96 @@ fn route(
     method: &str,
     url: &str,
     state: &ServerState,
-    thumb_cache: &Arc<Mutex<HashMap<usize, Vec<u8>>>>,
+    thumb_cache: &SharedThumbCache,
 ) -> HttpResult {
     match (method, url) {
         ("GET", "/") => serve_gallery(request, state, 0),
+        ("GET", "/events") => {
+            state.events.subscribe(request.into_writer());
+            Ok(())
+        }
         ("GET", path) if path.starts_with("/page/") => {
             let page: usize = path[6..].parse().unwrap_or(0);
             serve_gallery(request, state, page)
         }
-        ("GET", path) if path.starts_with("/thumb/") => {
-            let index = parse_index_from_path(&path[7..]);
+        // HEAD gets the same headers as GET; tiny_http drops the body
+        ("GET" | "HEAD", path) if path.starts_with("/thumb/") => {
+            let index = resolve_index(state, &path[7..]);
             serve_thumbnail(request, state, index, thumb_cache)
         }
+        ("GET", path) if path.starts_with("/display/") => {
+            let index = resolve_index(state, &path[9..]);
+            let width = query_param(path, "w")
+                .and_then(|w| w.parse().ok())
+                .unwrap_or(DISPLAY_DEFAULT_SIZE);
+            serve_display_image(request, state, index, width)
+        }
         ("GET", path) if path.starts_with("/cast/") => {
-            let index = parse_index_from_path(&path[6..]);
-            serve_cast_image(request, state, index)
+            let index = resolve_index(state, &path[6..]);
+            serve_display_image(request, state, index, CAST_MAX_SIZE)
+        }
+        ("GET", path) if path.starts_with("/api/images") => serve_api_images(request, state, path),
+        ("GET", path) if path.starts_with("/api/image/") => {
+            let index = resolve_index(state, &path[11..]);
+            serve_image(request, state, index)
+        }
+        ("HEAD", path) if path.starts_with("/api/image/") => {
+            let index = resolve_index(state, &path[11..]);
+            serve_image_head(request, state, index, HeadOf::Image)
+        }
+        ("GET", path) if path.starts_with("/api/meta/") => {
+            let index = resolve_index(state, &path[10..]);
+            serve_api_meta(request, state, index)
         }
         ("GET", path) if path.starts_with("/image/") => {
-            let index = parse_index_from_path(&path[7..]);
+            let index = resolve_index(state, &path[7..]);
             serve_image(request, state, index)
         }
-        ("HEAD", path) if path.starts_with("/thumb/") => {
-            let index = parse_index_from_path(&path[7..]);
-            serve_image_head(request, state, index, true)
+        ("GET", path) if path.starts_with("/original/") => {
+            let index = resolve_index(state, &path[10..]);
+            serve_original(request, state, index)
         }
         ("HEAD", path) if path.starts_with("/image/") => {
-            let index = parse_index_from_path(&path[7..]);
-            serve_image_head(request, state, index, false)
+            let index = resolve_index(state, &path[7..]);
+            serve_image_head(request, state, index, HeadOf::Image)
+        }
+        ("HEAD", path) if path.starts_with("/original/") => {
+            let index = resolve_index(state, &path[10..]);
+            serve_image_head(request, state, index, HeadOf::Original)
+        }
+        ("GET" | "HEAD", path) if path.starts_with("/dlna/res/") => {
+            let rest = path[10..].split('?').next().unwrap_or_default();
+            let index = resolve_index(state, rest);
+            let profile = rest
+                .split_once('/')
+                .and_then(|(_, name)| name.strip_suffix(".jpg"))
+                .and_then(dlna::jpeg_profile);
+            match profile {
+                Some(profile) => serve_dlna_profile(request, state, index, profile),
+                None => serve_404(request),
+            }
         }
+        ("GET" | "HEAD", path) if path.starts_with("/dlna/icon/") => {
+            let icon = path[11..].split_once('.').and_then(|(size, ext)| {
+                Some((size.parse().ok()?, icon::IconFormat::from_extension(ext)?))
+            });
+            match icon {
+                Some((size, format)) => serve_icon(request, size, format),
+                None => serve_404(request),
+            }
+        }
+        ("GET" | "HEAD", "/favicon.ico") => serve_icon(request, icon::WEB_ICON_SIZES[0], icon::IconFormat::Ico),
+        ("GET", "/manifest.webmanifest") => serve_manifest(request, state),
         ("GET", "/dlna/device.xml") => serve_device_xml(request, state),
         ("GET", "/dlna/content.xml") => serve_static_xml(request, dlna::content_directory_scpd()),
         ("GET", "/dlna/connection.xml") => {
             serve_static_xml(request, dlna::connection_manager_scpd())
         }
+        ("POST", path) if path.starts_with("/upload") => serve_upload(request, state, path),
         ("POST", "/dlna/control/content") => serve_soap_content(request, state),
         ("POST", "/dlna/control/connection") => serve_soap_connection(request),
-        ("SUBSCRIBE", _) => serve_subscribe(request),
+        ("SUBSCRIBE", path) => serve_subscribe(request, state, path),
+        ("UNSUBSCRIBE", _) => serve_unsubscribe(request, state),
         _ => serve_404(request),
     }
 }
@@ -127,6 +265,23 @@ fn respond_xml(request: tiny_http::Request, xml: String) -> HttpResult {
     Ok(())
 }
 
+fn respond_json(request: tiny_http::Request, status: u16, json: String) -> HttpResult {
+    let response = tiny_http::Response::from_string(json)
+        .with_status_code(status)
+        .with_header(
+            "Content-Type: application/json; charset=utf-8"
+                .parse::<tiny_http::Header>()
+                .unwrap(),
+        )
+        .with_header(
+            "Access-Control-Allow-Origin: *"
+                .parse::<tiny_http::Header>()
+                .unwrap(),
+        );
+    request.respond(response)?;
+    Ok(())
+}
+
 fn respond_xml_static(request: tiny_http::Request, xml: &str) -> HttpResult {
     let response = tiny_http::Response::from_string(xml).with_header(
         "Content-Type: text/xml; charset=utf-8"
@@ -138,40 +293,53 @@ fn respond_xml_static(request: tiny_http::Request, xml: &str) -> HttpResult {
 }
 
 fn serve_gallery(request: tiny_http::Request, state: &ServerState, page: usize) -> HttpResult {
-    let total = state.image_paths.len();
+    let removed = state.removed.read().unwrap().clone();
+    let image_paths = state.image_paths.read().unwrap();
+    let shown: Vec<usize> = (0..image_paths.len()).filter(|i| !removed.contains(i)).collect();
+    let total = shown.len();
     let total_pages = (total + THUMBS_PER_PAGE - 1).max(1) / THUMBS_PER_PAGE.max(1);
     let page = page.min(total_pages.saturating_sub(1));
     let start = page * THUMBS_PER_PAGE;
     let end = (start + THUMBS_PER_PAGE).min(total);
 
     let mut thumbs_html = String::new();
-    for i in start..end {
-        if let Some(path) = state.image_paths.get(i) {
-            let title = path
+    let mut photo_info = state.photo_info.lock().unwrap();
+    for &i in &shown[start..end] {
+        if let Some(path) = image_paths.get(i) {
+            let mut title = path
                 .file_name()
                 .map(|n| n.to_string_lossy().to_string())
                 .unwrap_or_default();
+            if let Some(date) = photo_info.get(&image_paths, i).and_then(|info| info.date.as_deref()) {
+                title = format!("{title} \u{00B7} {}", metadata::display_taken(date));
+            }
             let title_escaped = html_escape(&title);
+            let id = super::photo_id(path);
+            let v = thumbnail::version_token(path);
             thumbs_html.push_str(&format!(
-                r#"<a href="/image/{i}" title="{title_escaped}"><img src="/thumb/{i}" loading="lazy" alt="{title_escaped}"></a>"#,
+                r#"<a href="/display/{id}?v={v}" title="{title_escaped}"><img src="/thumb/{id}?v={v}" loading="lazy" alt="{title_escaped}"></a>"#,
             ));
         }
     }
+    drop(photo_info);
+    drop(image_paths);
 
     let mut pagination = String::new();
     if total_pages > 1 {
         pagination.push_str("<div class=\"pages\">");
         if page > 0 {
             pagination.push_str(&format!(
-                r#"<a href="/page/{}">&laquo; Prev</a> "#,
-                page - 1
+                r#"<a href="/page/{}">&laquo; {}</a> "#,
+                page - 1,
+                tr("Prev")
             ));
         }
-        pagination.push_str(&format!("Page {} of {}", page + 1, total_pages));
+        pagination.push_str(&trf("Page {} of {}", &[&(page + 1), &total_pages]));
         if page + 1 < total_pages {
             pagination.push_str(&format!(
-                r#" <a href="/page/{}">Next &raquo;</a>"#,
-                page + 1
+                r#" <a href="/page/{}">{} &raquo;</a>"#,
+                page + 1,
+                tr("Next")
             ));
         }
         pagination.push_str("</div>");
@@ -183,76 +351,190 @@ fn serve_gallery(request: tiny_http::Request, state: &ServerState, page: usize)
 <meta charset="utf-8">
 <meta name="viewport" content="width=device-width, initial-scale=1">
 <title>Looky — {folder}</title>
-<style>
-body {{ margin: 0; background: #1a1a1a; color: #ccc; font-family: system-ui, sans-serif; }}
-.header {{ padding: 12px 16px; background: #222; border-bottom: 1px solid #333; }}
-.header h1 {{ margin: 0; font-size: 18px; font-weight: 500; }}
-.header .count {{ color: #888; font-size: 14px; }}
-.grid {{ display: grid; grid-template-columns: repeat(auto-fill, minmax(150px, 1fr)); gap: 4px; padding: 4px; }}
-.grid a {{ display: block; aspect-ratio: 1; overflow: hidden; }}
-.grid img {{ width: 100%; height: 100%; object-fit: cover; display: block; }}
-.pages {{ text-align: center; padding: 16px; }}
-.pages a {{ color: #6af; text-decoration: none; margin: 0 8px; }}
-</style>
+<link rel="icon" href="/favicon.ico">
+<link rel="apple-touch-icon" href="/dlna/icon/180.png">
+<link rel="manifest" href="/manifest.webmanifest">
+<meta name="theme-color" content="{theme_color}">
+{style}
+{format_script}
 </head><body>
 <div class="header">
   <h1>Looky — {folder}</h1>
-  <span class="count">{total} photos</span>
+  <span class="count" id="count" data-total="{total}">{count}</span>
+  {upload_html}
 </div>
-<div class="grid">{thumbs_html}</div>
+<div class="grid" id="grid">{thumbs_html}</div>
 {pagination}
+{lightbox}
+{live_script}
 </body></html>"#,
         folder = html_escape(&state.folder_name),
+        theme_color = icon::THEME_COLOR,
+        style = GALLERY_STYLE,
+        lightbox = LIGHTBOX_SCRIPT,
+        count = trn(total, "{} photo", "{} photos"),
+        format_script = FORMAT_SCRIPT,
+        // New photos are prepended, which only makes sense on the first page.
+        live_script = if page == 0 { live_update_script() } else { String::new() },
+        upload_html = if state.uploads_enabled.load(Ordering::Relaxed) {
+            upload_html()
+        } else {
+            String::new()
+        },
     );
 
     respond_html(request, html)
 }
 
+/// Looks of the gallery page, shared with exported galleries.
+pub const GALLERY_STYLE: &str = r#"<style>
+body { margin: 0; background: #1a1a1a; color: #ccc; font-family: system-ui, sans-serif; }
+.header { padding: 12px 16px; background: #222; border-bottom: 1px solid #333; }
+.header h1 { margin: 0; font-size: 18px; font-weight: 500; }
+.header .count { color: #888; font-size: 14px; }
+.grid { display: grid; grid-template-columns: repeat(auto-fill, minmax(150px, 1fr)); gap: 4px; padding: 4px; }
+.grid a { display: block; aspect-ratio: 1; overflow: hidden; }
+.grid img { width: 100%; height: 100%; object-fit: cover; display: block; }
+.pages { text-align: center; padding: 16px; }
+.pages a { color: #6af; text-decoration: none; margin: 0 8px; }
+.upload { float: right; color: #6af; cursor: pointer; font-size: 14px; }
+.upload-status { float: right; color: #888; font-size: 14px; margin-right: 12px; }
+.lightbox { position: fixed; inset: 0; background: rgba(0, 0, 0, 0.92); display: none; align-items: center; justify-content: center; }
+.lightbox.open { display: flex; }
+.lightbox img { max-width: 100vw; max-height: 100vh; object-fit: contain; }
+.lightbox .caption { position: fixed; bottom: 12px; left: 0; right: 0; text-align: center; color: #aaa; font-size: 14px; }
+</style>"#;
+
+/// Opens grid links in an overlay instead of navigating to the bare image.
+/// Arrow keys step through the grid, Escape or a click closes it. Works on
+/// photos added later too, as it looks links up when they're clicked.
+pub const LIGHTBOX_SCRIPT: &str = r#"<div class="lightbox" id="lightbox"><img alt=""><div class="caption"></div></div>
+<script>
+(function () {
+  var grid = document.getElementById('grid');
+  var box = document.getElementById('lightbox');
+  var img = box.querySelector('img');
+  var caption = box.querySelector('.caption');
+  var current = null;
+  function show(a) {
+    if (!a) return;
+    current = a;
+    img.src = a.href;
+    caption.textContent = a.title;
+    box.classList.add('open');
+  }
+  function close() {
+    box.classList.remove('open');
+    img.removeAttribute('src');
+    current = null;
+  }
+  grid.addEventListener('click', function (e) {
+    var a = e.target.closest('a');
+    if (!a) return;
+    e.preventDefault();
+    show(a);
+  });
+  box.addEventListener('click', close);
+  document.addEventListener('keydown', function (e) {
+    if (!current) return;
+    if (e.key === 'Escape') close();
+    else if (e.key === 'ArrowRight') show(current.nextElementSibling);
+    else if (e.key === 'ArrowLeft') show(current.previousElementSibling);
+  });
+})();
+</script>"#;
+
+/// Fills each `{}` in a translated string with the next argument.
+const FORMAT_SCRIPT: &str = r#"<script>
+function fmt(s) {
+  var args = arguments, i = 1;
+  return s.replace(/\{\}/g, function () { return args[i++]; });
+}
+</script>"#;
+
+/// Subscribes to `/events` and inserts newly shared photos at the top of the grid.
+fn live_update_script() -> String {
+    LIVE_UPDATE_SCRIPT.replace("{photos}", tr("{} photos"))
+}
+
+const LIVE_UPDATE_SCRIPT: &str = r#"<script>
+(function () {
+  if (!window.EventSource) return;
+  var grid = document.getElementById('grid');
+  var count = document.getElementById('count');
+  var es = new EventSource('/events');
+  es.addEventListener('photo', function (e) {
+    var p = JSON.parse(e.data);
+    var a = document.createElement('a');
+    a.href = '/display/' + p.id + '?v=' + p.version;
+    a.title = p.name;
+    var img = document.createElement('img');
+    img.src = '/thumb/' + p.id + '?v=' + p.version;
+    img.alt = p.name;
+    a.appendChild(img);
+    grid.insertBefore(a, grid.firstChild);
+    var total = parseInt(count.dataset.total, 10) + 1;
+    count.dataset.total = total;
+    count.textContent = fmt('{photos}', total);
+  });
+})();
+</script>"#;
+
+/// Upload button for the gallery header. Files are POSTed one at a time as raw
+/// bodies; they show up in the grid via the live-update stream once saved.
+fn upload_html() -> String {
+    UPLOAD_HTML
+        .replace("{upload}", tr("Upload"))
+        .replace("{uploading}", tr("Uploading {} / {}..."))
+        .replace("{failed}", tr("{} upload(s) failed"))
+        .replace("{uploaded}", tr("Uploaded {}"))
+}
+
+const UPLOAD_HTML: &str = r#"<label class="upload">{upload}<input type="file" id="upload" accept="image/*" multiple hidden></label>
+<span class="upload-status" id="upload-status"></span>
+<script>
+document.getElementById('upload').addEventListener('change', async function (e) {
+  var status = document.getElementById('upload-status');
+  var files = Array.from(e.target.files);
+  var failed = 0;
+  for (var i = 0; i < files.length; i++) {
+    status.textContent = fmt('{uploading}', i + 1, files.length);
+    try {
+      var res = await fetch('/upload?name=' + encodeURIComponent(files[i].name), { method: 'POST', body: files[i] });
+      if (!res.ok) failed++;
+    } catch (err) {
+      failed++;
+    }
+  }
+  status.textContent = failed ? fmt('{failed}', failed) : fmt('{uploaded}', files.length);
+  e.target.value = '';
+});
+</script>"#;
+
 fn serve_thumbnail(
     request: tiny_http::Request,
     state: &ServerState,
     index: usize,
-    cache: &Arc<Mutex<HashMap<usize, Vec<u8>>>>,
+    cache: &SharedThumbCache,
 ) -> HttpResult {
-    if index >= state.image_paths.len() {
+    let Some(path) = image_path(state, index) else {
         return serve_404(request);
-    }
-
-    // Check cache
-    {
-        let lock = cache.lock().unwrap();
-        if let Some(bytes) = lock.get(&index) {
-            let response = tiny_http::Response::from_data(bytes.clone())
-                .with_header(
-                    "Content-Type: image/jpeg"
-                        .parse::<tiny_http::Header>()
-                        .unwrap(),
-                )
-                .with_header(
-                    "Cache-Control: public, max-age=3600"
-                        .parse::<tiny_http::Header>()
-                        .unwrap(),
-                )
-                .with_header(DLNA_TRANSFER_INTERACTIVE.parse::<tiny_http::Header>().unwrap())
-                .with_header(DLNA_CONTENT_FEATURES.parse::<tiny_http::Header>().unwrap());
-            request.respond(response)?;
-            return Ok(());
+    };
+    let format = ThumbFormat::negotiate(header_value(&request, "Accept").as_deref(), state.avif_thumbs);
+
+    let cached = cache.lock().unwrap().get(&(index, format));
+    let bytes = match cached {
+        Some(bytes) => bytes,
+        None => {
+            let bytes = format.encode(&path);
+            cache.lock().unwrap().insert((index, format), bytes.clone());
+            bytes
         }
-    }
+    };
 
-    // Generate
-    let path = &state.image_paths[index];
-    let jpeg_bytes = thumbnail::thumbnail_jpeg_bytes(path, THUMB_MAX_SIZE, THUMB_QUALITY);
-
-    // Store in cache
-    {
-        let mut lock = cache.lock().unwrap();
-        lock.insert(index, jpeg_bytes.clone());
-    }
-
-    let response = tiny_http::Response::from_data(jpeg_bytes)
+    let mut response = tiny_http::Response::from_data(bytes)
         .with_header(
-            "Content-Type: image/jpeg"
+            format!("Content-Type: {}", format.mime())
                 .parse::<tiny_http::Header>()
                 .unwrap(),
         )
@@ -261,80 +543,91 @@ fn serve_thumbnail(
                 .parse::<tiny_http::Header>()
                 .unwrap(),
         )
-        .with_header(DLNA_TRANSFER_INTERACTIVE.parse::<tiny_http::Header>().unwrap())
-        .with_header(DLNA_CONTENT_FEATURES.parse::<tiny_http::Header>().unwrap());
+        .with_header("Vary: Accept".parse::<tiny_http::Header>().unwrap());
+    if format == ThumbFormat::Jpeg {
+        response = response
+            .with_header(DLNA_TRANSFER_INTERACTIVE.parse::<tiny_http::Header>().unwrap())
+            .with_header(DLNA_CONTENT_FEATURES.parse::<tiny_http::Header>().unwrap());
+    }
     request.respond(response)?;
     Ok(())
 }
 
 fn serve_image(request: tiny_http::Request, state: &ServerState, index: usize) -> HttpResult {
-    if index >= state.image_paths.len() {
-        log::debug!("Image request index {index} out of range (total {})", state.image_paths.len());
+    let Some(path) = image_path(state, index) else {
+        log::debug!("Image request index {index} out of range (total {})", state.image_paths.read().unwrap().len());
         return serve_404(request);
+    };
+    if !thumbnail::needs_upright(&path) {
+        return stream_file(request, index, &path);
     }
+    // Image needs rotation — serve the upright copy, turned losslessly where
+    // possible, the same bytes a HEAD request measured
+    log::debug!("Serving image {index} with orientation correction: {}", path.display());
+    let Some(buf) = thumbnail::upright_jpeg_bytes(&path, UPRIGHT_QUALITY) else {
+        return serve_404(request);
+    };
+    let response = tiny_http::Response::from_data(buf)
+        .with_header("Content-Type: image/jpeg".parse::<tiny_http::Header>().unwrap())
+        .with_header("Cache-Control: public, max-age=3600".parse::<tiny_http::Header>().unwrap())
+        .with_header(DLNA_TRANSFER_INTERACTIVE.parse::<tiny_http::Header>().unwrap())
+        .with_header(DLNA_CONTENT_FEATURES.parse::<tiny_http::Header>().unwrap());
+    request.respond(response)?;
+    Ok(())
+}
 
-    let path = &state.image_paths[index];
-    let orientation = crate::thumbnail::read_orientation(path);
-
-    if orientation > 1 {
-        // Image needs rotation — decode, rotate, re-encode as JPEG
-        log::debug!("Serving image {index} with orientation correction ({orientation}): {}", path.display());
-        let img = image::open(path)?;
-        let rotated = match orientation {
-            2 => img.fliph(),
-            3 => img.rotate180(),
-            4 => img.flipv(),
-            5 => img.rotate90().fliph(),
-            6 => img.rotate90(),
-            7 => img.rotate270().fliph(),
-            8 => img.rotate270(),
-            _ => img,
-        };
-        let mut buf = Vec::new();
-        let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buf, 92);
-        let (w, h) = rotated.dimensions();
-        use image::ImageEncoder;
-        encoder.write_image(rotated.to_rgb8().as_raw(), w, h, image::ExtendedColorType::Rgb8)?;
-
-        let response = tiny_http::Response::from_data(buf)
-            .with_header("Content-Type: image/jpeg".parse::<tiny_http::Header>().unwrap())
-            .with_header("Cache-Control: public, max-age=3600".parse::<tiny_http::Header>().unwrap())
-            .with_header(DLNA_TRANSFER_INTERACTIVE.parse::<tiny_http::Header>().unwrap())
-            .with_header(DLNA_CONTENT_FEATURES.parse::<tiny_http::Header>().unwrap());
-        request.respond(response)?;
-    } else {
-        // No rotation needed — stream original file
-        let file = std::fs::File::open(path)?;
-        let len = file.metadata()?.len();
-        let mime = dlna::mime_for_path(path);
-
-        log::debug!("Serving image {index}: path={} mime={mime} size={len}", path.display());
-
-        let reader = std::io::BufReader::new(file);
-        let response = tiny_http::Response::new(
-            tiny_http::StatusCode(200),
-            vec![
-                format!("Content-Type: {mime}").parse::<tiny_http::Header>().unwrap(),
-                "Cache-Control: public, max-age=3600".parse::<tiny_http::Header>().unwrap(),
-                DLNA_TRANSFER_INTERACTIVE.parse::<tiny_http::Header>().unwrap(),
-                DLNA_CONTENT_FEATURES.parse::<tiny_http::Header>().unwrap(),
-            ],
-            reader,
-            Some(len as usize),
-            None,
-        );
-        request.respond(response)?;
-    }
+/// `/original/{i}`: the file exactly as it is on disk, even when `/image/`
+/// would turn it upright, for downloading the original.
+fn serve_original(request: tiny_http::Request, state: &ServerState, index: usize) -> HttpResult {
+    let Some(path) = image_path(state, index) else {
+        return serve_404(request);
+    };
+    stream_file(request, index, &path)
+}
+
+fn stream_file(request: tiny_http::Request, index: usize, path: &std::path::Path) -> HttpResult {
+    let file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    let mime = dlna::mime_for_path(path);
+
+    log::debug!("Serving image {index}: path={} mime={mime} size={len}", path.display());
+
+    let reader = std::io::BufReader::new(file);
+    let response = tiny_http::Response::new(
+        tiny_http::StatusCode(200),
+        vec![
+            format!("Content-Type: {mime}").parse::<tiny_http::Header>().unwrap(),
+            "Cache-Control: public, max-age=3600".parse::<tiny_http::Header>().unwrap(),
+            DLNA_TRANSFER_INTERACTIVE.parse::<tiny_http::Header>().unwrap(),
+            DLNA_CONTENT_FEATURES.parse::<tiny_http::Header>().unwrap(),
+        ],
+        reader,
+        Some(len as usize),
+        None,
+    );
+    request.respond(response)?;
     Ok(())
 }
 
-/// Serve a TV-sized (1920px) JPEG for Chromecast — much faster to transfer than full-res.
-fn serve_cast_image(request: tiny_http::Request, state: &ServerState, index: usize) -> HttpResult {
-    if index >= state.image_paths.len() {
+/// Serve a downscaled JPEG no larger than `width` on its long edge — much faster
+/// to transfer to phones and TVs than full-res originals.
+fn serve_display_image(
+    request: tiny_http::Request,
+    state: &ServerState,
+    index: usize,
+    width: u32,
+) -> HttpResult {
+    let Some(path) = image_path(state, index) else {
         return serve_404(request);
-    }
-    let path = &state.image_paths[index];
-    let jpeg_bytes = thumbnail::thumbnail_jpeg_bytes(path, CAST_MAX_SIZE, CAST_QUALITY);
+    };
+    let size = DISPLAY_SIZES
+        .iter()
+        .copied()
+        .find(|&s| s >= width)
+        .unwrap_or(DISPLAY_SIZES[DISPLAY_SIZES.len() - 1]);
+    let Some(jpeg_bytes) = thumbnail::display_jpeg_bytes(&path, size, DISPLAY_QUALITY) else {
+        return serve_404(request);
+    };
     let response = tiny_http::Response::from_data(jpeg_bytes)
         .with_header("Content-Type: image/jpeg".parse::<tiny_http::Header>().unwrap())
         .with_header("Cache-Control: public, max-age=3600".parse::<tiny_http::Header>().unwrap());
@@ -342,19 +635,59 @@ fn serve_cast_image(request: tiny_http::Request, state: &ServerState, index: usi
     Ok(())
 }
 
+/// Serve an image resized to fit a DLNA JPEG profile. HEAD requests get the
+/// same headers; tiny_http drops the body.
+fn serve_dlna_profile(
+    request: tiny_http::Request,
+    state: &ServerState,
+    index: usize,
+    profile: &dlna::JpegProfile,
+) -> HttpResult {
+    let Some(path) = image_path(state, index) else {
+        return serve_404(request);
+    };
+    let Some(jpeg_bytes) = thumbnail::display_jpeg_bytes(&path, profile.max_size, DISPLAY_QUALITY) else {
+        return serve_404(request);
+    };
+    let features = format!(
+        "contentFeatures.dlna.org: DLNA.ORG_PN={};DLNA.ORG_OP=01;DLNA.ORG_CI=1;DLNA.ORG_FLAGS=00D00000000000000000000000000000",
+        profile.name
+    );
+    let response = tiny_http::Response::from_data(jpeg_bytes)
+        .with_header("Content-Type: image/jpeg".parse::<tiny_http::Header>().unwrap())
+        .with_header("Cache-Control: public, max-age=3600".parse::<tiny_http::Header>().unwrap())
+        .with_header(DLNA_TRANSFER_INTERACTIVE.parse::<tiny_http::Header>().unwrap())
+        .with_header(features.parse::<tiny_http::Header>().unwrap());
+    request.respond(response)?;
+    Ok(())
+}
+
+/// Which GET a HEAD request stands in for.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum HeadOf {
+    Image,
+    Original,
+}
+
 fn serve_image_head(
     request: tiny_http::Request,
     state: &ServerState,
     index: usize,
-    is_thumb: bool,
+    of: HeadOf,
 ) -> HttpResult {
-    if index >= state.image_paths.len() {
+    let Some(path) = image_path(state, index) else {
         return serve_404(request);
-    }
+    };
 
-    let path = &state.image_paths[index];
-    let mime = if is_thumb { "image/jpeg" } else { dlna::mime_for_path(path) };
-    let len = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let (mime, len) = if of == HeadOf::Image && thumbnail::needs_upright(&path) {
+        // GET sends these photos re-encoded upright, so report that size
+        let Some(upright) = thumbnail::upright_jpeg_bytes(&path, UPRIGHT_QUALITY) else {
+            return serve_404(request);
+        };
+        ("image/jpeg", upright.len() as u64)
+    } else {
+        (dlna::mime_for_path(&path), std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0))
+    };
 
     let response = tiny_http::Response::new(
         tiny_http::StatusCode(200),
@@ -380,10 +713,50 @@ fn serve_image_head(
 }
 
 fn serve_device_xml(request: tiny_http::Request, state: &ServerState) -> HttpResult {
-    let xml = dlna::device_xml(&state.device_uuid, &state.folder_name, state.server_addr);
+    let xml = dlna::device_xml(&state.device_uuid, &state.friendly_name, request_addr(&request, state));
     respond_xml(request, xml)
 }
 
+fn serve_icon(request: tiny_http::Request, size: u32, format: icon::IconFormat) -> HttpResult {
+    let Some(bytes) = icon::icon_bytes(size, format) else {
+        return serve_404(request);
+    };
+    let response = tiny_http::Response::from_data(bytes)
+        .with_header(format!("Content-Type: {}", format.mime()).parse::<tiny_http::Header>().unwrap())
+        .with_header("Cache-Control: public, max-age=86400".parse::<tiny_http::Header>().unwrap());
+    request.respond(response)?;
+    Ok(())
+}
+
+/// Web app manifest, so the gallery can be added to a phone's home screen
+/// and opens there like an app.
+fn serve_manifest(request: tiny_http::Request, state: &ServerState) -> HttpResult {
+    let icons: Vec<String> = icon::WEB_ICON_SIZES[2..]
+        .iter()
+        .map(|size| {
+            api::JsonObject::new()
+                .str("src", Some(&format!("/dlna/icon/{size}.png")))
+                .str("sizes", Some(&format!("{size}x{size}")))
+                .str("type", Some("image/png"))
+                .finish()
+        })
+        .collect();
+    let json = api::JsonObject::new()
+        .str("name", Some(&format!("Looky — {}", state.folder_name)))
+        .str("short_name", Some("Looky"))
+        .str("start_url", Some("/"))
+        .str("display", Some("standalone"))
+        .str("background_color", Some("#1a1a1a"))
+        .str("theme_color", Some(icon::THEME_COLOR))
+        .raw("icons", &format!("[{}]", icons.join(",")))
+        .finish();
+    let response = tiny_http::Response::from_string(json)
+        .with_header("Content-Type: application/manifest+json".parse::<tiny_http::Header>().unwrap())
+        .with_header("Cache-Control: public, max-age=86400".parse::<tiny_http::Header>().unwrap());
+    request.respond(response)?;
+    Ok(())
+}
+
 fn serve_static_xml(request: tiny_http::Request, xml: &str) -> HttpResult {
     respond_xml_static(request, xml)
 }
@@ -391,7 +764,19 @@ fn serve_static_xml(request: tiny_http::Request, xml: &str) -> HttpResult {
 fn serve_soap_content(mut request: tiny_http::Request, state: &ServerState) -> HttpResult {
     let mut body = String::new();
     request.as_reader().read_to_string(&mut body)?;
-    let xml = dlna::handle_content_directory(&body, state.server_addr, &state.image_paths);
+    // Copies, so reading metadata for a large Browse doesn't hold up the
+    // app adding or removing images meanwhile.
+    let image_paths = state.image_paths.read().unwrap().clone();
+    let removed = state.removed.read().unwrap().clone();
+    let library = dlna::Library {
+        addr: request_addr(&request, state),
+        root: &state.root_dir,
+        image_paths: &image_paths,
+        photo_info: &state.photo_info,
+        removed: &removed,
+        update_id: state.system_update_id.load(Ordering::Relaxed),
+    };
+    let xml = dlna::handle_content_directory(&body, &library);
     respond_xml(request, xml)
 }
 
@@ -402,16 +787,143 @@ fn serve_soap_connection(mut request: tiny_http::Request) -> HttpResult {
     respond_xml(request, xml)
 }
 
-fn serve_subscribe(request: tiny_http::Request) -> HttpResult {
+/// GENA SUBSCRIBE: a new subscription (CALLBACK + NT) or a renewal (SID).
+fn serve_subscribe(request: tiny_http::Request, state: &ServerState, path: &str) -> HttpResult {
+    let Some(service) = gena::Service::from_event_path(path) else {
+        return serve_404(request);
+    };
+    let sid = header_value(&request, "SID");
+    let callback = header_value(&request, "CALLBACK");
+    let nt = header_value(&request, "NT");
+    let timeout = gena::parse_timeout(header_value(&request, "TIMEOUT").as_deref());
+
+    let (sid, is_new) = match sid {
+        Some(_) if callback.is_some() || nt.is_some() => return respond_status(request, 400),
+        Some(sid) => {
+            if !state.gena.renew(&sid, timeout) {
+                return respond_status(request, 412);
+            }
+            (sid, false)
+        }
+        None => {
+            let callbacks = callback.as_deref().map(gena::parse_callbacks).unwrap_or_default();
+            if nt.as_deref() != Some("upnp:event") || callbacks.is_empty() {
+                return respond_status(request, 412);
+            }
+            (state.gena.subscribe(service, callbacks, timeout), true)
+        }
+    };
+
     let response = tiny_http::Response::from_string("")
         .with_status_code(200)
-        .with_header("SID: uuid:dummy".parse::<tiny_http::Header>().unwrap())
-        .with_header(
-            "TIMEOUT: Second-300"
-                .parse::<tiny_http::Header>()
-                .unwrap(),
-        );
+        .with_header(format!("SID: {sid}").parse::<tiny_http::Header>().unwrap())
+        .with_header(format!("TIMEOUT: Second-{}", timeout.as_secs()).parse::<tiny_http::Header>().unwrap())
+        .with_header("SERVER: Looky/1.0 UPnP/1.0".parse::<tiny_http::Header>().unwrap());
     request.respond(response)?;
+
+    // The initial event carries every evented variable and must follow the response.
+    if is_new {
+        let update_id = state.system_update_id.load(Ordering::Relaxed);
+        state.gena.notify_one(&sid, &dlna::evented_variables(service, update_id));
+    }
+    Ok(())
+}
+
+fn serve_unsubscribe(request: tiny_http::Request, state: &ServerState) -> HttpResult {
+    let status = match header_value(&request, "SID") {
+        Some(sid) if state.gena.unsubscribe(&sid) => 200,
+        _ => 412,
+    };
+    respond_status(request, status)
+}
+
+fn respond_status(request: tiny_http::Request, status: u16) -> HttpResult {
+    request.respond(tiny_http::Response::empty(status))?;
+    Ok(())
+}
+
+/// The address the client reached us on, from its `Host` header, so URLs we
+/// hand out work over whichever interface (or IP version) it came in on.
+fn request_addr(request: &tiny_http::Request, state: &ServerState) -> SocketAddr {
+    header_value(request, "Host")
+        .and_then(|host| host.parse().ok())
+        .unwrap_or(state.server_addr)
+}
+
+fn header_value(request: &tiny_http::Request, name: &'static str) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str().trim().to_string())
+}
+
+fn serve_api_images(request: tiny_http::Request, state: &ServerState, url: &str) -> HttpResult {
+    let page = query_param(url, "page")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    let per_page = query_param(url, "per_page")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(api::DEFAULT_PER_PAGE);
+    let removed = state.removed.read().unwrap().clone();
+    let json = api::images_json(&state.image_paths.read().unwrap(), &removed, page, per_page);
+    respond_json(request, 200, json)
+}
+
+fn serve_api_meta(request: tiny_http::Request, state: &ServerState, index: usize) -> HttpResult {
+    let Some(path) = image_path(state, index) else {
+        return respond_json(request, 404, api::error_json("No such image"));
+    };
+    respond_json(request, 200, api::meta_json(index, &path))
+}
+
+fn serve_upload(mut request: tiny_http::Request, state: &ServerState, url: &str) -> HttpResult {
+    if !state.uploads_enabled.load(Ordering::Relaxed) {
+        let response = tiny_http::Response::from_string("Uploads are disabled").with_status_code(403);
+        request.respond(response)?;
+        return Ok(());
+    }
+
+    let name = query_param(url, "name").unwrap_or_default();
+    if request.body_length().is_some_and(|len| len as u64 > upload::MAX_UPLOAD_BYTES) {
+        let response = tiny_http::Response::from_string("File too large").with_status_code(413);
+        request.respond(response)?;
+        return Ok(());
+    }
+    let mut bytes = Vec::new();
+    request
+        .as_reader()
+        .take(upload::MAX_UPLOAD_BYTES + 1)
+        .read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > upload::MAX_UPLOAD_BYTES {
+        let response = tiny_http::Response::from_string("File too large").with_status_code(413);
+        request.respond(response)?;
+        return Ok(());
+    }
+
+    match upload::save_upload(&state.upload_dir, &name, &bytes) {
+        Ok(path) => {
+            let saved = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let body = api::JsonObject::new()
+                .bool("ok", true)
+                .str("name", Some(&saved))
+                .finish();
+            let response = tiny_http::Response::from_string(body).with_header(
+                "Content-Type: application/json"
+                    .parse::<tiny_http::Header>()
+                    .unwrap(),
+            );
+            request.respond(response)?;
+        }
+        Err(e) => {
+            log::debug!("Rejected upload '{name}': {e}");
+            let response = tiny_http::Response::from_string(e).with_status_code(400);
+            request.respond(response)?;
+        }
+    }
     Ok(())
 }
 
@@ -421,14 +933,60 @@ fn serve_404(request: tiny_http::Request) -> HttpResult {
     Ok(())
 }
 
-/// Parse index from path like "42", "42.jpg", or "42/filename.jpg".
-fn parse_index_from_path(s: &str) -> usize {
+/// Look up a shared image by index, cloning the path so the lock isn't held during I/O.
+fn image_path(state: &ServerState, index: usize) -> Option<PathBuf> {
+    state.image_paths.read().unwrap().get(index).cloned()
+}
+
+/// Index of the image a path like "p1a2b…", "p1a2b…/filename.jpg" or
+/// "p1a2b…?w=1600" names by its ID. Bare indices ("42", "42.jpg") as
+/// handed out before IDs still work.
+fn resolve_index(state: &ServerState, s: &str) -> usize {
+    let s = s.split('?').next().unwrap_or(s);
     let first_segment = s.split('/').next().unwrap_or(s);
-    let num_part = first_segment.split('.').next().unwrap_or(first_segment);
-    num_part.parse().unwrap_or(usize::MAX)
+    let key = first_segment.split('.').next().unwrap_or(first_segment);
+    if let Ok(index) = key.parse() {
+        return index;
+    }
+    state.ids.read().unwrap().get(key).copied().unwrap_or(usize::MAX)
+}
+
+/// Extract and percent-decode a query string parameter.
+fn query_param(url: &str, key: &str) -> Option<String> {
+    let query = url.split_once('?')?.1;
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| percent_decode(v))
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(b) => {
+                        out.push(b);
+                        i += 3;
+                        continue;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b'+' => out.push(b' '),
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
 }
 
-fn html_escape(s: &str) -> String {
+pub fn html_escape(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
         .replace('>', "&gt;")
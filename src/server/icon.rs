@@ -0,0 +1,134 @@
+//! Device icon for the UPnP description and the web gallery, drawn at
+//! runtime so no image assets need to ship with the binary: a white eye on a
+//! blue rounded square.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+/// Sizes listed in the device description's `iconList`.
+pub const ICON_SIZES: [u32; 2] = [48, 120];
+/// Sizes the gallery links for browser tabs, home screens and its manifest.
+pub const WEB_ICON_SIZES: [u32; 4] = [32, 180, 192, 512];
+/// The blue of the tile, for the manifest's theme colour.
+pub const THEME_COLOR: &str = "#3478f6";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IconFormat {
+    Png,
+    Jpeg,
+    Ico,
+}
+
+impl IconFormat {
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext {
+            "png" => Some(Self::Png),
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            "ico" => Some(Self::Ico),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::Ico => "ico",
+        }
+    }
+
+    pub fn mime(self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Jpeg => "image/jpeg",
+            Self::Ico => "image/x-icon",
+        }
+    }
+}
+
+type IconCache = HashMap<(u32, IconFormat), Vec<u8>>;
+
+/// Encoded icon at one of [`ICON_SIZES`] or [`WEB_ICON_SIZES`], rendered
+/// once and kept in memory.
+pub fn icon_bytes(size: u32, format: IconFormat) -> Option<Vec<u8>> {
+    static CACHE: OnceLock<Mutex<IconCache>> = OnceLock::new();
+    if !ICON_SIZES.contains(&size) && !WEB_ICON_SIZES.contains(&size) {
+        return None;
+    }
+    let mut cache = CACHE.get_or_init(Default::default).lock().unwrap();
+    if let Some(bytes) = cache.get(&(size, format)) {
+        return Some(bytes.clone());
+    }
+    let bytes = encode(&render(size), format)?;
+    cache.insert((size, format), bytes.clone());
+    Some(bytes)
+}
+
+fn encode(img: &image::RgbImage, format: IconFormat) -> Option<Vec<u8>> {
+    let mut buf = std::io::Cursor::new(Vec::new());
+    let format = match format {
+        IconFormat::Png => image::ImageFormat::Png,
+        IconFormat::Jpeg => image::ImageFormat::Jpeg,
+        IconFormat::Ico => image::ImageFormat::Ico,
+    };
+    img.write_to(&mut buf, format)
+        .map_err(|e| log::warn!("Failed to encode icon: {}", e))
+        .ok()?;
+    Some(buf.into_inner())
+}
+
+const BACKGROUND: [f32; 3] = [255.0, 255.0, 255.0];
+const TILE: [f32; 3] = [52.0, 120.0, 246.0];
+const EYE: [f32; 3] = [255.0, 255.0, 255.0];
+const PUPIL: [f32; 3] = [28.0, 32.0, 48.0];
+
+/// Draw the icon with 4x4 supersampling for smooth edges. Shapes are defined
+/// in unit coordinates (0..1) so every size looks the same.
+fn render(size: u32) -> image::RgbImage {
+    const SAMPLES: u32 = 4;
+    image::RgbImage::from_fn(size, size, |px, py| {
+        let mut acc = [0.0f32; 3];
+        for sy in 0..SAMPLES {
+            for sx in 0..SAMPLES {
+                let x = (px as f32 + (sx as f32 + 0.5) / SAMPLES as f32) / size as f32;
+                let y = (py as f32 + (sy as f32 + 0.5) / SAMPLES as f32) / size as f32;
+                let c = shade(x, y);
+                for (a, v) in acc.iter_mut().zip(c) {
+                    *a += v;
+                }
+            }
+        }
+        let n = (SAMPLES * SAMPLES) as f32;
+        image::Rgb(acc.map(|v| (v / n).round() as u8))
+    })
+}
+
+fn shade(x: f32, y: f32) -> [f32; 3] {
+    if !in_rounded_square(x, y, 0.04, 0.2) {
+        return BACKGROUND;
+    }
+    // Eye: the lens where two offset circles overlap.
+    let (cx, cy) = (0.5, 0.5);
+    let in_eye = (x - cx).powi(2) + (y - (cy + 0.32)).powi(2) < 0.5f32.powi(2)
+        && (x - cx).powi(2) + (y - (cy - 0.32)).powi(2) < 0.5f32.powi(2);
+    if !in_eye {
+        return TILE;
+    }
+    let d2 = (x - cx).powi(2) + (y - cy).powi(2);
+    let highlight = (x - (cx + 0.05)).powi(2) + (y - (cy - 0.05)).powi(2) < 0.035f32.powi(2);
+    if d2 < 0.13f32.powi(2) && !highlight {
+        PUPIL
+    } else {
+        EYE
+    }
+}
+
+fn in_rounded_square(x: f32, y: f32, margin: f32, radius: f32) -> bool {
+    let (lo, hi) = (margin, 1.0 - margin);
+    if x < lo || x > hi || y < lo || y > hi {
+        return false;
+    }
+    let qx = (x - (lo + radius)).min(0.0) + (x - (hi - radius)).max(0.0);
+    let qy = (y - (lo + radius)).min(0.0) + (y - (hi - radius)).max(0.0);
+    qx * qx + qy * qy <= radius * radius
+}
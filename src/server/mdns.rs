@@ -0,0 +1,170 @@
+//! mDNS / DNS-SD advertisement of the share as `looky.local`.
+//!
+//! The gallery is registered as an `_http._tcp` service with an `app=looky`
+//! TXT record, so browsers can reach it by name and other Looky instances can
+//! find it with [`discover_peers`]. mdns-sd doesn't resolve name conflicts,
+//! so when another host already answers for `looky.local` the share takes
+//! `looky-<id>.local` instead, `<id>` being the start of its UUID.
+
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use mdns_sd::{HostnameResolutionEvent, ServiceDaemon, ServiceEvent, ServiceInfo};
+
+const HTTP_SERVICE: &str = "_http._tcp.local.";
+const HOST_NAME: &str = "looky.local.";
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(3);
+/// How long to listen for another host answering for [`HOST_NAME`].
+const HOST_PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A registered mDNS service. Unregistered when stopped.
+pub struct Advertisement {
+    daemon: ServiceDaemon,
+    fullname: String,
+    /// The host name registered, `looky.local.` unless that was taken.
+    host_name: String,
+    port: u16,
+}
+
+impl Advertisement {
+    /// `http://looky.local:port`, or the name taken instead, for the
+    /// advertised gallery.
+    pub fn url(&self) -> String {
+        format!("http://{}:{}", self.host_name.trim_end_matches('.'), self.port)
+    }
+
+    pub fn stop(self) {
+        if let Ok(rx) = self.daemon.unregister(&self.fullname) {
+            // Wait briefly so the goodbye packet goes out before shutdown.
+            let _ = rx.recv_timeout(Duration::from_secs(1));
+        }
+        let _ = self.daemon.shutdown();
+    }
+}
+
+/// Register the gallery at `addr` as `looky.local`, or under a name of its
+/// own if another host already has that. Blocks for about a second while
+/// checking.
+pub fn advertise(addr: SocketAddr, folder_name: &str, uuid: &str) -> Result<Advertisement, String> {
+    let daemon = ServiceDaemon::new().map_err(|e| format!("mDNS daemon: {e}"))?;
+    let host_name = if host_taken(&daemon, addr.ip()) {
+        format!("looky-{}.local.", &uuid[..uuid.len().min(8)])
+    } else {
+        HOST_NAME.to_string()
+    };
+    let instance = format!("Looky - {folder_name}");
+    let properties = [
+        ("app", "looky"),
+        ("path", "/"),
+        ("folder", folder_name),
+        ("uuid", uuid),
+    ];
+    let info = ServiceInfo::new(
+        HTTP_SERVICE,
+        &instance,
+        &host_name,
+        addr.ip(),
+        addr.port(),
+        &properties[..],
+    )
+    .map_err(|e| format!("mDNS service info: {e}"))?;
+    let fullname = info.get_fullname().to_string();
+    if let Err(e) = daemon.register(info) {
+        let _ = daemon.shutdown();
+        return Err(format!("mDNS register: {e}"));
+    }
+    log::info!("Advertising {fullname} on {host_name}");
+    Ok(Advertisement {
+        daemon,
+        fullname,
+        host_name,
+        port: addr.port(),
+    })
+}
+
+/// Whether a host other than `own` answers for [`HOST_NAME`].
+fn host_taken(daemon: &ServiceDaemon, own: IpAddr) -> bool {
+    let timeout = HOST_PROBE_TIMEOUT.as_millis() as u64;
+    let Ok(receiver) = daemon.resolve_hostname(HOST_NAME, Some(timeout)) else {
+        return false;
+    };
+    let deadline = Instant::now() + HOST_PROBE_TIMEOUT;
+    let mut taken = false;
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        match receiver.recv_timeout(remaining) {
+            Ok(HostnameResolutionEvent::AddressesFound(_, addrs)) => {
+                if addrs.iter().any(|a| a.to_ip_addr() != own) {
+                    taken = true;
+                    break;
+                }
+            }
+            Ok(HostnameResolutionEvent::SearchTimeout(_) | HostnameResolutionEvent::SearchStopped(_)) => break,
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+    let _ = daemon.stop_resolve_hostname(HOST_NAME);
+    taken
+}
+
+/// Another Looky share found on the LAN.
+#[derive(Debug, Clone)]
+pub struct Peer {
+    pub folder_name: String,
+    pub url: String,
+}
+
+/// Find other Looky shares on the LAN (blocking, ~3 seconds).
+///
+/// `own_uuid` filters out this instance's own advertisement.
+pub fn discover_peers(own_uuid: Option<&str>) -> Vec<Peer> {
+    let daemon = match ServiceDaemon::new() {
+        Ok(d) => d,
+        Err(e) => {
+            log::warn!("mDNS daemon failed to start: {e}");
+            return Vec::new();
+        }
+    };
+    let receiver = match daemon.browse(HTTP_SERVICE) {
+        Ok(r) => r,
+        Err(e) => {
+            log::warn!("mDNS browse failed: {e}");
+            let _ = daemon.shutdown();
+            return Vec::new();
+        }
+    };
+
+    let mut peers: Vec<Peer> = Vec::new();
+    let deadline = Instant::now() + DISCOVERY_TIMEOUT;
+
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        match receiver.recv_timeout(remaining) {
+            Ok(ServiceEvent::ServiceResolved(info)) => {
+                if info.get_property_val_str("app") != Some("looky") {
+                    continue;
+                }
+                if own_uuid.is_some() && info.get_property_val_str("uuid") == own_uuid {
+                    continue;
+                }
+                let Some(ip) = info.get_addresses_v4().into_iter().next() else {
+                    continue;
+                };
+                let url = format!("http://{}", SocketAddr::new(IpAddr::V4(ip), info.get_port()));
+                if !peers.iter().any(|p| p.url == url) {
+                    peers.push(Peer {
+                        folder_name: info
+                            .get_property_val_str("folder")
+                            .unwrap_or("Photos")
+                            .to_string(),
+                        url,
+                    });
+                }
+            }
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+
+    let _ = daemon.shutdown();
+    peers
+}
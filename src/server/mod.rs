@@ -1,31 +1,179 @@
+pub mod api;
 pub mod cast;
 pub mod dlna;
+pub mod events;
+pub mod gena;
 pub mod http;
+pub mod icon;
+pub mod mdns;
+pub mod photo_info;
+pub mod search;
 pub mod ssdp;
+pub mod thumb_cache;
+pub mod upload;
 
-use std::net::{SocketAddr, UdpSocket};
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use events::EventHub;
+
+use crate::thumbnail;
+
+/// Gallery thumbnails kept in memory when the settings don't say otherwise;
+/// a few thousand at typical sizes.
+pub const DEFAULT_THUMB_CACHE_MB: u64 = 64;
+/// HTTP requests served at once when the settings don't say otherwise.
+pub const DEFAULT_HTTP_WORKERS: usize = 4;
 
 pub struct ServerState {
-    pub image_paths: Vec<PathBuf>,
+    /// Shared images. Only ever appended to, so indices handed out stay valid.
+    pub image_paths: RwLock<Vec<PathBuf>>,
+    /// Indices of shared images whose files have since been deleted.
+    pub removed: RwLock<HashSet<usize>>,
+    /// Index of each shared image by its [`photo_id`].
+    pub ids: RwLock<HashMap<String, usize>>,
     pub server_addr: SocketAddr,
+    /// Interface the share is limited to; `None` listens on all of them.
+    pub interface: Option<String>,
+    /// Whether the HTTP server also accepts IPv6 connections.
+    pub ipv6: bool,
     pub device_uuid: String,
     pub folder_name: String,
+    /// Name DLNA renderers list the server under.
+    pub friendly_name: String,
+    /// The shared folder, used to mirror its layout over DLNA.
+    pub root_dir: PathBuf,
+    /// Date, size and resolution per image for DLNA, filled on demand.
+    pub photo_info: Mutex<photo_info::PhotoInfoIndex>,
     pub shutdown: AtomicBool,
+    pub events: EventHub,
+    /// Where photos uploaded from the web gallery are saved.
+    pub upload_dir: PathBuf,
+    pub uploads_enabled: AtomicBool,
+    /// Serve AVIF thumbnails to browsers that accept them.
+    pub avif_thumbs: bool,
+    /// Bytes of encoded gallery thumbnails kept in memory.
+    pub thumb_cache_bytes: u64,
+    /// Threads serving HTTP requests, each handling one at a time.
+    pub http_workers: usize,
+    /// UPnP event subscribers (TVs and control points watching for changes).
+    pub gena: gena::Subscriptions,
+    /// ContentDirectory SystemUpdateID; bumped whenever the shared content changes.
+    pub system_update_id: AtomicU32,
+    /// When the last HTTP request arrived, for stopping idle shares.
+    pub last_request: Mutex<Instant>,
 }
 
 pub struct ServerHandle {
     state: Arc<ServerState>,
     http_thread: Option<JoinHandle<()>>,
     ssdp_thread: Option<JoinHandle<()>>,
+    mdns_thread: Option<JoinHandle<()>>,
+    /// Filled in by `mdns_thread` once the advertisement is up.
+    mdns: Arc<Mutex<Option<mdns::Advertisement>>>,
 }
 
 impl ServerHandle {
+    /// Append newly discovered images and push them to live gallery clients.
+    pub fn add_images(&self, paths: &[PathBuf]) {
+        if paths.is_empty() {
+            return;
+        }
+        let start = {
+            let mut lock = self.state.image_paths.write().unwrap();
+            let start = lock.len();
+            lock.extend_from_slice(paths);
+            let mut ids = self.state.ids.write().unwrap();
+            ids.extend(paths.iter().enumerate().map(|(i, path)| (photo_id(path), start + i)));
+            start
+        };
+        for (i, path) in paths.iter().enumerate() {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let data = api::JsonObject::new()
+                .num("index", start + i)
+                .str("id", Some(&photo_id(path)))
+                .str("name", Some(&name))
+                .str("version", Some(&thumbnail::version_token(path)))
+                .finish();
+            self.state.events.broadcast("photo", &data);
+        }
+        self.content_changed();
+    }
+
+    /// Hide shared images whose files were deleted or moved away.
+    pub fn remove_images(&self, paths: &[PathBuf]) {
+        self.set_removed(paths, true);
+    }
+
+    /// Show previously removed images again when their files reappear.
+    pub fn restore_images(&self, paths: &[PathBuf]) {
+        self.set_removed(paths, false);
+    }
+
+    fn set_removed(&self, paths: &[PathBuf], removed: bool) {
+        let changed = {
+            let image_paths = self.state.image_paths.read().unwrap();
+            let mut hidden = self.state.removed.write().unwrap();
+            let mut changed = false;
+            for (idx, path) in image_paths.iter().enumerate() {
+                if paths.contains(path) {
+                    changed |= if removed { hidden.insert(idx) } else { hidden.remove(&idx) };
+                }
+            }
+            changed
+        };
+        if changed {
+            self.content_changed();
+        }
+    }
+
+    /// Bump SystemUpdateID and tell DLNA subscribers so they refresh.
+    fn content_changed(&self) {
+        let update_id = self.state.system_update_id.fetch_add(1, Ordering::Relaxed) + 1;
+        self.state.gena.notify(
+            gena::Service::ContentDirectory,
+            &dlna::evented_variables(gena::Service::ContentDirectory, update_id),
+        );
+    }
+
+    /// `http://looky.local:port`, or the name registered instead, if the
+    /// mDNS advertisement is up.
+    pub fn local_url(&self) -> Option<String> {
+        self.mdns.lock().unwrap().as_ref().map(|m| m.url())
+    }
+
+    /// Identifier advertised over DLNA and mDNS.
+    pub fn device_uuid(&self) -> &str {
+        &self.state.device_uuid
+    }
+
+    /// Time since the last HTTP request (or since the share started).
+    pub fn idle_for(&self) -> Duration {
+        self.state.last_request.lock().unwrap().elapsed()
+    }
+
+    /// Allow or refuse uploads from the web gallery.
+    pub fn set_uploads_enabled(&self, enabled: bool) {
+        self.state.uploads_enabled.store(enabled, Ordering::Relaxed);
+    }
+
     pub fn stop(mut self) {
         self.state.shutdown.store(true, Ordering::Relaxed);
+        self.state.events.close_all();
+        if let Some(t) = self.mdns_thread.take() {
+            let _ = t.join();
+        }
+        if let Some(m) = self.mdns.lock().unwrap().take() {
+            m.stop();
+        }
         if let Some(t) = self.http_thread.take() {
             let _ = t.join();
         }
@@ -41,51 +189,200 @@ impl Drop for ServerHandle {
     }
 }
 
-/// Detect the local LAN IP by connecting a UDP socket to an external address.
-fn local_ip() -> Option<std::net::IpAddr> {
-    let sock = UdpSocket::bind("0.0.0.0:0").ok()?;
-    sock.connect("8.8.8.8:80").ok()?;
-    Some(sock.local_addr().ok()?.ip())
+/// How and where to run the share.
+pub struct ServerOptions {
+    pub folder_name: String,
+    /// Name shown to DLNA renderers; `None` uses "Looky — {folder}".
+    pub friendly_name: Option<String>,
+    /// The shared folder; uploads go into a subfolder of it.
+    pub root_dir: PathBuf,
+    /// Catalog database to read cached photo metadata from.
+    pub catalog_path: Option<PathBuf>,
+    pub allow_uploads: bool,
+    /// Offer AVIF thumbnails as well as WebP and JPEG.
+    pub avif_thumbs: bool,
+    /// Megabytes of gallery thumbnails to keep in memory; `None` uses the default.
+    pub thumb_cache_mb: Option<u64>,
+    /// Requests to serve at once; `None` uses the default.
+    pub http_workers: Option<usize>,
+    /// Interface name to bind to; `None` uses the default route.
+    pub interface: Option<String>,
+    /// Fixed port; `None` lets the OS pick one.
+    pub port: Option<u16>,
+}
+
+/// A local network interface the share can be bound to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetInterface {
+    pub name: String,
+    pub ip: IpAddr,
+}
+
+/// List non-loopback IPv4 interfaces, in the order the OS reports them.
+pub fn list_interfaces() -> Vec<NetInterface> {
+    match if_addrs::get_if_addrs() {
+        Ok(addrs) => addrs
+            .into_iter()
+            .filter(|a| !a.is_loopback() && a.ip().is_ipv4())
+            .map(|a| NetInterface {
+                ip: a.ip(),
+                name: a.name,
+            })
+            .collect(),
+        Err(e) => {
+            log::warn!("Failed to list network interfaces: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Detect the local LAN IP by connecting a UDP socket to an external address,
+/// trying IPv6 when there's no IPv4 route.
+fn local_ip() -> Option<IpAddr> {
+    let route = |bind: &str, remote: &str| -> Option<IpAddr> {
+        let sock = UdpSocket::bind(bind).ok()?;
+        sock.connect(remote).ok()?;
+        Some(sock.local_addr().ok()?.ip())
+    };
+    route("0.0.0.0:0", "8.8.8.8:80").or_else(|| route("[::]:0", "[2001:4860:4860::8888]:80"))
+}
+
+/// Resolve the address to bind from the chosen interface, falling back to the
+/// default route when none is chosen.
+fn bind_ip(interface: Option<&str>) -> Result<IpAddr, String> {
+    match interface {
+        Some(name) => list_interfaces()
+            .into_iter()
+            .find(|i| i.name == name)
+            .map(|i| i.ip)
+            .ok_or_else(|| format!("Interface {name} is not available")),
+        None => local_ip().ok_or_else(|| "No network connection".to_string()),
+    }
+}
+
+/// Listen on `[::]` accepting both IPv4 and IPv6, so DLNA renderers can reach
+/// us on any interface. Returns `None` if the host has no IPv6 support.
+fn listen_dual_stack(port: u16) -> Option<std::net::TcpListener> {
+    use socket2::{Domain, Socket, Type};
+    let socket = Socket::new(Domain::IPV6, Type::STREAM, None).ok()?;
+    socket.set_only_v6(false).ok()?;
+    socket.set_reuse_address(true).ok()?;
+    let addr = SocketAddr::new(std::net::Ipv6Addr::UNSPECIFIED.into(), port);
+    socket.bind(&addr.into()).ok()?;
+    socket.listen(128).ok()?;
+    Some(socket.into())
+}
+
+/// ID for a shared image in URLs. Unlike its index it's the same whatever
+/// else is shared and from one share to the next, so bookmarks and queued
+/// slides keep pointing at it. Starts with a letter so it's never taken
+/// for an index.
+pub fn photo_id(path: &Path) -> String {
+    use sha2::{Digest, Sha256};
+    let hash = Sha256::digest(path.to_string_lossy().as_bytes());
+    let hex: String = hash[..8].iter().map(|b| format!("{b:02x}")).collect();
+    format!("p{hex}")
 }
 
 /// Start the HTTP + DLNA server. Returns the handle and the gallery URL.
 pub fn start_server(
     image_paths: Vec<PathBuf>,
-    folder_name: String,
-) -> Option<(ServerHandle, String)> {
-    let ip = local_ip()?;
-    let bind_addr: SocketAddr = format!("{ip}:0").parse().ok()?;
-    let server = tiny_http::Server::http(bind_addr).ok()?;
-    let server_addr = server.server_addr().to_ip().unwrap();
+    options: ServerOptions,
+) -> Result<(ServerHandle, String), String> {
+    let ip = bind_ip(options.interface.as_deref())?;
+    let port = options.port.unwrap_or(0);
+    let dual_stack = if options.interface.is_some() {
+        None
+    } else {
+        listen_dual_stack(port)
+    };
+    let ipv6 = dual_stack.is_some();
+    let server = match dual_stack {
+        Some(listener) => tiny_http::Server::from_listener(listener, None)
+            .map_err(|e| format!("Can't listen on port {port}: {e}"))?,
+        None => {
+            let bind_addr = if options.interface.is_some() {
+                SocketAddr::new(ip, port)
+            } else {
+                SocketAddr::new(std::net::Ipv4Addr::UNSPECIFIED.into(), port)
+            };
+            tiny_http::Server::http(bind_addr)
+                .map_err(|e| format!("Can't listen on {bind_addr}: {e}"))?
+        }
+    };
+    let server_port = server
+        .server_addr()
+        .to_ip()
+        .ok_or("Server has no IP address")?
+        .port();
+    // Advertise the interface's own address; the listener may be a wildcard.
+    let server_addr = SocketAddr::new(ip, server_port);
     let url = format!("http://{server_addr}");
+    log::info!("Sharing on {url}");
 
     let device_uuid = uuid::Uuid::new_v4().to_string();
 
+    let ids = image_paths.iter().enumerate().map(|(i, path)| (photo_id(path), i)).collect();
     let state = Arc::new(ServerState {
-        image_paths,
+        image_paths: RwLock::new(image_paths),
+        removed: RwLock::new(HashSet::new()),
+        ids: RwLock::new(ids),
         server_addr,
+        interface: options.interface,
+        ipv6,
         device_uuid,
-        folder_name,
+        friendly_name: options
+            .friendly_name
+            .unwrap_or_else(|| format!("Looky — {}", options.folder_name)),
+        folder_name: options.folder_name,
         shutdown: AtomicBool::new(false),
+        events: EventHub::default(),
+        upload_dir: options.root_dir.join(upload::UPLOAD_SUBFOLDER),
+        root_dir: options.root_dir,
+        photo_info: Mutex::new(photo_info::PhotoInfoIndex::new(options.catalog_path.as_deref())),
+        uploads_enabled: AtomicBool::new(options.allow_uploads),
+        avif_thumbs: options.avif_thumbs,
+        thumb_cache_bytes: options.thumb_cache_mb.unwrap_or(DEFAULT_THUMB_CACHE_MB) * 1024 * 1024,
+        http_workers: options.http_workers.unwrap_or(DEFAULT_HTTP_WORKERS).max(1),
+        gena: gena::Subscriptions::default(),
+        system_update_id: AtomicU32::new(1),
+        last_request: Mutex::new(Instant::now()),
     });
 
     let http_state = Arc::clone(&state);
     let http_thread = std::thread::Builder::new()
         .name("looky-http".into())
         .spawn(move || http::run(server, http_state))
-        .ok()?;
+        .map_err(|e| format!("Spawn HTTP thread: {e}"))?;
 
     let ssdp_state = Arc::clone(&state);
     let ssdp_thread = std::thread::Builder::new()
         .name("looky-ssdp".into())
         .spawn(move || ssdp::run(ssdp_state))
-        .ok()?;
+        .map_err(|e| format!("Spawn SSDP thread: {e}"))?;
+
+    // Checking whether looky.local is taken blocks for a moment, so the
+    // advertisement goes up on its own thread
+    let mdns = Arc::new(Mutex::new(None));
+    let mdns_slot = Arc::clone(&mdns);
+    let folder_name = state.folder_name.clone();
+    let uuid = state.device_uuid.clone();
+    let mdns_thread = std::thread::Builder::new()
+        .name("looky-mdns".into())
+        .spawn(move || match mdns::advertise(server_addr, &folder_name, &uuid) {
+            Ok(m) => *mdns_slot.lock().unwrap() = Some(m),
+            Err(e) => log::warn!("mDNS advertisement failed: {e}"),
+        })
+        .map_err(|e| log::warn!("Failed to spawn mDNS thread: {e}"))
+        .ok();
 
-    Some((
+    Ok((
         ServerHandle {
             state,
             http_thread: Some(http_thread),
             ssdp_thread: Some(ssdp_thread),
+            mdns_thread,
+            mdns,
         },
         url,
     ))
@@ -0,0 +1,125 @@
+//! Date, size and resolution of each shared image, for DLNA metadata,
+//! sorting and the date containers.
+//!
+//! Values come from the catalog when it has a current summary for the file,
+//! otherwise from the file itself (EXIF date falling back to the modification
+//! time), and are written back so the next share starts warm. Entries are
+//! filled on demand, so listing one page doesn't read the whole library.
+
+use std::path::{Path, PathBuf};
+
+use crate::catalog::{self, Catalog};
+use crate::metadata;
+
+/// `(year, month)` with month in 1..=12.
+pub type YearMonth = (u16, u8);
+
+#[derive(Debug, Clone, Default)]
+pub struct PhotoInfo {
+    /// ISO 8601, `YYYY-MM-DDTHH:MM:SS`.
+    pub date: Option<String>,
+    pub dimensions: Option<(u32, u32)>,
+    pub file_size: u64,
+}
+
+pub struct PhotoInfoIndex {
+    catalog: Option<Catalog>,
+    /// One slot per image, in `image_paths` order.
+    infos: Vec<Option<PhotoInfo>>,
+    /// Date of each image, for the first `dates.len()` images. Images are
+    /// only ever appended, so this only needs extending.
+    dates: Vec<Option<String>>,
+}
+
+impl PhotoInfoIndex {
+    pub fn new(catalog_path: Option<&Path>) -> Self {
+        let catalog = catalog_path.and_then(|p| {
+            Catalog::open(p)
+                .map_err(|e| log::warn!("Failed to open catalog for sharing: {}", e))
+                .ok()
+        });
+        Self {
+            catalog,
+            infos: Vec::new(),
+            dates: Vec::new(),
+        }
+    }
+
+    /// Info for one image, reading it if not yet indexed.
+    pub fn get(&mut self, image_paths: &[PathBuf], index: usize) -> Option<&PhotoInfo> {
+        let path = image_paths.get(index)?;
+        if self.infos.len() < image_paths.len() {
+            self.infos.resize(image_paths.len(), None);
+        }
+        if self.infos[index].is_none() {
+            self.infos[index] = Some(self.lookup(path));
+        }
+        self.infos[index].as_ref()
+    }
+
+    /// Date of every image, reading any not yet indexed.
+    pub fn dates(&mut self, image_paths: &[PathBuf]) -> &[Option<String>] {
+        for i in self.dates.len()..image_paths.len() {
+            let date = self.get(image_paths, i).and_then(|info| info.date.clone());
+            self.dates.push(date);
+        }
+        &self.dates[..image_paths.len()]
+    }
+
+    fn lookup(&self, path: &Path) -> PhotoInfo {
+        let cached = self.catalog.as_ref().and_then(|c| c.get_file_summary(path));
+        let summary = match cached {
+            Some(s) => s,
+            None => {
+                let summary = metadata::read_file_summary(path);
+                let disk = catalog::file_size_and_mtime_for(path);
+                if let (Some(cat), Some((size, mtime))) = (self.catalog.as_ref(), disk) {
+                    cat.insert_file_summary(path, size, mtime, &summary);
+                }
+                summary
+            }
+        };
+        let date = summary
+            .date_taken
+            .as_deref()
+            .and_then(normalize_date)
+            .or_else(|| summary.date_modified.as_deref().and_then(normalize_date));
+        PhotoInfo {
+            date,
+            dimensions: summary.dimensions,
+            file_size: summary.file_size,
+        }
+    }
+}
+
+/// Turn `YYYY-MM-DD HH:MM:SS` (or EXIF-style `YYYY:MM:DD HH:MM:SS`) into
+/// ISO 8601. The time part is optional.
+pub fn normalize_date(date: &str) -> Option<String> {
+    let year: u16 = date.get(0..4)?.parse().ok()?;
+    let month: u8 = date.get(5..7)?.parse().ok()?;
+    let day: u8 = date.get(8..10)?.parse().ok()?;
+    if year == 0 || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let time = date
+        .get(11..19)
+        .filter(|t| t.bytes().all(|b| b.is_ascii_digit() || b == b':'))
+        .unwrap_or("00:00:00");
+    Some(format!("{year:04}-{month:02}-{day:02}T{time}"))
+}
+
+/// Year and month of a date from [`PhotoInfo::date`].
+pub fn year_month(date: &str) -> Option<YearMonth> {
+    Some((date.get(0..4)?.parse().ok()?, date.get(5..7)?.parse().ok()?))
+}
+
+pub fn month_name(month: u8) -> &'static str {
+    const NAMES: [&str; 12] = [
+        "January", "February", "March", "April", "May", "June", "July", "August", "September",
+        "October", "November", "December",
+    ];
+    NAMES
+        .get(usize::from(month).wrapping_sub(1))
+        .copied()
+        .unwrap_or("Unknown")
+}
@@ -0,0 +1,230 @@
+//! UPnP ContentDirectory `SearchCriteria` parsing and matching.
+//!
+//! Supports the grammar from the ContentDirectory spec: `*`, relational and
+//! string operators (`=`, `!=`, `<`, `<=`, `>`, `>=`, `contains`,
+//! `doesNotContain`, `startsWith`, `derivedfrom`), `exists`, `and`/`or` and
+//! parentheses. String comparisons are case-insensitive, as the spec asks.
+
+/// A parsed search expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Criteria {
+    All,
+    And(Box<Criteria>, Box<Criteria>),
+    Or(Box<Criteria>, Box<Criteria>),
+    Compare { property: String, op: Op, value: String },
+    Exists { property: String, exists: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+    DoesNotContain,
+    StartsWith,
+    DerivedFrom,
+}
+
+impl Criteria {
+    /// Parse a `SearchCriteria` string. An empty string means "everything".
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() || tokens == [Token::Word("*".to_string())] {
+            return Ok(Criteria::All);
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let criteria = parser.or_expr()?;
+        if parser.pos != parser.tokens.len() {
+            return Err("Unexpected trailing input".to_string());
+        }
+        Ok(criteria)
+    }
+
+    /// Evaluate against an object whose properties are looked up by name.
+    pub fn matches(&self, property: &dyn Fn(&str) -> Option<String>) -> bool {
+        match self {
+            Criteria::All => true,
+            Criteria::And(a, b) => a.matches(property) && b.matches(property),
+            Criteria::Or(a, b) => a.matches(property) || b.matches(property),
+            Criteria::Exists { property: name, exists } => property(name).is_some() == *exists,
+            Criteria::Compare { property: name, op, value } => match property(name) {
+                Some(actual) => compare(&actual, *op, value),
+                None => false,
+            },
+        }
+    }
+}
+
+fn compare(actual: &str, op: Op, expected: &str) -> bool {
+    let a = actual.to_lowercase();
+    let e = expected.to_lowercase();
+    // Numeric properties (e.g. @id) compare as numbers when both sides parse.
+    let ordering = match (a.parse::<f64>(), e.parse::<f64>()) {
+        (Ok(x), Ok(y)) => x.partial_cmp(&y),
+        _ => Some(a.cmp(&e)),
+    };
+    match op {
+        Op::Eq => a == e,
+        Op::Ne => a != e,
+        Op::Lt => ordering.is_some_and(|o| o.is_lt()),
+        Op::Le => ordering.is_some_and(|o| o.is_le()),
+        Op::Gt => ordering.is_some_and(|o| o.is_gt()),
+        Op::Ge => ordering.is_some_and(|o| o.is_ge()),
+        Op::Contains => a.contains(&e),
+        Op::DoesNotContain => !a.contains(&e),
+        Op::StartsWith => a.starts_with(&e),
+        // "object.item" is derived from "object", and a class derives from itself.
+        Op::DerivedFrom => a == e || a.starts_with(&format!("{e}.")),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    Word(String),
+    Quoted(String),
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            '"' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => value.extend(chars.next()),
+                        Some('"') => break,
+                        Some(c) => value.push(c),
+                        None => return Err("Unterminated string".to_string()),
+                    }
+                }
+                tokens.push(Token::Quoted(value));
+            }
+            '=' | '!' | '<' | '>' => {
+                let mut op = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !matches!(c, '=' | '!' | '<' | '>') {
+                        break;
+                    }
+                    op.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(op));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '(' | ')' | '"' | '=' | '!' | '<' | '>') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.tokens.get(self.pos), Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    // `and` binds tighter than `or`.
+    fn or_expr(&mut self) -> Result<Criteria, String> {
+        let mut left = self.and_expr()?;
+        while self.peek_keyword("or") {
+            self.pos += 1;
+            let right = self.and_expr()?;
+            left = Criteria::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn and_expr(&mut self) -> Result<Criteria, String> {
+        let mut left = self.primary()?;
+        while self.peek_keyword("and") {
+            self.pos += 1;
+            let right = self.primary()?;
+            left = Criteria::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn primary(&mut self) -> Result<Criteria, String> {
+        match self.next() {
+            Some(Token::LParen) => {
+                let inner = self.or_expr()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(inner),
+                    _ => Err("Missing )".to_string()),
+                }
+            }
+            Some(Token::Word(property)) => self.relation(property),
+            other => Err(format!("Expected a property, got {other:?}")),
+        }
+    }
+
+    fn relation(&mut self, property: String) -> Result<Criteria, String> {
+        let Some(Token::Word(op)) = self.next() else {
+            return Err(format!("Expected an operator after {property}"));
+        };
+        if op.eq_ignore_ascii_case("exists") {
+            let exists = match self.next() {
+                Some(Token::Word(b)) if b.eq_ignore_ascii_case("true") => true,
+                Some(Token::Word(b)) if b.eq_ignore_ascii_case("false") => false,
+                _ => return Err("exists needs true or false".to_string()),
+            };
+            return Ok(Criteria::Exists { property, exists });
+        }
+        let op = match op.as_str() {
+            "=" => Op::Eq,
+            "!=" => Op::Ne,
+            "<" => Op::Lt,
+            "<=" => Op::Le,
+            ">" => Op::Gt,
+            ">=" => Op::Ge,
+            w if w.eq_ignore_ascii_case("contains") => Op::Contains,
+            w if w.eq_ignore_ascii_case("doesNotContain") => Op::DoesNotContain,
+            w if w.eq_ignore_ascii_case("startsWith") => Op::StartsWith,
+            w if w.eq_ignore_ascii_case("derivedfrom") => Op::DerivedFrom,
+            other => return Err(format!("Unknown operator {other}")),
+        };
+        let Some(Token::Quoted(value)) = self.next() else {
+            return Err(format!("Expected a quoted value after {property}"));
+        };
+        Ok(Criteria::Compare { property, op, value })
+    }
+}
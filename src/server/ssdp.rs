@@ -1,79 +1,275 @@
-use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+//! SSDP discovery: NOTIFY announcements and M-SEARCH replies on every
+//! network interface, over IPv4 (239.255.255.250) and IPv6 (ff02::c).
+//!
+//! Each interface gets its own LOCATION so a renderer is always pointed at an
+//! address it can actually reach.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use socket2::{Domain, Protocol, SockRef, Socket, Type};
+
 use super::ServerState;
 
-const MULTICAST_ADDR: Ipv4Addr = Ipv4Addr::new(239, 255, 255, 250);
+const MULTICAST_V4: Ipv4Addr = Ipv4Addr::new(239, 255, 255, 250);
+const MULTICAST_V6: Ipv6Addr = Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 0xc);
 const SSDP_PORT: u16 = 1900;
 const NOTIFY_INTERVAL: Duration = Duration::from_secs(60);
+/// Per-socket receive timeout; the loop polls the IPv4 and IPv6 sockets in turn
+/// and sends any delayed M-SEARCH replies that have come due.
+const POLL_TIMEOUT: Duration = Duration::from_millis(100);
+/// Longest M-SEARCH reply delay we honour; UPnP 1.1 caps MX at 5 seconds.
+const MAX_MX: u64 = 5;
 
-pub fn run(state: Arc<ServerState>) {
-    let multicast = SocketAddrV4::new(MULTICAST_ADDR, SSDP_PORT);
+/// An M-SEARCH reply waiting out its random delay.
+struct PendingReply {
+    due: Instant,
+    dest: SocketAddr,
+    response: String,
+}
+
+/// A local address we announce on.
+#[derive(Debug, Clone, PartialEq)]
+enum Link {
+    V4 { ip: Ipv4Addr, netmask: Ipv4Addr },
+    V6 { ip: Ipv6Addr, index: u32 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Endpoint {
+    link: Link,
+    location: String,
+}
+
+impl Endpoint {
+    fn group(&self) -> SocketAddr {
+        match self.link {
+            Link::V4 { .. } => SocketAddr::new(MULTICAST_V4.into(), SSDP_PORT),
+            Link::V6 { .. } => SocketAddr::new(MULTICAST_V6.into(), SSDP_PORT),
+        }
+    }
+
+    fn host_header(&self) -> &'static str {
+        match self.link {
+            Link::V4 { .. } => "239.255.255.250:1900",
+            Link::V6 { .. } => "[FF02::C]:1900",
+        }
+    }
+
+    /// Whether a message from `src` arrived over this endpoint's network.
+    fn reaches(&self, src: SocketAddr) -> bool {
+        match (&self.link, src) {
+            (Link::V4 { ip, netmask }, SocketAddr::V4(src)) => {
+                let mask = u32::from(*netmask);
+                u32::from(*ip) & mask == u32::from(*src.ip()) & mask
+            }
+            (Link::V6 { index, .. }, SocketAddr::V6(src)) => {
+                src.scope_id() == 0 || src.scope_id() == *index
+            }
+            _ => false,
+        }
+    }
 
-    // Try to bind to the standard SSDP port; fall back to random if another server owns it.
-    let sock = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, SSDP_PORT))
-        .or_else(|_| UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0)));
+    /// Point outgoing multicast at this endpoint's interface.
+    fn select(&self, sock: &UdpSocket) -> std::io::Result<()> {
+        match self.link {
+            Link::V4 { ip, .. } => SockRef::from(sock).set_multicast_if_v4(&ip),
+            Link::V6 { index, .. } => SockRef::from(sock).set_multicast_if_v6(index),
+        }
+    }
 
-    let sock = match sock {
-        Ok(s) => s,
+    fn join(&self, sock: &UdpSocket) -> std::io::Result<()> {
+        match self.link {
+            Link::V4 { ip, .. } => sock.join_multicast_v4(&MULTICAST_V4, &ip),
+            Link::V6 { index, .. } => sock.join_multicast_v6(&MULTICAST_V6, index),
+        }
+    }
+}
+
+/// Addresses to announce on: every non-loopback interface (or just the one
+/// the share is bound to), with one IPv6 address per interface when the HTTP
+/// server accepts IPv6. Link-local IPv6 addresses are skipped since a
+/// LOCATION URL can't carry a zone that renderers understand.
+fn endpoints(state: &ServerState) -> Vec<Endpoint> {
+    let port = state.server_addr.port();
+    let interfaces = match if_addrs::get_if_addrs() {
+        Ok(interfaces) => interfaces,
         Err(e) => {
-            log::warn!("SSDP: failed to bind socket: {}", e);
-            return;
+            log::warn!("SSDP: failed to list interfaces: {}", e);
+            return Vec::new();
+        }
+    };
+    let mut endpoints: Vec<Endpoint> = Vec::new();
+    for iface in interfaces {
+        if iface.is_loopback() || state.interface.as_ref().is_some_and(|name| *name != iface.name) {
+            continue;
+        }
+        let endpoint = match iface.addr {
+            if_addrs::IfAddr::V4(v4) => Endpoint {
+                link: Link::V4 {
+                    ip: v4.ip,
+                    netmask: v4.netmask,
+                },
+                location: format!("http://{}:{port}/dlna/device.xml", v4.ip),
+            },
+            if_addrs::IfAddr::V6(v6) => {
+                let Some(index) = iface.index else { continue };
+                let link_local = v6.ip.segments()[0] & 0xffc0 == 0xfe80;
+                let seen = endpoints
+                    .iter()
+                    .any(|e| matches!(e.link, Link::V6 { index: i, .. } if i == index));
+                if !state.ipv6 || link_local || seen {
+                    continue;
+                }
+                Endpoint {
+                    link: Link::V6 { ip: v6.ip, index },
+                    location: format!("http://[{}]:{port}/dlna/device.xml", v6.ip),
+                }
+            }
+        };
+        endpoints.push(endpoint);
+    }
+    endpoints
+}
+
+/// Bind the SSDP port for one IP version, sharing it with any other SSDP
+/// server on the host; falls back to a random port if that fails.
+fn bind(domain: Domain, unspecified: IpAddr) -> Option<UdpSocket> {
+    let open = |port: u16| -> std::io::Result<UdpSocket> {
+        let socket = Socket::new(domain, Type::DGRAM, Some(Protocol::UDP))?;
+        socket.set_reuse_address(true)?;
+        if domain == Domain::IPV6 {
+            socket.set_only_v6(true)?;
         }
+        socket.bind(&SocketAddr::new(unspecified, port).into())?;
+        Ok(socket.into())
     };
+    let sock = open(SSDP_PORT).or_else(|_| open(0));
+    match sock {
+        Ok(sock) => {
+            let _ = sock.set_read_timeout(Some(POLL_TIMEOUT));
+            Some(sock)
+        }
+        Err(e) => {
+            log::warn!("SSDP: failed to bind {:?} socket: {}", domain, e);
+            None
+        }
+    }
+}
+
+/// Join the multicast group on every endpoint not joined yet.
+fn join_all(sockets: &Sockets, endpoints: &[Endpoint], joined: &mut Vec<Endpoint>) {
+    for endpoint in endpoints {
+        if joined.contains(endpoint) {
+            continue;
+        }
+        let result = sockets.for_endpoint(endpoint).map(|sock| endpoint.join(sock));
+        if let Some(Err(e)) = result {
+            // Continue anyway — we can still send NOTIFYs
+            log::warn!("SSDP: failed to join multicast on {}: {}", endpoint.location, e);
+        }
+        joined.push(endpoint.clone());
+    }
+}
+
+struct Sockets {
+    v4: Option<UdpSocket>,
+    v6: Option<UdpSocket>,
+}
+
+impl Sockets {
+    fn for_addr(&self, addr: SocketAddr) -> Option<&UdpSocket> {
+        match addr {
+            SocketAddr::V4(_) => self.v4.as_ref(),
+            SocketAddr::V6(_) => self.v6.as_ref(),
+        }
+    }
+
+    fn for_endpoint(&self, endpoint: &Endpoint) -> Option<&UdpSocket> {
+        match endpoint.link {
+            Link::V4 { .. } => self.v4.as_ref(),
+            Link::V6 { .. } => self.v6.as_ref(),
+        }
+    }
 
-    // Join multicast group
-    if let Err(e) = sock.join_multicast_v4(&MULTICAST_ADDR, &Ipv4Addr::UNSPECIFIED) {
-        log::warn!("SSDP: failed to join multicast: {}", e);
-        // Continue anyway — we can still send NOTIFYs
+    /// Send a multicast NOTIFY out of every endpoint's interface.
+    fn announce(&self, endpoints: &[Endpoint], send: impl Fn(&UdpSocket, &Endpoint)) {
+        for endpoint in endpoints {
+            let Some(sock) = self.for_endpoint(endpoint) else { continue };
+            match endpoint.select(sock) {
+                Ok(()) => send(sock, endpoint),
+                Err(e) => log::debug!("SSDP: can't send on {}: {}", endpoint.location, e),
+            }
+        }
+    }
+}
+
+pub fn run(state: Arc<ServerState>) {
+    let sockets = Sockets {
+        v4: bind(Domain::IPV4, Ipv4Addr::UNSPECIFIED.into()),
+        v6: if state.ipv6 {
+            bind(Domain::IPV6, Ipv6Addr::UNSPECIFIED.into())
+        } else {
+            None
+        },
+    };
+    if sockets.v4.is_none() && sockets.v6.is_none() {
+        return;
     }
 
-    let _ = sock.set_read_timeout(Some(Duration::from_secs(2)));
+    let mut endpoints = endpoints(&state);
+    let mut joined = Vec::new();
+    join_all(&sockets, &endpoints, &mut joined);
 
     // Initial alive burst (send 3 times for reliability)
     for _ in 0..3 {
-        send_alive(&sock, &state, multicast);
+        sockets.announce(&endpoints, |sock, endpoint| send_alive(sock, &state, endpoint));
         std::thread::sleep(Duration::from_millis(100));
     }
 
     let mut last_notify = Instant::now();
+    let mut pending: Vec<PendingReply> = Vec::new();
 
-    loop {
-        if state.shutdown.load(Ordering::Relaxed) {
-            break;
-        }
-
-        let mut buf = [0u8; 2048];
-        match sock.recv_from(&mut buf) {
-            Ok((len, src)) => {
+    while !state.shutdown.load(Ordering::Relaxed) {
+        for sock in [&sockets.v4, &sockets.v6].into_iter().flatten() {
+            let mut buf = [0u8; 2048];
+            if let Ok((len, src)) = sock.recv_from(&mut buf) {
                 let msg = String::from_utf8_lossy(&buf[..len]);
                 if msg.contains("M-SEARCH") {
-                    handle_msearch(&sock, &state, &msg, src);
+                    pending.extend(handle_msearch(&state, &endpoints, &msg, src));
                 }
             }
-            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock || e.kind() == std::io::ErrorKind::TimedOut => {}
-            Err(_) => {}
         }
 
-        // Periodic NOTIFY alive
+        let now = Instant::now();
+        pending.retain(|reply| {
+            if reply.due > now {
+                return true;
+            }
+            if let Some(sock) = sockets.for_addr(reply.dest) {
+                let _ = sock.send_to(reply.response.as_bytes(), reply.dest);
+            }
+            false
+        });
+
+        // Periodic NOTIFY alive, picking up interfaces that came up since
         if last_notify.elapsed() >= NOTIFY_INTERVAL {
-            send_alive(&sock, &state, multicast);
+            endpoints = self::endpoints(&state);
+            join_all(&sockets, &endpoints, &mut joined);
+            sockets.announce(&endpoints, |sock, endpoint| send_alive(sock, &state, endpoint));
             last_notify = Instant::now();
         }
     }
 
     // Bye-bye on shutdown
-    send_byebye(&sock, &state, multicast);
-}
-
-fn location(state: &ServerState) -> String {
-    format!("http://{}/dlna/device.xml", state.server_addr)
+    sockets.announce(&endpoints, |sock, endpoint| send_byebye(sock, &state, endpoint));
 }
 
-fn send_alive(sock: &UdpSocket, state: &ServerState, dest: SocketAddrV4) {
-    let loc = location(state);
+fn send_alive(sock: &UdpSocket, state: &ServerState, endpoint: &Endpoint) {
+    let loc = &endpoint.location;
+    let host = endpoint.host_header();
     let uuid = &state.device_uuid;
 
     let notifications = [
@@ -96,7 +292,7 @@ fn send_alive(sock: &UdpSocket, state: &ServerState, dest: SocketAddrV4) {
     for (nt, usn) in &notifications {
         let msg = format!(
             "NOTIFY * HTTP/1.1\r\n\
-             HOST: 239.255.255.250:1900\r\n\
+             HOST: {host}\r\n\
              CACHE-CONTROL: max-age=1800\r\n\
              LOCATION: {loc}\r\n\
              NT: {nt}\r\n\
@@ -105,11 +301,12 @@ fn send_alive(sock: &UdpSocket, state: &ServerState, dest: SocketAddrV4) {
              USN: {usn}\r\n\
              \r\n"
         );
-        let _ = sock.send_to(msg.as_bytes(), dest);
+        let _ = sock.send_to(msg.as_bytes(), endpoint.group());
     }
 }
 
-fn send_byebye(sock: &UdpSocket, state: &ServerState, dest: SocketAddrV4) {
+fn send_byebye(sock: &UdpSocket, state: &ServerState, endpoint: &Endpoint) {
+    let host = endpoint.host_header();
     let uuid = &state.device_uuid;
 
     let notifications = [
@@ -123,17 +320,24 @@ fn send_byebye(sock: &UdpSocket, state: &ServerState, dest: SocketAddrV4) {
     for (nt, usn) in &notifications {
         let msg = format!(
             "NOTIFY * HTTP/1.1\r\n\
-             HOST: 239.255.255.250:1900\r\n\
+             HOST: {host}\r\n\
              NT: {nt}\r\n\
              NTS: ssdp:byebye\r\n\
              USN: {usn}\r\n\
              \r\n"
         );
-        let _ = sock.send_to(msg.as_bytes(), dest);
+        let _ = sock.send_to(msg.as_bytes(), endpoint.group());
     }
 }
 
-fn handle_msearch(sock: &UdpSocket, state: &ServerState, msg: &str, src: SocketAddr) {
+/// Build the reply to an M-SEARCH, due at a random point within its MX window
+/// so every device on the network doesn't answer at once.
+fn handle_msearch(
+    state: &ServerState,
+    endpoints: &[Endpoint],
+    msg: &str,
+    src: SocketAddr,
+) -> Option<PendingReply> {
     let st = extract_header(msg, "ST").unwrap_or_default();
 
     let should_respond = matches!(
@@ -145,10 +349,15 @@ fn handle_msearch(sock: &UdpSocket, state: &ServerState, msg: &str, src: SocketA
     ) || st.starts_with("uuid:");
 
     if !should_respond {
-        return;
+        return None;
     }
 
-    let loc = location(state);
+    // Answer with the address on the searcher's network.
+    let endpoint = endpoints
+        .iter()
+        .find(|e| e.reaches(src))
+        .or_else(|| endpoints.iter().find(|e| e.group().is_ipv4() == src.is_ipv4()))?;
+    let loc = &endpoint.location;
     let uuid = &state.device_uuid;
     let usn = if st == "ssdp:all" || st.starts_with("uuid:") {
         format!("uuid:{uuid}")
@@ -171,7 +380,17 @@ fn handle_msearch(sock: &UdpSocket, state: &ServerState, msg: &str, src: SocketA
          EXT:\r\n\
          \r\n"
     );
-    let _ = sock.send_to(response.as_bytes(), src);
+    // Unicast searches carry no MX and are answered straight away.
+    let mx = extract_header(msg, "MX")
+        .and_then(|mx| mx.parse::<u64>().ok())
+        .unwrap_or(0)
+        .min(MAX_MX);
+    let delay = Duration::from_millis(rand::random_range(0..=mx * 1000));
+    Some(PendingReply {
+        due: Instant::now() + delay,
+        dest: src,
+        response,
+    })
 }
 
 fn extract_header(msg: &str, name: &str) -> Option<String> {
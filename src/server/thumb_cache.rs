@@ -0,0 +1,81 @@
+//! Encoded gallery thumbnails kept in memory for the life of a share, within
+//! a byte budget. Once over it, the least recently served are dropped; the
+//! next request for one reads it back from the disk cache.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+struct Entry {
+    bytes: Vec<u8>,
+    /// `ThumbCache::clock` when last stored or served.
+    last_used: u64,
+}
+
+pub struct ThumbCache<K> {
+    entries: HashMap<K, Entry>,
+    /// Total size of all entries.
+    bytes: u64,
+    budget: u64,
+    /// Bumped on every access, to order entries by last use.
+    clock: u64,
+}
+
+impl<K: Hash + Eq + Copy> ThumbCache<K> {
+    pub fn new(budget: u64) -> Self {
+        Self {
+            entries: HashMap::new(),
+            bytes: 0,
+            budget,
+            clock: 0,
+        }
+    }
+
+    pub fn get(&mut self, key: &K) -> Option<Vec<u8>> {
+        self.clock += 1;
+        let entry = self.entries.get_mut(key)?;
+        entry.last_used = self.clock;
+        Some(entry.bytes.clone())
+    }
+
+    /// Store `bytes` under `key`, evicting the least recently used entries
+    /// if that goes over budget. Anything bigger than the whole budget isn't
+    /// kept.
+    pub fn insert(&mut self, key: K, bytes: Vec<u8>) {
+        if bytes.len() as u64 > self.budget {
+            return;
+        }
+        self.clock += 1;
+        self.bytes += bytes.len() as u64;
+        let entry = Entry {
+            bytes,
+            last_used: self.clock,
+        };
+        if let Some(old) = self.entries.insert(key, entry) {
+            self.bytes -= old.bytes.len() as u64;
+        }
+        if self.bytes > self.budget {
+            self.evict();
+        }
+    }
+
+    /// Drop the least recently used entries until a tenth under budget, so
+    /// the next few inserts don't each have to sort the cache again.
+    fn evict(&mut self) {
+        let target = self.budget - self.budget / 10;
+        let mut by_age: Vec<(u64, K)> = self.entries.iter().map(|(key, e)| (e.last_used, *key)).collect();
+        by_age.sort_unstable_by_key(|&(last_used, _)| last_used);
+        for (_, key) in by_age {
+            if self.bytes <= target {
+                break;
+            }
+            if let Some(entry) = self.entries.remove(&key) {
+                self.bytes -= entry.bytes.len() as u64;
+            }
+        }
+        log::debug!(
+            "Thumbnail cache evicted down to {} entries, {} bytes",
+            self.entries.len(),
+            self.bytes
+        );
+    }
+}
@@ -0,0 +1,83 @@
+//! Saving photos uploaded from the web gallery.
+
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+/// Subfolder of the shared directory that uploads are written into.
+pub const UPLOAD_SUBFOLDER: &str = "Uploads";
+
+/// Largest file accepted from a single upload request.
+pub const MAX_UPLOAD_BYTES: u64 = 200 * 1024 * 1024;
+
+/// Validate an uploaded image and save it into `dir` without overwriting anything.
+///
+/// The data is written under a hidden temporary name and renamed into place,
+/// so the folder watcher never sees a half-written file.
+pub fn save_upload(dir: &Path, requested_name: &str, bytes: &[u8]) -> Result<PathBuf, String> {
+    let name = sanitize_filename(requested_name).ok_or("Invalid file name")?;
+    let ext = Path::new(&name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .ok_or("File has no extension")?;
+
+    let format = image::guess_format(bytes).map_err(|_| "Not a supported image".to_string())?;
+    if !format.extensions_str().contains(&ext.as_str()) {
+        return Err(format!("File contents don't match .{ext}"));
+    }
+    // Parse the header so truncated or bogus files are rejected up front.
+    image::ImageReader::with_format(Cursor::new(bytes), format)
+        .into_dimensions()
+        .map_err(|e| format!("Unreadable image: {e}"))?;
+
+    std::fs::create_dir_all(dir).map_err(|e| format!("Create upload folder: {e}"))?;
+    let tmp = dir.join(format!(".{}.part", uuid::Uuid::new_v4()));
+    std::fs::write(&tmp, bytes).map_err(|e| format!("Write upload: {e}"))?;
+    let saved = claim_name(dir, &name, &tmp);
+    let _ = std::fs::remove_file(&tmp);
+    let dest = saved.map_err(|e| format!("Save upload: {e}"))?;
+    log::info!("Saved upload {}", dest.display());
+    Ok(dest)
+}
+
+/// Hard-link `tmp` under the first free name from [`candidate_names`]. A
+/// link fails rather than replacing a file, so an upload of the same name
+/// landing at the same moment takes the next number instead. Where links
+/// aren't supported the name is claimed with an empty file first and `tmp`
+/// renamed over it.
+fn claim_name(dir: &Path, name: &str, tmp: &Path) -> std::io::Result<PathBuf> {
+    for dest in candidate_names(dir, name) {
+        let claimed = match std::fs::hard_link(tmp, &dest) {
+            Err(e) if e.kind() == std::io::ErrorKind::Unsupported => std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&dest)
+                .and_then(|_| std::fs::rename(tmp, &dest)),
+            claimed => claimed,
+        };
+        match claimed {
+            Ok(()) => return Ok(dest),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!("candidate names are unbounded")
+}
+
+/// Reduce a client-supplied name to a bare, visible file name.
+fn sanitize_filename(name: &str) -> Option<String> {
+    let base = name.rsplit(['/', '\\']).next()?.trim();
+    let cleaned: String = base.chars().filter(|c| !c.is_control()).collect();
+    if cleaned.is_empty() || cleaned.starts_with('.') {
+        return None;
+    }
+    Some(cleaned)
+}
+
+/// `name`, then with " (2)", " (3)", ... appended to the stem.
+fn candidate_names<'a>(dir: &'a Path, name: &'a str) -> impl Iterator<Item = PathBuf> + 'a {
+    let path = Path::new(name);
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let ext = path.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+    std::iter::once(dir.join(name)).chain((2..).map(move |n| dir.join(format!("{stem} ({n}).{ext}"))))
+}
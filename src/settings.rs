@@ -0,0 +1,267 @@
+//! User preferences persisted to `~/.looky/settings` as `key=value` lines.
+//!
+//! Unknown keys are ignored and missing ones fall back to defaults, so the file
+//! stays readable by older and newer builds alike.
+
+use std::path::PathBuf;
+
+use crate::duplicates::GroupOrder;
+use crate::grid_layout::GridLayout;
+use crate::i18n::Language;
+use crate::viewer::{Background, FitMode, ZoomPreset};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Settings {
+    /// Network interface the share binds to. `None` picks the default route.
+    pub share_interface: Option<String>,
+    /// Name renderers show for the share. `None` uses "Looky — {folder}".
+    pub share_name: Option<String>,
+    /// Fixed port for the share. `None` lets the OS choose.
+    pub share_port: Option<u16>,
+    /// Stop sharing this many minutes after it starts.
+    pub share_time_limit: Option<u64>,
+    /// Stop sharing after this many minutes without a request.
+    pub share_idle_limit: Option<u64>,
+    /// Offer AVIF gallery thumbnails to browsers that accept them.
+    pub share_avif_thumbs: bool,
+    /// Megabytes of gallery thumbnails the share keeps in memory. `None` uses the default.
+    pub share_thumb_cache_mb: Option<u64>,
+    /// Requests the share serves at once. `None` uses the default.
+    pub share_http_workers: Option<usize>,
+    /// Neighbours on each side the viewer decodes ahead. `None` adapts to decode times.
+    pub viewer_preload_radius: Option<usize>,
+    /// Megabytes of decoded full-size images the viewer keeps. `None` uses the default.
+    pub viewer_cache_mb: Option<u64>,
+    /// How the viewer sizes images before zooming. `None` fits the whole image.
+    pub viewer_fit_mode: Option<FitMode>,
+    /// Highest zoom factor the viewer allows. `None` uses the default.
+    pub viewer_max_zoom: Option<u32>,
+    /// Magnifications the number keys jump to. `None` uses the defaults.
+    pub viewer_zoom_presets: Option<Vec<ZoomPreset>>,
+    /// Backdrop behind viewer images. `None` leaves the theme's.
+    pub viewer_background: Option<Background>,
+    /// The scroll wheel steps between photos until zoomed; Ctrl+scroll zooms.
+    pub viewer_wheel_navigates: bool,
+    /// A histogram and the exposure in a corner of the viewer, for culling.
+    pub viewer_culling_overlay: bool,
+    /// Megabytes of decoded grid thumbnails kept in memory. `None` uses the default.
+    pub grid_cache_mb: Option<u64>,
+    /// How grid thumbnails are laid out. `None` crops them to squares.
+    pub grid_layout: Option<GridLayout>,
+    /// Sharpen grid thumbnails after shrinking them.
+    pub grid_sharpen: bool,
+    /// Strength of that sharpening in percent. `None` uses the default.
+    pub grid_sharpen_percent: Option<u32>,
+    /// Leave star and heart badges off grid thumbnails.
+    pub grid_hide_ratings: bool,
+    /// Language for menus, labels and the web gallery. `None` follows the system locale.
+    pub language: Option<Language>,
+    /// IANA time zone dates are shown in, e.g. `Europe/Berlin`. `None` uses
+    /// the system's.
+    pub time_zone: Option<String>,
+    /// White-on-black theme with bright focus outlines.
+    pub high_contrast: bool,
+    /// Leave files that failed to decode out of duplicate scans.
+    pub dup_skip_unreadable: bool,
+    /// Report a RAW or HEIC file and its JPEG from the same capture as
+    /// duplicates rather than leaving them out as a pair.
+    pub dup_report_capture_pairs: bool,
+    /// Order of the duplicates list. `None` keeps folder order.
+    pub dup_order: Option<GroupOrder>,
+    /// Library folder imports copy into. `None` uses the open folder.
+    pub import_destination: Option<PathBuf>,
+    /// Eject the card once an import from it finishes.
+    pub eject_after_import: bool,
+    /// How strongly the screensaver favours recent photos. `None` shuffles evenly.
+    pub screensaver_recency: Option<u32>,
+    /// Levels of subfolders scanned in a folder that hasn't set its own.
+    /// `None` scans all of them.
+    pub scan_depth: Option<u32>,
+    /// Keep panoramas up longer and portraits shorter in the screensaver.
+    pub screensaver_dwell_by_aspect: bool,
+    /// Columns and rows of photos per contact sheet page. `None` uses the default.
+    pub contact_sheet_grid: Option<(usize, usize)>,
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        let mut settings = Settings::default();
+        let Some(path) = settings_path() else {
+            return settings;
+        };
+        let Ok(data) = std::fs::read_to_string(path) else {
+            return settings;
+        };
+        for line in data.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "share_interface" => settings.share_interface = non_empty(value),
+                "share_name" => settings.share_name = non_empty(value),
+                "share_port" => settings.share_port = value.parse().ok(),
+                "share_time_limit" => settings.share_time_limit = value.parse().ok(),
+                "share_idle_limit" => settings.share_idle_limit = value.parse().ok(),
+                "share_avif_thumbs" => settings.share_avif_thumbs = value == "true",
+                "share_thumb_cache_mb" => settings.share_thumb_cache_mb = value.parse().ok(),
+                "share_http_workers" => settings.share_http_workers = value.parse().ok(),
+                "viewer_preload_radius" => settings.viewer_preload_radius = value.parse().ok(),
+                "viewer_cache_mb" => settings.viewer_cache_mb = value.parse().ok(),
+                "viewer_fit_mode" => settings.viewer_fit_mode = FitMode::from_key(value),
+                "viewer_max_zoom" => settings.viewer_max_zoom = value.parse().ok(),
+                "viewer_background" => settings.viewer_background = Background::from_key(value),
+                "viewer_wheel_navigates" => settings.viewer_wheel_navigates = value == "true",
+                "viewer_culling_overlay" => settings.viewer_culling_overlay = value == "true",
+                "viewer_zoom_presets" => settings.viewer_zoom_presets = ZoomPreset::parse_list(value),
+                "grid_cache_mb" => settings.grid_cache_mb = value.parse().ok(),
+                "grid_layout" => settings.grid_layout = GridLayout::from_key(value),
+                "grid_sharpen" => settings.grid_sharpen = value == "true",
+                "grid_sharpen_percent" => settings.grid_sharpen_percent = value.parse().ok(),
+                "grid_hide_ratings" => settings.grid_hide_ratings = value == "true",
+                "language" => settings.language = Language::from_key(value),
+                "time_zone" => settings.time_zone = non_empty(value),
+                "high_contrast" => settings.high_contrast = value == "true",
+                "dup_skip_unreadable" => settings.dup_skip_unreadable = value == "true",
+                "dup_report_capture_pairs" => settings.dup_report_capture_pairs = value == "true",
+                "dup_order" => settings.dup_order = GroupOrder::from_key(value),
+                "import_destination" => settings.import_destination = non_empty(value).map(PathBuf::from),
+                "eject_after_import" => settings.eject_after_import = value == "true",
+                "screensaver_recency" => settings.screensaver_recency = value.parse().ok(),
+                "scan_depth" => settings.scan_depth = value.parse().ok(),
+                "screensaver_dwell_by_aspect" => settings.screensaver_dwell_by_aspect = value == "true",
+                "contact_sheet_grid" => {
+                    settings.contact_sheet_grid = value
+                        .split_once('x')
+                        .and_then(|(columns, rows)| Some((columns.parse().ok()?, rows.parse().ok()?)))
+                }
+                _ => {}
+            }
+        }
+        settings
+    }
+
+    pub fn save(&self) {
+        let Some(path) = settings_path() else {
+            return;
+        };
+        let mut out = String::new();
+        if let Some(iface) = &self.share_interface {
+            out.push_str(&format!("share_interface={iface}\n"));
+        }
+        if let Some(name) = &self.share_name {
+            out.push_str(&format!("share_name={name}\n"));
+        }
+        if let Some(port) = self.share_port {
+            out.push_str(&format!("share_port={port}\n"));
+        }
+        if let Some(minutes) = self.share_time_limit {
+            out.push_str(&format!("share_time_limit={minutes}\n"));
+        }
+        if let Some(minutes) = self.share_idle_limit {
+            out.push_str(&format!("share_idle_limit={minutes}\n"));
+        }
+        if self.share_avif_thumbs {
+            out.push_str("share_avif_thumbs=true\n");
+        }
+        if let Some(mb) = self.share_thumb_cache_mb {
+            out.push_str(&format!("share_thumb_cache_mb={mb}\n"));
+        }
+        if let Some(workers) = self.share_http_workers {
+            out.push_str(&format!("share_http_workers={workers}\n"));
+        }
+        if let Some(radius) = self.viewer_preload_radius {
+            out.push_str(&format!("viewer_preload_radius={radius}\n"));
+        }
+        if let Some(mb) = self.viewer_cache_mb {
+            out.push_str(&format!("viewer_cache_mb={mb}\n"));
+        }
+        if let Some(mode) = self.viewer_fit_mode {
+            out.push_str(&format!("viewer_fit_mode={}\n", mode.key()));
+        }
+        if let Some(zoom) = self.viewer_max_zoom {
+            out.push_str(&format!("viewer_max_zoom={zoom}\n"));
+        }
+        if let Some(background) = self.viewer_background {
+            out.push_str(&format!("viewer_background={}\n", background.key()));
+        }
+        if self.viewer_wheel_navigates {
+            out.push_str("viewer_wheel_navigates=true\n");
+        }
+        if self.viewer_culling_overlay {
+            out.push_str("viewer_culling_overlay=true\n");
+        }
+        if let Some(presets) = &self.viewer_zoom_presets {
+            out.push_str(&format!("viewer_zoom_presets={}\n", ZoomPreset::format_list(presets)));
+        }
+        if let Some(mb) = self.grid_cache_mb {
+            out.push_str(&format!("grid_cache_mb={mb}\n"));
+        }
+        if let Some(layout) = self.grid_layout {
+            out.push_str(&format!("grid_layout={}\n", layout.key()));
+        }
+        if self.grid_sharpen {
+            out.push_str("grid_sharpen=true\n");
+        }
+        if let Some(percent) = self.grid_sharpen_percent {
+            out.push_str(&format!("grid_sharpen_percent={percent}\n"));
+        }
+        if self.grid_hide_ratings {
+            out.push_str("grid_hide_ratings=true\n");
+        }
+        if let Some(language) = self.language {
+            out.push_str(&format!("language={}\n", language.key()));
+        }
+        if let Some(zone) = &self.time_zone {
+            out.push_str(&format!("time_zone={zone}\n"));
+        }
+        if self.high_contrast {
+            out.push_str("high_contrast=true\n");
+        }
+        if self.dup_skip_unreadable {
+            out.push_str("dup_skip_unreadable=true\n");
+        }
+        if self.dup_report_capture_pairs {
+            out.push_str("dup_report_capture_pairs=true\n");
+        }
+        if let Some(order) = self.dup_order {
+            out.push_str(&format!("dup_order={}\n", order.key()));
+        }
+        if let Some(dest) = &self.import_destination {
+            out.push_str(&format!("import_destination={}\n", dest.display()));
+        }
+        if self.eject_after_import {
+            out.push_str("eject_after_import=true\n");
+        }
+        if let Some(strength) = self.screensaver_recency {
+            out.push_str(&format!("screensaver_recency={strength}\n"));
+        }
+        if let Some(depth) = self.scan_depth {
+            out.push_str(&format!("scan_depth={depth}\n"));
+        }
+        if self.screensaver_dwell_by_aspect {
+            out.push_str("screensaver_dwell_by_aspect=true\n");
+        }
+        if let Some((columns, rows)) = self.contact_sheet_grid {
+            out.push_str(&format!("contact_sheet_grid={columns}x{rows}\n"));
+        }
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if let Err(e) = std::fs::write(&path, out) {
+            log::warn!("Failed to save settings: {}", e);
+        }
+    }
+}
+
+fn settings_path() -> Option<PathBuf> {
+    dirs_next::home_dir().map(|d| d.join(".looky").join("settings"))
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
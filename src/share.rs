@@ -0,0 +1,75 @@
+//! Handing photos to the system's share sheet, for one-off sends that don't
+//! need the LAN server.
+//!
+//! Only macOS is reached so far, through a small AppKit script, and the
+//! action isn't offered elsewhere; see [`AVAILABLE`]. Windows still needs
+//! its Share UI, opened through `IDataTransferManagerInterop` on the
+//! window's handle with the `windows` crate. Linux has no share portal
+//! in xdg-desktop-portal to call; its Email portal, which takes
+//! attachments, is the nearest.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Whether this platform has a share sheet Looky can open.
+pub const AVAILABLE: bool = cfg!(target_os = "macos");
+
+/// Shows the macOS sharing picker at the mouse pointer for the files passed
+/// as arguments, and exits once a service has finished or the picker closes.
+const MACOS_SHARE_SCRIPT: &str = r#"
+ObjC.import('AppKit');
+function run(argv) {
+    var app = $.NSApplication.sharedApplication;
+    app.setActivationPolicy($.NSApplicationActivationPolicyAccessory);
+    var items = $.NSMutableArray.array;
+    argv.forEach(function (path) { items.addObject($.NSURL.fileURLWithPath(path)); });
+    var done = function () { app.terminate(null); };
+    ObjC.registerSubclass({
+        name: 'LookyShareDelegate',
+        protocols: ['NSSharingServicePickerDelegate', 'NSSharingServiceDelegate'],
+        methods: {
+            'sharingServicePicker:delegateForSharingService:': {
+                types: ['id', ['id', 'id']],
+                implementation: function () { return delegate; },
+            },
+            'sharingServicePicker:didChooseSharingService:': {
+                types: ['void', ['id', 'id']],
+                implementation: function (picker, service) { if (service.isNil()) done(); },
+            },
+            'sharingService:didShareItems:': {
+                types: ['void', ['id', 'id']],
+                implementation: done,
+            },
+            'sharingService:didFailToShareItems:error:': {
+                types: ['void', ['id', 'id', 'id']],
+                implementation: done,
+            },
+        },
+    });
+    var delegate = $.LookyShareDelegate.alloc.init;
+    var mouse = $.NSEvent.mouseLocation;
+    var anchor = $.NSWindow.alloc.initWithContentRectStyleMaskBackingDefer(
+        $.NSMakeRect(mouse.x, mouse.y, 1, 1), $.NSWindowStyleMaskBorderless, $.NSBackingStoreBuffered, false);
+    anchor.makeKeyAndOrderFront(null);
+    app.activateIgnoringOtherApps(true);
+    var picker = $.NSSharingServicePicker.alloc.initWithItems(items);
+    picker.delegate = delegate;
+    picker.showRelativeToRectOfViewPreferredEdge(anchor.contentView.bounds, anchor.contentView, $.NSMinYEdge);
+    app.run;
+}
+"#;
+
+/// Open the share sheet for `paths`. Returns once it has closed.
+pub async fn share_files(paths: Vec<PathBuf>) -> Result<(), String> {
+    if !AVAILABLE {
+        return Err("No share sheet on this system".to_string());
+    }
+    let mut command = Command::new("osascript");
+    command.args(["-l", "JavaScript", "-e", MACOS_SHARE_SCRIPT]);
+    command.args(&paths);
+    match command.status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("share exited with {status}")),
+        Err(e) => Err(e.to_string()),
+    }
+}
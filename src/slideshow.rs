@@ -0,0 +1,72 @@
+//! Shuffled orders for the screensaver that can favour recent photos, so an
+//! ambient display leans towards new memories without hiding the old ones.
+
+use std::path::{Path, PathBuf};
+
+use rand::Rng;
+
+use crate::catalog::{self, Catalog};
+use crate::metadata;
+use crate::server::photo_info::normalize_date;
+
+/// How recent each photo is among `photos`, from 0 (oldest) to 1 (newest),
+/// by date taken or else last modified. Photos with neither sit in the middle.
+pub async fn recency_ranks(photos: Vec<(usize, PathBuf)>, catalog_path: Option<PathBuf>) -> Vec<(usize, f32)> {
+    let catalog = catalog_path.and_then(|p| Catalog::open(&p).ok());
+    let mut dated: Vec<(usize, Option<String>)> = photos
+        .iter()
+        .map(|(i, path)| (*i, photo_date(catalog.as_ref(), path)))
+        .collect();
+    dated.sort_by(|a, b| a.1.cmp(&b.1));
+
+    let known = dated.iter().filter(|(_, date)| date.is_some()).count();
+    let undated = dated.len() - known;
+    let last = known.saturating_sub(1).max(1) as f32;
+    dated
+        .into_iter()
+        .enumerate()
+        .map(|(pos, (i, date))| match date {
+            // Undated photos sort first, so dated ones start after them
+            Some(_) => (i, (pos - undated) as f32 / last),
+            None => (i, 0.5),
+        })
+        .collect()
+}
+
+/// Date taken, or else last modified, as ISO 8601. Reads the catalog's
+/// cached summary when it's current, otherwise the file, and caches that.
+fn photo_date(catalog: Option<&Catalog>, path: &Path) -> Option<String> {
+    let summary = match catalog.and_then(|c| c.get_file_summary(path)) {
+        Some(summary) => summary,
+        None => {
+            let summary = metadata::read_file_summary(path);
+            if let (Some(cat), Some((size, mtime))) = (catalog, catalog::file_size_and_mtime_for(path)) {
+                cat.insert_file_summary(path, size, mtime, &summary);
+            }
+            summary
+        }
+    };
+    summary
+        .date_taken
+        .as_deref()
+        .and_then(normalize_date)
+        .or_else(|| summary.date_modified.as_deref().and_then(normalize_date))
+}
+
+/// A random order of the ranked photos in which newer ones tend to come
+/// first. `strength` 0 is a plain shuffle; at `strength` k the newest photo
+/// is e^k times as likely as the oldest to come up next.
+pub fn weighted_shuffle(ranks: &[(usize, f32)], strength: f32) -> Vec<usize> {
+    let mut rng = rand::rng();
+    // Efraimidis–Spirakis: sort by u^(1/w), here as ln(u)/w to keep precision
+    let mut keyed: Vec<(f64, usize)> = ranks
+        .iter()
+        .map(|&(i, rank)| {
+            let weight = ((strength * rank) as f64).exp();
+            let u: f64 = rng.random_range(f64::MIN_POSITIVE..1.0);
+            (u.ln() / weight, i)
+        })
+        .collect();
+    keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+    keyed.into_iter().map(|(_, i)| i).collect()
+}
@@ -0,0 +1,46 @@
+//! Progress tracking for the background jobs shown in the status bar.
+
+use std::time::{Duration, Instant};
+
+/// Don't guess an ETA until the job has run this long.
+const ETA_WARMUP: Duration = Duration::from_secs(2);
+
+/// When a job started and how much of it was already done at that point
+/// (e.g. hashes found in the catalog), so the rate only counts real work.
+#[derive(Debug, Clone, Copy)]
+pub struct JobProgress {
+    started: Instant,
+    baseline: usize,
+}
+
+impl JobProgress {
+    pub fn start(baseline: usize) -> Self {
+        Self {
+            started: Instant::now(),
+            baseline,
+        }
+    }
+
+    /// Time left at the rate seen so far, or `None` until there's enough to go on.
+    pub fn eta(&self, done: usize, total: usize) -> Option<Duration> {
+        let elapsed = self.started.elapsed();
+        let worked = done.saturating_sub(self.baseline);
+        if elapsed < ETA_WARMUP || worked == 0 || done >= total {
+            return None;
+        }
+        let per_item = elapsed.as_secs_f64() / worked as f64;
+        Some(Duration::from_secs_f64(per_item * (total - done) as f64))
+    }
+}
+
+/// "3 s", "4 min", "1 h 20 min" — coarse on purpose, ETAs are estimates.
+pub fn format_eta(eta: Duration) -> String {
+    let secs = eta.as_secs();
+    if secs < 60 {
+        format!("{} s", secs.max(1))
+    } else if secs < 3600 {
+        format!("{} min", secs.div_ceil(60))
+    } else {
+        format!("{} h {} min", secs / 3600, (secs % 3600) / 60)
+    }
+}
@@ -1,45 +1,125 @@
+use std::collections::HashMap;
 use std::io::{BufReader, Cursor, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{LazyLock, RwLock};
 
 use image::imageops::FilterType;
 use image::{DynamicImage, GenericImageView};
 use sha2::{Digest, Sha256};
 
+use crate::edit::{self, PhotoEdit};
+use crate::jpeg_rotate;
+
+/// Unsharp-mask amount applied after shrinking, in percent; 0 is off.
+static SHARPEN_PERCENT: AtomicU32 = AtomicU32::new(0);
+/// Blur radius of the unsharp mask, in thumbnail pixels.
+const SHARPEN_SIGMA: f32 = 0.8;
+/// How long after a file last changed a failed decode is put down to the
+/// file still being written.
+const SETTLING_TIME: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Sharpen thumbnails made from now on by `percent`, or not at all for 0.
+/// Each amount has its own cache entries.
+pub fn set_sharpening(percent: u32) {
+    SHARPEN_PERCENT.store(percent, Ordering::Relaxed);
+}
+
+/// EXIF orientations used in place of files' own tags, for scanners that
+/// write bogus ones. Shared by every window and the share server.
+static ORIENTATION_OVERRIDES: LazyLock<RwLock<HashMap<PathBuf, u32>>> = LazyLock::new(Default::default);
+
+/// Show `path` as if tagged with `orientation` (1 ignores its tag), or go
+/// back to its own tag for `None`. Cached thumbnails for each have their
+/// own keys.
+pub fn set_orientation_override(path: &Path, orientation: Option<u32>) {
+    let mut overrides = ORIENTATION_OVERRIDES.write().unwrap();
+    match orientation {
+        Some(orientation) => overrides.insert(path.to_path_buf(), orientation),
+        None => overrides.remove(path),
+    };
+}
+
+pub fn orientation_override(path: &Path) -> Option<u32> {
+    ORIENTATION_OVERRIDES.read().unwrap().get(path).copied()
+}
+
+/// Whether clients that apply EXIF orientation themselves need an upright
+/// re-encode rather than the file: it's rotated, its tag is overridden, or
+/// it's been edited.
+pub fn needs_upright(path: &Path) -> bool {
+    orientation_override(path).is_some() || edit::edit_for(path).is_some() || read_orientation(path) > 1
+}
+
+/// `width` × `height` as stored in the file, turned upright and with any
+/// edit applied: the size the photo is shown and served at.
+pub fn upright_size(path: &Path, (width, height): (u32, u32)) -> (u32, u32) {
+    let (width, height) = if read_orientation(path) >= 5 { (height, width) } else { (width, height) };
+    match edit::edit_for(path) {
+        Some(photo_edit) => photo_edit.output_size(width, height),
+        None => (width, height),
+    }
+}
+
 /// Generate a thumbnail as RGBA bytes. Returns (rgba_bytes, width, height).
-/// Checks disk cache first; on miss, generates and caches.
+/// Checks disk cache first; on miss, generates and caches. Unreadable files
+/// get a gray placeholder.
 pub fn generate_thumbnail(path: &Path, max_size: u32) -> (Vec<u8>, u32, u32) {
+    try_generate_thumbnail(path, max_size).unwrap_or_else(|_| placeholder_thumbnail(max_size))
+}
+
+/// RGBA bytes, width and height, or the decode error for an unreadable file.
+pub type ThumbnailResult = Result<(Vec<u8>, u32, u32), String>;
+
+/// Like `generate_thumbnail`, but returns the decode error for unreadable
+/// files instead of a placeholder. Failures are never cached.
+pub fn try_generate_thumbnail(path: &Path, max_size: u32) -> ThumbnailResult {
     // Check disk cache (QOI format)
-    let cache_key = cache_key(path, max_size);
+    let sharpen = SHARPEN_PERCENT.load(Ordering::Relaxed);
+    let cache_key = cache_key(path, max_size).map(|key| match sharpen {
+        0 => key,
+        percent => format!("{key}-s{percent}"),
+    });
     if let Some(key) = cache_key.as_ref() {
-        // Try QOI cache first
+        // Try QOI cache first. Older builds cached the placeholder for
+        // unreadable files; treat that as a miss so the error resurfaces.
         if let Some(cache_path) = cache_file_path(key) {
-            if let Ok(data) = std::fs::read(&cache_path) {
-                if let Ok((header, pixels)) = qoi::decode_to_vec(&data) {
-                    return (pixels, header.width, header.height);
-                }
+            let cached = std::fs::read(&cache_path)
+                .ok()
+                .and_then(|data| qoi::decode_to_vec(&data).ok())
+                .filter(|(header, pixels)| {
+                    !is_placeholder(pixels, header.width, header.height, max_size)
+                });
+            if let Some((header, pixels)) = cached {
+                return Ok((pixels, header.width, header.height));
             }
         }
         // Fallback: try legacy JPEG cache
         if let Some(legacy_path) = cache_file_path_legacy(key) {
             if let Ok(img) = image::open(&legacy_path) {
                 let (w, h) = img.dimensions();
-                return (img.to_rgba8().into_raw(), w, h);
+                return Ok((img.to_rgba8().into_raw(), w, h));
             }
         }
     }
 
     // Cache miss — generate thumbnail
-    let (rgba, w, h) = generate_thumbnail_uncached(path, max_size);
+    let (rgba, w, h) = generate_thumbnail_uncached(path, max_size, sharpen, edit::edit_for(path))?;
 
     // Write to disk cache (best-effort, QOI format)
     if let Some(key) = cache_key {
         save_to_cache(&key, &rgba, w, h);
     }
 
-    (rgba, w, h)
+    Ok((rgba, w, h))
 }
 
-fn generate_thumbnail_uncached(path: &Path, max_size: u32) -> (Vec<u8>, u32, u32) {
+fn generate_thumbnail_uncached(
+    path: &Path,
+    max_size: u32,
+    sharpen: u32,
+    edit: Option<PhotoEdit>,
+) -> ThumbnailResult {
     let (orientation, exif_thumb) = read_exif_info(path);
 
     // Try embedded EXIF thumbnail first (fast — avoids full decode).
@@ -55,39 +135,128 @@ fn generate_thumbnail_uncached(path: &Path, max_size: u32) -> (Vec<u8>, u32, u32
         };
         if large_enough {
             if let Ok(img) = image::load_from_memory(&data) {
-                let thumb = img.resize(max_size, max_size, FilterType::Triangle);
-                let thumb = apply_orientation(thumb, orientation);
-                let (w, h) = thumb.dimensions();
-                return (thumb.to_rgba8().into_raw(), w, h);
+                return Ok(shrink(img, max_size, sharpen, orientation, edit));
             }
         }
     }
 
     // Try downscaled JPEG decode (avoids processing millions of unnecessary pixels)
     if let Some(img) = decode_jpeg_scaled(path, max_size) {
-        let thumb = img.resize(max_size, max_size, FilterType::Triangle);
-        let thumb = apply_orientation(thumb, orientation);
-        let (w, h) = thumb.dimensions();
-        return (thumb.to_rgba8().into_raw(), w, h);
+        return Ok(shrink(img, max_size, sharpen, orientation, edit));
     }
 
     // Fallback: full decode + resize
     match image::open(path) {
-        Ok(img) => {
-            let thumb = img.resize(max_size, max_size, FilterType::Triangle);
-            let thumb = apply_orientation(thumb, orientation);
-            let (w, h) = thumb.dimensions();
-            (thumb.to_rgba8().into_raw(), w, h)
-        }
+        Ok(img) => Ok(shrink(img, max_size, sharpen, orientation, edit)),
         Err(e) => {
             log::warn!("Failed to load image {}: {}", path.display(), e);
-            placeholder_thumbnail(max_size)
+            Err(e.to_string())
+        }
+    }
+}
+
+/// Whether a file that failed to decode may well read fine shortly: it can't
+/// be opened for some reason other than being gone, such as a lock held by a
+/// sync client, or it changed so recently that it may still be being written.
+pub fn failure_may_be_transient(path: &Path) -> bool {
+    if let Err(e) = std::fs::File::open(path) {
+        return e.kind() != std::io::ErrorKind::NotFound;
+    }
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age < SETTLING_TIME)
+}
+
+/// Resize to fit `max_size`, sharpen by `sharpen` percent, and turn upright,
+/// with `edit` applied.
+fn shrink(
+    img: DynamicImage,
+    max_size: u32,
+    sharpen: u32,
+    orientation: u32,
+    edit: Option<PhotoEdit>,
+) -> (Vec<u8>, u32, u32) {
+    // Edits are in upright coordinates, and crop away detail the resize
+    // should keep, so they go first
+    let (img, orientation) = match edit {
+        Some(edit) => (edit.apply(apply_orientation(img, orientation)), 1),
+        None => (img, orientation),
+    };
+    let thumb = img.resize(max_size, max_size, FilterType::Triangle);
+    let thumb = match sharpen {
+        0 => thumb,
+        percent => unsharp_mask(&thumb, percent as f32 / 100.0),
+    };
+    let thumb = apply_orientation(thumb, orientation);
+    let (w, h) = thumb.dimensions();
+    (thumb.to_rgba8().into_raw(), w, h)
+}
+
+/// Push each pixel away from a blurred copy by `amount` of the difference,
+/// to restore edges the resize softened. Alpha is left alone.
+fn unsharp_mask(img: &DynamicImage, amount: f32) -> DynamicImage {
+    let mut sharp = img.to_rgba8();
+    let blurred = image::imageops::blur(&sharp, SHARPEN_SIGMA);
+    for (pixel, soft) in sharp.pixels_mut().zip(blurred.pixels()) {
+        for c in 0..3 {
+            let edge = pixel[c] as f32 - soft[c] as f32;
+            pixel[c] = (pixel[c] as f32 + edge * amount).round().clamp(0.0, 255.0) as u8;
         }
     }
+    DynamicImage::ImageRgba8(sharp)
 }
 
 // --- Downscaled JPEG decode ---
 
+/// Decode a JPEG at roughly `max_size` on its long edge, oriented for display.
+/// A quick stand-in for the viewer while the full-resolution decode runs;
+/// `None` for non-JPEGs and images that are already that small.
+pub fn decode_screen_sized(path: &Path, max_size: u32) -> Option<image::RgbaImage> {
+    let img = decode_jpeg_scaled(path, edited_decode_size(path, max_size))?;
+    let upright = DynamicImage::ImageRgba8(upright_rgba(img, read_orientation(path)));
+    Some(edit::apply_for(path, upright).into_rgba8())
+}
+
+/// Decode the whole image fitted within `max_size` on its long edge, oriented
+/// and edited for display, for modes that show it on screen without zooming
+/// and so have no use for the full resolution.
+pub fn decode_fitted(path: &Path, max_size: u32) -> Result<image::RgbaImage, String> {
+    let img = match decode_jpeg_scaled(path, edited_decode_size(path, max_size)) {
+        Some(img) => img,
+        None => image::open(path).map_err(|e| {
+            log::warn!("Failed to open {}: {}", path.display(), e);
+            e.to_string()
+        })?,
+    };
+    let upright = DynamicImage::ImageRgba8(upright_rgba(img, read_orientation(path)));
+    let edited = edit::apply_for(path, upright);
+    // DCT scaling stops at the nearest eighth above the size asked for
+    if edited.width().max(edited.height()) > max_size {
+        return Ok(edited.resize(max_size, max_size, FilterType::Triangle).into_rgba8());
+    }
+    Ok(edited.into_rgba8())
+}
+
+/// Long edge to decode `path` at so its edit, which crops after decoding,
+/// still leaves about `max_size` on the long edge.
+fn edited_decode_size(path: &Path, max_size: u32) -> u32 {
+    let Some(edit) = edit::edit_for(path) else {
+        return max_size;
+    };
+    let Ok((width, height)) = image::image_dimensions(path) else {
+        return max_size;
+    };
+    let (width, height) = if (5..=8).contains(&read_orientation(path)) {
+        (height, width)
+    } else {
+        (width, height)
+    };
+    let fraction = edit.kept_fraction(width, height).max(f32::EPSILON);
+    (max_size as f32 / fraction).ceil().min(u16::MAX as f32) as u32
+}
+
 /// Decode a JPEG at reduced resolution using DCT scaling.
 /// For a 4000x3000 image targeting 400px, decodes at ~500x375 instead of 12M pixels.
 /// Returns None for non-JPEG files, small images, or on failure.
@@ -100,13 +269,17 @@ fn decode_jpeg_scaled(path: &Path, max_size: u32) -> Option<DynamicImage> {
     let file = std::fs::File::open(path).ok()?;
     let mut decoder = jpeg_decoder::Decoder::new(BufReader::new(file));
 
-    // scale() reads the header internally and picks the optimal DCT scale factor.
-    // It returns the actual output dimensions.
+    // info() reports the output size, which scale() changes, so take the
+    // stored size first
+    decoder.read_info().ok()?;
+    let info = decoder.info()?;
+
+    // scale() picks the optimal DCT scale factor and returns the actual
+    // output dimensions.
     let max_u16 = max_size as u16;
     let (actual_w, actual_h) = decoder.scale(max_u16, max_u16).ok()?;
 
     // Only beneficial if the decoder actually downscaled
-    let info = decoder.info()?;
     if actual_w == info.width && actual_h == info.height {
         return None;
     }
@@ -148,10 +321,26 @@ fn cache_key(path: &Path, max_size: u32) -> Option<String> {
     hasher.update(meta.len().to_le_bytes());
     hasher.update(mtime.to_le_bytes());
     hasher.update(max_size.to_le_bytes());
+    if let Some(orientation) = orientation_override(path) {
+        hasher.update(b"orientation");
+        hasher.update(orientation.to_le_bytes());
+    }
+    if let Some(edit) = edit::edit_for(path) {
+        hasher.update(b"edit");
+        for value in edit.crop.iter().chain([&edit.angle]) {
+            hasher.update(value.to_le_bytes());
+        }
+    }
     let hash = hasher.finalize();
     Some(hex_encode(hash))
 }
 
+/// Short token that changes whenever the file, its edit or its orientation
+/// override does, for served URLs so clients don't show a stale copy.
+pub fn version_token(path: &Path) -> String {
+    cache_key(path, 0).map(|key| key[..12].to_string()).unwrap_or_default()
+}
+
 fn hex_encode(bytes: impl AsRef<[u8]>) -> String {
     bytes.as_ref().iter().map(|b| format!("{b:02x}")).collect()
 }
@@ -172,36 +361,52 @@ fn save_to_cache(key: &str, rgba: &[u8], width: u32, height: u32) {
     let Some(path) = cache_file_path(key) else {
         return;
     };
-    if let Some(parent) = path.parent() {
-        let _ = std::fs::create_dir_all(parent);
-    }
     // QOI encode is ~10x faster than JPEG and keeps RGBA directly
     if let Ok(data) = qoi::encode_to_vec(rgba, width, height) {
-        let _ = std::fs::write(&path, data);
+        write_cache_file(&path, &data);
+    }
+}
+
+/// Write a cache entry under a temporary name and rename it into place, so
+/// a reader never sees it half-written.
+fn write_cache_file(path: &Path, data: &[u8]) {
+    let Some(parent) = path.parent() else {
+        return;
+    };
+    let _ = std::fs::create_dir_all(parent);
+    let tmp = parent.join(format!(".{}.part", uuid::Uuid::new_v4()));
+    if std::fs::write(&tmp, data).is_err() || std::fs::rename(&tmp, path).is_err() {
+        let _ = std::fs::remove_file(&tmp);
     }
 }
 
 // --- EXIF ---
 
-/// Read just the EXIF orientation value.
+/// Read just the EXIF orientation value, or its override.
 pub fn read_orientation(path: &Path) -> u32 {
-    read_exif_info(path).0
+    match orientation_override(path) {
+        Some(orientation) => orientation,
+        None => read_exif_info(path).0,
+    }
 }
 
 /// Single file open + EXIF parse: returns (orientation, optional embedded thumbnail JPEG bytes).
+/// An override replaces the file's orientation.
 fn read_exif_info(path: &Path) -> (u32, Option<Vec<u8>>) {
+    let overridden = orientation_override(path);
     let Ok(file) = std::fs::File::open(path) else {
-        return (1, None);
+        return (overridden.unwrap_or(1), None);
     };
     let mut reader = BufReader::new(file);
     let Ok(exif) = exif::Reader::new().read_from_container(&mut reader) else {
-        return (1, None);
+        return (overridden.unwrap_or(1), None);
     };
 
-    let orientation = exif
-        .get_field(exif::Tag::Orientation, exif::In::PRIMARY)
-        .and_then(|f| f.value.get_uint(0))
-        .unwrap_or(1);
+    let orientation = overridden.unwrap_or_else(|| {
+        exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)
+            .and_then(|f| f.value.get_uint(0))
+            .unwrap_or(1)
+    });
 
     let thumbnail = (|| {
         let offset = exif
@@ -225,7 +430,7 @@ fn read_exif_info(path: &Path) -> (u32, Option<Vec<u8>>) {
 }
 
 /// Apply EXIF orientation transform to an image.
-fn apply_orientation(img: DynamicImage, orientation: u32) -> DynamicImage {
+pub fn apply_orientation(img: DynamicImage, orientation: u32) -> DynamicImage {
     match orientation {
         2 => img.fliph(),
         3 => img.rotate180(),
@@ -238,12 +443,75 @@ fn apply_orientation(img: DynamicImage, orientation: u32) -> DynamicImage {
     }
 }
 
+/// `img` turned upright per EXIF `orientation` and converted to RGBA in one
+/// pass, the rows of the result shared out between threads. What
+/// [`apply_orientation`] then `to_rgba8` would give, without running two
+/// full-size copies on one core.
+pub fn upright_rgba(img: DynamicImage, orientation: u32) -> image::RgbaImage {
+    use rayon::prelude::*;
+
+    let (width, height) = img.dimensions();
+    let (channels, src) = match img {
+        DynamicImage::ImageLuma8(buf) => (1, buf.into_raw()),
+        DynamicImage::ImageRgb8(buf) => (3, buf.into_raw()),
+        DynamicImage::ImageRgba8(buf) if orientation <= 1 => return buf,
+        DynamicImage::ImageRgba8(buf) => (4, buf.into_raw()),
+        other => (4, other.to_rgba8().into_raw()),
+    };
+    let (out_w, out_h) = if (5..=8).contains(&orientation) {
+        (height, width)
+    } else {
+        (width, height)
+    };
+    // Where pixel (x, y) of the upright image comes from
+    let source = |x: u32, y: u32| match orientation {
+        2 => (width - 1 - x, y),
+        3 => (width - 1 - x, height - 1 - y),
+        4 => (x, height - 1 - y),
+        5 => (y, x),
+        6 => (y, height - 1 - x),
+        7 => (width - 1 - y, height - 1 - x),
+        8 => (width - 1 - y, x),
+        _ => (x, y),
+    };
+
+    let mut out = vec![0u8; out_w as usize * out_h as usize * 4];
+    out.par_chunks_mut(out_w as usize * 4).enumerate().for_each(|(y, row)| {
+        for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+            let (sx, sy) = source(x as u32, y as u32);
+            let i = (sy as usize * width as usize + sx as usize) * channels;
+            match channels {
+                1 => pixel.copy_from_slice(&[src[i], src[i], src[i], 255]),
+                3 => pixel.copy_from_slice(&[src[i], src[i + 1], src[i + 2], 255]),
+                _ => pixel.copy_from_slice(&src[i..i + 4]),
+            }
+        }
+    });
+    image::RgbaImage::from_raw(out_w, out_h, out).expect("buffer sized for the image")
+}
 
 fn placeholder_thumbnail(size: u32) -> (Vec<u8>, u32, u32) {
     let pixels = vec![60u8; (size * size * 4) as usize];
     (pixels, size, size)
 }
 
+/// Shrink RGBA pixels to at most `max_size` on the long edge. Used to keep a
+/// blurry stand-in for grid thumbnails evicted from memory.
+pub fn shrink_rgba(rgba: &[u8], width: u32, height: u32, max_size: u32) -> Option<(Vec<u8>, u32, u32)> {
+    let img = image::RgbaImage::from_raw(width, height, rgba.to_vec())?;
+    let scale = max_size as f32 / width.max(height).max(1) as f32;
+    let (w, h) = (
+        ((width as f32 * scale).round() as u32).max(1),
+        ((height as f32 * scale).round() as u32).max(1),
+    );
+    let small = image::imageops::thumbnail(&img, w, h);
+    Some((small.into_raw(), w, h))
+}
+
+fn is_placeholder(pixels: &[u8], width: u32, height: u32, size: u32) -> bool {
+    width == size && height == size && pixels.iter().all(|&b| b == 60)
+}
+
 /// Generate a JPEG thumbnail as raw bytes, suitable for HTTP serving.
 pub fn thumbnail_jpeg_bytes(path: &Path, max_size: u32, quality: u8) -> Vec<u8> {
     use image::ImageEncoder;
@@ -256,6 +524,131 @@ pub fn thumbnail_jpeg_bytes(path: &Path, max_size: u32, quality: u8) -> Vec<u8>
     buf
 }
 
+/// Generate a lossy WebP thumbnail, for browsers that accept it. Usually
+/// well under the size of the JPEG at the same quality.
+pub fn thumbnail_webp_bytes(path: &Path, max_size: u32, quality: u8) -> Vec<u8> {
+    let (rgba, w, h) = generate_thumbnail(path, max_size);
+    let rgb: Vec<u8> = rgba.chunks_exact(4).flat_map(|px| &px[..3]).copied().collect();
+    webp::Encoder::from_rgb(&rgb, w, h).encode(quality as f32).to_vec()
+}
+
+/// rav1e speed for AVIF thumbnails, 1 (smallest) to 10 (fastest).
+const AVIF_SPEED: u8 = 8;
+
+/// Generate an AVIF thumbnail. Smaller still than WebP, but much slower to
+/// encode.
+pub fn thumbnail_avif_bytes(path: &Path, max_size: u32, quality: u8) -> Vec<u8> {
+    use image::ImageEncoder;
+    let (rgba, w, h) = generate_thumbnail(path, max_size);
+    let rgb: Vec<u8> = rgba.chunks_exact(4).flat_map(|px| &px[..3]).copied().collect();
+    let mut buf = Vec::new();
+    let encoder = image::codecs::avif::AvifEncoder::new_with_speed_quality(&mut buf, AVIF_SPEED, quality);
+    if let Err(e) = encoder.write_image(&rgb, w, h, image::ExtendedColorType::Rgb8) {
+        log::warn!("Failed to encode AVIF thumbnail for {}: {}", path.display(), e);
+    }
+    buf
+}
+
+// --- Display-size images ---
+
+fn display_cache_path(key: &str) -> Option<PathBuf> {
+    let dir = dirs_next::home_dir()?
+        .join(".looky")
+        .join("cache")
+        .join("display")
+        .join(&key[..2]);
+    Some(dir.join(format!("{}.jpg", key)))
+}
+
+/// Downscale an image to fit within `max_size` and encode it as JPEG, for web
+/// and TV viewing. The encoded bytes are cached on disk, keyed like thumbnails.
+pub fn display_jpeg_bytes(path: &Path, max_size: u32, quality: u8) -> Option<Vec<u8>> {
+    use image::ImageEncoder;
+    let cache_path = cache_key(path, max_size).and_then(|k| display_cache_path(&format!("{k}-q{quality}")));
+    if let Some(data) = cache_path.as_ref().and_then(|p| std::fs::read(p).ok()) {
+        return Some(data);
+    }
+
+    let img = match decode_jpeg_scaled(path, edited_decode_size(path, max_size)) {
+        Some(img) => img,
+        None => image::open(path)
+            .map_err(|e| log::warn!("Failed to load image {}: {}", path.display(), e))
+            .ok()?,
+    };
+    // Upright before resizing, so an edit crops the detail decoded for it
+    let img = edit::apply_for(path, apply_orientation(img, read_orientation(path)));
+    let (w, h) = img.dimensions();
+    let img = if w > max_size || h > max_size {
+        img.resize(max_size, max_size, FilterType::Lanczos3)
+    } else {
+        img
+    };
+    let rgb = img.to_rgb8();
+
+    let mut buf = Vec::new();
+    let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buf, quality);
+    encoder
+        .write_image(rgb.as_raw(), rgb.width(), rgb.height(), image::ExtendedColorType::Rgb8)
+        .ok()?;
+
+    if let Some(cache_path) = cache_path {
+        write_cache_file(&cache_path, &buf);
+    }
+    Some(buf)
+}
+
+fn upright_cache_path(key: &str) -> Option<PathBuf> {
+    let dir = dirs_next::home_dir()?
+        .join(".looky")
+        .join("cache")
+        .join("upright")
+        .join(&key[..2]);
+    Some(dir.join(format!("{}.jpg", key)))
+}
+
+/// The full-size image turned upright per its EXIF orientation, with any
+/// edit applied, as a JPEG, for clients that ignore the orientation tag.
+/// Unedited JPEGs are turned losslessly; anything else is re-encoded at
+/// `quality`. Cached on disk so the same bytes, and so the same length, are
+/// served every time.
+pub fn upright_jpeg_bytes(path: &Path, quality: u8) -> Option<Vec<u8>> {
+    use image::ImageEncoder;
+    // Keyed like a display image with no size limit
+    let cache_path = cache_key(path, 0).and_then(|k| upright_cache_path(&format!("{k}-q{quality}")));
+    if let Some(data) = cache_path.as_ref().and_then(|p| std::fs::read(p).ok()) {
+        return Some(data);
+    }
+
+    let lossless = edit::edit_for(path)
+        .is_none()
+        .then(|| std::fs::read(path).ok())
+        .flatten()
+        .and_then(|data| jpeg_rotate::upright(&data, read_orientation(path)));
+    if let Some(buf) = lossless {
+        if let Some(cache_path) = cache_path {
+            write_cache_file(&cache_path, &buf);
+        }
+        return Some(buf);
+    }
+
+    let img = image::open(path)
+        .map_err(|e| log::warn!("Failed to load image {}: {}", path.display(), e))
+        .ok()?;
+    let img = edit::apply_for(path, apply_orientation(img, read_orientation(path)));
+    let rgb = img.to_rgb8();
+
+    let mut buf = Vec::new();
+    let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buf, quality);
+    encoder
+        .write_image(rgb.as_raw(), rgb.width(), rgb.height(), image::ExtendedColorType::Rgb8)
+        .ok()?;
+
+    if let Some(cache_path) = cache_path {
+        write_cache_file(&cache_path, &buf);
+    }
+    Some(buf)
+}
+
 /// Fast EXIF thumbnail extraction. Returns (rgba, w, h) or None.
 /// Does NOT check disk cache — that's for the full-quality path.
 pub fn extract_preview(path: &Path, max_size: u32) -> Option<(Vec<u8>, u32, u32)> {
@@ -263,7 +656,7 @@ pub fn extract_preview(path: &Path, max_size: u32) -> Option<(Vec<u8>, u32, u32)
     let data = exif_thumb?;
     let img = image::load_from_memory(&data).ok()?;
     let thumb = img.resize(max_size, max_size, FilterType::Triangle);
-    let thumb = apply_orientation(thumb, orientation);
+    let thumb = edit::apply_for(path, apply_orientation(thumb, orientation));
     let (w, h) = thumb.dimensions();
     Some((thumb.to_rgba8().into_raw(), w, h))
 }
@@ -281,17 +674,15 @@ pub fn extract_previews_parallel(
 }
 
 /// Generate thumbnails for multiple paths in parallel using rayon.
+/// Unreadable files come back with their decode error.
 pub fn generate_thumbnails_parallel(
     paths: &[std::path::PathBuf],
     max_size: u32,
-) -> Vec<(std::path::PathBuf, Vec<u8>, u32, u32)> {
+) -> Vec<(std::path::PathBuf, ThumbnailResult)> {
     use rayon::prelude::*;
 
     paths
         .par_iter()
-        .map(|p| {
-            let (rgba, w, h) = generate_thumbnail(p, max_size);
-            (p.clone(), rgba, w, h)
-        })
+        .map(|p| (p.clone(), try_generate_thumbnail(p, max_size)))
         .collect()
 }
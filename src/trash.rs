@@ -0,0 +1,56 @@
+//! Looky's own trash. Deleted photos are moved into a folder under
+//! `~/.looky` and recorded in the catalog, so the "Recently deleted" view can
+//! restore them or delete them for good whatever the OS trash does.
+
+use std::io;
+use std::path::Path;
+
+use crate::catalog::TrashedPhoto;
+use crate::file_ops::move_file;
+
+/// Move `path` into `trash_dir`, under a name that can't clash with other
+/// trashed files of the same name.
+pub fn move_to_trash(trash_dir: &Path, path: &Path) -> io::Result<TrashedPhoto> {
+    std::fs::create_dir_all(trash_dir)?;
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file"))?;
+    let trashed_path = trash_dir.join(format!(
+        "{}-{}",
+        uuid::Uuid::new_v4().simple(),
+        name.to_string_lossy()
+    ));
+    move_file(path, &trashed_path)?;
+    let deleted_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    Ok(TrashedPhoto {
+        trashed_path,
+        original_path: path.to_path_buf(),
+        deleted_at,
+    })
+}
+
+/// Put a trashed photo back where it was. Fails rather than overwrite a file
+/// that has since taken its place.
+pub fn restore(photo: &TrashedPhoto) -> io::Result<()> {
+    if photo.original_path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", photo.original_path.display()),
+        ));
+    }
+    if let Some(parent) = photo.original_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    move_file(&photo.trashed_path, &photo.original_path)
+}
+
+/// Delete a trashed photo for good. One that's already gone counts as done.
+pub fn purge(photo: &TrashedPhoto) -> io::Result<()> {
+    match std::fs::remove_file(&photo.trashed_path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
@@ -1,17 +1,169 @@
 use std::time::Instant;
 
 const CROSSFADE_DURATION_MS: f32 = 250.0;
+pub const DEFAULT_MAX_ZOOM: f32 = 8.0;
+
+/// How the image is sized at zoom 1.0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FitMode {
+    /// Whole image visible.
+    #[default]
+    Fit,
+    FitWidth,
+    FitHeight,
+    /// Viewport covered; the overflow can be panned.
+    Fill,
+}
+
+impl FitMode {
+    pub const ALL: [FitMode; 4] = [FitMode::Fit, FitMode::FitWidth, FitMode::FitHeight, FitMode::Fill];
+
+    pub fn label(self) -> &'static str {
+        crate::i18n::tr(match self {
+            FitMode::Fit => "Fit",
+            FitMode::FitWidth => "Fit Width",
+            FitMode::FitHeight => "Fit Height",
+            FitMode::Fill => "Fill",
+        })
+    }
+
+    /// Name used in the settings file.
+    pub fn key(self) -> &'static str {
+        match self {
+            FitMode::Fit => "fit",
+            FitMode::FitWidth => "width",
+            FitMode::FitHeight => "height",
+            FitMode::Fill => "fill",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.key() == key)
+    }
+
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&m| m == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// Size of an `img_w`×`img_h` image at zoom 1.0 in a `vp_w`×`vp_h` viewport.
+    pub fn base_size(self, img_w: u32, img_h: u32, vp_w: f32, vp_h: f32) -> (f32, f32) {
+        let sx = vp_w / img_w as f32;
+        let sy = vp_h / img_h as f32;
+        let scale = match self {
+            FitMode::Fit => sx.min(sy),
+            FitMode::FitWidth => sx,
+            FitMode::FitHeight => sy,
+            FitMode::Fill => sx.max(sy),
+        };
+        (img_w as f32 * scale, img_h as f32 * scale)
+    }
+}
+
+/// What the viewer draws behind images, visible through transparency and
+/// around the edges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Background {
+    /// Whatever the theme draws.
+    #[default]
+    Theme,
+    Black,
+    Gray,
+    White,
+}
+
+impl Background {
+    pub const ALL: [Background; 4] =
+        [Background::Theme, Background::Black, Background::Gray, Background::White];
+
+    pub fn label(self) -> &'static str {
+        crate::i18n::tr(match self {
+            Background::Theme => "Theme",
+            Background::Black => "Black",
+            Background::Gray => "Gray",
+            Background::White => "White",
+        })
+    }
+
+    /// Name used in the settings file.
+    pub fn key(self) -> &'static str {
+        match self {
+            Background::Theme => "theme",
+            Background::Black => "black",
+            Background::Gray => "gray",
+            Background::White => "white",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|b| b.key() == key)
+    }
+}
+
+/// A magnification the number keys jump to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ZoomPreset {
+    /// The fit mode's base size (zoom 1.0).
+    Fit,
+    /// Percent of the image's actual pixels.
+    Percent(u32),
+}
+
+pub const DEFAULT_ZOOM_PRESETS: [ZoomPreset; 3] =
+    [ZoomPreset::Percent(100), ZoomPreset::Percent(200), ZoomPreset::Fit];
+
+impl ZoomPreset {
+    /// Parse a comma-separated list like `100, 200, fit`. Returns None if any
+    /// entry is invalid or the list is empty.
+    pub fn parse_list(s: &str) -> Option<Vec<ZoomPreset>> {
+        let presets: Option<Vec<ZoomPreset>> = s
+            .split(',')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(|p| {
+                if p.eq_ignore_ascii_case("fit") {
+                    Some(ZoomPreset::Fit)
+                } else {
+                    let n: u32 = p.trim_end_matches('%').trim().parse().ok()?;
+                    (n > 0).then_some(ZoomPreset::Percent(n))
+                }
+            })
+            .collect();
+        presets.filter(|p| !p.is_empty())
+    }
+
+    pub fn format_list(presets: &[ZoomPreset]) -> String {
+        presets
+            .iter()
+            .map(|p| match p {
+                ZoomPreset::Fit => "fit".to_string(),
+                ZoomPreset::Percent(n) => n.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
 
 pub struct ViewerState {
     pub current_index: Option<usize>,
     pub transition: Option<Transition>,
     pub show_info: bool,
+    /// Draw the camera's autofocus points over the image.
+    pub show_focus: bool,
     pub zoom_level: f32,
     pub zoom_target: f32,
     pub zoom_offset: (f32, f32),
     /// Cursor position in window coordinates when zoom was initiated.
     /// Used to keep the point under the cursor fixed during zoom animation.
     pub zoom_anchor: Option<(f32, f32)>,
+    /// Sizing at zoom 1.0; kept across images.
+    pub fit_mode: FitMode,
+    /// Upper bound for zoom_level.
+    pub max_zoom: f32,
+    /// Index of the zoom preset last jumped to, for cycling.
+    pub zoom_preset: Option<usize>,
+    /// Image marked for A/B flipping against the current one.
+    pub flip_partner: Option<usize>,
     /// Last time tick_zoom advanced zoom_level — used to debounce so batched
     /// scroll events don't cause multiple advances per frame.
     last_zoom_tick: Option<Instant>,
@@ -23,10 +175,15 @@ impl Default for ViewerState {
             current_index: None,
             transition: None,
             show_info: false,
+            show_focus: false,
             zoom_level: 1.0,
             zoom_target: 1.0,
             zoom_offset: (0.0, 0.0),
             zoom_anchor: None,
+            fit_mode: FitMode::Fit,
+            max_zoom: DEFAULT_MAX_ZOOM,
+            zoom_preset: None,
+            flip_partner: None,
             last_zoom_tick: None,
         }
     }
@@ -46,17 +203,51 @@ impl ViewerState {
     pub fn close(&mut self) {
         self.current_index = None;
         self.transition = None;
+        self.flip_partner = None;
         self.reset_zoom();
     }
 
+    /// Mark the current image as the A/B partner, or unmark it if it already is.
+    pub fn toggle_flip_mark(&mut self) {
+        if self.flip_partner == self.current_index {
+            self.flip_partner = None;
+        } else {
+            self.flip_partner = self.current_index;
+        }
+    }
+
+    /// Swap to the A/B partner, keeping zoom and pan so the same spot can be
+    /// compared. Returns false if there's nothing to flip to.
+    pub fn flip(&mut self) -> bool {
+        match (self.current_index, self.flip_partner) {
+            (Some(current), Some(partner)) if current != partner => {
+                self.current_index = Some(partner);
+                self.flip_partner = Some(current);
+                self.transition = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
     pub fn toggle_info(&mut self) {
         self.show_info = !self.show_info;
     }
 
+    pub fn toggle_focus(&mut self) {
+        self.show_focus = !self.show_focus;
+    }
+
     pub fn is_zoomed(&self) -> bool {
         self.zoom_level > 1.0
     }
 
+    /// Whether the image is shown in the pannable view — zoomed in, or in a
+    /// fit mode that can overflow the viewport.
+    pub fn is_pannable(&self) -> bool {
+        self.is_zoomed() || self.fit_mode != FitMode::Fit
+    }
+
     pub fn is_zoom_animating(&self) -> bool {
         (self.zoom_level - self.zoom_target).abs() > 0.005
     }
@@ -76,19 +267,26 @@ impl ViewerState {
         self.zoom_target = 1.0;
         self.zoom_offset = (0.0, 0.0);
         self.zoom_anchor = None;
+        self.zoom_preset = None;
+    }
+
+    /// Animate toward `target`, keeping the point at `anchor` fixed.
+    pub fn zoom_to(&mut self, target: f32, anchor: (f32, f32)) {
+        self.zoom_target = target.clamp(1.0, self.max_zoom);
+        self.zoom_anchor = Some(anchor);
     }
 
     /// Set zoom target from a scroll delta. The actual zoom_level is animated
     /// toward this target on each tick.
     pub fn adjust_zoom(&mut self, delta: f32) {
         let factor = 2.0_f32.powf(delta * 0.15);
-        self.zoom_target = (self.zoom_target * factor).clamp(1.0, 8.0);
+        self.zoom_target = (self.zoom_target * factor).clamp(1.0, self.max_zoom);
         // Don't let target race too far ahead of current level — prevents
         // large jumps when scroll events accumulate before animation starts.
         self.zoom_target = self
             .zoom_target
             .clamp(self.zoom_level / 1.5, self.zoom_level * 1.5);
-        self.zoom_target = self.zoom_target.clamp(1.0, 8.0);
+        self.zoom_target = self.zoom_target.clamp(1.0, self.max_zoom);
         if self.zoom_target < 1.02 {
             self.zoom_target = 1.0;
         }
@@ -105,7 +303,9 @@ impl ViewerState {
             if self.zoom_level < 1.02 && self.zoom_target <= 1.0 {
                 self.zoom_level = 1.0;
                 self.zoom_target = 1.0;
-                self.zoom_offset = (0.0, 0.0);
+                if self.fit_mode == FitMode::Fit {
+                    self.zoom_offset = (0.0, 0.0);
+                }
             }
             return false;
         }
@@ -136,7 +336,9 @@ impl ViewerState {
         if self.zoom_level < 1.02 && self.zoom_target <= 1.0 {
             self.zoom_level = 1.0;
             self.zoom_target = 1.0;
-            self.zoom_offset = (0.0, 0.0);
+            if self.fit_mode == FitMode::Fit {
+                self.zoom_offset = (0.0, 0.0);
+            }
         }
         !was_zoomed && self.is_zoomed()
     }
@@ -150,22 +352,6 @@ impl ViewerState {
         }
     }
 
-    pub fn next(&mut self, total: usize) {
-        if let Some(i) = self.current_index {
-            if i + 1 < total {
-                self.navigate_to(i + 1);
-            }
-        }
-    }
-
-    pub fn prev(&mut self) {
-        if let Some(i) = self.current_index {
-            if i > 0 {
-                self.navigate_to(i - 1);
-            }
-        }
-    }
-
     /// Returns the crossfade progress (0.0 = just started, 1.0 = done).
     /// Returns None if no transition is active.
     pub fn transition_progress(&self) -> Option<f32> {
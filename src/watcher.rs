@@ -9,10 +9,15 @@ pub struct FolderWatcher {
 }
 
 impl FolderWatcher {
-    pub fn new(path: &Path) -> notify::Result<Self> {
+    /// Watch `path`, and its subfolders unless `depth` is `Some(0)`.
+    pub fn new(path: &Path, depth: Option<u32>) -> notify::Result<Self> {
         let (tx, rx) = mpsc::channel();
         let mut watcher = notify::recommended_watcher(tx)?;
-        watcher.watch(path, RecursiveMode::NonRecursive)?;
+        let mode = match depth {
+            Some(0) => RecursiveMode::NonRecursive,
+            _ => RecursiveMode::Recursive,
+        };
+        watcher.watch(path, mode)?;
         Ok(FolderWatcher {
             _watcher: watcher,
             events: rx,